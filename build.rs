@@ -0,0 +1,39 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    grpc::generate();
+}
+
+/// Generates the gRPC service from Rust definitions mirroring `proto/kitmap/v1/kitmap.proto`,
+/// so building kitmap doesn't need `protoc`. The messages are defined in `src/server/grpc.rs`.
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    const METHODS: [(&str, &str, &str, &str); 6] = [
+        ("status", "Status", "StatusRequest", "StatusReply"),
+        ("pause", "Pause", "PauseRequest", "StatusReply"),
+        ("resume", "Resume", "ResumeRequest", "StatusReply"),
+        ("summary", "Summary", "RangeRequest", "SummaryReply"),
+        ("top_keys", "TopKeys", "TopRequest", "TopKeysReply"),
+        ("top_combos", "TopCombos", "TopRequest", "TopCombosReply"),
+    ];
+
+    pub fn generate() {
+        println!("cargo:rerun-if-changed=build.rs");
+        let mut service = Service::builder().name("Kitmap").package("kitmap.v1");
+        for (name, route, input, output) in METHODS {
+            service = service.method(
+                Method::builder()
+                    .name(name)
+                    .route_name(route)
+                    .input_type(format!("crate::server::grpc::{}", input))
+                    .output_type(format!("crate::server::grpc::{}", output))
+                    .codec_path("tonic::codec::ProstCodec")
+                    .build(),
+            );
+        }
+        Builder::new()
+            .build_client(false)
+            .compile(&[service.build()]);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::config::load_config;
+use crate::db::{audit, init_db};
+use anyhow::Result;
+use crossterm::style::Stylize;
+
+/// Show how the dataset and its settings have been changed over time
+pub async fn run(limit: usize) -> Result<()> {
+    status!("{}", "📜 KitMap - Audit Log".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    let db = init_db()?;
+    // Pick up config edits made since the last listen
+    audit::track_config(&db, &load_config()?)?;
+    let entries = audit::recent(&db, limit)?;
+
+    if entries.is_empty() {
+        status!("{}", "No changes recorded yet.".dark_grey());
+        status!();
+        return Ok(());
+    }
+
+    for entry in entries {
+        status!(
+            "{}  {} {:<10} {}",
+            entry
+                .timestamp
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+                .dark_grey(),
+            format!("{:<8}", entry.action).cyan(),
+            entry.user,
+            entry.details
+        );
+    }
+    status!();
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::commands::listen::ListenState;
+use crate::config::Config;
+use crate::context::{ContextOptions, ContextTracker};
+use crate::db::{init_db, init_memory_db, models::LatencyReport};
+use anyhow::{bail, Result};
+use crossterm::style::Stylize;
+use rdev::Key;
+use std::time::Instant;
+
+/// Keys cycled through by the synthetic typing workload
+const BENCH_KEYS: &[Key] = &[
+    Key::KeyT,
+    Key::KeyH,
+    Key::KeyE,
+    Key::Space,
+    Key::KeyQ,
+    Key::KeyU,
+    Key::KeyI,
+    Key::KeyC,
+    Key::KeyK,
+    Key::Return,
+];
+
+/// Every this many keys the workload presses a Ctrl combo
+const COMBO_EVERY: usize = 20;
+
+/// Number of recorded session reports shown after the benchmark
+const RECENT_REPORTS: usize = 5;
+
+pub async fn run(events: usize) -> Result<()> {
+    if events == 0 {
+        bail!("--events must be at least 1");
+    }
+
+    status!("{}", "⏱️  KitMap Latency Benchmark".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+    status!(
+        "{} Replaying {} synthetic key presses through the recorder...",
+        "→".dark_grey(),
+        events
+    );
+
+    let db = init_memory_db()?;
+    let config = Config::default();
+    let tracker = ContextTracker::new(ContextOptions::default(), None, &config);
+    let mut state = ListenState::new(db, tracker, &config.storage).with_latency_measurement();
+    state.start_session()?;
+
+    for i in 0..events {
+        let key = BENCH_KEYS[i % BENCH_KEYS.len()];
+        let combo = i % COMBO_EVERY == COMBO_EVERY - 1;
+
+        if combo {
+            let started = Instant::now();
+            state.key_pressed(Key::ControlLeft, None);
+            state.record_latency(started);
+        }
+
+        let started = Instant::now();
+        state.key_pressed(key, None);
+        state.record_latency(started);
+
+        if combo {
+            let started = Instant::now();
+            state.key_released(Key::ControlLeft);
+            state.record_latency(started);
+        }
+    }
+
+    let summary = state
+        .latency_summary()
+        .expect("benchmark handles at least one event");
+    state.end_session()?;
+
+    status!();
+    status!("{}", "Synthetic workload".bold());
+    status!("   Events handled: {}", summary.events.to_string().cyan());
+    status!("   p50: {}", format_micros(summary.p50_us).green());
+    status!("   p99: {}", format_micros(summary.p99_us).yellow());
+    status!("   max: {}", format_micros(summary.max_us));
+    status!();
+
+    let reports = LatencyReport::recent(&init_db()?, RECENT_REPORTS)?;
+    status!("{}", "Recorded sessions".bold());
+    if reports.is_empty() {
+        status!(
+            "{}",
+            "   None yet. Run `kitmap listen --measure-latency` to measure real input handling."
+                .dark_grey()
+        );
+    }
+    for report in reports {
+        status!(
+            "   {}  {} events  p50 {}  p99 {}  max {}",
+            report.timestamp.format("%Y-%m-%d %H:%M"),
+            report.events,
+            format_micros(report.p50_us as u64),
+            format_micros(report.p99_us as u64).yellow(),
+            format_micros(report.max_us as u64)
+        );
+    }
+    status!();
+
+    Ok(())
+}
+
+fn format_micros(micros: u64) -> String {
+    if micros >= 1000 {
+        format!("{:.2}ms", micros as f64 / 1000.0)
+    } else {
+        format!("{}µs", micros)
+    }
+}
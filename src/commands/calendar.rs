@@ -0,0 +1,18 @@
+use crate::db::init_db;
+use crate::stats::{StatsCalculator, StatsFilter};
+use crate::ui::heatmap::render_calendar;
+use anyhow::Result;
+use crossterm::style::Stylize;
+
+/// Show key presses per day over the last year as a calendar
+pub async fn run() -> Result<()> {
+    let db = init_db()?;
+    let days = StatsCalculator::new(db).activity_calendar(&StatsFilter::default())?;
+    if days.iter().all(|day| day.count == 0) {
+        status!("{}", "No key presses recorded in the last year.".yellow());
+        return Ok(());
+    }
+
+    status_print!("{}", render_calendar(&days));
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use crate::config::load_config;
+use crate::db::init_db;
+use crate::stats::board::Board;
+use crate::stats::range::TimeRange;
+use crate::stats::{StatsCalculator, StatsFilter};
+use crate::ui::heatmap::render_comparison;
+use anyhow::Result;
+
+/// Print two time ranges side by side, with the change from `a` to `b`
+pub async fn run(a: &str, b: &str, keys: usize) -> Result<()> {
+    let a_range = TimeRange::parse_named(a)?;
+    let b_range = TimeRange::parse_named(b)?;
+
+    let db = init_db()?;
+    let config = load_config()?;
+    let calculator = StatsCalculator::new(db).with_board(Board::from_config(&config.keyboard)?);
+    let a_summary = calculator.summary(&StatsFilter::from(a_range))?;
+    let b_summary = calculator.summary(&StatsFilter::from(b_range))?;
+
+    status_print!(
+        "{}",
+        render_comparison(a.trim(), b.trim(), &a_summary, &b_summary, keys)
+    );
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use crate::context::ContextOptions;
+use crate::db::audit::{self, AuditAction};
+use crate::db::{models::Consent, DbConnection};
+use anyhow::{bail, Result};
+use crossterm::style::Stylize;
+use std::io::{self, IsTerminal};
+
+/// A kind of data `kitmap listen` records or sends elsewhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Keys,
+    Combos,
+    Bigrams,
+    TypingSpeed,
+    Workspaces,
+    Monitors,
+    Remote,
+    Projects,
+    Apps,
+    Latency,
+    Breaks,
+    Composed,
+    Chords,
+    Dynamics,
+    Discord,
+    Metrics,
+    #[cfg(feature = "mqtt")]
+    Mqtt,
+    #[cfg(feature = "postgres")]
+    Postgres,
+    Webhooks,
+    Hooks,
+    Processors,
+    Emit,
+}
+
+impl Scope {
+    /// Stable name stored with each consent
+    pub fn id(self) -> &'static str {
+        match self {
+            Scope::Keys => "keys",
+            Scope::Combos => "combos",
+            Scope::Bigrams => "bigrams",
+            Scope::TypingSpeed => "typing_speed",
+            Scope::Workspaces => "workspaces",
+            Scope::Monitors => "monitors",
+            Scope::Remote => "remote",
+            Scope::Projects => "projects",
+            Scope::Apps => "apps",
+            Scope::Latency => "latency",
+            Scope::Breaks => "breaks",
+            Scope::Composed => "composed",
+            Scope::Chords => "chords",
+            Scope::Dynamics => "dynamics",
+            Scope::Discord => "discord",
+            Scope::Metrics => "metrics",
+            #[cfg(feature = "mqtt")]
+            Scope::Mqtt => "mqtt",
+            #[cfg(feature = "postgres")]
+            Scope::Postgres => "postgres",
+            Scope::Webhooks => "webhooks",
+            Scope::Hooks => "hooks",
+            Scope::Processors => "processors",
+            Scope::Emit => "emit",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Scope::Keys => {
+                "Every key you press, with its timestamp and its order in the session. \
+                 Anyone with the database can read back what you typed."
+            }
+            Scope::Combos => "Shortcuts typed with modifier keys, e.g. ControlLeft+KeyC",
+            Scope::Bigrams => "How often each pair of keys is typed in a row",
+            Scope::TypingSpeed => {
+                "Your typing speed, sampled every 10 seconds, and your bursts of fast typing"
+            }
+            Scope::Workspaces => "The name of the focused workspace for each key",
+            Scope::Monitors => "The monitor showing the focused workspace for each key",
+            Scope::Remote => {
+                "Whether each key went to an SSH session, judged from the window title"
+            }
+            Scope::Projects => "The project each key was typed in, read from the window title",
+            Scope::Apps => "The application each key and shortcut was typed in, e.g. firefox",
+            Scope::Latency => "How long kitmap took to handle each key, summarized per session",
+            Scope::Breaks => "When breaks were suggested and whether you took them",
+            Scope::Composed => {
+                "The character each key press produced, including accented letters and \
+                 input method text. This is the text you typed, in your own script."
+            }
+            Scope::Chords => {
+                "Keys pressed down together, with when and how closely. Stenography \
+                 strokes spell out the words you typed."
+            }
+            Scope::Dynamics => {
+                "When each key is released, to the millisecond. How long you hold keys \
+                 and move between them can identify you, like a signature."
+            }
+            Scope::Discord => "Keys today, typing speed and session length, shown on Discord",
+            Scope::Metrics => "Per-minute key, combo and typing speed counts, sent to [metrics]",
+            #[cfg(feature = "mqtt")]
+            Scope::Mqtt => "Activity summaries, published to the MQTT broker in [mqtt]",
+            #[cfg(feature = "postgres")]
+            Scope::Postgres => {
+                "A copy of every key, combo and session, sent to the Postgres database in [postgres]"
+            }
+            Scope::Webhooks => "Milestones and session summaries, posted to the [[webhooks]] URLs",
+            Scope::Hooks => "Milestones and activity totals, passed to the [[hooks]] commands",
+            Scope::Emit => "Every key event, written as JSON to stdout or the --tee file",
+            Scope::Processors => {
+                "Every key event, handed to the [[processors]] in your config, which may \
+                 keep or forward it"
+            }
+        }
+    }
+
+    /// Whether the data leaves this computer
+    pub fn is_shared(self) -> bool {
+        match self {
+            Scope::Discord
+            | Scope::Metrics
+            | Scope::Webhooks
+            | Scope::Hooks
+            | Scope::Processors
+            | Scope::Emit => true,
+            #[cfg(feature = "mqtt")]
+            Scope::Mqtt => true,
+            #[cfg(feature = "postgres")]
+            Scope::Postgres => true,
+            _ => false,
+        }
+    }
+}
+
+/// Everything a listen run will record under its flags and config
+#[derive(Debug, Clone)]
+pub struct PrivacySummary {
+    scopes: Vec<Scope>,
+}
+
+impl PrivacySummary {
+    /// What every run records, plus the context dimensions that are enabled
+    pub fn new(context: &ContextOptions) -> Self {
+        let mut scopes = vec![
+            Scope::Keys,
+            Scope::Combos,
+            Scope::Bigrams,
+            Scope::TypingSpeed,
+        ];
+        for (enabled, scope) in [
+            (context.workspaces, Scope::Workspaces),
+            (context.monitors, Scope::Monitors),
+            (context.remote, Scope::Remote),
+            (context.projects, Scope::Projects),
+            (context.apps, Scope::Apps),
+        ] {
+            if enabled {
+                scopes.push(scope);
+            }
+        }
+        Self { scopes }
+    }
+
+    pub fn add(&mut self, scope: Scope) {
+        if !self.scopes.contains(&scope) {
+            self.scopes.push(scope);
+        }
+    }
+
+    /// The summary as plain text, listing only `scopes`
+    fn render(scopes: &[Scope]) -> String {
+        let mut text = String::new();
+        let (shared, local): (Vec<Scope>, Vec<Scope>) =
+            scopes.iter().partition(|scope| scope.is_shared());
+
+        if !local.is_empty() {
+            text.push_str("Stored in the database on this computer:\n");
+            for scope in local {
+                text.push_str(&format!("  • {}\n", scope.description()));
+            }
+        }
+        if !shared.is_empty() {
+            text.push_str("Sent to other services:\n");
+            for scope in shared {
+                text.push_str(&format!("  • {}\n", scope.description()));
+            }
+        }
+        text
+    }
+}
+
+/// Show what will be recorded and ask for consent to whatever wasn't agreed to in
+/// an earlier consent. Returns whether recording may start.
+///
+/// Every answer is stored in the database. With `yes` the summary is still printed
+/// and recorded, but no question is asked.
+pub fn confirm(db: &DbConnection, summary: &PrivacySummary, yes: bool) -> Result<bool> {
+    let previous = Consent::accepted(db)?;
+    let new_scopes: Vec<Scope> = summary
+        .scopes
+        .iter()
+        .copied()
+        .filter(|scope| {
+            !previous
+                .iter()
+                .any(|consent| consent.scopes.iter().any(|id| id == scope.id()))
+        })
+        .collect();
+
+    if new_scopes.is_empty() {
+        return Ok(true);
+    }
+
+    let text = PrivacySummary::render(&new_scopes);
+    status!("{}", "🔒 Privacy".cyan().bold());
+    if previous.is_empty() {
+        status!("With the current settings, kitmap will record:");
+    } else {
+        status!("These settings record more than you have agreed to before:");
+    }
+    status_print!("{}", text);
+    let never = if summary.scopes.contains(&Scope::Apps) {
+        "Never recorded: window titles, clipboard contents or mouse activity."
+    } else {
+        "Never recorded: window titles, application names, clipboard contents or mouse activity."
+    };
+    status!("{}", never.dark_grey());
+    status!();
+
+    let shown: Vec<&str> = new_scopes.iter().map(|scope| scope.id()).collect();
+    let ids = summary
+        .scopes
+        .iter()
+        .map(|scope| scope.id().to_string())
+        .collect();
+    if yes {
+        Consent::new(ids, text, true, "--yes").save(db)?;
+        audit::record(
+            db,
+            AuditAction::Consent,
+            &format!("Agreed to record {} with --yes", shown.join(", ")),
+        )?;
+        status!("{} Consent given with --yes", "✓".green());
+        status!();
+        return Ok(true);
+    }
+
+    if !io::stdin().is_terminal() {
+        bail!("Can't ask for consent without a terminal; run `kitmap listen --yes` to agree to the summary above");
+    }
+
+    status_print!("Start recording? [y/N]: ");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim().to_lowercase();
+    let accepted = input == "y" || input == "yes";
+
+    Consent::new(ids, text, accepted, "prompt").save(db)?;
+    let answer = if accepted { "Agreed to" } else { "Declined to" };
+    audit::record(
+        db,
+        AuditAction::Consent,
+        &format!("{} record {}", answer, shown.join(", ")),
+    )?;
+    status!();
+    Ok(accepted)
+}
@@ -0,0 +1,63 @@
+use crate::commands::status::format_minutes;
+use crate::server::control::{self, ListenerStatus, Request};
+use anyhow::{bail, Result};
+use chrono::Local;
+use crossterm::style::Stylize;
+
+/// Stop the running listener recording until `kitmap resume`
+pub async fn pause() -> Result<()> {
+    let status = send(Request::Pause)?;
+    status!("{} Recording paused", "⏸".yellow());
+    print_session(&status);
+    Ok(())
+}
+
+/// Undo `kitmap pause`
+pub async fn resume() -> Result<()> {
+    let status = send(Request::Resume)?;
+    if status.recording {
+        status!("{} Recording resumed", "▶".green());
+    } else {
+        status!("{} Still paused: {}", "⏸".yellow(), status.pause_causes());
+    }
+    print_session(&status);
+    Ok(())
+}
+
+/// Show the listener's current session, or end it and start another
+pub async fn session(new: bool) -> Result<()> {
+    let status = send(if new {
+        Request::NewSession
+    } else {
+        Request::Status
+    })?;
+    if new {
+        status!("{} Started a new session", "✓".green());
+    }
+    print_session(&status);
+    Ok(())
+}
+
+/// Send a request to the running listener, which must be there to carry it out
+fn send(request: Request) -> Result<ListenerStatus> {
+    match control::send(request)? {
+        Some(status) => Ok(status),
+        None => bail!("No listener is running. Start one with `kitmap listen`."),
+    }
+}
+
+fn print_session(status: &ListenerStatus) {
+    match &status.session {
+        Some(session) => status!(
+            "Session {}: started {}, {} ago, {} keys",
+            session.id.map_or("-".to_string(), |id| id.to_string()),
+            session.started.format("%Y-%m-%d %H:%M"),
+            format_minutes((Local::now() - session.started).num_minutes()),
+            session.keys.to_string().cyan()
+        ),
+        None => status!(
+            "{}",
+            format!("No session open while paused ({})", status.pause_causes()).dark_grey()
+        ),
+    }
+}
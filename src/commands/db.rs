@@ -1,8 +1,345 @@
-use crate::db::get_db_path;
-use anyhow::Result;
+use crate::config::load_config;
+use crate::db::analyze::{file_size, format_bytes, StorageAnalysis};
+use crate::db::audit::{self, AuditAction};
+use crate::db::schema::{prune_before, PRUNED_SESSIONS};
+use crate::db::{get_db_path, init_db};
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use crossterm::style::Stylize;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Commit interval suggested when key events are committed more often than this
+const SUGGESTED_COMMIT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Free space worth reclaiming, as a share of the database file
+const VACUUM_THRESHOLD: f64 = 0.1;
+
+/// WAL size at which checkpoints are probably being held back by a long-running reader
+const LARGE_WAL_BYTES: u64 = 64 * 1024 * 1024;
 
 pub async fn run() -> Result<()> {
     let db_path = get_db_path()?;
-    println!("Database path: {}", db_path.display());
+    status!("Database path: {}", db_path.display());
+    Ok(())
+}
+
+/// Report where the database's space goes and how much each keystroke writes to disk
+pub async fn analyze() -> Result<()> {
+    let path = get_db_path()?;
+    let db = init_db()?;
+    let config = load_config()?;
+    let analysis = StorageAnalysis::run(&db, &path)?;
+    let interval = Duration::from_millis(config.storage.commit_interval_ms);
+
+    status!("{}", "💾 KitMap Storage Analysis".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+    status!("Database: {}", path.display());
+    status!(
+        "   File: {}  WAL: {}  Free pages: {}",
+        format_bytes(analysis.file_bytes as f64).cyan(),
+        format_bytes(analysis.wal_bytes as f64),
+        format_bytes(analysis.free_bytes as f64)
+    );
+    status!();
+
+    status!("{}", "Space by table".bold());
+    for object in &analysis.objects {
+        let name = if object.is_index {
+            format!("  {} (index)", object.name)
+        } else {
+            object.name.clone()
+        };
+        status!("   {:<44} {:>10}", name, format_bytes(object.bytes as f64));
+    }
+    let (raw, aggregate) = analysis.raw_and_aggregate_bytes();
+    let total = (raw + aggregate).max(1) as f64;
+    status!(
+        "   Raw events: {} ({:.0}%)  Aggregates and sessions: {} ({:.0}%)",
+        format_bytes(raw as f64).yellow(),
+        raw as f64 / total * 100.0,
+        format_bytes(aggregate as f64),
+        aggregate as f64 / total * 100.0
+    );
+    status!();
+
+    status!("{}", "Write cost per keystroke".bold());
+    if analysis.key_events == 0 {
+        status!(
+            "{}",
+            "   No key events recorded yet. Run `kitmap listen` first.".dark_grey()
+        );
+        status!();
+        return Ok(());
+    }
+
+    let written = analysis.written_per_key(interval);
+    status!(
+        "   Stored: {} per key (row and {} indexes)",
+        format_bytes(analysis.stored_bytes_per_key).cyan(),
+        analysis.key_event_indexes
+    );
+    status!(
+        "   Written: ~{} per key with {}",
+        format_bytes(written).yellow(),
+        describe_interval(interval)
+    );
+    if let Some(amplification) = analysis.amplification(interval) {
+        status!("   Write amplification: ~{:.0}×", amplification);
+    }
+    status!(
+        "   At {:.0} keys per typing day: ~{} per day, ~{} per year",
+        analysis.keys_per_day,
+        format_bytes(written * analysis.keys_per_day),
+        format_bytes(written * analysis.keys_per_day * 365.0)
+    );
+    status!();
+
+    status!("{}", "Suggestions".bold());
+    let mut suggestions = Vec::new();
+
+    if interval < SUGGESTED_COMMIT_INTERVAL {
+        let batched = analysis.written_per_key(SUGGESTED_COMMIT_INTERVAL);
+        suggestions.push(format!(
+            "Commit key events in batches with `commit_interval_ms = {}` under [storage]: \
+             ~{} per key, ~{} per day. Up to {} s of keys can be lost if kitmap is killed.",
+            SUGGESTED_COMMIT_INTERVAL.as_millis(),
+            format_bytes(batched),
+            format_bytes(batched * analysis.keys_per_day),
+            SUGGESTED_COMMIT_INTERVAL.as_secs()
+        ));
+    }
+
+    if analysis.free_bytes as f64 > analysis.file_bytes as f64 * VACUUM_THRESHOLD {
+        suggestions.push(format!(
+            "{} of the file is free pages left by deleted data. Reclaim it with \
+             `kitmap db maintain --vacuum` while kitmap isn't running.",
+            format_bytes(analysis.free_bytes as f64)
+        ));
+    }
+
+    if analysis.wal_bytes > LARGE_WAL_BYTES {
+        suggestions.push(
+            "The WAL is unusually large, so checkpoints are being held back. Restart any \
+             long-running dashboard (`kitmap preview --web`), then fold it back in with \
+             `kitmap db maintain`."
+                .to_string(),
+        );
+    }
+
+    if suggestions.is_empty() {
+        status!(
+            "{}",
+            "   Current settings are already write-efficient.".green()
+        );
+    }
+    for suggestion in suggestions {
+        status!("   • {}", suggestion);
+    }
+    status!();
+
+    Ok(())
+}
+
+/// Refresh query planner statistics, optionally rebuild the file, and fold the WAL back
+/// into the database file, reporting the sizes before and after.
+///
+/// The checkpoint runs last so it also takes in the pages ANALYZE and VACUUM wrote to
+/// the WAL. It can only complete while no other process is reading the database.
+pub async fn maintain(vacuum: bool) -> Result<()> {
+    status!("{}", "🧹 KitMap - Database Maintenance".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    let path = get_db_path()?;
+    let db = init_db()?;
+    let before = DatabaseSize::of(&path);
+
+    status!("{} Updating query planner statistics...", "→".dark_grey());
+    db.call(|conn| Ok(conn.execute_batch("ANALYZE")?)).await?;
+
+    if vacuum {
+        status!("{} Rebuilding the database file...", "→".dark_grey());
+        db.call(|conn| Ok(conn.execute_batch("VACUUM")?)).await?;
+    }
+
+    status!("{} Checkpointing the write-ahead log...", "→".dark_grey());
+    let busy = db
+        .call(|conn| {
+            Ok(
+                conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+                    row.get::<_, i64>(0)
+                })? != 0,
+            )
+        })
+        .await?;
+
+    let after = DatabaseSize::of(&path);
+    status!();
+    status!("   {:<8} {:>12} {:>12}", "", "Before", "After");
+    for (label, before, after) in [
+        ("File", before.file, after.file),
+        ("WAL", before.wal, after.wal),
+        ("Total", before.total(), after.total()),
+    ] {
+        status!(
+            "   {:<8} {:>12} {:>12}",
+            label,
+            format_bytes(before as f64),
+            format_bytes(after as f64)
+        );
+    }
+    status!();
+
+    if busy {
+        status!(
+            "{}",
+            "⚠️  The WAL could not be fully checkpointed because another process is using the \
+             database. Stop `kitmap listen` and any dashboard, then run this again."
+                .yellow()
+        );
+    } else {
+        status!("{} Database maintenance complete", "✓".green());
+    }
+    status!();
+
     Ok(())
 }
+
+/// On-disk size of the database file and its write-ahead log
+struct DatabaseSize {
+    file: u64,
+    wal: u64,
+}
+
+impl DatabaseSize {
+    fn of(path: &Path) -> Self {
+        Self {
+            file: file_size(path),
+            wal: file_size(&path.with_extension("db-wal")),
+        }
+    }
+
+    fn total(&self) -> u64 {
+        self.file + self.wal
+    }
+}
+
+/// Tables pruned a month at a time, with how their rows are described
+const PRUNED_TABLES: &[(&str, &str)] = &[
+    ("key_events", "key events"),
+    ("key_combos", "combos"),
+    ("key_chords", "chords"),
+    ("typing_samples", "typing samples"),
+    ("typing_bursts", "typing bursts"),
+    ("imported_activity", "imported press counts"),
+];
+
+/// Delete everything recorded before `before` (YYYY-MM).
+///
+/// Rows are partitioned by the UTC month they were recorded in, so whole months are
+/// deleted through that index, and key counts and daily totals are updated once rather
+/// than for every row. Sessions go with the last of their key events. The freed pages are
+/// reused by new events rather than returned to the file system, so no VACUUM is needed.
+pub async fn prune(before: &str, force: bool) -> Result<()> {
+    let first = NaiveDate::parse_from_str(&format!("{}-01", before), "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid month \"{}\", expected YYYY-MM", before))?;
+    let month = first.format("%Y-%m").to_string();
+    let before = month.as_str();
+
+    status!("{}", "✂️  KitMap - Prune Data".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    let db = init_db()?;
+    let counts = db.read(|conn| {
+        let mut counts = PRUNED_TABLES
+            .iter()
+            .map(|(table, label)| {
+                let count: i64 = conn.query_row(
+                    &format!("SELECT COUNT(*) FROM {} WHERE month < ?1", table),
+                    [before],
+                    |row| row.get(0),
+                )?;
+                Ok((count, *label))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let sessions: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM sessions WHERE {}", PRUNED_SESSIONS),
+            [before],
+            |row| row.get(0),
+        )?;
+        counts.push((sessions, "sessions"));
+        Ok(counts)
+    })?;
+
+    if counts.iter().all(|(count, _)| *count == 0) {
+        status!("Nothing was recorded before {}.", before);
+        status!();
+        return Ok(());
+    }
+    let summary = counts
+        .iter()
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if !force {
+        status!(
+            "{}",
+            format!(
+                "⚠️  Warning: This will delete {} recorded before {}!",
+                summary, before
+            )
+            .yellow()
+        );
+        status!();
+        status_print!("Are you sure you want to continue? [y/N]: ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        let input = input.trim().to_lowercase();
+        if input != "y" && input != "yes" {
+            status!();
+            status!("{}", "Operation cancelled.".dark_grey());
+            return Ok(());
+        }
+        status!();
+    }
+
+    status!("{} Pruning database...", "→".dark_grey());
+    let partition = month.clone();
+    db.call(move |conn| prune_before(conn, &partition)).await?;
+    audit::record(
+        &db,
+        AuditAction::Prune,
+        &format!(
+            "Deleted {} recorded before {}{}",
+            summary,
+            before,
+            if force { " with --force" } else { "" }
+        ),
+    )?;
+
+    status!(
+        "{} Deleted {} recorded before {}",
+        "✓".green(),
+        summary,
+        before
+    );
+    status!();
+
+    Ok(())
+}
+
+fn describe_interval(interval: Duration) -> String {
+    if interval.is_zero() {
+        "every key committed on its own".to_string()
+    } else {
+        format!("commits every {} ms", interval.as_millis())
+    }
+}
@@ -0,0 +1,363 @@
+use crate::db::analyze::{file_size, format_bytes};
+use crate::db::{get_db_path, init_db, DbConnection};
+use anyhow::Result;
+use chrono::{Duration, Local, TimeZone};
+use crossterm::style::Stylize;
+use std::path::Path;
+
+/// Free disk space below which recording will soon fail
+const LOW_DISK_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Free disk space below which the database has little room left to grow
+const TIGHT_DISK_BYTES: u64 = 500 * 1024 * 1024;
+
+/// Days back that combo storms are flagged for
+const RECENT_STORM_DAYS: i64 = 7;
+
+/// How a check turned out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Health {
+    Ok,
+    Warning,
+    Problem,
+}
+
+/// The outcome of one diagnostic, with what to do about it when it isn't ok
+struct Check {
+    name: &'static str,
+    health: Health,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            health: Health::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warning(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            health: Health::Warning,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn problem(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            health: Health::Problem,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Check that keys can be captured and stored, and print how to fix what can't
+pub async fn run() -> Result<()> {
+    let mut checks = vec![input_capture()];
+
+    let path = get_db_path()?;
+    match init_db() {
+        Ok(db) => {
+            checks.push(database(&db, &path));
+            checks.push(last_key(&db));
+            checks.push(open_sessions(&db));
+            checks.push(combo_storms(&db));
+        }
+        Err(e) => checks.push(Check::problem(
+            "Database",
+            format!("{} could not be opened: {}", path.display(), e),
+            "Check that the file and its directory are writable by your user",
+        )),
+    }
+    checks.push(disk_space(&path));
+
+    status!("{}", "🩺 KitMap Doctor".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    for check in &checks {
+        let mark = match check.health {
+            Health::Ok => "✓".green(),
+            Health::Warning => "!".yellow(),
+            Health::Problem => "✗".red(),
+        };
+        status!("{} {}: {}", mark, check.name.bold(), check.detail);
+        if let Some(fix) = &check.fix {
+            status!("   {} {}", "→".dark_grey(), fix);
+        }
+    }
+    status!();
+
+    let problems = checks
+        .iter()
+        .filter(|c| c.health == Health::Problem)
+        .count();
+    let warnings = checks
+        .iter()
+        .filter(|c| c.health == Health::Warning)
+        .count();
+    if problems == 0 && warnings == 0 {
+        status!("{} Everything looks fine", "✓".green());
+    } else {
+        status!(
+            "{} problem(s), {} warning(s)",
+            problems.to_string().red(),
+            warnings.to_string().yellow()
+        );
+    }
+
+    Ok(())
+}
+
+/// Whether the X server that keys are recorded through is reachable
+#[cfg(target_os = "linux")]
+fn input_capture() -> Check {
+    const NAME: &str = "Input capture";
+    let display = std::env::var("DISPLAY").ok().filter(|d| !d.is_empty());
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland");
+
+    match (display, wayland) {
+        (None, _) => Check::problem(
+            NAME,
+            "no X display; kitmap records keys through X11",
+            "Run kitmap from your graphical session, or set DISPLAY (e.g. DISPLAY=:0)",
+        ),
+        (Some(display), true) => Check::warning(
+            NAME,
+            format!(
+                "Wayland session with XWayland on {}; only keys typed into X11 apps are seen",
+                display
+            ),
+            "Log into an X11 session to record keys typed into every window",
+        ),
+        (Some(display), false) => Check::ok(NAME, format!("X11 display {}", display)),
+    }
+}
+
+/// Whether this process may observe keys, which macOS grants per app under Accessibility
+#[cfg(target_os = "macos")]
+fn input_capture() -> Check {
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+    }
+
+    const NAME: &str = "Input capture";
+    // SAFETY: takes no arguments and only reads the permission of this process
+    if unsafe { AXIsProcessTrusted() } {
+        Check::ok(NAME, "Accessibility access granted")
+    } else {
+        Check::problem(
+            NAME,
+            "Accessibility access not granted, so no keys are seen",
+            "Allow your terminal under System Settings → Privacy & Security → Accessibility \
+             and Input Monitoring, then restart it",
+        )
+    }
+}
+
+/// Low-level keyboard hooks need no permission on Windows
+#[cfg(target_os = "windows")]
+fn input_capture() -> Check {
+    Check::ok(
+        "Input capture",
+        "keyboard hooks available; keys typed into windows running as administrator are \
+         only seen when kitmap runs as administrator too",
+    )
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn input_capture() -> Check {
+    Check::warning(
+        "Input capture",
+        "not supported on this platform",
+        "Run kitmap on Linux (X11), macOS or Windows",
+    )
+}
+
+/// Whether SQLite finds the database intact
+fn database(db: &DbConnection, path: &Path) -> Check {
+    const NAME: &str = "Database";
+    let result = db
+        .read(|conn| Ok(conn.query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0))?));
+
+    match result {
+        Ok(status) if status == "ok" => Check::ok(
+            NAME,
+            format!(
+                "{} ({})",
+                path.display(),
+                format_bytes(file_size(path) as f64)
+            ),
+        ),
+        Ok(status) => Check::problem(
+            NAME,
+            format!("{} is damaged: {}", path.display(), status),
+            "Stop kitmap, back up the file, and rebuild it with `sqlite3 kitmap.db .recover`",
+        ),
+        Err(e) => Check::problem(
+            NAME,
+            format!("{} could not be checked: {}", path.display(), e),
+            "Stop other programs using the database and try again",
+        ),
+    }
+}
+
+/// When the newest key press was recorded
+fn last_key(db: &DbConnection) -> Check {
+    const NAME: &str = "Last key press";
+    let result = db.read(|conn| {
+        Ok(
+            conn.query_row("SELECT MAX(timestamp_ms) FROM key_events", [], |row| {
+                row.get::<_, Option<i64>>(0)
+            })?,
+        )
+    });
+
+    match result {
+        Ok(Some(ms)) => match Local.timestamp_millis_opt(ms).single() {
+            Some(at) => Check::ok(NAME, at.format("%Y-%m-%d %H:%M:%S").to_string()),
+            None => Check::ok(NAME, format!("{} ms since the epoch", ms)),
+        },
+        Ok(None) => Check::warning(
+            NAME,
+            "no keys recorded yet",
+            "Run `kitmap listen` and type something",
+        ),
+        Err(e) => Check::problem(
+            NAME,
+            format!("could not be read: {}", e),
+            "Run `kitmap doctor` again once the database check passes",
+        ),
+    }
+}
+
+/// Sessions without an end; more than one means a listener stopped without closing its own
+fn open_sessions(db: &DbConnection) -> Check {
+    const NAME: &str = "Open sessions";
+    let result = db.read(|conn| {
+        Ok(conn.query_row(
+            "SELECT COUNT(*) FROM sessions WHERE end_time IS NULL",
+            [],
+            |row| row.get::<_, i64>(0),
+        )?)
+    });
+
+    match result {
+        Ok(0) => Check::ok(NAME, "none"),
+        Ok(1) => Check::ok(NAME, "1, normal while `kitmap listen` runs"),
+        Ok(open) => Check::warning(
+            NAME,
+            format!("{} left open by listeners that stopped unexpectedly", open),
+            "Start `kitmap listen`; it closes them at their last key press",
+        ),
+        Err(e) => Check::problem(
+            NAME,
+            format!("could not be read: {}", e),
+            "Run `kitmap doctor` again once the database check passes",
+        ),
+    }
+}
+
+/// Combos that fired at automated rates lately, which are kept out of the combo counts
+fn combo_storms(db: &DbConnection) -> Check {
+    const NAME: &str = "Combo storms";
+    let since = (Local::now() - Duration::days(RECENT_STORM_DAYS)).timestamp_millis();
+    let result = db.read(|conn| {
+        Ok(conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(count), 0),
+                    (SELECT combo FROM combo_storms WHERE start_ms >= ?1
+                     GROUP BY combo ORDER BY SUM(count) DESC LIMIT 1)
+             FROM combo_storms WHERE start_ms >= ?1",
+            [since],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            },
+        )?)
+    });
+
+    match result {
+        Ok((0, _, _)) => Check::ok(
+            NAME,
+            format!("none in the last {} days", RECENT_STORM_DAYS),
+        ),
+        Ok((storms, presses, combo)) => Check::warning(
+            NAME,
+            format!(
+                "{} in the last {} days, {} presses left out of the combo counts, mostly {}",
+                storms,
+                RECENT_STORM_DAYS,
+                presses,
+                combo.unwrap_or_default()
+            ),
+            "Look for a stuck key, macro or script sending the combo; `kitmap preview --section top` lists them",
+        ),
+        Err(e) => Check::problem(
+            NAME,
+            format!("could not be read: {}", e),
+            "Run `kitmap doctor` again once the database check passes",
+        ),
+    }
+}
+
+/// Free space on the disk holding the database
+fn disk_space(path: &Path) -> Check {
+    const NAME: &str = "Disk space";
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    match free_bytes(dir) {
+        Some(free) if free < LOW_DISK_BYTES => Check::problem(
+            NAME,
+            format!("only {} free", format_bytes(free as f64)),
+            "Free up space, or delete old key events with `kitmap db prune --before YYYY-MM`",
+        ),
+        Some(free) if free < TIGHT_DISK_BYTES => Check::warning(
+            NAME,
+            format!("{} free", format_bytes(free as f64)),
+            "Free up space before the database runs out of room to grow",
+        ),
+        Some(free) => Check::ok(NAME, format!("{} free", format_bytes(free as f64))),
+        None => Check::warning(
+            NAME,
+            "could not be determined",
+            format!("Check the free space of {} by hand", dir.display()),
+        ),
+    }
+}
+
+/// Bytes available to this user on the filesystem of `dir`, as reported by `df`
+#[cfg(unix)]
+fn free_bytes(dir: &Path) -> Option<u64> {
+    let output = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(dir)
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let kib: u64 = text
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(unix))]
+fn free_bytes(_dir: &Path) -> Option<u64> {
+    None
+}
@@ -0,0 +1,55 @@
+use crate::db::export::{write_export, DynamicsConsent, ExportData, ExportFormat, ExportOptions};
+use crate::db::init_db;
+use crate::stats::range::TimeRange;
+use crate::ui::plain;
+use anyhow::Result;
+use crossterm::style::Stylize;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+
+pub async fn run(
+    format: ExportFormat,
+    data: ExportData,
+    range: Option<String>,
+    gap: i64,
+    dynamics_consent: DynamicsConsent,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let options = ExportOptions {
+        data,
+        format,
+        range: match range {
+            Some(span) => TimeRange::parse_span(&span)?,
+            None => TimeRange::default(),
+        },
+        block_gap_minutes: gap,
+        dynamics_consent,
+    };
+    options.validate()?;
+
+    let db = init_db()?;
+    db.read(|conn| {
+        match output {
+            Some(path) => {
+                let mut out = BufWriter::new(File::create(&path)?);
+                write_export(conn, &options, &mut out)?;
+                eprintln!(
+                    "{}",
+                    plain::finish(&format!(
+                        "{} Exported {} to {}",
+                        "✓".green(),
+                        data.name(),
+                        path.display()
+                    ))
+                );
+            }
+            None => {
+                let mut out = BufWriter::new(io::stdout().lock());
+                write_export(conn, &options, &mut out)?;
+            }
+        }
+
+        Ok(())
+    })
+}
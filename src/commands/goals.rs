@@ -0,0 +1,95 @@
+use crate::db::init_db;
+use crate::stats::goals::{self, Goal, GoalMetric, GoalPeriod};
+use anyhow::{bail, Result};
+use crossterm::style::Stylize;
+
+/// Width of the progress bars, in characters
+const BAR_WIDTH: usize = 30;
+
+/// Show progress towards every goal in the current day and week
+pub async fn run() -> Result<()> {
+    status!("{}", "🎯 KitMap - Goals".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    let db = init_db()?;
+    let progress = db.read(goals::progress)?;
+
+    if progress.is_empty() {
+        status!(
+            "{}",
+            "No goals set. Add one with `kitmap goals set keys 10000`.".dark_grey()
+        );
+        status!();
+        return Ok(());
+    }
+
+    for goal in progress {
+        let filled = (goal.progress * BAR_WIDTH as f64) as usize;
+        let bar = format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled));
+        let label = format!(
+            "{:<24}",
+            format!("{} {}", goal.period.label(), goal.metric.label())
+        );
+        let line = format!(
+            "{} {} {:>5.0}%  {} / {}{}",
+            label,
+            if goal.met { bar.green() } else { bar.cyan() },
+            goal.progress * 100.0,
+            goal.metric.format(goal.current),
+            goal.metric.format(goal.target),
+            if goal.met {
+                " ✓".green().to_string()
+            } else {
+                String::new()
+            }
+        );
+        status!("{}", line);
+    }
+    status!();
+
+    Ok(())
+}
+
+/// Set or replace the target for a metric
+pub async fn set(metric: GoalMetric, target: f64, period: GoalPeriod) -> Result<()> {
+    if !(target > 0.0 && target.is_finite()) {
+        bail!("Goal target must be a positive number");
+    }
+
+    let db = init_db()?;
+    Goal {
+        metric,
+        period,
+        target,
+    }
+    .save(&db)?;
+
+    status!(
+        "{} {} {} goal set to {}",
+        "✓".green(),
+        period.label(),
+        metric.label(),
+        target
+    );
+    Ok(())
+}
+
+/// Remove the goal for a metric and period
+pub async fn remove(metric: GoalMetric, period: GoalPeriod) -> Result<()> {
+    let db = init_db()?;
+    if Goal::remove(&db, metric, period)? {
+        status!(
+            "{} Removed {} {} goal",
+            "✓".green(),
+            period.as_str(),
+            metric.label()
+        );
+    } else {
+        status!(
+            "{}",
+            format!("No {} {} goal is set", period.as_str(), metric.label()).dark_grey()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use crate::db::activitywatch::{import_buckets, AwExport};
+use crate::db::audit::{self, AuditAction};
+use crate::db::init_db;
+use anyhow::{Context, Result};
+use crossterm::style::Stylize;
+use std::path::PathBuf;
+
+/// Formats history can be imported from
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// ActivityWatch export containing keyboard (input watcher) buckets
+    Activitywatch,
+}
+
+pub async fn run(format: ImportFormat, file: PathBuf) -> Result<()> {
+    status!("{}", "📥 KitMap - Import Data".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    let contents = std::fs::read_to_string(&file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+
+    match format {
+        ImportFormat::Activitywatch => {
+            let export: AwExport = serde_json::from_str(&contents)
+                .context("Not an ActivityWatch export (expected a \"buckets\" object)")?;
+
+            let db = init_db()?;
+            let summary = db.call(move |conn| import_buckets(conn, &export)).await?;
+            if summary.buckets > 0 {
+                audit::record(
+                    &db,
+                    AuditAction::Import,
+                    &format!(
+                        "Imported {} key presses from {} ActivityWatch bucket(s) in {}",
+                        summary.keys,
+                        summary.buckets,
+                        file.display()
+                    ),
+                )?;
+            }
+
+            if summary.buckets == 0 {
+                status!(
+                    "{}",
+                    "No keyboard input buckets (os.hid.input) found in the export.".yellow()
+                );
+            } else {
+                status!(
+                    "{} Imported {} key presses from {} bucket(s)",
+                    "✓".green(),
+                    summary.keys,
+                    summary.buckets
+                );
+                status!(
+                    "{}",
+                    "ActivityWatch only records press counts, so imported presses count in the activity calendar and Grafana's key series, not in per-key stats."
+                        .dark_grey()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,55 +1,150 @@
+use crate::commands::consent::{self, PrivacySummary, Scope};
+use crate::config::{
+    load_config, BreaksConfig, BurstsConfig, ChordsConfig, CombosConfig, HookConfig,
+    MetricsProtocol, StorageConfig, WebhookConfig,
+};
+use crate::context::{
+    glob_match,
+    lock::LockWatcher,
+    sleep::{SleepEvent, SleepWatcher},
+    wm::WindowManager,
+    ContextOptions, ContextTracker,
+};
 use crate::db::{
-    init_db,
-    models::{KeyCombo, KeyEvent, Session, TypingSample},
+    audit::AuditAction,
+    get_lock_path, get_pid_path, init_db,
+    models::{KeyBigram, KeyCombo, KeyEvent, KeyRelease, LatencyReport, Session, TypingSample},
+    store::{commit_deadline, EventStore, SqliteStore, COMMIT_CHECKS_PER_INTERVAL},
+};
+use crate::keys::{is_modifier, key_to_code, key_to_name, modifier_kind};
+use crate::processors::{jsonl::JsonLines, EventProcessor, Registry, Verdict};
+use crate::publish::{
+    hooks, notify,
+    webhooks::{self, Milestone},
+    ActivitySnapshot,
 };
-use anyhow::Result;
+use crate::server::control::{self, ListenerStatus, Recorder, SessionStatus};
+use crate::stats::bigrams::BigramSketch;
+use crate::stats::breaks::{BreakDecision, BreakTracker};
+use crate::stats::bursts::BurstDetector;
+use crate::stats::chords::ChordDetector;
+use crate::stats::latency::{LatencyRecorder, LatencySummary};
+use crate::stats::storms::{ComboStormDetector, StormDecision};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
 use crossterm::style::Stylize;
 use rdev::{listen, Event, EventType, Key};
-use std::collections::HashSet;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-/// Modifier keys that can be part of key combinations
-const MODIFIER_KEYS: &[Key] = &[
-    Key::ShiftLeft,
-    Key::ShiftRight,
-    Key::ControlLeft,
-    Key::ControlRight,
-    Key::Alt,
-    Key::AltGr,
-    Key::MetaLeft,
-    Key::MetaRight,
-];
+/// How often the screen lock state is checked
+const LOCK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of distinct bigrams held in memory between flushes
+const BIGRAM_SKETCH_CAPACITY: usize = 512;
+
+/// How often sketched bigram counts are written to the database
+const BIGRAM_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the session's bigram counts are recomputed exactly from key events
+const BIGRAM_RECONCILE_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// The combination recorded for pressing `key_name` while `held` modifiers are down, if
+/// the `[combos]` rules count it as one. Every held modifier is part of its name.
+fn combo_name(rules: &CombosConfig, held: &HashSet<String>, key_name: &str) -> Option<String> {
+    let qualifying = held
+        .iter()
+        .filter(|name| modifier_kind(name).is_some_and(|kind| rules.modifiers.contains(&kind)))
+        .count();
+    if qualifying == 0 || qualifying < rules.min_modifiers {
+        return None;
+    }
+
+    let mut keys: Vec<_> = held.iter().cloned().collect();
+    keys.sort();
+    keys.push(key_name.to_string());
+    let combo = keys.join("+");
 
-/// Check if a key is a modifier
-fn is_modifier(key: &Key) -> bool {
-    MODIFIER_KEYS.contains(key)
+    let ignored = rules
+        .ignore
+        .iter()
+        .any(|pattern| glob_match(pattern, &combo));
+    (!ignored).then_some(combo)
 }
 
-/// Get a human-readable name for a key
-fn key_to_name(key: &Key) -> String {
-    format!("{:?}", key)
+/// What the platform composed for a key press, if it is printable text. Dead keys
+/// compose nothing until the next key, and control keys compose control characters.
+fn composed_text(name: Option<&str>) -> Option<String> {
+    name.filter(|text| !text.is_empty() && !text.chars().any(char::is_control))
+        .map(str::to_string)
 }
 
-/// Get a simplified key code
-fn key_to_code(key: &Key) -> String {
-    format!("{:?}", key)
+/// Why recording is paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseReason {
+    ScreenLocked,
+    Asleep,
+    /// Paused through `kitmap pause` or the gRPC API
+    Requested,
 }
 
-struct ListenState {
+impl PauseReason {
+    fn id(self) -> &'static str {
+        match self {
+            PauseReason::ScreenLocked => "screen_locked",
+            PauseReason::Asleep => "asleep",
+            PauseReason::Requested => "requested",
+        }
+    }
+}
+
+pub(crate) struct ListenState {
     db: crate::db::DbConnection,
+    store: Box<dyn EventStore>,
     session: Session,
     pressed_modifiers: HashSet<String>,
     last_key_time: Option<Instant>,
     keys_in_interval: u32,
     interval_start: Instant,
     total_keys: u64,
+    next_seq: i64,
+    last_key: Option<String>,
+    bigrams: BigramSketch,
+    last_bigram_flush: Instant,
+    last_bigram_reconcile: Instant,
+    storms: ComboStormDetector,
+    bursts: BurstDetector,
+    combo_rules: CombosConfig,
+    chords: Option<ChordDetector>,
+    context: ContextTracker,
+    latency: Option<LatencyRecorder>,
+    breaks: Option<BreakTracker>,
+    /// Processors from `[[processors]]` that see each key event before it is recorded
+    processors: Vec<Box<dyn EventProcessor>>,
+    /// Whether key events keep the text the platform composed for them
+    record_composed: bool,
+    /// Sessions and sequence numbers of the recorded presses of keys still held down,
+    /// when release times are recorded. A key held across a session restart is released
+    /// on its press in the session that recorded it.
+    held_keys: Option<HashMap<String, (i64, i64)>>,
+    /// Why recording is paused; while any reason holds no keys are recorded and no
+    /// session is open
+    paused: Vec<PauseReason>,
 }
 
 impl ListenState {
-    fn new(db: crate::db::DbConnection) -> Self {
+    pub(crate) fn new(
+        db: crate::db::DbConnection,
+        context: ContextTracker,
+        storage: &StorageConfig,
+    ) -> Self {
+        let store = SqliteStore::new(db.clone())
+            .with_commit_interval(Duration::from_millis(storage.commit_interval_ms));
         Self {
+            store: Box::new(store),
             db,
             session: Session::new(),
             pressed_modifiers: HashSet::new(),
@@ -57,32 +152,171 @@ impl ListenState {
             keys_in_interval: 0,
             interval_start: Instant::now(),
             total_keys: 0,
+            next_seq: 0,
+            last_key: None,
+            bigrams: BigramSketch::new(BIGRAM_SKETCH_CAPACITY),
+            last_bigram_flush: Instant::now(),
+            last_bigram_reconcile: Instant::now(),
+            storms: ComboStormDetector::new(),
+            bursts: BurstDetector::new(&BurstsConfig::default()),
+            combo_rules: CombosConfig::default(),
+            chords: None,
+            context,
+            latency: None,
+            breaks: None,
+            processors: Vec::new(),
+            record_composed: false,
+            held_keys: None,
+            paused: Vec::new(),
         }
     }
 
-    fn record_key_event(&mut self, key: Key) {
-        let key_name = key_to_name(&key);
-        let key_code = key_to_code(&key);
-        let is_mod = is_modifier(&key);
+    /// Time every handled event so the session can report its callback overhead
+    pub(crate) fn with_latency_measurement(mut self) -> Self {
+        self.latency = Some(LatencyRecorder::new());
+        self
+    }
 
-        // Record the key event
-        let event = KeyEvent::new(key_code, key_name.clone(), is_mod);
-        if let Err(e) = event.save(&self.db) {
-            eprintln!("Failed to save key event: {}", e);
+    /// Record how long an event took to handle, counted from when the callback was entered
+    pub(crate) fn record_latency(&mut self, started: Instant) {
+        if let Some(latency) = self.latency.as_mut() {
+            latency.record(started.elapsed());
         }
+    }
 
-        // If this is a non-modifier key and there are modifiers held, record a combo
-        if !is_mod && !self.pressed_modifiers.is_empty() {
-            let mut mods: Vec<_> = self.pressed_modifiers.iter().cloned().collect();
-            mods.sort();
-            mods.push(key_name.clone());
-            let combo_str = mods.join("+");
+    pub(crate) fn latency_summary(&self) -> Option<LatencySummary> {
+        self.latency.as_ref().and_then(LatencyRecorder::summary)
+    }
+
+    /// Suggest breaks after long stretches of continuous typing
+    pub(crate) fn with_break_reminders(mut self, config: &BreaksConfig) -> Self {
+        self.breaks = Some(BreakTracker::new(config));
+        self
+    }
+
+    /// Decide which key presses are combinations by the `[combos]` rules
+    pub(crate) fn with_combo_rules(mut self, config: &CombosConfig) -> Self {
+        self.combo_rules = config.clone();
+        self
+    }
+
+    /// Record bursts of fast typing by the `[bursts]` thresholds
+    pub(crate) fn with_burst_rules(mut self, config: &BurstsConfig) -> Self {
+        self.bursts = BurstDetector::new(config);
+        self
+    }
+
+    /// Record non-modifier keys pressed together within the `[chords]` window as chords
+    pub(crate) fn with_chords(mut self, config: &ChordsConfig) -> Self {
+        self.chords = Some(ChordDetector::new(config));
+        self
+    }
+
+    /// Record the character each key press composed, where the platform reports it
+    pub(crate) fn with_composed_characters(mut self) -> Self {
+        self.record_composed = true;
+        self
+    }
+
+    /// Record when each key press is released, for keystroke dynamics
+    pub(crate) fn with_key_releases(mut self) -> Self {
+        self.held_keys = Some(HashMap::new());
+        self
+    }
+
+    /// Hand each key event to `processors`, in order, before recording it
+    pub(crate) fn with_processors(mut self, processors: Vec<Box<dyn EventProcessor>>) -> Self {
+        self.processors = processors;
+        self
+    }
+
+    /// Copy every recorded event to a second store as well as the local database
+    #[cfg(feature = "postgres")]
+    pub(crate) fn with_mirror(mut self, mirror: Box<dyn EventStore>) -> Self {
+        self.store = Box::new(crate::db::store::MirroredStore::new(self.store, mirror));
+        self
+    }
 
-            let combo = KeyCombo::new(combo_str);
-            if let Err(e) = combo.save(&self.db) {
-                eprintln!("Failed to save key combo: {}", e);
+    pub(crate) fn start_session(&mut self) -> Result<()> {
+        self.store.start_session(&mut self.session)?;
+        for processor in &mut self.processors {
+            if let Err(e) = processor.session_started(&self.session) {
+                eprintln!("Event processor failed on session start: {}", e);
             }
         }
+        Ok(())
+    }
+
+    /// Record a key press and the text the platform composed for it, returning whether
+    /// it was recorded (it isn't while paused, or when a processor drops it)
+    pub(crate) fn key_pressed(&mut self, key: Key, composed: Option<&str>) -> bool {
+        if self.is_paused() {
+            return false;
+        }
+        if is_modifier(&key) {
+            self.modifier_pressed(key);
+        }
+        self.record_key_event(key, composed)
+    }
+
+    pub(crate) fn key_released(&mut self, key: Key) {
+        self.record_key_release(&key);
+        if is_modifier(&key) {
+            self.modifier_released(key);
+        } else if !self.is_paused() {
+            self.chord_key_released(key);
+        }
+    }
+
+    fn record_key_event(&mut self, key: Key, composed: Option<&str>) -> bool {
+        let mut event = KeyEvent::new(
+            key_to_code(&key),
+            key_to_name(&key),
+            is_modifier(&key),
+            self.session.id,
+            self.next_seq,
+        );
+        let context = self.context.current();
+        event.workspace = context.workspace.clone();
+        event.monitor = context.monitor.clone();
+        event.remote = context.remote;
+        event.project = context.project.clone();
+        event.app = context.app.clone();
+        if self.record_composed {
+            event.composed = composed_text(composed);
+        }
+        if self.process(&mut event) == Verdict::Drop {
+            return false;
+        }
+        let key_name = event.key_name.clone();
+        let is_mod = event.is_modifier;
+
+        // Record the key event with its position in the session
+        self.next_seq += 1;
+        if let Err(e) = self.store.save_key_event(&event) {
+            eprintln!("Failed to save key event: {}", e);
+        }
+        if let (Some(held), Some(session_id)) = (self.held_keys.as_mut(), event.session_id) {
+            held.insert(key_name.clone(), (session_id, event.seq));
+        }
+
+        // If this is a non-modifier key pressed with qualifying modifiers held, record a combo
+        if !is_mod {
+            if let Some(combo_str) =
+                combo_name(&self.combo_rules, &self.pressed_modifiers, &key_name)
+            {
+                self.record_combo(combo_str);
+            }
+            if let Some(chords) = self.chords.as_mut() {
+                chords.press(&key_name, Local::now());
+            }
+        }
+
+        // Track bigrams
+        if let Some(prev) = self.last_key.replace(key_name.clone()) {
+            self.bigrams.record(&prev, &key_name);
+        }
+        self.maintain_bigrams();
 
         // Track typing speed
         self.keys_in_interval += 1;
@@ -94,7 +328,7 @@ impl ListenState {
         if elapsed >= Duration::from_secs(10) {
             let chars_per_minute = (self.keys_in_interval as f64 / elapsed.as_secs_f64()) * 60.0;
             let sample = TypingSample::new(chars_per_minute);
-            if let Err(e) = sample.save(&self.db) {
+            if let Err(e) = self.store.save_typing_sample(&sample) {
                 eprintln!("Failed to save typing sample: {}", e);
             }
 
@@ -102,7 +336,312 @@ impl ListenState {
             self.interval_start = Instant::now();
         }
 
+        // Save the burst of fast typing this key ended, if any
+        if let Some(burst) = self.bursts.observe(Local::now()) {
+            if let Err(e) = self.store.save_typing_burst(&burst) {
+                eprintln!("Failed to save typing burst: {}", e);
+            }
+        }
+
         self.last_key_time = Some(Instant::now());
+        self.check_break();
+        true
+    }
+
+    /// Run the event processors over a key event, stopping at the first that drops it. A
+    /// processor that fails leaves the event as it was.
+    fn process(&mut self, event: &mut KeyEvent) -> Verdict {
+        for processor in &mut self.processors {
+            match processor.process(event) {
+                Ok(Verdict::Keep) => {}
+                Ok(Verdict::Drop) => return Verdict::Drop,
+                Err(e) => eprintln!("Event processor failed: {}", e),
+            }
+        }
+        Verdict::Keep
+    }
+
+    /// Suggest a break once typing has gone on too long, and save reminders whose
+    /// outcome is known
+    fn check_break(&mut self) {
+        let Some(breaks) = self.breaks.as_mut() else {
+            return;
+        };
+
+        if let BreakDecision::Remind {
+            streak_minutes,
+            repeat,
+        } = breaks.observe(self.session.id, Local::now())
+        {
+            let title = if repeat {
+                "Still typing - time for a break"
+            } else {
+                "Time for a break"
+            };
+            notify::desktop(
+                title,
+                &format!(
+                    "You've been typing for {} minutes without a pause. Rest your hands and eyes for a few minutes.",
+                    streak_minutes
+                ),
+            );
+        }
+
+        for reminder in breaks.drain_finished() {
+            if let Err(e) = self.store.save_break_reminder(&reminder) {
+                eprintln!("Failed to save break reminder: {}", e);
+            }
+        }
+    }
+
+    /// Commit batched key events that have waited out the commit interval
+    fn commit_due(&mut self) -> Result<()> {
+        self.store.commit_due()
+    }
+
+    /// Commit everything still waiting in a batch, before the listener exits
+    fn flush(&mut self) -> Result<()> {
+        self.store.flush()
+    }
+
+    /// Save the outcome of every break reminder, counting a pending one as not taken
+    pub(crate) fn finish_breaks(&mut self) -> Result<()> {
+        if let Some(breaks) = self.breaks.as_mut() {
+            for reminder in breaks.finish() {
+                self.store.save_break_reminder(&reminder)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Break reminders shown and breaks taken since listening started
+    pub(crate) fn break_summary(&self) -> Option<(usize, usize)> {
+        self.breaks
+            .as_ref()
+            .map(|breaks| (breaks.reminded, breaks.taken))
+    }
+
+    /// Stamp the release time on the recorded press of a key
+    fn record_key_release(&mut self, key: &Key) {
+        let Some(held) = self.held_keys.as_mut() else {
+            return;
+        };
+        let Some((session_id, seq)) = held.remove(&key_to_name(key)) else {
+            return;
+        };
+        let release = KeyRelease {
+            session_id,
+            seq,
+            timestamp: Local::now(),
+        };
+        if let Err(e) = self.store.save_key_release(&release) {
+            eprintln!("Failed to save key release: {}", e);
+        }
+    }
+
+    /// Save the chord completed by releasing a key, if any
+    fn chord_key_released(&mut self, key: Key) {
+        let Some(chord) = self
+            .chords
+            .as_mut()
+            .and_then(|chords| chords.release(&key_to_name(&key)))
+        else {
+            return;
+        };
+        if let Err(e) = self.store.save_chord(&chord) {
+            eprintln!("Failed to save key chord: {}", e);
+        }
+    }
+
+    /// Record a combo unless it is part of an automated storm
+    fn record_combo(&mut self, combo_str: String) {
+        let now = Local::now();
+        self.save_finished_storms();
+
+        match self.storms.observe(&combo_str, now) {
+            StormDecision::Record => {
+                let mut combo = KeyCombo::new(combo_str);
+                combo.app = self.context.current().app.clone();
+                if let Err(e) = self.store.save_combo(&combo) {
+                    eprintln!("Failed to save key combo: {}", e);
+                }
+            }
+            StormDecision::Started { since } => {
+                // The storm's earlier combos were saved individually; fold them into the storm
+                if let Err(e) = self.store.delete_combos_since(&combo_str, since) {
+                    eprintln!("Failed to collapse combo storm: {}", e);
+                }
+            }
+            StormDecision::Absorbed => {}
+        }
+    }
+
+    fn save_finished_storms(&mut self) {
+        for storm in self.storms.drain_finished(Local::now()) {
+            if let Err(e) = self.store.save_combo_storm(&storm) {
+                eprintln!("Failed to save combo storm: {}", e);
+            }
+        }
+    }
+
+    /// Periodically flush sketched bigrams and reconcile them exactly against key events
+    fn maintain_bigrams(&mut self) {
+        if self.last_bigram_reconcile.elapsed() >= BIGRAM_RECONCILE_INTERVAL {
+            self.reconcile_bigrams();
+        } else if self.last_bigram_flush.elapsed() >= BIGRAM_FLUSH_INTERVAL {
+            self.flush_bigrams();
+        }
+    }
+
+    fn flush_bigrams(&mut self) {
+        self.last_bigram_flush = Instant::now();
+
+        let Some(session_id) = self.session.id else {
+            return;
+        };
+        if self.bigrams.is_empty() {
+            return;
+        }
+
+        for (first, second, count) in self.bigrams.drain() {
+            let bigram = KeyBigram::new(session_id, first, second, count as i64);
+            if let Err(e) = self.store.save_bigram(&bigram) {
+                eprintln!("Failed to save key bigram: {}", e);
+            }
+        }
+    }
+
+    fn reconcile_bigrams(&mut self) {
+        self.last_bigram_reconcile = Instant::now();
+        self.last_bigram_flush = Instant::now();
+
+        // Exact counts cover every saved event, so pending sketch counts are superseded
+        self.bigrams.clear();
+
+        if let Some(session_id) = self.session.id {
+            if let Err(e) = self.store.reconcile_bigrams(session_id) {
+                eprintln!("Failed to reconcile key bigrams: {}", e);
+            }
+        }
+    }
+
+    /// Finalize bigram counts, combo storms, bursts and latency, then close the recording
+    /// session
+    pub(crate) fn end_session(&mut self) -> Result<()> {
+        if self.is_paused() {
+            // The session was already closed when recording paused
+            return Ok(());
+        }
+        self.reconcile_bigrams();
+        for storm in self.storms.finish() {
+            self.store.save_combo_storm(&storm)?;
+        }
+        if let Some(burst) = self.bursts.finish() {
+            self.store.save_typing_burst(&burst)?;
+        }
+        self.bursts.reset();
+        if let Some(summary) = self.latency_summary() {
+            self.store
+                .save_latency_report(&LatencyReport::new(self.session.id, &summary))?;
+        }
+        self.store.end_session(&mut self.session)?;
+        for processor in &mut self.processors {
+            if let Err(e) = processor.session_ended(&self.session) {
+                eprintln!("Event processor failed on session end: {}", e);
+            }
+        }
+        self.store.flush()
+    }
+
+    fn is_paused(&self) -> bool {
+        !self.paused.is_empty()
+    }
+
+    /// Stop recording while the screen is locked, the computer sleeps or when asked,
+    /// closing the session at `at` so the time away isn't counted as part of it
+    fn pause(&mut self, reason: PauseReason, at: DateTime<Local>) -> Result<()> {
+        if self.paused.contains(&reason) {
+            return Ok(());
+        }
+        if !self.is_paused() {
+            self.session.end_time = Some(at.max(self.session.start_time));
+            self.end_session()?;
+            self.pressed_modifiers.clear();
+            if let Some(chords) = self.chords.as_mut() {
+                chords.reset();
+            }
+            if let Some(held) = self.held_keys.as_mut() {
+                held.clear();
+            }
+            self.last_key = None;
+        }
+        self.paused.push(reason);
+        Ok(())
+    }
+
+    /// Resume recording in a fresh session once nothing keeps it paused
+    fn resume(&mut self, reason: PauseReason) -> Result<()> {
+        let was_paused = self.is_paused();
+        self.paused.retain(|paused| *paused != reason);
+        if !was_paused || self.is_paused() {
+            return Ok(());
+        }
+        self.begin_session()
+    }
+
+    /// End the session now and start another right away
+    fn restart_session(&mut self) -> Result<()> {
+        if self.is_paused() {
+            bail!("Recording is paused");
+        }
+        self.end_session()?;
+        self.last_key = None;
+        self.begin_session()
+    }
+
+    /// Start a fresh session after the previous one was closed
+    fn begin_session(&mut self) -> Result<()> {
+        self.session = Session::new();
+        self.start_session()?;
+        self.next_seq = 0;
+        self.keys_in_interval = 0;
+        self.interval_start = Instant::now();
+        self.last_key_time = None;
+        self.last_bigram_flush = Instant::now();
+        self.last_bigram_reconcile = Instant::now();
+        Ok(())
+    }
+
+    /// Tell subscribed webhooks and hooks that recording started, without waiting for them
+    fn notify_session_started(&self, webhooks: &[WebhookConfig], hooks: &[HookConfig]) {
+        if webhooks.is_empty() && hooks.is_empty() {
+            return;
+        }
+        match ActivitySnapshot::capture(&self.db, &self.session) {
+            Ok(snapshot) => {
+                let milestone = Milestone::session_started(&snapshot);
+                hooks::run(hooks, &milestone, &snapshot);
+                let webhooks = webhooks.to_vec();
+                std::thread::spawn(move || webhooks::send(&webhooks, &milestone));
+            }
+            Err(e) => eprintln!("Failed to summarize session for webhooks: {}", e),
+        }
+    }
+
+    /// Tell subscribed webhooks and hooks that the session ended, waiting for the webhooks
+    /// to respond
+    fn notify_session_ended(&self, webhooks: &[WebhookConfig], hooks: &[HookConfig]) {
+        if webhooks.is_empty() && hooks.is_empty() {
+            return;
+        }
+        match ActivitySnapshot::capture(&self.db, &self.session) {
+            Ok(snapshot) => {
+                let milestone = Milestone::session_ended(&snapshot);
+                hooks::run(hooks, &milestone, &snapshot);
+                webhooks::send(webhooks, &milestone);
+            }
+            Err(e) => eprintln!("Failed to summarize session for webhooks: {}", e),
+        }
     }
 
     fn modifier_pressed(&mut self, key: Key) {
@@ -114,56 +653,493 @@ impl ListenState {
     }
 }
 
-pub async fn run() -> Result<()> {
-    println!("{}", "🎹 KitMap - Keyboard Activity Tracker".cyan().bold());
-    println!("{}", "━".repeat(40).dark_grey());
-    println!();
-    println!("{} Initializing database...", "→".dark_grey());
+/// Formats `kitmap listen --emit` writes recorded events in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EmitFormat {
+    /// One JSON object per line
+    Jsonl,
+}
+
+/// What `kitmap listen` records and where it sends it, from its command-line flags
+#[derive(Debug, Clone)]
+pub struct ListenOptions {
+    pub context: ContextOptions,
+    /// Agree to the privacy summary without asking
+    pub yes: bool,
+    pub measure_latency: bool,
+    pub breaks: bool,
+    pub daily_summary: bool,
+    pub discord: bool,
+    pub metrics: bool,
+    pub composed: bool,
+    pub chords: bool,
+    /// Record when each key is released
+    pub dynamics: bool,
+    /// Also write recorded events in this format, to stdout or `tee`
+    pub emit: Option<EmitFormat>,
+    /// File or named pipe emitted events are written to instead of stdout
+    pub tee: Option<PathBuf>,
+    #[cfg(feature = "mqtt")]
+    pub mqtt: bool,
+    #[cfg(feature = "postgres")]
+    pub postgres: bool,
+    /// Serve the gRPC control and query API on this address
+    #[cfg(feature = "grpc")]
+    pub grpc: Option<std::net::SocketAddr>,
+    /// Token gRPC calls must carry
+    #[cfg(feature = "grpc")]
+    pub grpc_token: Option<String>,
+    /// Register a D-Bus service on the session bus
+    #[cfg(all(feature = "dbus", target_os = "linux"))]
+    pub dbus: bool,
+}
+
+pub async fn run(options: ListenOptions) -> Result<()> {
+    let ListenOptions {
+        context,
+        yes,
+        measure_latency,
+        breaks,
+        daily_summary,
+        discord,
+        metrics,
+        composed,
+        chords,
+        dynamics,
+        emit,
+        tee,
+        #[cfg(feature = "mqtt")]
+        mqtt,
+        #[cfg(feature = "postgres")]
+        postgres,
+        #[cfg(feature = "grpc")]
+        grpc,
+        #[cfg(feature = "grpc")]
+        grpc_token,
+        #[cfg(all(feature = "dbus", target_os = "linux"))]
+        dbus,
+    } = options;
+    if emit.is_some() && tee.is_none() {
+        crate::commands::move_status_to_stderr();
+    }
+    // A second listener would record every key twice, and its startup cleanup would
+    // close the running listener's session, so the lock is taken before opening the
+    // database and held until the process ends
+    let _lock = ListenerLock::acquire()?;
+
+    status!("{}", "🎹 KitMap - Keyboard Activity Tracker".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+    status!("{} Initializing database...", "→".dark_grey());
 
     let db = init_db()?;
+    let config = load_config()?;
+
+    status!("{} Database ready!", "✓".green());
+    status!();
+
+    let summary_time = if daily_summary {
+        Some(crate::publish::daily_summary::parse_time(
+            &config.daily_summary,
+        )?)
+    } else {
+        None
+    };
+
+    crate::db::audit::track_config(&db, &config)?;
+
+    let orphaned = Session::close_orphaned(&db)?;
+    if orphaned > 0 {
+        let details = format!(
+            "Closed {} session(s) left open by a listener that didn't stop cleanly",
+            orphaned
+        );
+        crate::db::audit::record(&db, AuditAction::Recover, &details)?;
+        status!("{} {}", "✓".green(), details);
+        status!();
+    }
+
+    let mut privacy = PrivacySummary::new(&context);
+    for (enabled, scope) in [
+        (measure_latency, Scope::Latency),
+        (breaks, Scope::Breaks),
+        (composed, Scope::Composed),
+        (chords, Scope::Chords),
+        (dynamics, Scope::Dynamics),
+        (discord, Scope::Discord),
+        (metrics, Scope::Metrics),
+        (!config.webhooks.is_empty(), Scope::Webhooks),
+        (!config.hooks.is_empty(), Scope::Hooks),
+        (!config.processors.is_empty(), Scope::Processors),
+        (emit.is_some(), Scope::Emit),
+    ] {
+        if enabled {
+            privacy.add(scope);
+        }
+    }
+    #[cfg(feature = "mqtt")]
+    if mqtt {
+        privacy.add(Scope::Mqtt);
+    }
+    #[cfg(feature = "postgres")]
+    if postgres {
+        privacy.add(Scope::Postgres);
+    }
+    if !consent::confirm(&db, &privacy, yes)? {
+        status!("{}", "Nothing was recorded.".dark_grey());
+        return Ok(());
+    }
 
-    println!("{} Database ready!", "✓".green());
-    println!();
-    println!("{}", "Starting keyboard listener...".yellow());
-    println!("{}", "Press Ctrl+C to stop recording.".dark_grey());
-    println!();
+    let wm = if context.needs_window_manager() {
+        let detected = WindowManager::detect();
+        match &detected {
+            Some(wm) => status!(
+                "{} Tracking workspace context via {}",
+                "✓".green(),
+                wm.name()
+            ),
+            None => status!(
+                "{}",
+                "⚠️  No supported window manager found (sway, i3, Hyprland); workspace context won't be recorded."
+                    .yellow()
+            ),
+        }
+        detected
+    } else {
+        None
+    };
+    status!();
+    status!("{}", "Starting keyboard listener...".yellow());
+    status!("{}", "Press Ctrl+C to stop recording.".dark_grey());
+    status!();
 
-    let state = Arc::new(Mutex::new(ListenState::new(db.clone())));
+    let mut listen_state = ListenState::new(
+        db.clone(),
+        ContextTracker::new(context, wm, &config),
+        &config.storage,
+    )
+    .with_combo_rules(&config.combos)
+    .with_burst_rules(&config.bursts);
+    if measure_latency {
+        status!(
+            "{} Measuring event handling latency for this session",
+            "✓".green()
+        );
+        listen_state = listen_state.with_latency_measurement();
+    }
+    if breaks {
+        status!(
+            "{} Suggesting a break after {} minutes of continuous typing",
+            "✓".green(),
+            config.breaks.typing_minutes
+        );
+        listen_state = listen_state.with_break_reminders(&config.breaks);
+    }
+    if composed {
+        status!(
+            "{} Recording composed characters from dead keys and input methods",
+            "✓".green()
+        );
+        listen_state = listen_state.with_composed_characters();
+    }
+    if chords {
+        status!(
+            "{} Recording keys pressed within {}ms of each other as chords",
+            "✓".green(),
+            config.chords.window_ms
+        );
+        listen_state = listen_state.with_chords(&config.chords);
+    }
+    if dynamics {
+        status!(
+            "{} Recording when each key is released, for keystroke dynamics",
+            "✓".green()
+        );
+        listen_state = listen_state.with_key_releases();
+    }
+    let mut processors = Registry::with_builtins().build(&config.processors)?;
+    if !processors.is_empty() {
+        status!(
+            "{} Passing key events through {} processor(s)",
+            "✓".green(),
+            processors.len()
+        );
+    }
+    if let Some(EmitFormat::Jsonl) = emit {
+        let emitter = match tee {
+            Some(path) => {
+                status!(
+                    "{} Writing events as JSON lines to {}",
+                    "✓".green(),
+                    path.display()
+                );
+                let destination = path.display().to_string();
+                JsonLines::spawn(destination, move || {
+                    OpenOptions::new().create(true).append(true).open(path)
+                })
+            }
+            None => {
+                status!("{} Writing events as JSON lines to stdout", "✓".green());
+                JsonLines::spawn("stdout".to_string(), || Ok(std::io::stdout()))
+            }
+        };
+        processors.push(Box::new(emitter));
+    }
+    listen_state = listen_state.with_processors(processors);
+    #[cfg(feature = "postgres")]
+    if postgres {
+        let store = crate::db::postgres::PostgresStore::connect(&config.postgres)?;
+        status!(
+            "{} Mirroring events to the central Postgres database",
+            "✓".green()
+        );
+        listen_state = listen_state.with_mirror(Box::new(store));
+    }
+    let state = Arc::new(Mutex::new(listen_state));
 
     // Start session
     {
         let mut s = state.lock().unwrap();
-        s.session.start(&db)?;
+        s.start_session()?;
+        s.notify_session_started(&config.webhooks, &config.hooks);
+    }
+
+    // Batched key events are committed on time even when typing stops
+    let commit_interval = Duration::from_millis(config.storage.commit_interval_ms);
+    if !commit_interval.is_zero() {
+        let commit_state = state.clone();
+        let check = commit_interval / COMMIT_CHECKS_PER_INTERVAL;
+        std::thread::Builder::new()
+            .name("kitmap-commit".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(check);
+                if let Err(e) = commit_state.lock().unwrap().commit_due() {
+                    eprintln!("\nFailed to commit key events: {}", e);
+                }
+            })?;
+    }
+
+    let control = ListenControl(state.clone());
+    if let Err(e) = control::spawn(control.clone()) {
+        status!(
+            "{}",
+            format!(
+                "⚠️  kitmap pause, resume and session can't reach this listener: {}",
+                e
+            )
+            .yellow()
+        );
+    }
+
+    #[cfg(feature = "mqtt")]
+    if mqtt {
+        let mqtt_config = config.mqtt.clone();
+        status!(
+            "{} Publishing activity to MQTT broker {}:{} under {}/",
+            "✓".green(),
+            mqtt_config.host,
+            mqtt_config.port,
+            mqtt_config.topic
+        );
+        tokio::spawn(crate::publish::mqtt::run(mqtt_config, db.clone()));
+    }
+
+    if discord {
+        crate::publish::discord::spawn(config.discord.clone(), db.clone())?;
+        status!(
+            "{} Sharing typing stats as Discord Rich Presence",
+            "✓".green()
+        );
+    }
+
+    if metrics {
+        let emitter = crate::publish::metrics::MetricsEmitter::new(config.metrics.clone())?;
+        let destination = match config.metrics.protocol {
+            MetricsProtocol::Influx => format!("InfluxDB at {}", config.metrics.url),
+            MetricsProtocol::Statsd => format!("StatsD at {}", config.metrics.statsd_addr),
+        };
+        status!(
+            "{} Shipping activity metrics to {}",
+            "✓".green(),
+            destination
+        );
+        tokio::spawn(crate::publish::metrics::run(
+            emitter,
+            db.clone(),
+            commit_deadline(Duration::from_millis(config.storage.commit_interval_ms)),
+        ));
+    }
+
+    if !config.webhooks.is_empty() {
+        status!(
+            "{} Notifying {} webhook(s) of milestones",
+            "✓".green(),
+            config.webhooks.len()
+        );
+    }
+    if !config.hooks.is_empty() {
+        status!(
+            "{} Running {} hook command(s) on milestones",
+            "✓".green(),
+            config.hooks.len()
+        );
     }
+    if !config.webhooks.is_empty() || !config.hooks.is_empty() {
+        tokio::spawn(crate::publish::webhooks::run(
+            config.webhooks.clone(),
+            config.hooks.clone(),
+            config.milestones.clone(),
+            db.clone(),
+        ));
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(addr) = grpc {
+        status!(
+            "{} Serving the gRPC API on {}{}",
+            "✓".green(),
+            addr,
+            if grpc_token.is_some() {
+                " (token required)"
+            } else {
+                ""
+            }
+        );
+        let control = control.clone();
+        let grpc_db = db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = crate::server::grpc::serve(addr, grpc_token, control, grpc_db).await {
+                eprintln!("\ngRPC API stopped: {:#}", e);
+            }
+        });
+    }
+
+    #[cfg(all(feature = "dbus", target_os = "linux"))]
+    if dbus {
+        status!(
+            "{} Serving {} on the session bus",
+            "✓".green(),
+            crate::server::dbus::BUS_NAME
+        );
+        let control = control.clone();
+        let dbus_db = db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = crate::server::dbus::serve(control, dbus_db).await {
+                eprintln!("\nD-Bus service stopped: {:#}", e);
+            }
+        });
+    }
+
+    if let Some(time) = summary_time {
+        status!(
+            "{} Showing a summary of the day at {}",
+            "✓".green(),
+            time.format("%H:%M")
+        );
+        tokio::spawn(crate::publish::daily_summary::run(
+            time,
+            config.score.clone(),
+            db.clone(),
+        ));
+    }
+
+    match LockWatcher::detect() {
+        Some(watcher) => {
+            let probes: Vec<_> = watcher.probes().iter().map(|p| p.name()).collect();
+            status!(
+                "{} Pausing while the screen is locked (via {})",
+                "✓".green(),
+                probes.join(", ")
+            );
+            let lock_state = state.clone();
+            watcher.spawn(LOCK_POLL_INTERVAL, move |locked| {
+                let mut s = lock_state.lock().unwrap();
+                let (result, action) = if locked {
+                    (
+                        s.pause(PauseReason::ScreenLocked, Local::now()),
+                        "paused: screen locked",
+                    )
+                } else {
+                    (
+                        s.resume(PauseReason::ScreenLocked),
+                        "resumed: screen unlocked",
+                    )
+                };
+                report_pause(result, action, "screen lock");
+            });
+        }
+        None => status!(
+            "{}",
+            "⚠️  Screen lock detection is unavailable; keys typed on the lock screen may be recorded."
+                .yellow()
+        ),
+    }
+
+    let sleep_watcher = SleepWatcher::detect();
+    status!(
+        "{} Ending the session while the computer sleeps (via {})",
+        "✓".green(),
+        sleep_watcher.source().name()
+    );
+    let sleep_state = state.clone();
+    sleep_watcher.spawn(move |event| {
+        let mut s = sleep_state.lock().unwrap();
+        let (result, action) = match event {
+            SleepEvent::Sleeping { at } => {
+                (s.pause(PauseReason::Asleep, at), "paused: computer asleep")
+            }
+            SleepEvent::Awake => (s.resume(PauseReason::Asleep), "resumed: computer awake"),
+        };
+        report_pause(result, action, "sleep");
+    });
 
-    // Set up Ctrl+C handler with atomic flag
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
+    // Set up Ctrl+C handler
     let state_clone = state.clone();
-    let db_clone = db.clone();
+    let webhooks = config.webhooks.clone();
+    let hooks = config.hooks.clone();
 
     ctrlc::set_handler(move || {
-        println!();
-        println!("{}", "Stopping listener...".yellow());
+        status!();
+        status!("{}", "Stopping listener...".yellow());
 
         // End session
         {
             let mut s = state_clone.lock().unwrap();
-            if let Err(e) = s.session.end(&db_clone) {
+            if let Err(e) = s.finish_breaks() {
+                eprintln!("Failed to save break reminders: {}", e);
+            }
+            if let Err(e) = s.end_session() {
                 eprintln!("Failed to end session: {}", e);
             }
+            if let Err(e) = s.flush() {
+                eprintln!("Failed to commit key events: {}", e);
+            }
 
-            println!();
-            println!("{}", "━".repeat(40).dark_grey());
-            println!("{} Session ended!", "✓".green());
-            println!(
+            status!();
+            status!("{}", "━".repeat(40).dark_grey());
+            status!("{} Session ended!", "✓".green());
+            status!(
                 "   Total keys recorded: {}",
                 s.total_keys.to_string().cyan()
             );
-            println!();
+            if let Some(latency) = s.latency_summary() {
+                status!(
+                    "   Handling latency: p50 {}µs, p99 {}µs, max {}µs",
+                    latency.p50_us,
+                    latency.p99_us,
+                    latency.max_us
+                );
+            }
+            if let Some((reminded, taken)) = s.break_summary() {
+                status!("   Breaks suggested: {}, taken: {}", reminded, taken);
+            }
+            status!();
+
+            s.notify_session_ended(&webhooks, &hooks);
         }
+        remove_pid_file();
+        control::remove_socket();
 
-        r.store(false, Ordering::SeqCst);
         std::process::exit(0);
     })
     .expect("Failed to set Ctrl+C handler");
@@ -174,31 +1150,28 @@ pub async fn run() -> Result<()> {
     let callback = move |event: Event| {
         match event.event_type {
             EventType::KeyPress(key) => {
+                let started = Instant::now();
                 let mut s = state_for_callback.lock().unwrap();
 
-                if is_modifier(&key) {
-                    s.modifier_pressed(key);
-                }
-
-                s.record_key_event(key);
-
                 // Print feedback
-                let key_name = key_to_name(&key);
-                print!(
-                    "\r{} {} recorded (total: {})",
-                    "⌨".cyan(),
-                    key_name.green(),
-                    s.total_keys.to_string().yellow()
-                );
-                print!("                    "); // Clear any remaining chars
-                use std::io::Write;
-                let _ = std::io::stdout().flush();
-            }
-            EventType::KeyRelease(key) => {
-                if is_modifier(&key) {
-                    let mut s = state_for_callback.lock().unwrap();
-                    s.modifier_released(key);
+                if s.key_pressed(key, event.name.as_deref()) {
+                    let key_name = key_to_name(&key);
+                    status_print!(
+                        "\r{} {} recorded (total: {})",
+                        "⌨".cyan(),
+                        key_name.green(),
+                        s.total_keys.to_string().yellow()
+                    );
+                    status_print!("                    "); // Clear any remaining chars
                 }
+
+                s.record_latency(started);
+            }
+            EventType::KeyRelease(key) if chords || dynamics || is_modifier(&key) => {
+                let started = Instant::now();
+                let mut s = state_for_callback.lock().unwrap();
+                s.key_released(key);
+                s.record_latency(started);
             }
             _ => {}
         }
@@ -210,8 +1183,138 @@ pub async fn run() -> Result<()> {
 
         // End session on error
         let mut s = state.lock().unwrap();
-        s.session.end(&db)?;
+        s.finish_breaks()?;
+        s.end_session()?;
+        s.flush()?;
+        s.notify_session_ended(&config.webhooks, &config.hooks);
     }
+    remove_pid_file();
+    control::remove_socket();
 
     Ok(())
 }
+
+/// The listener as the control socket and gRPC API control it
+#[derive(Clone)]
+struct ListenControl(Arc<Mutex<ListenState>>);
+
+impl Recorder for ListenControl {
+    fn status(&self) -> ListenerStatus {
+        let s = self.0.lock().unwrap();
+        let recording = !s.is_paused();
+        ListenerStatus {
+            pid: std::process::id(),
+            recording,
+            paused_because: s
+                .paused
+                .iter()
+                .map(|reason| reason.id().to_string())
+                .collect(),
+            session: recording.then(|| SessionStatus {
+                id: s.session.id,
+                started: s.session.start_time,
+                keys: s.session.total_keys.max(0) as u64,
+            }),
+            keys_recorded: s.total_keys,
+        }
+    }
+
+    fn pause(&self) -> Result<()> {
+        let mut s = self.0.lock().unwrap();
+        s.pause(PauseReason::Requested, Local::now())
+    }
+
+    fn resume(&self) -> Result<()> {
+        let mut s = self.0.lock().unwrap();
+        s.resume(PauseReason::Requested)
+    }
+
+    fn new_session(&self) -> Result<()> {
+        let mut s = self.0.lock().unwrap();
+        s.restart_session()
+    }
+}
+
+/// Exclusive lock on the lock file, held by the running listener. The OS releases it
+/// however the process ends, so a killed listener never leaves a stale lock behind, and
+/// unlike a process id it can't be mistaken for an unrelated process that reused the id.
+struct ListenerLock {
+    _file: File,
+}
+
+impl ListenerLock {
+    /// Take the lock and record this process id, or fail if another listener holds it
+    fn acquire() -> Result<Self> {
+        let path = get_lock_path()?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => match read_pid()? {
+                Some(pid) => bail!(
+                    "kitmap listen is already running (process {}); stop it before starting another",
+                    pid
+                ),
+                None => bail!("kitmap listen is already running; stop it before starting another"),
+            },
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock {}", path.display()))
+            }
+        }
+
+        std::fs::write(get_pid_path()?, std::process::id().to_string())?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Whether a listener is running, found by the lock it holds, with its process id once it
+/// has written it
+pub fn running_listener() -> Result<Option<Option<u32>>> {
+    let file = match File::open(get_lock_path()?) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    match file.try_lock_shared() {
+        Ok(()) => Ok(None),
+        Err(TryLockError::WouldBlock) => Ok(Some(read_pid()?)),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+/// Process id in the PID file, which is only current while the lock is held
+fn read_pid() -> Result<Option<u32>> {
+    match std::fs::read_to_string(get_pid_path()?) {
+        Ok(text) => Ok(text.trim().parse::<u32>().ok()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Delete the PID file of this listener, if it is still ours
+fn remove_pid_file() {
+    let Ok(path) = get_pid_path() else {
+        return;
+    };
+    let ours = std::fs::read_to_string(&path)
+        .is_ok_and(|text| text.trim() == std::process::id().to_string());
+    if ours {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Show that recording paused or resumed, or why that failed
+fn report_pause(result: Result<()>, action: &str, cause: &str) {
+    match result {
+        Ok(()) => {
+            status_print!("\r{} Recording {} ", "⏯".yellow(), action);
+            status_print!("                              "); // Clear any remaining chars
+        }
+        Err(e) => eprintln!("\nFailed to update recording after {}: {}", cause, e),
+    }
+}
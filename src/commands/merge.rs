@@ -0,0 +1,57 @@
+use crate::db::audit::{self, AuditAction};
+use crate::db::{get_db_path, init_db, merge};
+use anyhow::{bail, Result};
+use crossterm::style::Stylize;
+use std::path::Path;
+
+pub async fn run(file: &Path) -> Result<()> {
+    status!("{}", "🔀 KitMap - Merge Database".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    if !file.is_file() {
+        bail!("{} does not exist", file.display());
+    }
+    if file.canonicalize()? == get_db_path()?.canonicalize()? {
+        bail!("{} is this computer's own database", file.display());
+    }
+
+    status!("{} Merging {}...", "→".dark_grey(), file.display());
+    let db = init_db()?;
+    let other = file.to_path_buf();
+    let summary = db.call(move |conn| merge::merge(conn, &other)).await?;
+    audit::record(
+        &db,
+        AuditAction::Merge,
+        &format!(
+            "Merged {} key events and {} sessions ({} folded into overlapping ones) from {}",
+            summary.key_events,
+            summary.sessions_added + summary.sessions_reconciled,
+            summary.sessions_reconciled,
+            file.display()
+        ),
+    )?;
+
+    status!(
+        "{} Merged {}",
+        "✓".green(),
+        file.display().to_string().cyan()
+    );
+    status!();
+    status!(
+        "   Sessions: {} added, {} folded into overlapping sessions",
+        summary.sessions_added,
+        summary.sessions_reconciled
+    );
+    status!(
+        "   Key events: {} added, {} already recorded",
+        summary.key_events,
+        summary.duplicate_key_events
+    );
+    status!("   Combos: {}", summary.combos);
+    status!("   Chords: {}", summary.chords);
+    status!("   Typing samples: {}", summary.typing_samples);
+    status!("   Typing bursts: {}", summary.typing_bursts);
+
+    Ok(())
+}
@@ -1,4 +1,70 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether messages for the person running kitmap go to stderr, because stdout carries
+/// data (`kitmap listen --emit jsonl`)
+static STATUS_ON_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Send messages for the person running kitmap to stderr from now on
+pub(crate) fn move_status_to_stderr() {
+    STATUS_ON_STDERR.store(true, Ordering::Relaxed);
+}
+
+pub(crate) fn status_on_stderr() -> bool {
+    STATUS_ON_STDERR.load(Ordering::Relaxed)
+}
+
+/// `println!` for messages to the person running kitmap, on stderr while stdout carries data.
+/// Every command's terminal output goes through here or `plain::finish`, so `--no-color`
+/// and `--ascii` apply to all of it.
+macro_rules! status {
+    () => {
+        status!("")
+    };
+    ($($arg:tt)*) => {{
+        let line = $crate::ui::plain::finish(&format!($($arg)*));
+        if $crate::commands::status_on_stderr() {
+            eprintln!("{}", line)
+        } else {
+            println!("{}", line)
+        }
+    }};
+}
+
+/// `print!` for messages to the person running kitmap, flushed so they show up right away
+macro_rules! status_print {
+    ($($arg:tt)*) => {{
+        use std::io::Write;
+        let text = $crate::ui::plain::finish(&format!($($arg)*));
+        if $crate::commands::status_on_stderr() {
+            eprint!("{}", text);
+            let _ = std::io::stderr().flush();
+        } else {
+            print!("{}", text);
+            let _ = std::io::stdout().flush();
+        }
+    }};
+}
+
+pub mod audit;
+pub mod bench;
+pub mod calendar;
+pub mod compare;
+pub mod consent;
+pub mod control;
 pub mod db;
+pub mod doctor;
+pub mod export;
+pub mod goals;
+pub mod import;
 pub mod listen;
+pub mod merge;
 pub mod preview;
+pub mod replay;
+pub mod report;
 pub mod reset;
+pub mod sanitize;
+pub mod schema;
+pub mod speed;
+pub mod stats;
+pub mod status;
+pub mod top;
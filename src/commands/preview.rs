@@ -1,141 +1,303 @@
+use crate::config::load_config;
 use crate::db::init_db;
-use crate::stats::StatsCalculator;
+use crate::server::{self, AppState};
+use crate::stats::board::Board;
+use crate::stats::calculator::StatsSection;
+use crate::stats::custom;
+use crate::stats::normalize::Normalization;
+use crate::stats::{StatsCalculator, StatsFilter};
+use crate::ui::cast::{Cast, CAST_COLUMNS};
+use crate::ui::heatmap::{render_roll_table, render_sequences};
 use crate::ui::AsciiHeatmap;
-use anyhow::Result;
-use axum::{
-    extract::State,
-    http::{header, StatusCode},
-    response::{Html, IntoResponse, Response},
-    routing::get,
-    Json, Router,
-};
+use crate::ui::{pager, plain};
+use anyhow::{bail, Context, Result};
 use crossterm::style::Stylize;
-use include_dir::{include_dir, Dir};
+use std::fs::File;
+use std::io::BufWriter;
+use std::net::IpAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
 
-// Embed the web dist directory into the binary
-static WEB_DIST: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/web/dist");
+/// Keys and 3-key sequences listed in the terminal's key sequences section
+const SEQUENCE_ROWS: usize = 10;
 
-pub async fn run(web: bool, port: u16) -> Result<()> {
-    println!("{}", "📊 KitMap - Keyboard Statistics".cyan().bold());
-    println!("{}", "━".repeat(40).dark_grey());
-    println!();
+/// Options for the terminal view
+#[derive(Debug, Clone, clap::Args)]
+pub struct TerminalArgs {
+    /// Color the terminal heatmap by how much more often keys are pressed than in a reference
+    #[arg(long, value_enum, value_name = "REFERENCE", conflicts_with = "web")]
+    pub normalize: Option<Normalization>,
+    /// Only calculate and show these terminal sections (default: heatmap and overview)
+    #[arg(
+        long = "section",
+        value_enum,
+        value_name = "SECTION",
+        conflicts_with = "web"
+    )]
+    pub sections: Vec<StatsSection>,
+    /// Calculate and show every terminal section
+    #[arg(long, conflicts_with_all = ["web", "sections"])]
+    pub full: bool,
+    /// Print everything at once instead of paging output taller than the terminal
+    #[arg(long, conflicts_with = "web")]
+    pub no_pager: bool,
+    /// Record the terminal preview to this asciinema cast file instead of printing it
+    #[arg(long, value_name = "FILE", conflicts_with = "web")]
+    pub cast: Option<PathBuf>,
+}
+
+impl TerminalArgs {
+    /// Sections to calculate and show
+    fn sections(&self) -> Vec<StatsSection> {
+        if self.full {
+            StatsSection::ALL.to_vec()
+        } else if self.sections.is_empty() {
+            vec![StatsSection::Heatmap, StatsSection::Overview]
+        } else {
+            self.sections.clone()
+        }
+    }
+}
+
+pub async fn run(
+    web: bool,
+    host: IpAddr,
+    port: u16,
+    token: Option<String>,
+    assets: Option<PathBuf>,
+    terminal: TerminalArgs,
+    #[cfg(feature = "tls")] tls: server::tls::TlsArgs,
+) -> Result<()> {
+    let title = format!(
+        "{}\n{}\n",
+        "📊 KitMap - Keyboard Statistics".cyan().bold(),
+        "━".repeat(40).dark_grey()
+    );
+    // A recorded preview starts with the title instead
+    if terminal.cast.is_none() {
+        status!("{}", title);
+    }
 
     let db = init_db()?;
-    let calculator = StatsCalculator::new(db);
-    let stats = calculator.calculate_all()?;
+    let config = load_config()?;
+    let board = Board::from_config(&config.keyboard)?;
+    let metrics = custom::from_config(&config.custom_metrics);
+    let calculator = StatsCalculator::new(db.clone())
+        .with_score_config(config.score.clone())
+        .with_effort_config(config.effort.clone())
+        .with_board(board.clone())
+        .with_metrics(metrics.clone());
+    // The dashboard shows everything; the terminal only what was asked for
+    let sections = if web {
+        StatsSection::ALL.to_vec()
+    } else {
+        terminal.sections()
+    };
+    let latest_id = calculator.latest_event_id()?;
+    let stats = calculator.calculate_sections_cached(&StatsFilter::default(), &sections)?;
 
     if stats.total_keys == 0 {
-        println!("{}", "No keyboard data recorded yet!".yellow());
-        println!("Run {} to start recording.", "kitmap listen".cyan());
+        status!("{}", "No keyboard data recorded yet!".yellow());
+        status!("Run {} to start recording.", "kitmap listen".cyan());
         return Ok(());
     }
 
     if web {
+        if let Some(dir) = &assets {
+            if !dir.join("index.html").is_file() {
+                bail!("{} does not contain an index.html", dir.display());
+            }
+        }
+
+        #[cfg(feature = "tls")]
+        let scheme = if tls.enabled() { "https" } else { "http" };
+        #[cfg(not(feature = "tls"))]
+        let scheme = "http";
+
+        let url = if host.is_loopback() {
+            format!("{}://localhost:{}", scheme, port)
+        } else {
+            format!("{}://{}:{}", scheme, host, port)
+        };
+
         // Start web server
-        println!(
-            "{} Starting web server on port {}...",
+        status!(
+            "{} Starting web server on {}:{}...",
             "→".dark_grey(),
+            host,
             port
         );
-        println!();
-        println!(
+        status!();
+        status!(
             "{} Open {} in your browser",
             "✓".green(),
-            format!("http://localhost:{}", port).cyan().underlined()
+            url.clone().cyan().underlined()
         );
-        println!("{}", "Press Ctrl+C to stop the server.".dark_grey());
+        if let Some(dir) = &assets {
+            status!(
+                "{}",
+                format!("Serving dashboard files from {}", dir.display()).dark_grey()
+            );
+        }
+        if token.is_some() {
+            status!(
+                "{}",
+                "Token authentication enabled. Append ?token=<token> to the URL or log in with any username and the token as password."
+                    .dark_grey()
+            );
+            status!(
+                "{}",
+                format!("Streaming overlay: {}/overlay?token=<token>", url).dark_grey()
+            );
+        } else {
+            if !host.is_loopback() {
+                status!(
+                    "{}",
+                    "⚠️  Warning: The dashboard is reachable from the network without a token. Use --token to protect it."
+                        .yellow()
+                );
+            }
+            status!(
+                "{}",
+                "The /overlay key stream and key press downloads need --token; add ?token=<token> to the overlay's browser source URL."
+                    .dark_grey()
+            );
+        }
+        status!("{}", "Press Ctrl+C to stop the server.".dark_grey());
 
-        let app_state = Arc::new(stats);
+        let allowed_origins = config
+            .dashboard
+            .allowed_origins
+            .iter()
+            .map(|origin| {
+                origin
+                    .parse()
+                    .with_context(|| format!("Invalid allowed origin '{}'", origin))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        let cors = CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods(Any)
-            .allow_headers(Any);
+        let app_state = Arc::new(
+            AppState::new(db, token)
+                .with_score_config(config.score)
+                .with_effort_config(config.effort)
+                .with_board(board)
+                .with_metrics(metrics)
+                .with_assets_dir(assets)
+                .with_allowed_origins(allowed_origins)
+                .with_cached_stats(latest_id, stats),
+        );
+        let app = server::router(app_state.clone());
 
-        let app = Router::new()
-            .route("/", get(serve_index))
-            .route("/api/stats", get(get_stats))
-            .route("/assets/*path", get(serve_static))
-            .layer(cors)
-            .with_state(app_state);
+        #[cfg(feature = "tls")]
+        let rustls_config = if tls.enabled() {
+            Some(tls.rustls_config(host).await?)
+        } else {
+            None
+        };
 
-        let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).await?;
+        let listener = TcpListener::bind((host, port)).await?;
+
+        // Open browser automatically, passing the token along so the first load is authorized
+        let browser_url = match app_state.token() {
+            Some(token) => format!("{}/?token={}", url, token),
+            None => url,
+        };
 
-        // Open browser automatically
         #[cfg(target_os = "macos")]
-        let _ = std::process::Command::new("open")
-            .arg(format!("http://localhost:{}", port))
-            .spawn();
+        let _ = std::process::Command::new("open").arg(&browser_url).spawn();
 
         #[cfg(target_os = "linux")]
         let _ = std::process::Command::new("xdg-open")
-            .arg(format!("http://localhost:{}", port))
+            .arg(&browser_url)
             .spawn();
 
         #[cfg(target_os = "windows")]
         let _ = std::process::Command::new("cmd")
-            .args(["/C", "start", &format!("http://localhost:{}", port)])
+            .args(["/C", "start", &browser_url])
             .spawn();
 
+        #[cfg(feature = "tls")]
+        if let Some(rustls_config) = rustls_config {
+            axum_server::from_tcp_rustls(listener.into_std()?, rustls_config)
+                .serve(app.into_make_service())
+                .await?;
+            return Ok(());
+        }
+
         axum::serve(listener, app).await?;
     } else {
         // ASCII heatmap mode
-        let heatmap = AsciiHeatmap::new(&stats);
+        let mut heatmap = AsciiHeatmap::new(&stats).with_board(board);
+        if let Some(normalization) = terminal.normalize {
+            heatmap = heatmap.with_normalization(normalization);
+        }
+        // Casts are recorded at a fixed width, whatever the terminal's
+        let columns = match terminal.cast {
+            Some(_) => Some(CAST_COLUMNS),
+            None => plain::terminal_columns(),
+        };
+        if let Some(columns) = columns {
+            heatmap = heatmap.with_columns(columns);
+        }
 
-        println!("{}", heatmap.render());
-        println!("{}", heatmap.render_stats(&stats));
+        // Collected first so output taller than the terminal can go through a pager
+        let mut output = String::new();
+        if terminal.cast.is_some() {
+            output.push_str(&format!("{}\n", title));
+        }
+        if sections.contains(&StatsSection::Heatmap) {
+            output.push_str(&format!("{}\n", heatmap.render()));
+        }
+        output.push_str(&format!("{}\n", heatmap.render_stats(&stats, &sections)));
 
-        println!();
-        println!(
-            "{}",
-            "Tip: Run `kitmap preview --web` for detailed web visualization.".dark_grey()
-        );
-    }
-
-    Ok(())
-}
+        if sections.contains(&StatsSection::Rolls) {
+            let rolls = calculator.roll_analysis(&StatsFilter::default())?;
+            if rolls.bigrams > 0 || rolls.trigrams > 0 {
+                output.push_str(&format!("{}\n", render_roll_table(&rolls)));
+            }
+        }
 
-async fn serve_index() -> impl IntoResponse {
-    match WEB_DIST.get_file("index.html") {
-        Some(file) => Html(file.contents_utf8().unwrap_or("")).into_response(),
-        None => (StatusCode::NOT_FOUND, "Index not found").into_response(),
-    }
-}
+        if sections.contains(&StatsSection::Sequences) {
+            let sequences = calculator.key_sequences(&StatsFilter::default(), SEQUENCE_ROWS)?;
+            if sequences.trigrams > 0 || !sequences.transitions.is_empty() {
+                output.push_str(&format!("{}\n", render_sequences(&sequences)));
+            }
+        }
 
-async fn get_stats(
-    State(stats): State<Arc<crate::stats::calculator::AllStats>>,
-) -> Json<crate::stats::calculator::AllStats> {
-    Json((*stats).clone())
-}
-
-async fn serve_static(axum::extract::Path(path): axum::extract::Path<String>) -> Response {
-    let content_type = if path.ends_with(".js") {
-        "application/javascript"
-    } else if path.ends_with(".css") {
-        "text/css"
-    } else if path.ends_with(".svg") {
-        "image/svg+xml"
-    } else if path.ends_with(".png") {
-        "image/png"
-    } else if path.ends_with(".ico") {
-        "image/x-icon"
-    } else {
-        "application/octet-stream"
-    };
+        output.push('\n');
+        if !terminal.full {
+            output.push_str(&format!(
+                "{}\n",
+                "Tip: Run `kitmap preview --full` for every section, or pick them with --section."
+                    .dark_grey()
+            ));
+        }
+        output.push_str(&format!(
+            "{}\n",
+            "Tip: Run `kitmap preview --web` for detailed web visualization.".dark_grey()
+        ));
 
-    // Try to get file from embedded assets
-    let asset_path = format!("assets/{}", path);
-
-    match WEB_DIST.get_file(&asset_path) {
-        Some(file) => (
-            StatusCode::OK,
-            [(header::CONTENT_TYPE, content_type)],
-            file.contents().to_vec(),
-        )
-            .into_response(),
-        None => (StatusCode::NOT_FOUND, "Not Found").into_response(),
+        let output = plain::finish(&output);
+        if let Some(path) = &terminal.cast {
+            let mut cast = Cast::new().with_title("kitmap preview");
+            cast.capture(Duration::ZERO, &output);
+            cast.write(BufWriter::new(File::create(path)?))?;
+            eprintln!(
+                "{}",
+                plain::finish(&format!(
+                    "{} Recorded preview to {}",
+                    "✓".green(),
+                    path.display()
+                ))
+            );
+        } else if terminal.no_pager {
+            print!("{}", output);
+        } else {
+            pager::page(&output)?;
+        }
     }
+
+    Ok(())
 }
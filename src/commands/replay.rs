@@ -0,0 +1,211 @@
+use crate::config::load_config;
+use crate::db::init_db;
+use crate::stats::board::Board;
+use crate::stats::range::TimeRange;
+use crate::stats::replay::{Replay, ReplaySpeed, ReplayStep};
+use crate::stats::{StatsCalculator, StatsFilter};
+use crate::ui::cast::{Cast, CAST_COLUMNS};
+use crate::ui::{plain, AsciiHeatmap};
+use anyhow::{bail, Result};
+use chrono::{Duration, NaiveDateTime};
+use crossterm::style::Stylize;
+use crossterm::{cursor, execute, terminal};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
+
+/// Frames drawn per second of playback
+const FRAMES_PER_SECOND: i32 = 10;
+
+/// Steps through a replay a frame at a time, adding up the key presses recorded up to
+/// each frame's time
+struct Playback {
+    replay: Replay,
+    /// Recorded time each frame moves on by
+    advance: Duration,
+    end: NaiveDateTime,
+    time: NaiveDateTime,
+    /// Number of the replay's steps added to `counts` so far
+    played: usize,
+    counts: HashMap<String, i64>,
+}
+
+impl Playback {
+    /// None when nothing was recorded to replay
+    fn new(replay: Replay, advance: Duration) -> Option<Self> {
+        let first = replay.frames.first()?.start;
+        let end = replay.frames.last()?.start + replay.step.duration();
+        Some(Self {
+            replay,
+            advance,
+            end,
+            time: first,
+            played: 0,
+            counts: HashMap::new(),
+        })
+    }
+
+    fn finished(&self) -> bool {
+        self.time >= self.end
+    }
+}
+
+impl Iterator for Playback {
+    type Item = NaiveDateTime;
+
+    /// Move on to the next frame, returning its time
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        if self.finished() {
+            return None;
+        }
+        self.time = self
+            .time
+            .checked_add_signed(self.advance)
+            .map_or(self.end, |time| time.min(self.end));
+        for recorded in self.replay.frames[self.played..]
+            .iter()
+            .take_while(|f| f.start < self.time)
+        {
+            for (key, count) in &recorded.keys {
+                *self.counts.entry(key.clone()).or_default() += count;
+            }
+            self.played += 1;
+        }
+        Some(self.time)
+    }
+}
+
+/// Animate the heatmap filling up with the key presses recorded since `since`, playing
+/// `speed` of recorded time each second, or record the animation to an asciinema cast
+/// at `cast`
+pub async fn run(speed: ReplaySpeed, since: Option<&str>, cast: Option<&Path>) -> Result<()> {
+    if cast.is_none() && !io::stdout().is_terminal() {
+        bail!("kitmap replay animates in a terminal; run `kitmap preview` for a still heatmap, or record the replay with --cast");
+    }
+    let filter = StatsFilter::from(
+        since
+            .map(TimeRange::parse_span)
+            .transpose()?
+            .unwrap_or_default(),
+    );
+
+    // Hours when a frame covers less than a day, so slow replays don't jump a day at a time
+    let advance = speed.per_second() / FRAMES_PER_SECOND;
+    let step = if advance < Duration::days(1) {
+        ReplayStep::Hour
+    } else {
+        ReplayStep::Day
+    };
+
+    let db = init_db()?;
+    let config = load_config()?;
+    let board = Board::from_config(&config.keyboard)?;
+    let replay = StatsCalculator::new(db).replay(&filter, step)?;
+    let Some(playback) = Playback::new(replay, advance) else {
+        status!("{}", "No key presses recorded to replay.".yellow());
+        return Ok(());
+    };
+
+    match cast {
+        Some(path) => record(playback, &board, speed, path),
+        None => play(playback, &board, speed).await,
+    }
+}
+
+/// Draw each frame over the last one in the terminal's alternate screen, until the replay
+/// is over or Ctrl+C is pressed
+async fn play(mut playback: Playback, board: &Board, speed: ReplaySpeed) -> Result<()> {
+    let columns = plain::terminal_columns();
+    let mut stdout = io::stdout();
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let mut frame = String::new();
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let interrupted = loop {
+        let Some(time) = playback.next() else {
+            break false;
+        };
+        frame = render_frame(&playback.counts, board, time, speed, columns, true);
+        // Drawn over the last frame, which is only cleared below it, to avoid flicker
+        execute!(stdout, cursor::MoveTo(0, 0))?;
+        stdout.write_all(frame.as_bytes())?;
+        execute!(stdout, terminal::Clear(terminal::ClearType::FromCursorDown))?;
+
+        if playback.finished() {
+            break false;
+        }
+        let pause = std::time::Duration::from_millis(1000 / FRAMES_PER_SECOND as u64);
+        tokio::select! {
+            _ = tokio::time::sleep(pause) => {}
+            _ = &mut ctrl_c => break true,
+        }
+    };
+
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+    // The last frame drawn stays on screen once the replay is over
+    print!("{}", frame);
+    if interrupted {
+        status!("{}", "Replay stopped.".dark_grey());
+    }
+    Ok(())
+}
+
+/// Capture every frame at the time it would be drawn and write them to an asciinema cast
+fn record(mut playback: Playback, board: &Board, speed: ReplaySpeed, path: &Path) -> Result<()> {
+    let interval = std::time::Duration::from_millis(1000 / FRAMES_PER_SECOND as u64);
+    let mut cast = Cast::new().with_title("kitmap replay");
+    let mut at = std::time::Duration::ZERO;
+    while let Some(time) = playback.next() {
+        let frame = render_frame(
+            &playback.counts,
+            board,
+            time,
+            speed,
+            Some(CAST_COLUMNS),
+            false,
+        );
+        cast.capture(at, &frame);
+        at += interval;
+    }
+    cast.write(BufWriter::new(File::create(path)?))?;
+    eprintln!(
+        "{}",
+        plain::finish(&format!(
+            "{} Recorded replay to {}",
+            "✓".green(),
+            path.display()
+        ))
+    );
+    Ok(())
+}
+
+/// The heatmap with every key press up to `time`, under a line saying when that is, fit
+/// in `columns`. Only `live` frames, drawn while playing, say how to stop.
+fn render_frame(
+    counts: &HashMap<String, i64>,
+    board: &Board,
+    time: NaiveDateTime,
+    speed: ReplaySpeed,
+    columns: Option<usize>,
+    live: bool,
+) -> String {
+    let total: i64 = counts.values().sum();
+    let mut heatmap = AsciiHeatmap::from_counts(counts.clone()).with_board(board.clone());
+    if let Some(columns) = columns {
+        heatmap = heatmap.with_columns(columns);
+    }
+
+    let mut details = format!("{} keys · {}", total, speed);
+    if live {
+        details.push_str(" · Ctrl+C to stop");
+    }
+    let heading = format!(
+        "{}  {}  {}",
+        "⏩ KitMap - Replay".cyan().bold(),
+        time.format("%Y-%m-%d %H:%M"),
+        details.dark_grey()
+    );
+    plain::finish(&format!("{}\n{}", heading, heatmap.render()))
+}
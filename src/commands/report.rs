@@ -0,0 +1,229 @@
+use crate::config::load_config;
+#[cfg(feature = "wasm")]
+use crate::config::PluginConfig;
+use crate::db::init_db;
+use crate::stats::board::Board;
+#[cfg(feature = "wasm")]
+use crate::stats::plugins;
+#[cfg(feature = "wasm")]
+use crate::stats::range::TimeRange;
+use crate::stats::range::{parse_time, Period};
+use crate::stats::{StatsCalculator, StatsFilter};
+#[cfg(feature = "wasm")]
+use crate::ui::markdown::render_plugin_sections;
+use crate::ui::markdown::{
+    render_app_shortcuts, render_day_summary, render_period_report, render_seasonality,
+    DEFAULT_TEMPLATE,
+};
+#[cfg(feature = "wasm")]
+use crate::ui::plain;
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate};
+use crossterm::style::Stylize;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Number of most pressed keys listed for each month of the seasonality report
+const SEASONALITY_TOP_KEYS: usize = 3;
+
+/// Number of most typed in apps whose shortcut use periodic reports list
+const REPORT_APPS: usize = 10;
+
+pub async fn run(
+    obsidian: bool,
+    vault: Option<PathBuf>,
+    date: Option<String>,
+    seasonality: bool,
+    period: Option<Period>,
+) -> Result<()> {
+    let config = load_config()?;
+
+    if seasonality {
+        let calculator = StatsCalculator::new(init_db()?);
+        let seasonality = calculator.seasonality(&StatsFilter::default(), SEASONALITY_TOP_KEYS)?;
+        status!("{}", render_seasonality(&seasonality).trim_end());
+        return Ok(());
+    }
+
+    let date = match date {
+        Some(input) => parse_time(&input)?.date_naive(),
+        None => Local::now().date_naive(),
+    };
+
+    if let Some(period) = period {
+        let calculator =
+            StatsCalculator::new(init_db()?).with_board(Board::from_config(&config.keyboard)?);
+        let start = period.start(date);
+        let previous_start = period.previous(start);
+        let current = calculator.summary(&period.range(start)?.into())?;
+        let previous = calculator.summary(&period.range(previous_start)?.into())?;
+        let mut report = render_period_report(
+            period,
+            &period.name(start),
+            &period.name(previous_start),
+            &current,
+            &previous,
+        );
+        let apps = calculator.app_shortcuts(&period.range(start)?.into(), REPORT_APPS)?;
+        if !apps.is_empty() {
+            report.push_str(&render_app_shortcuts(&apps, 3));
+        }
+        #[cfg(feature = "wasm")]
+        let report = format!(
+            "{}\n{}",
+            report.trim_end(),
+            plugin_sections(
+                &config.plugins,
+                &calculator,
+                &period.range(start)?.into(),
+                3
+            )?
+        );
+        status!("{}", report.trim_end());
+        return Ok(());
+    }
+
+    let db = init_db()?;
+    let calculator = StatsCalculator::new(db).with_score_config(config.score.clone());
+    let summary = calculator.day_summary(&StatsFilter::default(), date)?;
+
+    let obsidian_config = config.obsidian;
+    let template = obsidian_config
+        .template
+        .as_deref()
+        .unwrap_or(DEFAULT_TEMPLATE);
+    let block = render_day_summary(&summary, template);
+    #[cfg(feature = "wasm")]
+    let block = format!(
+        "{}\n{}",
+        block.trim_end(),
+        plugin_sections(
+            &config.plugins,
+            &calculator,
+            &TimeRange::day(date)?.into(),
+            heading_level(&obsidian_config.heading).unwrap_or(1) + 1,
+        )?
+    );
+
+    if !obsidian {
+        status!("{}\n\n{}", obsidian_config.heading, block.trim_end());
+        return Ok(());
+    }
+
+    let vault = vault.or(obsidian_config.vault).ok_or_else(|| {
+        anyhow!("No Obsidian vault given. Pass --vault or set `vault` under [obsidian] in the config file")
+    })?;
+    if !vault.is_dir() {
+        return Err(anyhow!("Vault {} does not exist", vault.display()));
+    }
+
+    let note_path = daily_note_path(
+        &vault.join(&obsidian_config.daily_notes_folder),
+        date,
+        &obsidian_config.date_format,
+    )?;
+    if let Some(parent) = note_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let note = match std::fs::read_to_string(&note_path) {
+        Ok(note) => note,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let updated = upsert_section(&note, &obsidian_config.heading, &block);
+    std::fs::write(&note_path, updated)?;
+
+    status!(
+        "{} Typing summary for {} written to {}",
+        "✓".green(),
+        summary.date,
+        note_path.display()
+    );
+
+    Ok(())
+}
+
+/// Sections the configured plugins add to a report covering `filter`, under headings of
+/// `level`. A plugin that fails is skipped with a warning.
+#[cfg(feature = "wasm")]
+fn plugin_sections(
+    configs: &[PluginConfig],
+    calculator: &StatsCalculator,
+    filter: &StatsFilter,
+    level: usize,
+) -> Result<String> {
+    let plugins = plugins::from_config(configs)?;
+    if plugins.is_empty() {
+        return Ok(String::new());
+    }
+    let stats = calculator.calculate_all(filter)?;
+
+    let mut sections = Vec::new();
+    for plugin in &plugins {
+        match plugin.analyze(&stats) {
+            Ok(added) => sections.extend(added),
+            Err(e) => eprintln!(
+                "{}",
+                plain::finish(
+                    &format!("⚠️  Plugin {} failed: {:#}", plugin.name(), e)
+                        .yellow()
+                        .to_string()
+                )
+            ),
+        }
+    }
+    Ok(render_plugin_sections(&sections, level))
+}
+
+/// Path of the daily note for `date` inside `folder`
+fn daily_note_path(folder: &Path, date: NaiveDate, date_format: &str) -> Result<PathBuf> {
+    let mut name = String::new();
+    write!(name, "{}", date.format(date_format))
+        .map_err(|_| anyhow!("Invalid daily note date format '{}'", date_format))?;
+
+    Ok(folder.join(format!("{}.md", name)))
+}
+
+/// Replace the section under `heading` with `block`, or append it when the note lacks one.
+///
+/// A section runs until the next heading of the same or a higher level, so running the
+/// report again on the same day updates the summary instead of duplicating it.
+fn upsert_section(note: &str, heading: &str, block: &str) -> String {
+    let section = format!("{}\n\n{}\n", heading.trim(), block.trim_end());
+    let level = heading_level(heading).unwrap_or(usize::MAX);
+    let lines: Vec<&str> = note.lines().collect();
+
+    let Some(start) = lines.iter().position(|line| line.trim() == heading.trim()) else {
+        let mut note = note.trim_end().to_string();
+        if !note.is_empty() {
+            note.push_str("\n\n");
+        }
+        note.push_str(&section);
+        return note;
+    };
+
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| heading_level(line).is_some_and(|l| l <= level))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+
+    let mut updated = lines[..start].join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    updated.push_str(&section);
+    if end < lines.len() {
+        updated.push('\n');
+        updated.push_str(&lines[end..].join("\n"));
+        updated.push('\n');
+    }
+    updated
+}
+
+/// Markdown heading level of a line (`## Foo` is 2), if it is a heading
+fn heading_level(line: &str) -> Option<usize> {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[hashes..];
+    (hashes > 0 && (rest.is_empty() || rest.starts_with(' '))).then_some(hashes)
+}
@@ -1,44 +1,420 @@
+use crate::db::audit::{self, AuditAction};
 use crate::db::{init_db, schema};
+use crate::stats::range::{TimeRange, RANGE_CONDITION};
 use anyhow::Result;
 use crossterm::style::Stylize;
+use rusqlite::Connection;
 use std::io::{self, Write};
 
-pub async fn run(force: bool) -> Result<()> {
-    println!("{}", "🗑️  KitMap - Reset Data".cyan().bold());
-    println!("{}", "━".repeat(40).dark_grey());
-    println!();
+/// Recorded data that can be reset on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ResetTable {
+    /// Key presses, along with the key counts and per-minute totals built from them
+    Events,
+    /// Modifier combos
+    Combos,
+    /// Chords of keys pressed together
+    Chords,
+    /// Recording sessions, by when they started, along with the key presses, bigrams,
+    /// latency reports and break reminders recorded in them
+    Sessions,
+    /// Typing speed samples
+    Samples,
+    /// Bursts of fast typing
+    Bursts,
+    /// Counts of consecutive key pairs, of sessions that ran at any time in the range
+    Bigrams,
+    /// Summarized bursts of a combo firing at automated rates, by when they started
+    Storms,
+    /// Break reminders, by when they were shown
+    Breaks,
+    /// Key handling latency reports
+    Latency,
+    /// Key presses imported from other trackers, such as ActivityWatch
+    Imported,
+}
+
+/// Sessions that started within the range bound as ?1 (since) and ?2 (until)
+const SESSION_CONDITION: &str = "(?1 IS NULL OR start_ms >= ?1) AND (?2 IS NULL OR start_ms < ?2)";
+
+/// Tables with rows that belong to a session, deleted along with it
+const SESSION_TABLES: [&str; 4] = [
+    "key_events",
+    "key_bigrams",
+    "latency_reports",
+    "break_reminders",
+];
+
+impl ResetTable {
+    const ALL: &'static [ResetTable] = &[
+        ResetTable::Events,
+        ResetTable::Combos,
+        ResetTable::Chords,
+        ResetTable::Sessions,
+        ResetTable::Samples,
+        ResetTable::Bursts,
+        ResetTable::Bigrams,
+        ResetTable::Storms,
+        ResetTable::Breaks,
+        ResetTable::Latency,
+        ResetTable::Imported,
+    ];
+
+    fn table(self) -> &'static str {
+        match self {
+            ResetTable::Events => "key_events",
+            ResetTable::Combos => "key_combos",
+            ResetTable::Chords => "key_chords",
+            ResetTable::Sessions => "sessions",
+            ResetTable::Samples => "typing_samples",
+            ResetTable::Bursts => "typing_bursts",
+            ResetTable::Bigrams => "key_bigrams",
+            ResetTable::Storms => "combo_storms",
+            ResetTable::Breaks => "break_reminders",
+            ResetTable::Latency => "latency_reports",
+            ResetTable::Imported => "imported_activity",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ResetTable::Events => "key events",
+            ResetTable::Combos => "combos",
+            ResetTable::Chords => "chords",
+            ResetTable::Sessions => "sessions",
+            ResetTable::Samples => "typing samples",
+            ResetTable::Bursts => "typing bursts",
+            ResetTable::Bigrams => "bigram counts",
+            ResetTable::Storms => "combo storms",
+            ResetTable::Breaks => "break reminders",
+            ResetTable::Latency => "latency reports",
+            ResetTable::Imported => "imported press counts",
+        }
+    }
+
+    /// Rows of this table within the range bound as ?1 (since) and ?2 (until)
+    fn condition(self) -> &'static str {
+        match self {
+            ResetTable::Sessions => SESSION_CONDITION,
+            // Bigrams are counted per session, so sessions overlapping the range lose theirs
+            ResetTable::Bigrams => {
+                "session_id IN (SELECT id FROM sessions
+                     WHERE (?2 IS NULL OR start_ms < ?2)
+                       AND (?1 IS NULL OR end_ms IS NULL OR end_ms >= ?1))"
+            }
+            ResetTable::Storms => {
+                "(?1 IS NULL OR start_ms >= ?1) AND (?2 IS NULL OR start_ms < ?2)"
+            }
+            ResetTable::Breaks => {
+                "(?1 IS NULL OR reminded_ms >= ?1) AND (?2 IS NULL OR reminded_ms < ?2)"
+            }
+            _ => RANGE_CONDITION,
+        }
+    }
+}
+
+/// Delete recorded data: everything, or only some tables and rows recorded between
+/// `after` and `before`
+pub async fn run(
+    force: bool,
+    before: Option<String>,
+    after: Option<String>,
+    only: Vec<ResetTable>,
+) -> Result<()> {
+    if before.is_none() && after.is_none() && only.is_empty() {
+        return reset_all(force).await;
+    }
+
+    let range = TimeRange::parse(after.as_deref(), before.as_deref())?;
+    let tables = if only.is_empty() {
+        ResetTable::ALL.to_vec()
+    } else {
+        only
+    };
+    let span = describe_range(&range);
+
+    status!("{}", "🗑️  KitMap - Reset Data".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    let db = init_db()?;
+    let counts = db.read(|conn| {
+        tables
+            .iter()
+            .map(|table| {
+                let count: i64 = conn.query_row(
+                    &format!(
+                        "SELECT COUNT(*) FROM {} WHERE {}",
+                        table.table(),
+                        table.condition()
+                    ),
+                    bounds(&range),
+                    |row| row.get(0),
+                )?;
+                Ok((*table, count))
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+    // Key presses recorded in deleted sessions go with them, even outside the range
+    let session_keys: i64 = if tables.contains(&ResetTable::Sessions) {
+        db.read(|conn| {
+            Ok(conn.query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM key_events WHERE session_id IN
+                         (SELECT id FROM sessions WHERE {})",
+                    SESSION_CONDITION
+                ),
+                bounds(&range),
+                |row| row.get(0),
+            )?)
+        })?
+    } else {
+        0
+    };
+
+    let mut summary = counts
+        .iter()
+        .map(|(table, count)| format!("{} {}", count, table.label()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if session_keys > 0 {
+        summary.push_str(&format!(
+            " (and the {} key events recorded in those sessions)",
+            session_keys
+        ));
+    }
+    if counts.iter().all(|(_, count)| *count == 0) {
+        status!("Nothing was recorded{}.", span);
+        status!();
+        return Ok(());
+    }
 
     if !force {
-        println!(
+        status!(
             "{}",
-            "⚠️  Warning: This will delete ALL recorded keyboard data!".yellow()
+            format!(
+                "⚠️  Warning: This will delete {} recorded{}!",
+                summary, span
+            )
+            .yellow()
         );
-        println!();
-        print!("Are you sure you want to continue? [y/N]: ");
-        io::stdout().flush()?;
+        status!();
+        if !confirm()? {
+            return Ok(());
+        }
+    }
+
+    status!();
+    status!("{} Deleting data...", "→".dark_grey());
+    db.call(move |conn| delete(conn, &tables, &range)).await?;
+    audit::record(
+        &db,
+        AuditAction::Reset,
+        &format!(
+            "Deleted {} recorded{}{}",
+            summary,
+            span,
+            if force { " with --force" } else { "" }
+        ),
+    )?;
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+    status!("{} Deleted {} recorded{}", "✓".green(), summary, span);
+    status!();
 
-        let input = input.trim().to_lowercase();
-        if input != "y" && input != "yes" {
-            println!();
-            println!("{}", "Operation cancelled.".dark_grey());
+    Ok(())
+}
+
+async fn reset_all(force: bool) -> Result<()> {
+    status!("{}", "🗑️  KitMap - Reset Data".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    if !force {
+        status!(
+            "{}",
+            "⚠️  Warning: This will delete ALL recorded keyboard data!".yellow()
+        );
+        status!();
+        if !confirm()? {
             return Ok(());
         }
     }
 
-    println!();
-    println!("{} Clearing database...", "→".dark_grey());
+    status!();
+    status!("{} Clearing database...", "→".dark_grey());
 
     let db = init_db()?;
-    {
-        let conn = db.lock().unwrap();
-        schema::clear_all_data(&conn)?;
-    }
+    let keys = db
+        .call(|conn| {
+            let keys: i64 =
+                conn.query_row("SELECT COUNT(*) FROM key_events", [], |row| row.get(0))?;
+            schema::clear_all_data(conn)?;
+            Ok(keys)
+        })
+        .await?;
+    audit::record(
+        &db,
+        AuditAction::Reset,
+        &format!(
+            "Cleared all recorded data ({} key events){}",
+            keys,
+            if force { " with --force" } else { "" }
+        ),
+    )?;
 
-    println!("{} All keyboard data has been cleared!", "✓".green());
-    println!();
+    status!("{} All keyboard data has been cleared!", "✓".green());
+    status!();
 
     Ok(())
 }
+
+/// Delete the rows of `tables` within `range`, and the rows recorded in deleted sessions
+fn delete(conn: &mut Connection, tables: &[ResetTable], range: &TimeRange) -> Result<()> {
+    let tx = conn.transaction()?;
+    // Sessions go last, since bigrams are matched through them
+    for table in tables.iter().filter(|t| **t != ResetTable::Sessions) {
+        tx.execute(
+            &format!("DELETE FROM {} WHERE {}", table.table(), table.condition()),
+            bounds(range),
+        )?;
+    }
+    if tables.contains(&ResetTable::Sessions) {
+        for child in SESSION_TABLES {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {} WHERE session_id IN (SELECT id FROM sessions WHERE {})",
+                    child, SESSION_CONDITION
+                ),
+                bounds(range),
+            )?;
+        }
+        tx.execute(
+            &format!("DELETE FROM sessions WHERE {}", SESSION_CONDITION),
+            bounds(range),
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Ask before deleting, returning whether the user agreed
+fn confirm() -> Result<bool> {
+    status_print!("Are you sure you want to continue? [y/N]: ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let input = input.trim().to_lowercase();
+    if input != "y" && input != "yes" {
+        status!();
+        status!("{}", "Operation cancelled.".dark_grey());
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// The range bound as ?1 (since) and ?2 (until), in epoch milliseconds
+fn bounds(range: &TimeRange) -> [rusqlite::types::Value; 2] {
+    use rusqlite::types::Value;
+    [
+        range.since_param().map_or(Value::Null, Value::Integer),
+        range.until_param().map_or(Value::Null, Value::Integer),
+    ]
+}
+
+/// The range as it reads after "recorded", e.g. ` between 2024-03-05 00:00 and 2024-03-06 00:00`
+fn describe_range(range: &TimeRange) -> String {
+    let format = |t: &chrono::DateTime<chrono::Local>| t.format("%Y-%m-%d %H:%M").to_string();
+    match (&range.since, &range.until) {
+        (Some(since), Some(until)) => format!(" between {} and {}", format(since), format(until)),
+        (Some(since), None) => format!(" since {}", format(since)),
+        (None, Some(until)) => format!(" before {}", format(until)),
+        (None, None) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::range::local_midnight;
+    use chrono::NaiveDate;
+
+    fn recorded() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO sessions (id, start_time, end_time) VALUES
+                 (1, '2024-02-10T09:00:00.000Z', '2024-02-10T10:00:00.000Z'),
+                 (2, '2024-03-10T09:00:00.000Z', '2024-03-10T10:00:00.000Z');
+             INSERT INTO key_events
+                 (key_code, key_name, timestamp, timestamp_ms, hour, day_of_week, session_id)
+             VALUES
+                 ('KeyA', 'KeyA', '2024-02-10T09:30:00.000Z', 1707557400000, 9, 5, 1),
+                 ('KeyB', 'KeyB', '2024-03-10T09:30:00.000Z', 1710063000000, 9, 6, 2);
+             INSERT INTO key_combos (combo, timestamp, timestamp_ms) VALUES
+                 ('Ctrl+C', '2024-02-10T09:30:00.000Z', 1707557400000),
+                 ('Ctrl+V', '2024-03-10T09:30:00.000Z', 1710063000000);
+             INSERT INTO key_bigrams (session_id, first_key, second_key, count) VALUES
+                 (1, 'KeyA', 'KeyB', 2),
+                 (2, 'KeyB', 'KeyA', 3);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn since_march() -> TimeRange {
+        TimeRange {
+            since: Some(local_midnight(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()).unwrap()),
+            until: None,
+        }
+    }
+
+    fn names(conn: &Connection, sql: &str) -> Vec<String> {
+        let mut stmt = conn.prepare(sql).unwrap();
+        let rows = stmt.query_map([], |row| row.get(0));
+        rows.unwrap().collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn deletes_only_the_tables_and_range_asked_for() {
+        let mut conn = recorded();
+        delete(&mut conn, &[ResetTable::Combos], &since_march()).unwrap();
+
+        assert_eq!(names(&conn, "SELECT combo FROM key_combos"), ["Ctrl+C"]);
+        assert_eq!(
+            names(&conn, "SELECT key_name FROM key_events ORDER BY id"),
+            ["KeyA", "KeyB"]
+        );
+    }
+
+    #[test]
+    fn deletes_sessions_with_what_was_recorded_in_them() {
+        let mut conn = recorded();
+        delete(&mut conn, &[ResetTable::Sessions], &since_march()).unwrap();
+
+        assert_eq!(
+            names(&conn, "SELECT start_time FROM sessions"),
+            ["2024-02-10T09:00:00.000Z"]
+        );
+        assert_eq!(names(&conn, "SELECT key_name FROM key_events"), ["KeyA"]);
+        assert_eq!(names(&conn, "SELECT first_key FROM key_bigrams"), ["KeyA"]);
+        // The key counts follow the deleted key events
+        assert_eq!(names(&conn, "SELECT key_name FROM key_counts"), ["KeyA"]);
+        assert_eq!(
+            names(&conn, "SELECT combo FROM key_combos ORDER BY id"),
+            ["Ctrl+C", "Ctrl+V"]
+        );
+    }
+
+    #[test]
+    fn deletes_bigrams_of_sessions_overlapping_the_range() {
+        let mut conn = recorded();
+        let february = TimeRange::day(NaiveDate::from_ymd_opt(2024, 2, 10).unwrap()).unwrap();
+        delete(&mut conn, &[ResetTable::Bigrams], &february).unwrap();
+
+        assert_eq!(names(&conn, "SELECT first_key FROM key_bigrams"), ["KeyB"]);
+        // The sessions themselves stay
+        assert_eq!(names(&conn, "SELECT start_time FROM sessions").len(), 2);
+    }
+}
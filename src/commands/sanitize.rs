@@ -0,0 +1,54 @@
+use crate::db::analyze::format_bytes;
+use crate::db::{init_db, sanitize};
+use anyhow::{bail, Result};
+use crossterm::style::Stylize;
+use std::path::Path;
+
+pub async fn run(output: &Path, force: bool) -> Result<()> {
+    status!("{}", "🧹 KitMap - Sanitize Database".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    if output.exists() {
+        if !force {
+            bail!(
+                "{} already exists; pass --force to overwrite it",
+                output.display()
+            );
+        }
+        std::fs::remove_file(output)?;
+    }
+
+    status!("{} Writing a redacted copy...", "→".dark_grey());
+    let db = init_db()?;
+    let summary = db.read(|conn| sanitize::sanitize(conn, output))?;
+    let size = std::fs::metadata(output)?.len();
+
+    status!(
+        "{} Sanitized copy written to {} ({})",
+        "✓".green(),
+        output.display().to_string().cyan(),
+        format_bytes(size as f64)
+    );
+    status!();
+    status!("   Key events: {}", summary.key_events);
+    status!("   Letter and number keys hashed: {}", summary.keys_hashed);
+    status!(
+        "   Combos generalized: {} → {}",
+        summary.combos_before,
+        summary.combos_after
+    );
+    status!(
+        "   Workspace and project names hashed: {}",
+        summary.contexts_hashed
+    );
+    status!("   Timestamps coarsened to the hour; key order shuffled within each hour");
+    status!();
+    status!(
+        "{}",
+        "Hashes are salted for this copy only, so they can't be matched against other copies."
+            .dark_grey()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,289 @@
+use crate::db::activitywatch::AwExport;
+use crate::db::export::{ComboRecord, DynamicsRecord, KeyEventRecord};
+use crate::server::api::Capabilities;
+use crate::server::grafana::GrafanaSeries;
+use crate::stats::blocks::ActivityBlock;
+use crate::stats::calculator::{
+    AllStats, ChordStats, ComboStats, DaySummary, HourlyStats, KeyHeat, KeyStats, LiveKeys,
+    Seasonality, SessionStats,
+};
+use crate::stats::calendar::CalendarDay;
+use crate::stats::replay::Replay;
+use crate::stats::rolls::RollAnalysis;
+use crate::stats::score::DailyScore;
+use crate::stats::sequences::KeySequences;
+use crate::stats::speed::DailySpeed;
+use crate::ui::plain;
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use crossterm::style::Stylize;
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Output formats with a published JSON Schema
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum SchemaName {
+    /// `GET /api/stats` and `/api/stats/stream` events
+    Stats,
+    /// `GET /api/keys`
+    Keys,
+    /// `GET /api/keys/stream` events
+    LiveKeys,
+    /// `GET /api/combos`
+    Combos,
+    /// `GET /api/chords`
+    Chords,
+    /// `GET /api/hourly`
+    Hourly,
+    /// `GET /api/heatmap`
+    Heatmap,
+    /// `GET /api/replay`
+    Replay,
+    /// `GET /api/sessions`
+    Sessions,
+    /// `GET /api/score`
+    Score,
+    /// `GET /api/speed`
+    Speed,
+    /// `GET /api/calendar`
+    Calendar,
+    /// `POST /api/grafana/query`
+    GrafanaQuery,
+    /// `GET /api/seasonality`
+    Seasonality,
+    /// `GET /api/rolls`
+    Rolls,
+    /// `GET /api/sequences`
+    Sequences,
+    /// `GET /api/capabilities`
+    Capabilities,
+    /// A single day's report summary
+    DaySummary,
+    /// `kitmap export --data keys --format json`
+    ExportKeys,
+    /// `kitmap export --data combos --format json`
+    ExportCombos,
+    /// `kitmap export --data blocks --format json`
+    ExportBlocks,
+    /// `kitmap export --data dynamics --format json`
+    ExportDynamics,
+    /// `kitmap export --format activitywatch`
+    Activitywatch,
+}
+
+impl SchemaName {
+    fn schema(&self) -> RootSchema {
+        match self {
+            Self::Stats => schema_for!(AllStats),
+            Self::Keys => schema_for!(Vec<KeyStats>),
+            Self::LiveKeys => schema_for!(LiveKeys),
+            Self::Combos => schema_for!(Vec<ComboStats>),
+            Self::Chords => schema_for!(Vec<ChordStats>),
+            Self::Hourly => schema_for!(Vec<HourlyStats>),
+            Self::Heatmap => schema_for!(Vec<KeyHeat>),
+            Self::Replay => schema_for!(Replay),
+            Self::Sessions => schema_for!(Vec<SessionStats>),
+            Self::Score => schema_for!(Vec<DailyScore>),
+            Self::Speed => schema_for!(Vec<DailySpeed>),
+            Self::Calendar => schema_for!(Vec<CalendarDay>),
+            Self::GrafanaQuery => schema_for!(Vec<GrafanaSeries>),
+            Self::Seasonality => schema_for!(Seasonality),
+            Self::Rolls => schema_for!(RollAnalysis),
+            Self::Sequences => schema_for!(KeySequences),
+            Self::Capabilities => schema_for!(Capabilities),
+            Self::DaySummary => schema_for!(DaySummary),
+            Self::ExportKeys => schema_for!(Vec<KeyEventRecord>),
+            Self::ExportCombos => schema_for!(Vec<ComboRecord>),
+            Self::ExportBlocks => schema_for!(Vec<ActivityBlock>),
+            Self::ExportDynamics => schema_for!(Vec<DynamicsRecord>),
+            Self::Activitywatch => schema_for!(AwExport),
+        }
+    }
+
+    /// File name of the schema's snapshot, e.g. `day-summary.json`
+    fn file_name(&self) -> String {
+        let name = self
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        format!("{}.json", name)
+    }
+}
+
+pub async fn run(name: Option<SchemaName>, check: Option<PathBuf>) -> Result<()> {
+    if let Some(dir) = check {
+        return check_snapshots(&dir);
+    }
+
+    if let Some(name) = name {
+        println!("{}", serde_json::to_string_pretty(&name.schema())?);
+    }
+    Ok(())
+}
+
+/// Verify every current schema is backward compatible with its snapshot in `dir`.
+///
+/// Within an API version, fields may be added but never removed, retyped or made
+/// optional. Snapshots are the published contract; regenerate them only when
+/// releasing a change listed in API_CHANGELOG.md.
+fn check_snapshots(dir: &Path) -> Result<()> {
+    let mut problems = Vec::new();
+
+    for name in SchemaName::value_variants() {
+        let path = dir.join(name.file_name());
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            problems.push(format!("{}: snapshot missing", path.display()));
+            continue;
+        };
+
+        let published: Value = serde_json::from_str(&contents)?;
+        let current = serde_json::to_value(name.schema())?;
+        for problem in compatibility_problems(&published, &current) {
+            problems.push(format!("{}: {}", name.file_name(), problem));
+        }
+    }
+
+    if problems.is_empty() {
+        status!(
+            "{} All schemas are compatible with {}",
+            "✓".green(),
+            dir.display()
+        );
+        return Ok(());
+    }
+
+    for problem in &problems {
+        eprintln!("{}", plain::finish(&format!("{} {}", "✗".red(), problem)));
+    }
+    bail!("{} breaking schema change(s)", problems.len())
+}
+
+/// Breaking differences between a published schema and the current one
+fn compatibility_problems(published: &Value, current: &Value) -> Vec<String> {
+    let mut problems = Vec::new();
+    compare(published, current, "#", &mut problems);
+
+    let empty = Map::new();
+    let old_definitions = published["definitions"].as_object().unwrap_or(&empty);
+    let new_definitions = current["definitions"].as_object().unwrap_or(&empty);
+    for (name, old) in old_definitions {
+        match new_definitions.get(name) {
+            Some(new) => compare(old, new, name, &mut problems),
+            None => problems.push(format!("type {} was removed", name)),
+        }
+    }
+
+    problems
+}
+
+fn compare(old: &Value, new: &Value, path: &str, problems: &mut Vec<String>) {
+    for key in ["type", "$ref", "format"] {
+        if old.get(key) != new.get(key) {
+            problems.push(format!(
+                "{} changed {} from {} to {}",
+                path,
+                key,
+                old.get(key).unwrap_or(&Value::Null),
+                new.get(key).unwrap_or(&Value::Null)
+            ));
+        }
+    }
+
+    if let (Some(old_items), Some(new_items)) = (old.get("items"), new.get("items")) {
+        compare(old_items, new_items, &format!("{}[]", path), problems);
+    }
+
+    let required = |schema: &Value| -> Vec<String> {
+        schema["required"]
+            .as_array()
+            .map(|r| {
+                r.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let new_required = required(new);
+    for field in required(old) {
+        if !new_required.contains(&field) {
+            problems.push(format!("{}.{} is no longer always present", path, field));
+        }
+    }
+
+    if let Some(old_properties) = old["properties"].as_object() {
+        for (field, old_field) in old_properties {
+            match new["properties"].get(field) {
+                Some(new_field) => compare(
+                    old_field,
+                    new_field,
+                    &format!("{}.{}", path, field),
+                    problems,
+                ),
+                None => problems.push(format!("{}.{} was removed", path, field)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn published() -> Value {
+        json!({
+            "type": "object",
+            "required": ["count", "key"],
+            "properties": {
+                "key": { "type": "string" },
+                "count": { "type": "integer", "format": "int64" },
+                "keys": { "type": "array", "items": { "$ref": "#/definitions/Key" } }
+            },
+            "definitions": {
+                "Key": { "type": "object", "properties": { "name": { "type": "string" } } }
+            }
+        })
+    }
+
+    #[test]
+    fn additions_are_compatible() {
+        let mut current = published();
+        current["properties"]["share"] = json!({ "type": "number" });
+        current["definitions"]["Combo"] = json!({ "type": "object" });
+        current["definitions"]["Key"]["properties"]["label"] = json!({ "type": "string" });
+
+        assert!(compatibility_problems(&published(), &published()).is_empty());
+        assert!(compatibility_problems(&published(), &current).is_empty());
+    }
+
+    #[test]
+    fn removed_and_optional_fields_are_breaking() {
+        let mut current = published();
+        current["properties"].as_object_mut().unwrap().remove("key");
+        current["required"] = json!(["key"]);
+
+        let problems = compatibility_problems(&published(), &current);
+        assert!(problems.contains(&"#.key was removed".to_string()));
+        assert!(problems.contains(&"#.count is no longer always present".to_string()));
+    }
+
+    #[test]
+    fn retyped_fields_and_removed_types_are_breaking() {
+        let mut current = published();
+        current["properties"]["count"]["format"] = json!("double");
+        current["properties"]["keys"]["items"]["$ref"] = json!("#/definitions/Name");
+        current["definitions"] = json!({});
+
+        let problems = compatibility_problems(&published(), &current);
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems.contains(&"type Key was removed".to_string()));
+    }
+
+    #[test]
+    fn published_snapshots_match_the_current_build() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schemas/v1");
+        check_snapshots(&dir).unwrap();
+    }
+}
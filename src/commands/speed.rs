@@ -0,0 +1,49 @@
+use crate::db::init_db;
+use crate::server::api::MAX_LIMIT;
+use crate::stats::speed::DailySpeed;
+use crate::stats::{StatsCalculator, StatsFilter};
+use crate::ui::heatmap::render_speed_chart;
+use crate::ui::markdown::relative_change;
+use anyhow::Result;
+use crossterm::style::Stylize;
+
+/// Chart average typing speed per day over the last `weeks` weeks, at most as many as the
+/// API allows, then compare the first week with the last
+pub async fn run(weeks: usize) -> Result<()> {
+    let weeks = weeks.clamp(1, MAX_LIMIT);
+    let db = init_db()?;
+    let days = StatsCalculator::new(db).daily_speed(&StatsFilter::default(), weeks)?;
+
+    if days.iter().all(|day| day.samples == 0) {
+        status!(
+            "{}",
+            format!("No typing speed recorded in the last {} weeks.", weeks).yellow()
+        );
+        return Ok(());
+    }
+
+    status_print!("{}", render_speed_chart(&days));
+
+    let weekly: Vec<Option<f64>> = days.chunks(7).map(average_wpm).collect();
+    if let (Some(Some(first)), Some(Some(last))) = (weekly.first(), weekly.last()) {
+        if weekly.len() > 1 {
+            status!(
+                "\nFirst week {:.1} WPM, last week {:.1} WPM ({})",
+                first,
+                last,
+                relative_change(*last, *first)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Average speed over days, weighing each by its samples
+fn average_wpm(days: &[DailySpeed]) -> Option<f64> {
+    let samples: i64 = days.iter().map(|day| day.samples).sum();
+    let total: f64 = days
+        .iter()
+        .filter_map(|day| Some(day.average_wpm? * day.samples as f64))
+        .sum();
+    (samples > 0).then(|| total / samples as f64)
+}
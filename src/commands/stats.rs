@@ -0,0 +1,105 @@
+use crate::config::load_config;
+use crate::db::export::csv_field;
+use crate::db::init_db;
+use crate::stats::board::Board;
+use crate::stats::calculator::{StatsSection, STATS_FIELDS};
+use crate::stats::custom;
+use crate::stats::{StatsCalculator, StatsFilter};
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+/// How `kitmap stats` prints the fields it was asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsOutput {
+    /// One `field: value` line per field, or just the value of a single field
+    Text,
+    /// A JSON object of the fields
+    Json,
+    /// A header row of field names and one row of values
+    Csv,
+}
+
+/// Print overall stats for scripts and status bars, calculating only the sections the
+/// requested fields need. Nested values are printed as compact JSON in text and CSV.
+pub async fn run(output: StatsOutput, fields: Vec<String>) -> Result<()> {
+    let fields = if fields.is_empty() {
+        STATS_FIELDS
+            .iter()
+            .map(|(field, _)| field.to_string())
+            .collect()
+    } else {
+        fields
+    };
+
+    let mut sections: Vec<StatsSection> = Vec::new();
+    for field in &fields {
+        let Some((_, section)) = STATS_FIELDS.iter().find(|(name, _)| name == field) else {
+            let known: Vec<&str> = STATS_FIELDS.iter().map(|(name, _)| *name).collect();
+            bail!(
+                "Unknown field \"{}\", expected one of: {}",
+                field,
+                known.join(", ")
+            );
+        };
+        if let Some(section) = section {
+            if !sections.contains(section) {
+                sections.push(*section);
+            }
+        }
+    }
+
+    let db = init_db()?;
+    let config = load_config()?;
+    let calculator = StatsCalculator::new(db)
+        .with_score_config(config.score.clone())
+        .with_effort_config(config.effort.clone())
+        .with_board(Board::from_config(&config.keyboard)?)
+        .with_metrics(custom::from_config(&config.custom_metrics));
+    let stats = calculator.calculate_sections_cached(&StatsFilter::default(), &sections)?;
+
+    let Value::Object(mut all) = serde_json::to_value(&stats)? else {
+        bail!("Stats did not serialize to an object");
+    };
+    let values: Vec<(String, Value)> = fields
+        .into_iter()
+        .map(|field| {
+            let value = all.remove(&field).unwrap_or(Value::Null);
+            (field, value)
+        })
+        .collect();
+
+    match output {
+        StatsOutput::Json => {
+            let object: Map<String, Value> = values.into_iter().collect();
+            println!("{}", serde_json::to_string_pretty(&object)?);
+        }
+        StatsOutput::Csv => {
+            let header: Vec<String> = values.iter().map(|(field, _)| csv_field(field)).collect();
+            let row: Vec<String> = values
+                .iter()
+                .map(|(_, value)| csv_field(&plain(value)))
+                .collect();
+            println!("{}", header.join(","));
+            println!("{}", row.join(","));
+        }
+        StatsOutput::Text => match values.as_slice() {
+            [(_, value)] => println!("{}", plain(value)),
+            _ => {
+                for (field, value) in &values {
+                    println!("{}: {}", field, plain(value));
+                }
+            }
+        },
+    }
+    Ok(())
+}
+
+/// A value as plain text: strings unquoted, nothing for null, and compact JSON for
+/// arrays and objects
+fn plain(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,135 @@
+use crate::commands::listen::running_listener;
+use crate::db::models::Session;
+use crate::db::{get_db_path, init_db, DbConnection};
+use crate::publish::status_bar::{StatusFormat, StatusSnapshot};
+use crate::server::control::{self, Request};
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use crossterm::style::Stylize;
+
+/// Print a status bar module showing today's keys, current typing speed and whether
+/// kitmap is recording, or without a format, the state of the recorder
+pub async fn run(format: Option<StatusFormat>) -> Result<()> {
+    let db = init_db()?;
+    let Some(format) = format else {
+        return recorder(&db);
+    };
+    let snapshot = StatusSnapshot::capture(&db)?;
+    println!("{}", snapshot.render(format));
+    Ok(())
+}
+
+/// Whether a listener is running, what its session has recorded, and how fresh the data is.
+/// A running listener is asked directly; otherwise this is pieced together from the lock it
+/// holds, its PID file and the database.
+fn recorder(db: &DbConnection) -> Result<()> {
+    let now = Local::now();
+    let live = match control::send(Request::Status) {
+        Ok(live) => live,
+        Err(e) => {
+            eprintln!("Couldn't ask the listener: {:#}", e);
+            None
+        }
+    };
+    let listener = match &live {
+        Some(live) => Some(Some(live.pid)),
+        None => running_listener()?,
+    };
+    let session = match &live {
+        Some(live) => live.session.as_ref().map(|s| (s.started, s.keys as i64)),
+        None => match Session::current(db)? {
+            Some(session) => Some((session.start_time, session_keys(db, session.id)?)),
+            None => None,
+        },
+    };
+    let last_key_ms: Option<i64> = db.read(|conn| {
+        Ok(
+            conn.query_row("SELECT MAX(timestamp_ms) FROM key_events", [], |row| {
+                row.get(0)
+            })?,
+        )
+    })?;
+
+    status!("{}", "🎹 KitMap Status".cyan().bold());
+    status!("{}", "━".repeat(40).dark_grey());
+    status!();
+
+    match listener {
+        Some(Some(pid)) => status!(
+            "Listener:       {} (PID {})",
+            "running".green(),
+            pid.to_string().cyan()
+        ),
+        Some(None) => status!("Listener:       {}", "running".green()),
+        None => status!("Listener:       {}", "not running".red()),
+    }
+
+    match session {
+        Some((started, keys)) => status!(
+            "Session:        started {}, {} ago, {} keys",
+            started.format("%Y-%m-%d %H:%M"),
+            format_minutes((now - started).num_minutes()),
+            keys.to_string().cyan()
+        ),
+        None => status!("Session:        {}", "none open".dark_grey()),
+    }
+
+    match last_key_ms.and_then(|ms| Local.timestamp_millis_opt(ms).single()) {
+        Some(at) => status!(
+            "Last key press: {} ({} ago)",
+            at.format("%Y-%m-%d %H:%M:%S"),
+            format_minutes((now - at).num_minutes())
+        ),
+        None => status!("Last key press: {}", "never".dark_grey()),
+    }
+    status!("Database:       {}", get_db_path()?.display());
+
+    match (listener, &session) {
+        (None, Some(_)) => {
+            status!();
+            status!(
+                "{} The open session belongs to a listener that stopped without closing it. \
+                 `kitmap listen` closes it at its last key press.",
+                "!".yellow()
+            );
+        }
+        (Some(_), None) => {
+            let causes = live
+                .map(|live| live.pause_causes())
+                .unwrap_or_else(|| "screen locked or asleep".to_string());
+            status!();
+            status!(
+                "{} The listener is running but recording is paused ({}).",
+                "!".yellow(),
+                causes
+            );
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Keys recorded in a session so far
+fn session_keys(db: &DbConnection, session_id: Option<i64>) -> Result<i64> {
+    let Some(id) = session_id else {
+        return Ok(0);
+    };
+    db.read(move |conn| {
+        Ok(conn.query_row(
+            "SELECT COUNT(*) FROM key_events WHERE session_id = ?1",
+            [id],
+            |row| row.get(0),
+        )?)
+    })
+}
+
+/// A duration in minutes as e.g. `45m`, `3h 20m` or `2d 4h`
+pub(crate) fn format_minutes(minutes: i64) -> String {
+    let minutes = minutes.max(0);
+    match (minutes / 1440, minutes % 1440 / 60, minutes % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
@@ -0,0 +1,34 @@
+use crate::config::load_config;
+use crate::db::init_db;
+use crate::stats::board::Board;
+use crate::stats::range::TimeRange;
+use crate::stats::{StatsCalculator, StatsFilter};
+use crate::ui::heatmap::{render_combo_table, render_key_table};
+use anyhow::Result;
+
+/// Print the key and combo leaderboards, optionally limited to a time span
+pub async fn run(keys: usize, combos: usize, since: Option<&str>) -> Result<()> {
+    let filter = StatsFilter::from(
+        since
+            .map(TimeRange::parse_span)
+            .transpose()?
+            .unwrap_or_default(),
+    );
+
+    let db = init_db()?;
+    let config = load_config()?;
+    let calculator = StatsCalculator::new(db).with_board(Board::from_config(&config.keyboard)?);
+
+    if keys > 0 {
+        let top_keys = calculator.top_keys(&filter, keys)?;
+        status_print!(
+            "{}",
+            render_key_table(&format!("🔝 TOP {} KEYS", keys), &top_keys)
+        );
+    }
+    if combos > 0 {
+        let top_combos = calculator.top_combos(&filter, combos)?;
+        status_print!("{}", render_combo_table("⌨️  TOP KEY COMBOS", &top_combos));
+    }
+    Ok(())
+}
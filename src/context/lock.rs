@@ -0,0 +1,187 @@
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// Screen lockers recognized by their running process
+#[cfg(target_os = "linux")]
+const LOCKER_PROCESSES: &[&str] = &[
+    "swaylock",
+    "hyprlock",
+    "waylock",
+    "gtklock",
+    "i3lock",
+    "xsecurelock",
+    "slock",
+];
+
+/// Ways of telling whether the screen is locked on the current platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockProbe {
+    /// systemd-logind's `LockedHint`, set by GNOME, KDE and other desktops
+    #[cfg(target_os = "linux")]
+    Logind,
+    /// The freedesktop screensaver service's active state
+    #[cfg(target_os = "linux")]
+    ScreenSaver,
+    /// A standalone screen locker process is running
+    #[cfg(target_os = "linux")]
+    LockerProcess,
+    /// The console session's `CGSSessionScreenIsLocked` flag
+    #[cfg(target_os = "macos")]
+    ConsoleSession,
+    /// The Windows lock screen (LogonUI) is running
+    #[cfg(windows)]
+    LogonUi,
+}
+
+impl LockProbe {
+    /// Probes for this platform, in the order they are tried
+    fn all() -> &'static [Self] {
+        #[cfg(target_os = "linux")]
+        return &[Self::Logind, Self::ScreenSaver, Self::LockerProcess];
+        #[cfg(target_os = "macos")]
+        return &[Self::ConsoleSession];
+        #[cfg(windows)]
+        return &[Self::LogonUi];
+        #[allow(unreachable_code)]
+        &[]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            #[cfg(target_os = "linux")]
+            Self::Logind => "systemd-logind",
+            #[cfg(target_os = "linux")]
+            Self::ScreenSaver => "the screensaver service",
+            #[cfg(target_os = "linux")]
+            Self::LockerProcess => "screen locker processes",
+            #[cfg(target_os = "macos")]
+            Self::ConsoleSession => "the console session",
+            #[cfg(windows)]
+            Self::LogonUi => "the Windows lock screen",
+        }
+    }
+
+    /// Whether the screen is locked, or `None` when this probe doesn't work here
+    fn locked(&self) -> Option<bool> {
+        match *self {
+            #[cfg(target_os = "linux")]
+            Self::Logind => {
+                let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".into());
+                let out = run(
+                    "loginctl",
+                    &["show-session", &session, "-p", "LockedHint", "--value"],
+                )?;
+                match out.trim() {
+                    "yes" => Some(true),
+                    "no" => Some(false),
+                    _ => None,
+                }
+            }
+            #[cfg(target_os = "linux")]
+            Self::ScreenSaver => {
+                let out = run(
+                    "gdbus",
+                    &[
+                        "call",
+                        "--session",
+                        "--dest",
+                        "org.freedesktop.ScreenSaver",
+                        "--object-path",
+                        "/org/freedesktop/ScreenSaver",
+                        "--method",
+                        "org.freedesktop.ScreenSaver.GetActive",
+                    ],
+                )?;
+                Some(out.contains("true"))
+            }
+            #[cfg(target_os = "linux")]
+            Self::LockerProcess => {
+                let pattern = format!("^({})$", LOCKER_PROCESSES.join("|"));
+                let status = Command::new("pgrep")
+                    .args(["-x", &pattern])
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .ok()?;
+                // pgrep exits with 1 when nothing matched and higher codes on errors
+                match status.code() {
+                    Some(0) => Some(true),
+                    Some(1) => Some(false),
+                    _ => None,
+                }
+            }
+            #[cfg(target_os = "macos")]
+            Self::ConsoleSession => {
+                let out = run("ioreg", &["-n", "Root", "-d1"])?;
+                Some(out.contains("\"CGSSessionScreenIsLocked\"=Yes"))
+            }
+            #[cfg(windows)]
+            Self::LogonUi => {
+                let out = run(
+                    "tasklist",
+                    &["/FI", "IMAGENAME eq LogonUI.exe", "/FO", "CSV", "/NH"],
+                )?;
+                Some(out.contains("LogonUI.exe"))
+            }
+        }
+    }
+}
+
+/// Run a command and capture its standard output, or `None` when it can't be run or fails
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Polls the working lock probes and reports lock and unlock transitions
+pub struct LockWatcher {
+    probes: Vec<LockProbe>,
+}
+
+impl LockWatcher {
+    /// Find the probes that work in this session, or `None` when the lock state can't be detected
+    pub fn detect() -> Option<Self> {
+        let probes: Vec<LockProbe> = LockProbe::all()
+            .iter()
+            .copied()
+            .filter(|probe| probe.locked().is_some())
+            .collect();
+
+        (!probes.is_empty()).then_some(Self { probes })
+    }
+
+    pub fn probes(&self) -> &[LockProbe] {
+        &self.probes
+    }
+
+    /// Locked when any working probe says so
+    pub fn locked(&self) -> bool {
+        self.probes
+            .iter()
+            .any(|probe| probe.locked().unwrap_or(false))
+    }
+
+    /// Check the lock state every `interval` on a background thread, calling
+    /// `on_change` with the new state whenever the screen is locked or unlocked
+    pub fn spawn(self, interval: Duration, mut on_change: impl FnMut(bool) + Send + 'static) {
+        thread::spawn(move || {
+            let mut was_locked = false;
+            loop {
+                let locked = self.locked();
+                if locked != was_locked {
+                    on_change(locked);
+                    was_locked = locked;
+                }
+                thread::sleep(interval);
+            }
+        });
+    }
+}
@@ -0,0 +1,184 @@
+pub mod lock;
+pub mod projects;
+pub mod sleep;
+pub mod wm;
+
+use crate::config::Config;
+use projects::ProjectDetector;
+use std::time::{Duration, Instant};
+use wm::WindowManager;
+
+/// How long a sampled context is reused before asking the window manager again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Where the user was typing when a batch of keystrokes was recorded
+#[derive(Debug, Clone, Default)]
+pub struct FocusContext {
+    pub workspace: Option<String>,
+    pub monitor: Option<String>,
+    /// Whether the focused window looks like an SSH/remote session
+    pub remote: Option<bool>,
+    /// Project the focused window's title was attributed to
+    pub project: Option<String>,
+    /// Application of the focused window (Wayland app_id or X11 class)
+    pub app: Option<String>,
+}
+
+/// Which context dimensions should be recorded
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContextOptions {
+    pub workspaces: bool,
+    pub monitors: bool,
+    pub remote: bool,
+    pub projects: bool,
+    pub apps: bool,
+}
+
+impl ContextOptions {
+    /// Whether any dimension needs the window manager
+    pub fn needs_window_manager(&self) -> bool {
+        self.workspaces || self.monitors || self.remote || self.projects || self.apps
+    }
+}
+
+/// Samples the focus context at most once per refresh interval so keystroke
+/// batches share one IPC round trip.
+pub struct ContextTracker {
+    options: ContextOptions,
+    wm: Option<WindowManager>,
+    remote_patterns: Vec<String>,
+    projects: ProjectDetector,
+    current: FocusContext,
+    last_refresh: Option<Instant>,
+}
+
+impl ContextTracker {
+    pub fn new(options: ContextOptions, wm: Option<WindowManager>, config: &Config) -> Self {
+        Self {
+            options,
+            wm,
+            remote_patterns: config.remote.title_patterns.clone(),
+            projects: ProjectDetector::new(&config.projects),
+            current: FocusContext::default(),
+            last_refresh: None,
+        }
+    }
+
+    /// Get the current focus context, refreshing it if it has gone stale
+    pub fn current(&mut self) -> &FocusContext {
+        let stale = self
+            .last_refresh
+            .is_none_or(|t| t.elapsed() >= REFRESH_INTERVAL);
+
+        if stale {
+            self.refresh();
+        }
+
+        &self.current
+    }
+
+    fn refresh(&mut self) {
+        self.last_refresh = Some(Instant::now());
+
+        let Some(wm) = &self.wm else {
+            return;
+        };
+
+        if self.options.workspaces || self.options.monitors {
+            let workspace = wm.focused_workspace().ok().flatten();
+            let (name, monitor) = match workspace {
+                Some(w) => (Some(w.name), w.monitor),
+                None => (None, None),
+            };
+
+            self.current.workspace = name.filter(|_| self.options.workspaces);
+            self.current.monitor = monitor.filter(|_| self.options.monitors);
+        }
+
+        if self.options.remote || self.options.projects || self.options.apps {
+            let window = wm.focused_window();
+
+            if self.options.remote {
+                self.current.remote = match &window {
+                    Ok(Some(window)) => Some(
+                        self.remote_patterns
+                            .iter()
+                            .any(|pattern| glob_match(pattern, &window.title)),
+                    ),
+                    Ok(None) => Some(false),
+                    Err(_) => None,
+                };
+            }
+
+            if self.options.projects {
+                self.current.project = match &window {
+                    Ok(Some(window)) => self.projects.detect(window),
+                    _ => None,
+                };
+            }
+
+            if self.options.apps {
+                self.current.app = match &window {
+                    Ok(Some(window)) => window.app.clone(),
+                    _ => None,
+                };
+            }
+        }
+    }
+}
+
+/// Case-insensitive match of `text` against a pattern where `*` matches any run of characters
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn matches_literally_without_stars() {
+        assert!(glob_match("code", "code"));
+        assert!(!glob_match("code", "vscode"));
+        assert!(!glob_match("code", "cod"));
+    }
+
+    #[test]
+    fn ignores_case() {
+        assert!(glob_match("Firefox", "firefox"));
+        assert!(glob_match("*SSH*", "user@host: ssh"));
+    }
+
+    #[test]
+    fn stars_match_any_run_of_characters() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("ssh *", "ssh server"));
+        assert!(glob_match("*@*:*", "me@box:~/src"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYbZ"));
+        assert!(!glob_match("*.rs", "main.rst"));
+    }
+}
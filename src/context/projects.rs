@@ -0,0 +1,136 @@
+use super::glob_match;
+use super::wm::Window;
+use crate::config::{ProjectRule, ProjectsConfig};
+
+/// Window title suffixes of editors that show the open folder as the title's last segment
+const FOLDER_TITLE_EDITORS: &[&str] = &[
+    " - Visual Studio Code",
+    " - VSCodium",
+    " - Cursor",
+    " - Code - OSS",
+];
+
+/// Window title suffixes of Vim-style editors, whose titles hold the file's directory in parentheses
+const VIM_TITLE_SUFFIXES: &[&str] = &[" - NVIM", " - VIM", " - Neovim"];
+
+/// Attributes window titles to projects, WakaTime-style
+#[derive(Debug, Clone, Default)]
+pub struct ProjectDetector {
+    rules: Vec<ProjectRule>,
+    /// Directories whose immediate children are projects, written with `~/`
+    roots: Vec<String>,
+    home: Option<String>,
+}
+
+impl ProjectDetector {
+    pub fn new(config: &ProjectsConfig) -> Self {
+        Self {
+            rules: config.rules.clone(),
+            roots: config
+                .roots
+                .iter()
+                .map(|root| root.trim_end_matches('/').to_string())
+                .collect(),
+            home: std::env::var("HOME")
+                .or_else(|_| std::env::var("USERPROFILE"))
+                .ok()
+                .map(|home| home.trim_end_matches('/').to_string()),
+        }
+    }
+
+    /// The project the focused window belongs to: configured rules first, then
+    /// editor-specific title formats, then any project path in the title
+    pub fn detect(&self, window: &Window) -> Option<String> {
+        let title = window.title.trim();
+
+        if let Some(rule) = self
+            .rules
+            .iter()
+            .find(|rule| glob_match(&rule.pattern, title))
+        {
+            return Some(rule.project.clone()).filter(|p| !p.is_empty());
+        }
+
+        editor_project(title, window.app.as_deref())
+            .or_else(|| {
+                title_paths(title)
+                    .into_iter()
+                    .find_map(|path| self.project_in_path(path))
+            })
+            .map(|project| project.trim().to_string())
+            .filter(|project| !project.is_empty())
+    }
+
+    /// The project directory a path lies in, when it is under one of the roots
+    fn project_in_path(&self, path: &str) -> Option<String> {
+        let path = match &self.home {
+            Some(home) if path.starts_with(&format!("{}/", home)) => {
+                format!("~{}", &path[home.len()..])
+            }
+            _ => path.to_string(),
+        };
+
+        self.roots.iter().find_map(|root| {
+            let rest = path.strip_prefix(root.as_str())?.strip_prefix('/')?;
+            rest.split('/')
+                .next()
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+        })
+    }
+}
+
+/// Read the project out of the title formats of common IDEs
+fn editor_project(title: &str, app: Option<&str>) -> Option<String> {
+    // VS Code and its forks: "● main.rs - kitmap - Visual Studio Code"
+    if let Some(rest) = FOLDER_TITLE_EDITORS
+        .iter()
+        .find_map(|suffix| title.strip_suffix(suffix))
+    {
+        // Without an open folder the title is just the file and the editor name
+        let (_, folder) = rest.rsplit_once(" - ")?;
+        return Some(strip_bracketed(folder).to_string());
+    }
+
+    // JetBrains IDEs: "kitmap [~/code/kitmap] – main.rs"
+    let jetbrains = app.is_some_and(|app| app.to_lowercase().starts_with("jetbrains-"));
+    if jetbrains {
+        let project = title.split(" – ").next()?;
+        return Some(strip_bracketed(project).to_string());
+    }
+
+    // Sublime Text: "~/code/kitmap/src/main.rs (kitmap) - Sublime Text"
+    if let Some(rest) = title.strip_suffix(" - Sublime Text") {
+        let rest = rest.trim_end_matches(" •");
+        if let Some(open) = rest.rfind(" (") {
+            if rest.ends_with(')') {
+                return Some(rest[open + 2..rest.len() - 1].to_string());
+            }
+        }
+    }
+
+    None
+}
+
+/// Path-like words in a title, e.g. from shell prompts ("me@host: ~/code/kitmap") or
+/// Vim titles ("main.rs (~/code/kitmap/src) - NVIM")
+fn title_paths(title: &str) -> Vec<&str> {
+    let title = VIM_TITLE_SUFFIXES
+        .iter()
+        .find_map(|suffix| title.strip_suffix(suffix))
+        .unwrap_or(title);
+
+    title
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| "()[]{}<>\"'`:,;".contains(c)))
+        .filter(|word| word.starts_with("~/") || word.starts_with('/'))
+        .collect()
+}
+
+/// Drop a trailing bracketed suffix, e.g. " [SSH: devbox]" or " [~/code/kitmap]"
+fn strip_bracketed(name: &str) -> &str {
+    match name.find(" [") {
+        Some(i) if name.ends_with(']') => &name[..i],
+        _ => name,
+    }
+}
@@ -0,0 +1,153 @@
+use chrono::{DateTime, Local};
+#[cfg(target_os = "linux")]
+use std::io::{BufRead, BufReader};
+#[cfg(target_os = "linux")]
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// How often the wall clock is checked for a jump left by a suspend
+const CLOCK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Seconds the wall clock may run ahead of a poll before the gap counts as a suspend
+const CLOCK_JUMP_SECS: i64 = 15;
+
+/// A change in whether the computer is asleep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepEvent {
+    /// The computer is going to sleep, or was asleep since `at`
+    Sleeping { at: DateTime<Local> },
+    /// The computer woke up
+    Awake,
+}
+
+/// Ways of noticing that the computer suspends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepSource {
+    /// systemd-logind's `PrepareForSleep` signal, sent before suspending and after resuming
+    #[cfg(target_os = "linux")]
+    Logind,
+    /// A jump of the wall clock between polls, noticed once the computer is awake again
+    ClockJump,
+}
+
+impl SleepSource {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            #[cfg(target_os = "linux")]
+            Self::Logind => "systemd-logind",
+            Self::ClockJump => "the system clock",
+        }
+    }
+}
+
+/// Reports when the computer goes to sleep and wakes up again
+pub struct SleepWatcher {
+    source: SleepSource,
+}
+
+impl SleepWatcher {
+    /// Use the platform's sleep notifications where they are reachable, and the
+    /// system clock everywhere else
+    pub fn detect() -> Self {
+        #[cfg(target_os = "linux")]
+        if logind_reachable() {
+            return Self {
+                source: SleepSource::Logind,
+            };
+        }
+        Self {
+            source: SleepSource::ClockJump,
+        }
+    }
+
+    pub fn source(&self) -> SleepSource {
+        self.source
+    }
+
+    /// Watch for sleep on a background thread, calling `on_event` when the computer
+    /// goes to sleep and when it wakes up
+    pub fn spawn(self, mut on_event: impl FnMut(SleepEvent) + Send + 'static) {
+        thread::spawn(move || {
+            #[cfg(target_os = "linux")]
+            if self.source == SleepSource::Logind {
+                monitor_logind(&mut on_event);
+                // The monitor went away; the clock still catches later suspends
+            }
+            watch_clock(&mut on_event);
+        });
+    }
+}
+
+/// Whether systemd-logind answers on the system bus
+#[cfg(target_os = "linux")]
+fn logind_reachable() -> bool {
+    Command::new("gdbus")
+        .args([
+            "call",
+            "--system",
+            "--dest",
+            "org.freedesktop.login1",
+            "--object-path",
+            "/org/freedesktop/login1",
+            "--method",
+            "org.freedesktop.DBus.Peer.Ping",
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Report `PrepareForSleep` signals until the monitor exits. They look like
+/// `/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)`.
+#[cfg(target_os = "linux")]
+fn monitor_logind(on_event: &mut impl FnMut(SleepEvent)) {
+    let Ok(mut child) = Command::new("gdbus")
+        .args([
+            "monitor",
+            "--system",
+            "--dest",
+            "org.freedesktop.login1",
+            "--object-path",
+            "/org/freedesktop/login1",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return;
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return;
+    };
+
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        if !line.contains(".PrepareForSleep ") {
+            continue;
+        }
+        if line.contains("(true") {
+            on_event(SleepEvent::Sleeping { at: Local::now() });
+        } else if line.contains("(false") {
+            on_event(SleepEvent::Awake);
+        }
+    }
+    let _ = child.wait();
+}
+
+/// Poll the wall clock forever. A suspended computer runs no threads, so a poll that
+/// comes back far later than it should means the computer slept in between.
+fn watch_clock(on_event: &mut impl FnMut(SleepEvent)) {
+    let jump = chrono::Duration::from_std(CLOCK_POLL_INTERVAL).unwrap_or_default()
+        + chrono::Duration::seconds(CLOCK_JUMP_SECS);
+    let mut last = Local::now();
+    loop {
+        thread::sleep(CLOCK_POLL_INTERVAL);
+        let now = Local::now();
+        if now - last > jump {
+            on_event(SleepEvent::Sleeping { at: last });
+            on_event(SleepEvent::Awake);
+        }
+        last = now;
+    }
+}
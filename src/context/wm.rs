@@ -0,0 +1,209 @@
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+/// A tiling window manager reachable over its IPC socket
+#[derive(Debug, Clone)]
+pub enum WindowManager {
+    /// sway or i3, which share the i3 IPC protocol
+    I3 {
+        socket: PathBuf,
+    },
+    Hyprland {
+        socket: PathBuf,
+    },
+}
+
+/// The focused workspace as reported by the window manager
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Workspace {
+    pub name: String,
+    /// Output (monitor) the workspace is shown on
+    pub monitor: Option<String>,
+}
+
+/// The focused window as reported by the window manager
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Window {
+    pub title: String,
+    /// Application identifier (Wayland app_id or X11 class)
+    pub app: Option<String>,
+}
+
+/// i3 IPC message type for listing workspaces
+const I3_GET_WORKSPACES: u32 = 1;
+
+/// i3 IPC message type for fetching the layout tree
+const I3_GET_TREE: u32 = 4;
+
+/// Magic string prefixing every i3 IPC message
+#[cfg(unix)]
+const I3_MAGIC: &[u8] = b"i3-ipc";
+
+#[derive(Debug, Deserialize)]
+struct I3Workspace {
+    name: String,
+    focused: bool,
+    output: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HyprlandWorkspace {
+    name: String,
+    monitor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HyprlandWindow {
+    title: Option<String>,
+    class: Option<String>,
+}
+
+impl WindowManager {
+    /// Detect a running sway, i3 or Hyprland session from the environment
+    pub fn detect() -> Option<Self> {
+        if let Some(socket) = std::env::var_os("SWAYSOCK").or_else(|| std::env::var_os("I3SOCK")) {
+            return Some(Self::I3 {
+                socket: PathBuf::from(socket),
+            });
+        }
+
+        if let Ok(signature) = std::env::var("HYPRLAND_INSTANCE_SIGNATURE") {
+            let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+                .map(|dir| PathBuf::from(dir).join("hypr"))
+                .filter(|dir| dir.join(&signature).exists())
+                .unwrap_or_else(|| PathBuf::from("/tmp/hypr"));
+
+            return Some(Self::Hyprland {
+                socket: runtime_dir.join(signature).join(".socket.sock"),
+            });
+        }
+
+        None
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::I3 { .. } => "sway/i3",
+            Self::Hyprland { .. } => "Hyprland",
+        }
+    }
+
+    /// Ask the window manager which workspace currently has focus
+    pub fn focused_workspace(&self) -> Result<Option<Workspace>> {
+        match self {
+            Self::I3 { socket } => {
+                let reply = i3_request(socket, I3_GET_WORKSPACES)?;
+                let workspaces: Vec<I3Workspace> = serde_json::from_slice(&reply)?;
+                Ok(workspaces
+                    .into_iter()
+                    .find(|w| w.focused)
+                    .map(|w| Workspace {
+                        name: w.name,
+                        monitor: w.output,
+                    }))
+            }
+            Self::Hyprland { socket } => {
+                let reply = hyprland_request(socket, "j/activeworkspace")?;
+                let workspace: HyprlandWorkspace = serde_json::from_slice(&reply)?;
+                Ok(Some(Workspace {
+                    name: workspace.name,
+                    monitor: workspace.monitor,
+                }))
+            }
+        }
+    }
+
+    /// Ask the window manager for the focused window's title and application
+    pub fn focused_window(&self) -> Result<Option<Window>> {
+        match self {
+            Self::I3 { socket } => {
+                let reply = i3_request(socket, I3_GET_TREE)?;
+                let tree: serde_json::Value = serde_json::from_slice(&reply)?;
+                Ok(find_focused_i3_node(&tree).map(|node| Window {
+                    title: node["name"].as_str().unwrap_or_default().to_string(),
+                    app: node["app_id"]
+                        .as_str()
+                        .or_else(|| node["window_properties"]["class"].as_str())
+                        .map(str::to_string),
+                }))
+            }
+            Self::Hyprland { socket } => {
+                let reply = hyprland_request(socket, "j/activewindow")?;
+                let window: HyprlandWindow = match serde_json::from_slice(&reply) {
+                    Ok(window) => window,
+                    // Hyprland answers with an empty object when nothing has focus
+                    Err(_) => return Ok(None),
+                };
+                Ok(window.title.map(|title| Window {
+                    title,
+                    app: window.class.filter(|c| !c.is_empty()),
+                }))
+            }
+        }
+    }
+}
+
+/// Depth-first search of an i3/sway layout tree for the focused node
+fn find_focused_i3_node(node: &serde_json::Value) -> Option<&serde_json::Value> {
+    if node["focused"].as_bool() == Some(true) {
+        return Some(node);
+    }
+
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[*key].as_array())
+        .flatten()
+        .find_map(find_focused_i3_node)
+}
+
+/// Send an empty-payload i3 IPC message and return the reply payload
+#[cfg(unix)]
+fn i3_request(socket: &std::path::Path, message_type: u32) -> Result<Vec<u8>> {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(std::time::Duration::from_millis(200)))?;
+
+    let mut message = I3_MAGIC.to_vec();
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(&message_type.to_ne_bytes());
+    stream.write_all(&message)?;
+
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    if &header[..6] != I3_MAGIC {
+        bail!("Unexpected reply from window manager IPC");
+    }
+
+    let length = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]) as usize;
+    let mut payload = vec![0u8; length];
+    stream.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// Send a command over Hyprland's request socket and return the full reply
+#[cfg(unix)]
+fn hyprland_request(socket: &std::path::Path, command: &str) -> Result<Vec<u8>> {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(std::time::Duration::from_millis(200)))?;
+    stream.write_all(command.as_bytes())?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply)?;
+    Ok(reply)
+}
+
+#[cfg(not(unix))]
+fn i3_request(_socket: &std::path::Path, _message_type: u32) -> Result<Vec<u8>> {
+    bail!("Window manager IPC is only supported on Unix")
+}
+
+#[cfg(not(unix))]
+fn hyprland_request(_socket: &std::path::Path, _command: &str) -> Result<Vec<u8>> {
+    bail!("Window manager IPC is only supported on Unix")
+}
@@ -0,0 +1,146 @@
+use crate::db::time;
+use crate::stats::range::{TimeRange, RANGE_CONDITION};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// ActivityWatch bucket type used by keyboard/mouse input watchers
+const INPUT_BUCKET_TYPE: &str = "os.hid.input";
+
+/// An ActivityWatch export file (`/api/0/export` or a single bucket export)
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AwExport {
+    pub buckets: BTreeMap<String, AwBucket>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AwBucket {
+    pub id: String,
+    #[serde(default)]
+    pub created: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub client: Option<String>,
+    #[serde(default)]
+    pub hostname: Option<String>,
+    #[serde(default)]
+    pub events: Vec<AwEvent>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AwEvent {
+    pub timestamp: String,
+    /// Event length in seconds
+    pub duration: f64,
+    pub data: serde_json::Value,
+}
+
+/// Build an ActivityWatch export with one input event per minute of typing
+pub fn export_buckets(conn: &Connection, range: &TimeRange) -> Result<AwExport> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT timestamp_ms / 60000 as minute, COUNT(*) FROM key_events WHERE {}
+         GROUP BY minute ORDER BY minute",
+        RANGE_CONDITION
+    ))?;
+
+    let minutes = stmt.query_map((range.since_param(), range.until_param()), |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+    })?;
+
+    let mut events = Vec::new();
+    for minute in minutes {
+        let (minute, presses) = minute?;
+        let Some(start) = DateTime::from_timestamp(minute * 60, 0) else {
+            continue;
+        };
+
+        events.push(AwEvent {
+            timestamp: start.to_rfc3339(),
+            duration: 60.0,
+            data: json!({
+                "presses": presses,
+                "clicks": 0,
+                "deltaX": 0,
+                "deltaY": 0,
+                "scrollX": 0,
+                "scrollY": 0,
+            }),
+        });
+    }
+
+    let hostname = super::hostname();
+    let id = format!("aw-watcher-kitmap_{}", hostname);
+    let bucket = AwBucket {
+        id: id.clone(),
+        created: Some(Utc::now().to_rfc3339()),
+        kind: INPUT_BUCKET_TYPE.to_string(),
+        client: Some("kitmap".to_string()),
+        hostname: Some(hostname),
+        events,
+    };
+
+    Ok(AwExport {
+        buckets: BTreeMap::from([(id, bucket)]),
+    })
+}
+
+/// Summary of an ActivityWatch import
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub buckets: usize,
+    pub keys: i64,
+}
+
+/// Import key presses from the input watcher buckets of an ActivityWatch export.
+///
+/// Watchers only record how many keys were pressed, not which, so each event is stored
+/// as a count in `imported_activity` rather than as key events, and per-key stats are
+/// left to what kitmap recorded itself. Imported rows are tagged with their bucket, and
+/// importing a bucket again replaces its earlier import.
+pub fn import_buckets(conn: &mut Connection, export: &AwExport) -> Result<ImportSummary> {
+    let tx = conn.transaction()?;
+    let mut summary = ImportSummary::default();
+
+    for (id, bucket) in &export.buckets {
+        if bucket.kind != INPUT_BUCKET_TYPE {
+            continue;
+        }
+
+        let source = format!("activitywatch:{}", id);
+        tx.execute("DELETE FROM imported_activity WHERE source = ?1", [&source])?;
+        // Earlier versions imported each press as an `Unknown` key event
+        tx.execute("DELETE FROM key_events WHERE source = ?1", [&source])?;
+
+        let mut insert = tx.prepare(
+            "INSERT INTO imported_activity (source, presses, timestamp, timestamp_ms)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+
+        for event in &bucket.events {
+            let presses = event.data["presses"].as_i64().unwrap_or(0);
+            if presses <= 0 {
+                continue;
+            }
+
+            let start = DateTime::parse_from_rfc3339(&event.timestamp)
+                .map_err(|e| anyhow!("Invalid event timestamp '{}': {}", event.timestamp, e))?;
+            insert.execute((
+                &source,
+                presses,
+                time::format(&start),
+                start.timestamp_millis(),
+            ))?;
+            summary.keys += presses;
+        }
+
+        summary.buckets += 1;
+    }
+
+    tx.commit()?;
+    Ok(summary)
+}
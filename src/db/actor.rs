@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Result};
+use rusqlite::{Connection, OpenFlags};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tokio::sync::oneshot;
+
+/// Read-only connections kept for reuse between reads
+const MAX_IDLE_READERS: usize = 4;
+
+/// A command run on the database thread against the connection it owns
+type Command = Box<dyn FnOnce(&mut Connection) + Send>;
+
+/// Handle to the SQLite database.
+///
+/// The read-write connection is owned by a background thread that runs commands one
+/// at a time, in the order they were sent. Reads run on the caller's thread against
+/// read-only connections of their own, so a long stats query from the dashboard or
+/// a publisher never holds up the key listener's writes. Clones share the thread,
+/// which stops once the last clone is dropped.
+#[derive(Clone)]
+pub struct DbConnection {
+    /// Queue of the database thread, or `None` when the database was opened read-only
+    commands: Option<Sender<Command>>,
+    /// Path or URI read-only connections open the database with
+    path: String,
+    /// Read-only connections returned by earlier reads
+    readers: Arc<Mutex<Vec<Connection>>>,
+}
+
+impl DbConnection {
+    /// Start the database thread with `conn`, which was opened from `path`
+    pub fn spawn(mut conn: Connection, path: String) -> Result<Self> {
+        let (commands, queue) = mpsc::channel::<Command>();
+        thread::Builder::new()
+            .name("kitmap-db".to_string())
+            .spawn(move || {
+                for command in queue {
+                    command(&mut conn);
+                }
+            })?;
+
+        Ok(Self {
+            commands: Some(commands),
+            path,
+            readers: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    /// A handle that only reads the database at `path`, without a database thread.
+    /// Commands fail instead of writing.
+    pub fn read_only(path: String) -> Self {
+        Self {
+            commands: None,
+            path,
+            readers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Whether commands that write are refused
+    pub fn is_read_only(&self) -> bool {
+        self.commands.is_none()
+    }
+
+    /// Run `command` on the database thread and wait for its result without blocking
+    /// the async runtime
+    pub async fn call<T, F>(&self, command: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        self.send(move |conn| {
+            let _ = reply.send(command(conn));
+        })?;
+        result.await.map_err(|_| stopped())?
+    }
+
+    /// Run `command` on the database thread and wait for its result, for code outside
+    /// the async runtime such as the key listener's callback
+    pub fn call_blocking<T, F>(&self, command: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let (reply, result) = mpsc::sync_channel(1);
+        self.send(move |conn| {
+            let _ = reply.send(command(conn));
+        })?;
+        result.recv().map_err(|_| stopped())?
+    }
+
+    /// Run `query` on this thread against a read-only connection. It sees every write
+    /// the database thread has committed.
+    pub fn read<T>(&self, query: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let idle = self.readers.lock().unwrap().pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => Connection::open_with_flags(
+                &self.path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | OpenFlags::SQLITE_OPEN_URI
+                    | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )?,
+        };
+
+        let result = query(&conn);
+        let mut readers = self.readers.lock().unwrap();
+        if readers.len() < MAX_IDLE_READERS {
+            readers.push(conn);
+        }
+        result
+    }
+
+    fn send(&self, command: impl FnOnce(&mut Connection) + Send + 'static) -> Result<()> {
+        let Some(commands) = &self.commands else {
+            return Err(anyhow!("The database was opened read-only"));
+        };
+        commands.send(Box::new(command)).map_err(|_| stopped())
+    }
+}
+
+fn stopped() -> anyhow::Error {
+    anyhow!("The database thread has stopped")
+}
@@ -0,0 +1,200 @@
+use crate::db::DbConnection;
+use crate::stats::range::LOCAL_DAY;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Bytes of header SQLite adds to every page written to the WAL
+const WAL_FRAME_HEADER: f64 = 24.0;
+
+/// Typing speed assumed when no typing samples have been recorded yet
+const DEFAULT_KEYS_PER_SECOND: f64 = 3.0;
+
+/// Tables holding one row per recorded key press, combo or chord
+const RAW_TABLES: &[&str] = &["key_events", "key_combos", "key_chords"];
+
+/// Disk space used by one table or index
+#[derive(Debug, Clone)]
+pub struct ObjectSize {
+    pub name: String,
+    /// Table the object belongs to (itself for tables)
+    pub table: String,
+    pub is_index: bool,
+    /// Size of the object's pages on disk
+    pub bytes: i64,
+    /// Bytes of those pages holding data
+    pub used_bytes: i64,
+}
+
+/// How the database uses disk space and how much recording writes per keystroke
+#[derive(Debug, Clone)]
+pub struct StorageAnalysis {
+    pub page_size: i64,
+    pub file_bytes: u64,
+    pub wal_bytes: u64,
+    /// Space in pages freed by deletions that the file still occupies
+    pub free_bytes: i64,
+    /// Tables, largest first, each followed by its indexes
+    pub objects: Vec<ObjectSize>,
+    pub key_events: i64,
+    /// Bytes of key event rows and their indexes per recorded key
+    pub stored_bytes_per_key: f64,
+    /// Indexes updated by every key event insert
+    pub key_event_indexes: i64,
+    /// Typing speed while typing, used to size batched commits
+    pub keys_per_second: f64,
+    /// Average keys recorded on days with any typing
+    pub keys_per_day: f64,
+}
+
+impl StorageAnalysis {
+    pub fn run(db: &DbConnection, path: &Path) -> Result<Self> {
+        db.read(|conn| {
+            let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+            let free_pages: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+
+            let mut stmt = conn.prepare(
+                "SELECT s.name, COALESCE(m.tbl_name, s.name), COALESCE(m.type, 'table'),
+                    SUM(s.pgsize), SUM(s.pgsize - s.unused)
+             FROM dbstat s LEFT JOIN sqlite_master m ON m.name = s.name
+             GROUP BY s.name",
+            )?;
+            let objects = stmt
+                .query_map([], |row| {
+                    Ok(ObjectSize {
+                        name: row.get(0)?,
+                        table: row.get(1)?,
+                        is_index: row.get::<_, String>(2)? == "index",
+                        bytes: row.get(3)?,
+                        used_bytes: row.get(4)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let objects = group_by_table(objects);
+
+            let key_events: i64 =
+                conn.query_row("SELECT COUNT(*) FROM key_events", [], |row| row.get(0))?;
+            let key_event_bytes: i64 = objects
+                .iter()
+                .filter(|o| o.table == "key_events")
+                .map(|o| o.used_bytes)
+                .sum();
+            let key_event_indexes = objects
+                .iter()
+                .filter(|o| o.table == "key_events" && o.is_index)
+                .count() as i64;
+
+            let chars_per_minute: Option<f64> = conn.query_row(
+                "SELECT AVG(chars_per_minute) FROM typing_samples WHERE chars_per_minute > 0",
+                [],
+                |row| row.get(0),
+            )?;
+            let active_days: i64 = conn.query_row(
+                &format!("SELECT COUNT(DISTINCT {}) FROM key_events", LOCAL_DAY),
+                [],
+                |row| row.get(0),
+            )?;
+
+            Ok(Self {
+                page_size,
+                file_bytes: file_size(path),
+                wal_bytes: file_size(&path.with_extension("db-wal")),
+                free_bytes: free_pages * page_size,
+                objects,
+                key_events,
+                stored_bytes_per_key: if key_events > 0 {
+                    key_event_bytes as f64 / key_events as f64
+                } else {
+                    0.0
+                },
+                key_event_indexes,
+                keys_per_second: chars_per_minute
+                    .map(|cpm| cpm / 60.0)
+                    .unwrap_or(DEFAULT_KEYS_PER_SECOND),
+                keys_per_day: if active_days > 0 {
+                    key_events as f64 / active_days as f64
+                } else {
+                    0.0
+                },
+            })
+        })
+    }
+
+    /// Bytes of raw per-keystroke tables and of everything else (aggregates and sessions)
+    pub fn raw_and_aggregate_bytes(&self) -> (i64, i64) {
+        self.objects
+            .iter()
+            .fold((0, 0), |(raw, aggregate), object| {
+                if RAW_TABLES.contains(&object.table.as_str()) {
+                    (raw + object.bytes, aggregate)
+                } else {
+                    (raw, aggregate + object.bytes)
+                }
+            })
+    }
+
+    /// Estimated bytes written to disk per keystroke when key events are committed
+    /// every `interval` (zero commits each key on its own).
+    ///
+    /// Every commit writes each page it dirtied to the WAL, and a checkpoint later
+    /// copies it into the database file. A commit dirties the table's last leaf page,
+    /// one leaf page per index, the AUTOINCREMENT counter and the per-minute rollup,
+    /// plus any pages the batch fills up.
+    pub fn written_per_key(&self, interval: Duration) -> f64 {
+        let keys_per_commit = (self.keys_per_second * interval.as_secs_f64()).max(1.0);
+        let page_size = self.page_size as f64;
+
+        let touched_pages = 3.0 + self.key_event_indexes as f64;
+        let filled_pages = keys_per_commit * self.stored_bytes_per_key / page_size;
+        let pages_per_commit = touched_pages + filled_pages;
+
+        // Once into the WAL and once more when checkpointed into the database
+        let bytes_per_commit = pages_per_commit * (2.0 * page_size + WAL_FRAME_HEADER);
+        bytes_per_commit / keys_per_commit
+    }
+
+    /// How many times more bytes reach the disk than the key event data itself
+    pub fn amplification(&self, interval: Duration) -> Option<f64> {
+        (self.stored_bytes_per_key > 0.0)
+            .then(|| self.written_per_key(interval) / self.stored_bytes_per_key)
+    }
+}
+
+/// Order tables by their total size including indexes, listing each table before its indexes
+fn group_by_table(mut objects: Vec<ObjectSize>) -> Vec<ObjectSize> {
+    let mut table_bytes: HashMap<String, i64> = HashMap::new();
+    for object in &objects {
+        *table_bytes.entry(object.table.clone()).or_default() += object.bytes;
+    }
+
+    objects.sort_by(|a, b| {
+        table_bytes[&b.table]
+            .cmp(&table_bytes[&a.table])
+            .then_with(|| a.table.cmp(&b.table))
+            .then_with(|| a.is_index.cmp(&b.is_index))
+            .then_with(|| b.bytes.cmp(&a.bytes))
+    });
+    objects
+}
+
+/// Size of a file in bytes, 0 if it does not exist
+pub fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Format a byte count with a binary unit, e.g. `4.0 KiB`
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
@@ -0,0 +1,165 @@
+use crate::config::Config;
+use crate::db::time;
+use crate::db::DbConnection;
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Kinds of changes recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    /// All recorded data was deleted
+    Reset,
+    /// Key events and combos of past months were deleted
+    Prune,
+    /// History was imported from another tool
+    Import,
+    /// Another kitmap database was merged in
+    Merge,
+    /// The privacy summary was agreed to or declined
+    Consent,
+    /// Sections of the config file were edited
+    Config,
+    /// Sessions left open by a listener that didn't stop cleanly were closed
+    Recover,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Reset => "reset",
+            AuditAction::Prune => "prune",
+            AuditAction::Import => "import",
+            AuditAction::Merge => "merge",
+            AuditAction::Consent => "consent",
+            AuditAction::Config => "config",
+            AuditAction::Recover => "recover",
+        }
+    }
+}
+
+/// One recorded change to the dataset or the settings that shape it
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Local>,
+    pub action: String,
+    pub details: String,
+    /// OS user that ran the command
+    pub user: String,
+}
+
+/// Append an entry to the audit log
+pub fn record(db: &DbConnection, action: AuditAction, details: &str) -> Result<()> {
+    let params = (
+        time::now(),
+        action.as_str(),
+        details.to_string(),
+        current_user(),
+    );
+    db.call_blocking(move |conn| {
+        conn.execute(
+            "INSERT INTO audit_log (timestamp, action, details, user) VALUES (?1, ?2, ?3, ?4)",
+            params,
+        )?;
+        Ok(())
+    })
+}
+
+/// The `limit` most recent entries, oldest first
+pub fn recent(db: &DbConnection, limit: usize) -> Result<Vec<AuditEntry>> {
+    db.read(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, action, details, user FROM
+             (SELECT * FROM audit_log ORDER BY id DESC LIMIT ?1)
+         ORDER BY id",
+        )?;
+        let entries = stmt
+            .query_map([limit as i64], |row| {
+                let timestamp: String = row.get(0)?;
+                Ok(AuditEntry {
+                    timestamp: time::parse(&timestamp).unwrap_or_else(|_| Local::now()),
+                    action: row.get(1)?,
+                    details: row.get(2)?,
+                    user: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    })
+}
+
+/// Log the config sections that changed since the config was last seen.
+///
+/// Only fingerprints of each section are kept, so passwords and tokens in the config
+/// never reach the database. Sections are compared against their defaults the first time.
+pub fn track_config(db: &DbConnection, config: &Config) -> Result<()> {
+    let current = section_fingerprints(config)?;
+    let defaults = section_fingerprints(&Config::default())?;
+
+    let stored: BTreeMap<String, String> = db.read(|conn| {
+        let mut stmt = conn.prepare("SELECT section, fingerprint FROM config_fingerprints")?;
+        let stored = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(stored)
+    })?;
+    let previous = |section: &String| stored.get(section).or_else(|| defaults.get(section));
+    let changed: BTreeMap<String, String> = current
+        .into_iter()
+        .filter(|(section, fingerprint)| previous(section) != Some(fingerprint))
+        .collect();
+
+    if changed.is_empty() {
+        return Ok(());
+    }
+
+    let sections: Vec<String> = changed
+        .keys()
+        .map(|section| format!("[{}]", section))
+        .collect();
+    record(
+        db,
+        AuditAction::Config,
+        &format!("Changed {}", sections.join(", ")),
+    )?;
+
+    db.call_blocking(move |conn| {
+        for (section, fingerprint) in changed {
+            conn.execute(
+                "INSERT INTO config_fingerprints (section, fingerprint) VALUES (?1, ?2)
+                 ON CONFLICT (section) DO UPDATE SET fingerprint = excluded.fingerprint",
+                (section, fingerprint),
+            )?;
+        }
+        Ok(())
+    })
+}
+
+/// A stable fingerprint of each top-level config section
+fn section_fingerprints(config: &Config) -> Result<BTreeMap<String, String>> {
+    let Value::Object(sections) = serde_json::to_value(config)? else {
+        return Ok(BTreeMap::new());
+    };
+    Ok(sections
+        .into_iter()
+        .map(|(section, value)| {
+            let fingerprint = format!("{:016x}", fnv1a(value.to_string().as_bytes()));
+            (section, fingerprint)
+        })
+        .collect())
+}
+
+/// 64-bit FNV-1a, which unlike the standard library's hasher is stable across releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
@@ -0,0 +1,515 @@
+use crate::db::activitywatch;
+use crate::db::sanitize::key_class;
+use crate::stats::blocks::{detect_blocks, ActivityBlock};
+use crate::stats::range::{TimeRange, RANGE_CONDITION};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Local, Utc};
+use rusqlite::Connection;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::io::Write;
+
+/// File format for exported data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    /// iCalendar events, only for activity blocks
+    Ics,
+    /// ActivityWatch bucket/event JSON, only for keys
+    Activitywatch,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Ics => "ics",
+            Self::Activitywatch => "json",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Json => "application/json",
+            Self::Ics => "text/calendar; charset=utf-8",
+            Self::Activitywatch => "application/json",
+        }
+    }
+}
+
+/// Which recorded data to export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportData {
+    /// Individual key presses
+    #[default]
+    Keys,
+    /// Modifier + key combinations
+    Combos,
+    /// Active typing periods, for timesheets and calendars
+    Blocks,
+    /// Press and release times of keys recorded with `kitmap listen --dynamics`, for
+    /// keystroke-dynamics research
+    Dynamics,
+}
+
+impl ExportData {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Keys => "keys",
+            Self::Combos => "combos",
+            Self::Blocks => "blocks",
+            Self::Dynamics => "dynamics",
+        }
+    }
+}
+
+/// A row of exported data, written as a CSV row or a JSON object
+trait ExportRecord: Serialize {
+    const CSV_HEADER: &'static str;
+
+    fn csv_row(&self) -> String;
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct KeyEventRecord {
+    id: i64,
+    timestamp: String,
+    key_name: String,
+    key_code: String,
+    is_modifier: bool,
+    session_id: Option<i64>,
+    workspace: Option<String>,
+    monitor: Option<String>,
+    remote: Option<bool>,
+    project: Option<String>,
+    composed: Option<String>,
+    app: Option<String>,
+}
+
+impl ExportRecord for KeyEventRecord {
+    const CSV_HEADER: &'static str =
+        "id,timestamp,key_name,key_code,is_modifier,session_id,workspace,monitor,remote,project,composed,app";
+
+    fn csv_row(&self) -> String {
+        [
+            self.id.to_string(),
+            csv_field(&self.timestamp),
+            csv_field(&self.key_name),
+            csv_field(&self.key_code),
+            self.is_modifier.to_string(),
+            optional(self.session_id),
+            self.workspace.as_deref().map(csv_field).unwrap_or_default(),
+            self.monitor.as_deref().map(csv_field).unwrap_or_default(),
+            optional(self.remote),
+            self.project.as_deref().map(csv_field).unwrap_or_default(),
+            self.composed.as_deref().map(csv_field).unwrap_or_default(),
+            self.app.as_deref().map(csv_field).unwrap_or_default(),
+        ]
+        .join(",")
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ComboRecord {
+    id: i64,
+    timestamp: String,
+    combo: String,
+    app: Option<String>,
+}
+
+impl ExportRecord for ComboRecord {
+    const CSV_HEADER: &'static str = "id,timestamp,combo,app";
+
+    fn csv_row(&self) -> String {
+        [
+            self.id.to_string(),
+            csv_field(&self.timestamp),
+            csv_field(&self.combo),
+            self.app.as_deref().map(csv_field).unwrap_or_default(),
+        ]
+        .join(",")
+    }
+}
+
+/// A key press and its release, in the column layout of the Aalto 136M Keystrokes
+/// dataset. Times are milliseconds since the first exported press, so no dates are given
+/// away, and sessions are numbered from 1.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct DynamicsRecord {
+    /// Random for each export, so exports can't be linked to each other
+    participant_id: u32,
+    test_section_id: i64,
+    keystroke_id: i64,
+    press_time: i64,
+    release_time: i64,
+    /// The key's name, or `Letter` or `Digit` for keys that reveal typed text unless
+    /// they were consented to
+    letter: String,
+}
+
+impl ExportRecord for DynamicsRecord {
+    const CSV_HEADER: &'static str =
+        "PARTICIPANT_ID,TEST_SECTION_ID,KEYSTROKE_ID,PRESS_TIME,RELEASE_TIME,LETTER";
+
+    fn csv_row(&self) -> String {
+        [
+            self.participant_id.to_string(),
+            self.test_section_id.to_string(),
+            self.keystroke_id.to_string(),
+            self.press_time.to_string(),
+            self.release_time.to_string(),
+            csv_field(&self.letter),
+        ]
+        .join(",")
+    }
+}
+
+/// What a keystroke-dynamics export may contain, each part agreed to with its own flag
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DynamicsConsent {
+    /// Press and release times, which can identify the typist
+    pub timing: bool,
+    /// Which letter or digit each key is, which lets the typed text be read back
+    pub keys: bool,
+}
+
+/// What to export and how
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub data: ExportData,
+    pub format: ExportFormat,
+    pub range: TimeRange,
+    /// Idle minutes that end an activity block
+    pub block_gap_minutes: i64,
+    pub dynamics_consent: DynamicsConsent,
+}
+
+impl ExportOptions {
+    /// Reject format/data combinations that cannot be exported
+    pub fn validate(&self) -> Result<()> {
+        if self.format == ExportFormat::Ics && self.data != ExportData::Blocks {
+            bail!("ICS export is only available for activity blocks (--data blocks)");
+        }
+        if self.format == ExportFormat::Activitywatch && self.data != ExportData::Keys {
+            bail!("ActivityWatch export is only available for keys (--data keys)");
+        }
+        if self.data == ExportData::Dynamics && !self.dynamics_consent.timing {
+            bail!(
+                "Key press and release times can identify you, so they are only exported \
+                 with explicit consent (kitmap export --data dynamics --consent-timing)"
+            );
+        }
+        if self.data == ExportData::Blocks {
+            self.block_gap()?;
+        }
+        Ok(())
+    }
+
+    /// Idle time that ends an activity block, at least a minute
+    fn block_gap(&self) -> Result<Duration> {
+        Duration::try_minutes(self.block_gap_minutes.max(1)).ok_or_else(|| {
+            anyhow!(
+                "A block gap of {} minutes is too long",
+                self.block_gap_minutes
+            )
+        })
+    }
+}
+
+/// Write recorded data to `out` as described by `options`
+pub fn write_export(
+    conn: &Connection,
+    options: &ExportOptions,
+    out: &mut impl Write,
+) -> Result<()> {
+    options.validate()?;
+    let (format, range) = (options.format, &options.range);
+
+    if format == ExportFormat::Activitywatch {
+        let export = activitywatch::export_buckets(conn, range)?;
+        serde_json::to_writer(&mut *out, &export)?;
+        out.flush()?;
+        return Ok(());
+    }
+
+    match options.data {
+        ExportData::Keys => {
+            let mut records = RecordWriter::start(out, format, KeyEventRecord::CSV_HEADER)?;
+            key_event_records(conn, range, |record| records.write(&record))?;
+            records.finish()?;
+        }
+        ExportData::Combos => {
+            let mut records = RecordWriter::start(out, format, ComboRecord::CSV_HEADER)?;
+            combo_records(conn, range, |record| records.write(&record))?;
+            records.finish()?;
+        }
+        ExportData::Dynamics => {
+            let mut records = RecordWriter::start(out, format, DynamicsRecord::CSV_HEADER)?;
+            dynamics_records(conn, range, options.dynamics_consent, |record| {
+                records.write(&record)
+            })?;
+            records.finish()?;
+        }
+        ExportData::Blocks => {
+            let blocks = key_blocks(conn, range, options.block_gap()?)?;
+            match format {
+                ExportFormat::Csv => write_csv(
+                    out,
+                    "date,start,end,duration_minutes,keys",
+                    blocks.iter().map(block_csv_row),
+                )?,
+                ExportFormat::Json => serde_json::to_writer(&mut *out, &blocks)?,
+                ExportFormat::Ics => write_ics(out, &blocks)?,
+                ExportFormat::Activitywatch => unreachable!(),
+            }
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+/// Hand each key event in `range` to `each` as it is read, oldest first
+fn key_event_records(
+    conn: &Connection,
+    range: &TimeRange,
+    mut each: impl FnMut(KeyEventRecord) -> Result<()>,
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, timestamp, key_name, key_code, is_modifier, session_id, workspace, monitor, remote, project, composed, app
+         FROM key_events WHERE {} ORDER BY id",
+        RANGE_CONDITION
+    ))?;
+
+    let records = stmt.query_map((range.since_param(), range.until_param()), |row| {
+        Ok(KeyEventRecord {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            key_name: row.get(2)?,
+            key_code: row.get(3)?,
+            is_modifier: row.get(4)?,
+            session_id: row.get(5)?,
+            workspace: row.get(6)?,
+            monitor: row.get(7)?,
+            remote: row.get(8)?,
+            project: row.get(9)?,
+            composed: row.get(10)?,
+            app: row.get(11)?,
+        })
+    })?;
+
+    for record in records {
+        each(record?)?;
+    }
+    Ok(())
+}
+
+/// Hand each combo in `range` to `each` as it is read, oldest first
+fn combo_records(
+    conn: &Connection,
+    range: &TimeRange,
+    mut each: impl FnMut(ComboRecord) -> Result<()>,
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, timestamp, combo, app FROM key_combos WHERE {} ORDER BY id",
+        RANGE_CONDITION
+    ))?;
+
+    let records = stmt.query_map((range.since_param(), range.until_param()), |row| {
+        Ok(ComboRecord {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            combo: row.get(2)?,
+            app: row.get(3)?,
+        })
+    })?;
+
+    for record in records {
+        each(record?)?;
+    }
+    Ok(())
+}
+
+/// Hand each key press in `range` with a recorded release to `each`, in typing order
+fn dynamics_records(
+    conn: &Connection,
+    range: &TimeRange,
+    consent: DynamicsConsent,
+    mut each: impl FnMut(DynamicsRecord) -> Result<()>,
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT session_id, timestamp_ms, released_ms, key_name FROM key_events
+         WHERE released_ms IS NOT NULL AND session_id IS NOT NULL AND seq IS NOT NULL AND {}
+         ORDER BY session_id, seq",
+        RANGE_CONDITION
+    ))?;
+
+    let participant_id = RandomState::new().hash_one(0) as u32;
+    let mut origin = None;
+    let mut section = (None, 0);
+
+    let rows = stmt.query_map((range.since_param(), range.until_param()), |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, String>(3)?,
+        ))
+    })?;
+    for (index, row) in rows.enumerate() {
+        let (session_id, pressed_ms, released_ms, key_name) = row?;
+        let origin = *origin.get_or_insert(pressed_ms);
+        if section.0 != Some(session_id) {
+            section = (Some(session_id), section.1 + 1);
+        }
+        let letter = match key_class(&key_name) {
+            Some(class) if !consent.keys => class.to_string(),
+            _ => key_name,
+        };
+        each(DynamicsRecord {
+            participant_id,
+            test_section_id: section.1,
+            keystroke_id: index as i64 + 1,
+            press_time: pressed_ms - origin,
+            release_time: released_ms - origin,
+            letter,
+        })?;
+    }
+    Ok(())
+}
+
+/// Activity blocks of the key events in `range`, detected while the timestamps are read
+fn key_blocks(conn: &Connection, range: &TimeRange, gap: Duration) -> Result<Vec<ActivityBlock>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT timestamp FROM key_events WHERE {} ORDER BY id",
+        RANGE_CONDITION
+    ))?;
+
+    let timestamps = stmt.query_map((range.since_param(), range.until_param()), |row| {
+        row.get::<_, String>(0)
+    })?;
+
+    Ok(detect_blocks(
+        timestamps
+            .filter_map(|t| t.ok())
+            .filter_map(|t| DateTime::parse_from_rfc3339(&t).ok())
+            .map(|t| t.with_timezone(&Local)),
+        gap,
+    ))
+}
+
+/// One timesheet row per block, in local time
+fn block_csv_row(block: &ActivityBlock) -> String {
+    format!(
+        "{},{},{},{:.1},{}",
+        block.start.format("%Y-%m-%d"),
+        block.start.format("%H:%M:%S"),
+        block.end.format("%H:%M:%S"),
+        block.duration_minutes(),
+        block.keys
+    )
+}
+
+/// Write blocks as an iCalendar (RFC 5545) calendar with one event per block
+fn write_ics(out: &mut impl Write, blocks: &[ActivityBlock]) -> std::io::Result<()> {
+    let utc = |t: &DateTime<Local>| t.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string();
+    let stamp = utc(&Local::now());
+
+    write!(
+        out,
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//kitmap//activity blocks//EN\r\nCALSCALE:GREGORIAN\r\n"
+    )?;
+    for block in blocks {
+        write!(
+            out,
+            "BEGIN:VEVENT\r\nUID:{}-{}@kitmap\r\nDTSTAMP:{}\r\nDTSTART:{}\r\nDTEND:{}\r\nSUMMARY:Typing ({} keys)\r\nEND:VEVENT\r\n",
+            block.start.timestamp(),
+            block.end.timestamp(),
+            stamp,
+            utc(&block.start),
+            utc(&block.end),
+            block.keys
+        )?;
+    }
+    write!(out, "END:VCALENDAR\r\n")
+}
+
+/// Writes records one at a time, as CSV rows or as the elements of a JSON array, so
+/// exports never hold more than one record in memory
+struct RecordWriter<'a, W: Write> {
+    out: &'a mut W,
+    format: ExportFormat,
+    written: usize,
+}
+
+impl<'a, W: Write> RecordWriter<'a, W> {
+    /// Write what comes before the first record, `csv_header` for CSV
+    fn start(out: &'a mut W, format: ExportFormat, csv_header: &str) -> Result<Self> {
+        match format {
+            ExportFormat::Csv => writeln!(out, "{}", csv_header)?,
+            ExportFormat::Json => out.write_all(b"[")?,
+            ExportFormat::Ics | ExportFormat::Activitywatch => unreachable!(),
+        }
+        Ok(Self {
+            out,
+            format,
+            written: 0,
+        })
+    }
+
+    fn write<R: ExportRecord>(&mut self, record: &R) -> Result<()> {
+        match self.format {
+            ExportFormat::Csv => writeln!(self.out, "{}", record.csv_row())?,
+            _ => {
+                if self.written > 0 {
+                    self.out.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut *self.out, record)?;
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Write what comes after the last record
+    fn finish(self) -> Result<()> {
+        if self.format == ExportFormat::Json {
+            self.out.write_all(b"]")?;
+        }
+        Ok(())
+    }
+}
+
+fn write_csv(
+    out: &mut impl Write,
+    header: &str,
+    rows: impl Iterator<Item = String>,
+) -> std::io::Result<()> {
+    writeln!(out, "{}", header)?;
+    for row in rows {
+        writeln!(out, "{}", row)?;
+    }
+    Ok(())
+}
+
+/// Quote a CSV field when it contains separators, quotes or newlines
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
@@ -0,0 +1,392 @@
+use crate::db::schema;
+use anyhow::{bail, Context, Result};
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+/// Columns of `key_events` other than `id`, `session_id` and `seq`, copied as they are
+const KEY_EVENT_COLUMNS: &str = "key_code, key_name, is_modifier, timestamp, timestamp_ms, hour, \
+     day_of_week, workspace, monitor, remote, source, project, category, composed, app, \
+     released_ms";
+
+/// Tables without sessions copied row by row, with the columns copied and the columns
+/// that make two rows the same recording
+const EVENT_TABLES: [(&str, &str, &str); 6] = [
+    (
+        "key_combos",
+        "combo, timestamp, timestamp_ms, app",
+        "combo = o.combo AND timestamp_ms IS o.timestamp_ms",
+    ),
+    (
+        "key_chords",
+        "chord, key_count, spread_ms, timestamp, timestamp_ms",
+        "chord = o.chord AND timestamp_ms = o.timestamp_ms",
+    ),
+    (
+        "typing_samples",
+        "chars_per_minute, timestamp, timestamp_ms",
+        "timestamp = o.timestamp AND chars_per_minute = o.chars_per_minute",
+    ),
+    (
+        "typing_bursts",
+        "keys, duration_ms, peak_cpm, average_cpm, timestamp, timestamp_ms",
+        "timestamp_ms = o.timestamp_ms AND keys = o.keys",
+    ),
+    (
+        "combo_storms",
+        "combo, start_time, end_time, count",
+        "combo = o.combo AND start_time = o.start_time",
+    ),
+    (
+        "imported_activity",
+        "source, presses, timestamp, timestamp_ms",
+        "source = o.source AND timestamp_ms = o.timestamp_ms",
+    ),
+];
+
+/// What merging another database added
+#[derive(Debug, Clone, Default)]
+pub struct MergeSummary {
+    /// Sessions copied over because nothing here overlapped them
+    pub sessions_added: usize,
+    /// Sessions that overlapped one here and were folded into it
+    pub sessions_reconciled: usize,
+    pub key_events: usize,
+    /// Key events already recorded here, and skipped
+    pub duplicate_key_events: usize,
+    pub combos: usize,
+    pub chords: usize,
+    pub typing_samples: usize,
+    pub typing_bursts: usize,
+}
+
+/// Merge the kitmap database at `other` into `conn`.
+///
+/// Sessions of `other` that overlap a session here in time are folded into it, and the
+/// rest are added. Key events, combos, chords, typing samples and bursts already
+/// recorded here (the same key at the same millisecond) are skipped, so merging the same
+/// file twice adds nothing. `other` is read from a migrated copy and is never modified.
+pub fn merge(conn: &mut Connection, other: &Path) -> Result<MergeSummary> {
+    // Only readable by us, and deleted when dropped however the merge ends
+    let copy = NamedTempFile::with_prefix("kitmap-merge-")?;
+    {
+        let source = Connection::open_with_flags(other, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open {}", other.display()))?;
+        let is_kitmap: i64 = source
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'key_events'",
+                [],
+                |row| row.get(0),
+            )
+            .with_context(|| format!("{} is not a SQLite database", other.display()))?;
+        if is_kitmap == 0 {
+            bail!("{} is not a kitmap database", other.display());
+        }
+        source.execute("VACUUM INTO ?1", [copy.path().to_string_lossy()])?;
+    }
+    // Bring a database written by an older version up to date
+    schema::create_tables(&Connection::open(copy.path())?)?;
+
+    conn.execute(
+        "ATTACH DATABASE ?1 AS other",
+        [copy.path().to_string_lossy()],
+    )?;
+    let result = merge_attached(conn);
+    conn.execute("DETACH DATABASE other", [])?;
+    result
+}
+
+fn merge_attached(conn: &mut Connection) -> Result<MergeSummary> {
+    let mut summary = MergeSummary::default();
+    let tx = conn.transaction()?;
+
+    tx.execute_batch(
+        "CREATE TEMP TABLE merge_sessions (
+             other_id INTEGER PRIMARY KEY,
+             local_id INTEGER NOT NULL,
+             reconciled INTEGER NOT NULL
+         )",
+    )?;
+
+    // Sessions still open in the other database end at their last key press
+    let sessions: Vec<(i64, String, String, i64, i64)> = {
+        let mut stmt = tx.prepare(
+            "SELECT id, start_time,
+                    COALESCE(end_time,
+                             (SELECT MAX(timestamp) FROM other.key_events WHERE session_id = s.id),
+                             start_time),
+                    start_ms,
+                    COALESCE(end_ms,
+                             (SELECT MAX(timestamp_ms) FROM other.key_events WHERE session_id = s.id),
+                             start_ms)
+             FROM other.sessions s ORDER BY start_ms",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for (other_id, start, end, start_ms, end_ms) in &sessions {
+        // A session here that is still open is being recorded and runs until now
+        let overlapping: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM main.sessions
+                 WHERE start_ms <= ?2 AND (end_ms IS NULL OR end_ms >= ?1)
+                 ORDER BY start_ms LIMIT 1",
+                (start_ms, end_ms),
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let (local_id, reconciled) = match overlapping {
+            Some(id) => {
+                tx.execute(
+                    "UPDATE main.sessions SET
+                         start_time = MIN(start_time, ?2),
+                         end_time = CASE WHEN end_time IS NULL THEN NULL ELSE MAX(end_time, ?3) END
+                     WHERE id = ?1",
+                    (id, start, end),
+                )?;
+                summary.sessions_reconciled += 1;
+                (id, true)
+            }
+            None => {
+                tx.execute(
+                    "INSERT INTO main.sessions (start_time, end_time, total_keys) VALUES (?1, ?2, 0)",
+                    (start, end),
+                )?;
+                summary.sessions_added += 1;
+                (tx.last_insert_rowid(), false)
+            }
+        };
+        tx.execute(
+            "INSERT INTO temp.merge_sessions (other_id, local_id, reconciled) VALUES (?1, ?2, ?3)",
+            (other_id, local_id, reconciled),
+        )?;
+    }
+
+    let other_keys: i64 = tx.query_row("SELECT COUNT(*) FROM other.key_events", [], |row| {
+        row.get(0)
+    })?;
+    summary.key_events = tx.execute(
+        &format!(
+            "INSERT INTO main.key_events ({0}, session_id, seq)
+             SELECT {1}, m.local_id, o.seq
+             FROM other.key_events o
+             LEFT JOIN temp.merge_sessions m ON m.other_id = o.session_id
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM main.key_events
+                 WHERE key_name = o.key_name AND timestamp_ms IS o.timestamp_ms
+                   AND key_code = o.key_code
+             )
+             ORDER BY o.id",
+            KEY_EVENT_COLUMNS,
+            prefixed(KEY_EVENT_COLUMNS, "o")
+        ),
+        [],
+    )?;
+    summary.duplicate_key_events = other_keys as usize - summary.key_events;
+
+    // Keys from two sessions folded together are put back in the order they were typed
+    tx.execute(
+        "UPDATE main.key_events SET seq = ordered.position
+         FROM (
+             SELECT id, ROW_NUMBER() OVER (
+                 PARTITION BY session_id ORDER BY timestamp_ms, seq, id
+             ) - 1 AS position
+             FROM main.key_events
+             WHERE session_id IN (SELECT local_id FROM temp.merge_sessions WHERE reconciled)
+         ) AS ordered
+         WHERE key_events.id = ordered.id",
+        [],
+    )?;
+    tx.execute(
+        "UPDATE main.sessions SET
+             total_keys = (SELECT COUNT(*) FROM main.key_events WHERE session_id = sessions.id)
+         WHERE end_time IS NOT NULL
+           AND id IN (SELECT local_id FROM temp.merge_sessions)",
+        [],
+    )?;
+
+    for (table, columns, same) in EVENT_TABLES {
+        let added = tx.execute(
+            &format!(
+                "INSERT INTO main.{0} ({1})
+                 SELECT {2} FROM other.{0} o
+                 WHERE NOT EXISTS (SELECT 1 FROM main.{0} WHERE {3})
+                 ORDER BY o.id",
+                table,
+                columns,
+                prefixed(columns, "o"),
+                same
+            ),
+            [],
+        )?;
+        match table {
+            "key_combos" => summary.combos = added,
+            "key_chords" => summary.chords = added,
+            "typing_samples" => summary.typing_samples = added,
+            "typing_bursts" => summary.typing_bursts = added,
+            _ => {}
+        }
+    }
+
+    // Bigram counts of a folded session may be the same typing seen twice, so the larger
+    // count is kept rather than the sum
+    tx.execute(
+        "INSERT INTO main.key_bigrams (session_id, first_key, second_key, count)
+         SELECT m.local_id, o.first_key, o.second_key, o.count
+         FROM other.key_bigrams o JOIN temp.merge_sessions m ON m.other_id = o.session_id
+         WHERE true
+         ON CONFLICT (session_id, first_key, second_key)
+         DO UPDATE SET count = MAX(count, excluded.count)",
+        [],
+    )?;
+    tx.execute(
+        "INSERT INTO main.break_reminders
+             (session_id, reminded_at, streak_minutes, reminders, taken, break_started_at, break_secs)
+         SELECT m.local_id, o.reminded_at, o.streak_minutes, o.reminders, o.taken,
+                o.break_started_at, o.break_secs
+         FROM other.break_reminders o LEFT JOIN temp.merge_sessions m ON m.other_id = o.session_id
+         WHERE NOT EXISTS (SELECT 1 FROM main.break_reminders WHERE reminded_at = o.reminded_at)",
+        [],
+    )?;
+    tx.execute(
+        "INSERT INTO main.latency_reports (session_id, events, p50_us, p99_us, max_us, timestamp)
+         SELECT m.local_id, o.events, o.p50_us, o.p99_us, o.max_us, o.timestamp
+         FROM other.latency_reports o LEFT JOIN temp.merge_sessions m ON m.other_id = o.session_id
+         WHERE NOT EXISTS (SELECT 1 FROM main.latency_reports WHERE timestamp = o.timestamp)",
+        [],
+    )?;
+
+    tx.execute_batch("DROP TABLE temp.merge_sessions")?;
+    tx.commit()?;
+    Ok(summary)
+}
+
+/// `columns` with each name qualified by `alias`, e.g. `o.combo, o.timestamp`
+fn prefixed(columns: &str, alias: &str) -> String {
+    columns
+        .split(',')
+        .map(|column| format!("{}.{}", alias, column.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYS: &str = "INSERT INTO key_events
+        (key_code, key_name, timestamp, timestamp_ms, hour, day_of_week, session_id, seq)";
+
+    fn database(path: &Path, sql: &str) -> Connection {
+        let conn = Connection::open(path).unwrap();
+        schema::create_tables(&conn).unwrap();
+        conn.execute_batch(sql).unwrap();
+        conn
+    }
+
+    fn rows<T: rusqlite::types::FromSql>(conn: &Connection, sql: &str) -> Vec<T> {
+        let mut stmt = conn.prepare(sql).unwrap();
+        let rows = stmt.query_map([], |row| row.get(0));
+        rows.unwrap().collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn folds_overlapping_sessions_and_skips_what_is_already_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut conn = database(
+            &dir.path().join("here.db"),
+            &format!(
+                "INSERT INTO sessions (id, start_time, end_time, total_keys) VALUES
+                     (1, '2024-03-01T10:00:00.000Z', '2024-03-01T11:00:00.000Z', 2);
+                 {KEYS} VALUES
+                     ('KeyA', 'KeyA', '2024-03-01T10:10:00.000Z', 1709287800000, 10, 4, 1, 0),
+                     ('KeyC', 'KeyC', '2024-03-01T10:30:00.000Z', 1709289000000, 10, 4, 1, 1);"
+            ),
+        );
+        let other = dir.path().join("other.db");
+        database(
+            &other,
+            &format!(
+                "INSERT INTO sessions (id, start_time, end_time, total_keys) VALUES
+                     (7, '2024-03-01T09:50:00.000Z', '2024-03-01T10:20:00.000Z', 2),
+                     (8, '2024-03-02T08:00:00.000Z', NULL, 1);
+                 {KEYS} VALUES
+                     ('KeyA', 'KeyA', '2024-03-01T10:10:00.000Z', 1709287800000, 10, 4, 7, 0),
+                     ('KeyB', 'KeyB', '2024-03-01T10:15:00.000Z', 1709288100000, 10, 4, 7, 1),
+                     ('KeyD', 'KeyD', '2024-03-02T08:05:00.000Z', 1709366700000, 8, 5, 8, 0);
+                 INSERT INTO key_combos (combo, timestamp, timestamp_ms) VALUES
+                     ('Ctrl+C', '2024-03-01T10:12:00.000Z', 1709287920000);
+                 INSERT INTO key_bigrams (session_id, first_key, second_key, count) VALUES
+                     (7, 'KeyA', 'KeyB', 1);"
+            ),
+        );
+
+        let summary = merge(&mut conn, &other).unwrap();
+        assert_eq!(summary.sessions_reconciled, 1);
+        assert_eq!(summary.sessions_added, 1);
+        assert_eq!(summary.key_events, 2);
+        assert_eq!(summary.duplicate_key_events, 1);
+        assert_eq!(summary.combos, 1);
+
+        // The folded session spans both and has its keys in the order they were typed
+        assert_eq!(
+            rows::<String>(&conn, "SELECT start_time FROM sessions WHERE id = 1"),
+            ["2024-03-01T09:50:00.000Z"]
+        );
+        assert_eq!(
+            rows::<String>(
+                &conn,
+                "SELECT key_name FROM key_events WHERE session_id = 1 ORDER BY seq"
+            ),
+            ["KeyA", "KeyB", "KeyC"]
+        );
+        assert_eq!(
+            rows::<i64>(&conn, "SELECT total_keys FROM sessions ORDER BY id"),
+            [3, 1]
+        );
+        // The session still open over there ends at its last key here
+        assert_eq!(
+            rows::<String>(&conn, "SELECT end_time FROM sessions WHERE id = 2"),
+            ["2024-03-02T08:05:00.000Z"]
+        );
+        assert_eq!(
+            rows::<i64>(&conn, "SELECT session_id FROM key_bigrams"),
+            [1]
+        );
+
+        let again = merge(&mut conn, &other).unwrap();
+        assert_eq!(again.key_events, 0);
+        assert_eq!(again.combos, 0);
+        assert_eq!(again.sessions_added, 0);
+        assert_eq!(rows::<i64>(&conn, "SELECT COUNT(*) FROM key_events"), [4]);
+    }
+
+    #[test]
+    fn refuses_databases_that_are_not_kitmaps() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        let other = dir.path().join("notes.db");
+        Connection::open(&other)
+            .unwrap()
+            .execute_batch("CREATE TABLE notes (text TEXT)")
+            .unwrap();
+
+        let error = merge(&mut conn, &other).unwrap_err();
+        assert!(
+            error.to_string().contains("not a kitmap database"),
+            "{}",
+            error
+        );
+    }
+}
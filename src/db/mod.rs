@@ -1,13 +1,24 @@
+pub mod activitywatch;
+mod actor;
+pub mod analyze;
+pub mod audit;
+pub mod export;
+pub mod merge;
 pub mod models;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod sanitize;
 pub mod schema;
+pub mod store;
+pub mod time;
 
-use anyhow::Result;
+pub use actor::DbConnection;
+
+use anyhow::{bail, Result};
 use directories::ProjectDirs;
-use rusqlite::Connection;
+use rusqlite::{Connection, OpenFlags};
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
-
-pub type DbConnection = Arc<Mutex<Connection>>;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Get the database path in the user's data directory
 pub fn get_db_path() -> Result<PathBuf> {
@@ -20,7 +31,52 @@ pub fn get_db_path() -> Result<PathBuf> {
     }
 }
 
-/// Initialize the database connection and create tables
+/// Path of the file holding the process id of the running listener
+pub fn get_pid_path() -> Result<PathBuf> {
+    Ok(get_db_path()?.with_file_name("kitmap.pid"))
+}
+
+/// Path of the file the running listener holds an exclusive lock on
+pub fn get_lock_path() -> Result<PathBuf> {
+    Ok(get_db_path()?.with_file_name("kitmap.lock"))
+}
+
+/// Path of the socket `kitmap pause`, `resume`, `session` and `status` reach the running
+/// listener through
+pub fn get_socket_path() -> Result<PathBuf> {
+    Ok(get_db_path()?.with_file_name("kitmap.sock"))
+}
+
+/// Best-effort name of this machine
+pub fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Open the database for reading only. Unlike `init_db`, nothing is created, migrated
+/// or written, so other programs can read it safely while `kitmap listen` records. A
+/// database written by an older kitmap must be opened by the CLI once first.
+pub fn open_db_read_only() -> Result<DbConnection> {
+    let path = get_db_path()?;
+    if !path.exists() {
+        bail!(
+            "No kitmap database at {}; run `kitmap listen` to record one",
+            path.display()
+        );
+    }
+    let db = DbConnection::read_only(path.to_string_lossy().into_owned());
+    // Fail here rather than on the first query when the file isn't a database
+    db.read(|conn| Ok(conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))?))?;
+    Ok(db)
+}
+
+/// Initialize the database connection, create tables and bring older databases up to date,
+/// including rewriting their timestamps in UTC. Writes go through a database thread.
 pub fn init_db() -> Result<DbConnection> {
     let db_path = get_db_path()?;
     let conn = Connection::open(&db_path)?;
@@ -30,13 +86,23 @@ pub fn init_db() -> Result<DbConnection> {
 
     schema::create_tables(&conn)?;
 
-    Ok(Arc::new(Mutex::new(conn)))
+    DbConnection::spawn(conn, db_path.to_string_lossy().into_owned())
 }
 
-/// Initialize an in-memory database for testing
-#[cfg(test)]
-pub fn init_test_db() -> Result<DbConnection> {
-    let conn = Connection::open_in_memory()?;
+/// Initialize a throwaway in-memory database with the full schema
+pub fn init_memory_db() -> Result<DbConnection> {
+    // Named and shared, so read-only connections can open the same database
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let uri = format!(
+        "file:kitmap-memory-{}?mode=memory&cache=shared",
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    );
+    let conn = Connection::open_with_flags(
+        &uri,
+        OpenFlags::SQLITE_OPEN_READ_WRITE
+            | OpenFlags::SQLITE_OPEN_CREATE
+            | OpenFlags::SQLITE_OPEN_URI,
+    )?;
     schema::create_tables(&conn)?;
-    Ok(Arc::new(Mutex::new(conn)))
+    DbConnection::spawn(conn, uri)
 }
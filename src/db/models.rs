@@ -1,4 +1,6 @@
+use crate::db::time;
 use crate::db::DbConnection;
+use crate::stats::latency::LatencySummary;
 use anyhow::Result;
 use chrono::{DateTime, Datelike, Local, Timelike};
 use serde::{Deserialize, Serialize};
@@ -10,42 +12,179 @@ pub struct KeyEvent {
     pub key_name: String,
     pub is_modifier: bool,
     pub timestamp: DateTime<Local>,
+    pub session_id: Option<i64>,
+    /// Monotonic position of the event within its session
+    pub seq: i64,
+    /// Focused window manager workspace, when workspace tracking is enabled
+    pub workspace: Option<String>,
+    /// Monitor showing the focused workspace, when monitor tracking is enabled
+    pub monitor: Option<String>,
+    /// Whether the key was typed into an SSH/remote session, when remote tracking is enabled
+    pub remote: Option<bool>,
+    /// Project parsed from the focused window's title, when project tracking is enabled
+    pub project: Option<String>,
+    /// Application of the focused window, when app tracking is enabled
+    pub app: Option<String>,
+    /// Text the key press produced after dead keys and IME composition, when recording
+    /// composed characters is enabled and the platform reports it
+    pub composed: Option<String>,
 }
 
 impl KeyEvent {
-    pub fn new(key_code: String, key_name: String, is_modifier: bool) -> Self {
+    pub fn new(
+        key_code: String,
+        key_name: String,
+        is_modifier: bool,
+        session_id: Option<i64>,
+        seq: i64,
+    ) -> Self {
         Self {
             id: None,
             key_code,
             key_name,
             is_modifier,
             timestamp: Local::now(),
+            session_id,
+            seq,
+            workspace: None,
+            monitor: None,
+            remote: None,
+            project: None,
+            app: None,
+            composed: None,
         }
     }
 
     pub fn save(&self, db: &DbConnection) -> Result<()> {
-        let conn = db.lock().unwrap();
-        conn.execute(
-            "INSERT INTO key_events (key_code, key_name, is_modifier, timestamp, hour, day_of_week)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            (
-                &self.key_code,
-                &self.key_name,
-                self.is_modifier as i32,
-                self.timestamp.to_rfc3339(),
-                self.timestamp.hour() as i32,
-                self.timestamp.weekday().num_days_from_monday() as i32,
-            ),
+        let event = self.clone();
+        db.call_blocking(move |conn| event.insert(conn))
+    }
+
+    /// Save several events in a single transaction
+    pub fn save_all(db: &DbConnection, events: Vec<Self>) -> Result<()> {
+        db.call_blocking(move |conn| {
+            let tx = conn.transaction()?;
+            for event in &events {
+                event.insert(&tx)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn insert(&self, conn: &rusqlite::Connection) -> Result<()> {
+        // Cached, as this runs for every key press
+        let mut stmt = conn.prepare_cached(
+            "INSERT INTO key_events (key_code, key_name, is_modifier, timestamp, timestamp_ms, hour, day_of_week, session_id, seq, workspace, monitor, remote, project, category, composed, app)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        )?;
+        stmt.execute((
+            &self.key_code,
+            &self.key_name,
+            self.is_modifier as i32,
+            time::format(&self.timestamp),
+            self.timestamp.timestamp_millis(),
+            self.timestamp.hour() as i32,
+            self.timestamp.weekday().num_days_from_monday() as i32,
+            self.session_id,
+            self.seq,
+            &self.workspace,
+            &self.monitor,
+            self.remote,
+            &self.project,
+            KeyCategory::of(&self.key_name).as_str(),
+            &self.composed,
+            &self.app,
+        ))?;
+        Ok(())
+    }
+}
+
+/// When a recorded key press was released, for keystroke dynamics
+#[derive(Debug, Clone)]
+pub struct KeyRelease {
+    pub session_id: i64,
+    /// Position of the released key's press within its session
+    pub seq: i64,
+    pub timestamp: DateTime<Local>,
+}
+
+impl KeyRelease {
+    /// Stamp several release times on their key events in a single transaction
+    pub fn save_all(db: &DbConnection, releases: Vec<Self>) -> Result<()> {
+        db.call_blocking(move |conn| {
+            let tx = conn.transaction()?;
+            for release in &releases {
+                release.update(&tx)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn update(&self, conn: &rusqlite::Connection) -> Result<()> {
+        let mut stmt = conn.prepare_cached(
+            "UPDATE key_events SET released_ms = ?1 WHERE session_id = ?2 AND seq = ?3",
         )?;
+        stmt.execute((self.timestamp.timestamp_millis(), self.session_id, self.seq))?;
         Ok(())
     }
 }
 
+/// Kind of key, stored with each key event so stats can count letters and digits
+/// through an index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyCategory {
+    Letter,
+    Digit,
+    Other,
+}
+
+impl KeyCategory {
+    pub fn of(key_name: &str) -> Self {
+        let single = |rest: &str, digit: bool| {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if digit => c.is_ascii_digit(),
+                (Some(c), None) => c.is_ascii_alphabetic(),
+                _ => false,
+            }
+        };
+
+        if key_name
+            .strip_prefix("Key")
+            .is_some_and(|rest| single(rest, false))
+        {
+            KeyCategory::Letter
+        } else if ["Num", "Kp"].iter().any(|prefix| {
+            key_name
+                .strip_prefix(prefix)
+                .is_some_and(|rest| single(rest, true))
+        }) {
+            KeyCategory::Digit
+        } else {
+            KeyCategory::Other
+        }
+    }
+
+    /// Value of the `category` column
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeyCategory::Letter => "letter",
+            KeyCategory::Digit => "digit",
+            KeyCategory::Other => "other",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyCombo {
     pub id: Option<i64>,
     pub combo: String,
     pub timestamp: DateTime<Local>,
+    /// Application of the focused window, when app tracking is enabled
+    pub app: Option<String>,
 }
 
 impl KeyCombo {
@@ -54,16 +193,122 @@ impl KeyCombo {
             id: None,
             combo,
             timestamp: Local::now(),
+            app: None,
         }
     }
 
     pub fn save(&self, db: &DbConnection) -> Result<()> {
-        let conn = db.lock().unwrap();
-        conn.execute(
-            "INSERT INTO key_combos (combo, timestamp) VALUES (?1, ?2)",
-            (&self.combo, self.timestamp.to_rfc3339()),
-        )?;
-        Ok(())
+        let params = (
+            self.combo.clone(),
+            time::format(&self.timestamp),
+            self.timestamp.timestamp_millis(),
+            self.app.clone(),
+        );
+        db.call_blocking(move |conn| {
+            conn.prepare_cached(
+                "INSERT INTO key_combos (combo, timestamp, timestamp_ms, app) VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(params)?;
+            Ok(())
+        })
+    }
+
+    /// Delete recorded occurrences of a combo since the given time, returning how many were removed
+    pub fn delete_since(db: &DbConnection, combo: &str, since: DateTime<Local>) -> Result<usize> {
+        let params = (combo.to_string(), since.timestamp_millis());
+        db.call_blocking(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM key_combos WHERE combo = ?1 AND timestamp_ms >= ?2",
+                params,
+            )?;
+            Ok(deleted)
+        })
+    }
+}
+
+/// Non-modifier keys pressed down together, such as a stenography stroke
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyChord {
+    pub id: Option<i64>,
+    /// Key names in alphabetical order, joined with `+`
+    pub chord: String,
+    pub key_count: i64,
+    /// Milliseconds between the first and last key going down
+    pub spread_ms: i64,
+    /// When the first key went down
+    pub timestamp: DateTime<Local>,
+}
+
+impl KeyChord {
+    pub fn new(mut keys: Vec<String>, timestamp: DateTime<Local>, spread_ms: i64) -> Self {
+        keys.sort();
+        Self {
+            id: None,
+            key_count: keys.len() as i64,
+            chord: keys.join("+"),
+            spread_ms,
+            timestamp,
+        }
+    }
+
+    pub fn save(&self, db: &DbConnection) -> Result<()> {
+        let params = (
+            self.chord.clone(),
+            self.key_count,
+            self.spread_ms,
+            time::format(&self.timestamp),
+            self.timestamp.timestamp_millis(),
+        );
+        db.call_blocking(move |conn| {
+            conn.prepare_cached(
+                "INSERT INTO key_chords (chord, key_count, spread_ms, timestamp, timestamp_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?
+            .execute(params)?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComboStorm {
+    pub id: Option<i64>,
+    pub combo: String,
+    pub start_time: DateTime<Local>,
+    pub end_time: DateTime<Local>,
+    pub count: i64,
+}
+
+impl ComboStorm {
+    pub fn new(
+        combo: String,
+        start_time: DateTime<Local>,
+        end_time: DateTime<Local>,
+        count: i64,
+    ) -> Self {
+        Self {
+            id: None,
+            combo,
+            start_time,
+            end_time,
+            count,
+        }
+    }
+
+    pub fn save(&self, db: &DbConnection) -> Result<()> {
+        let params = (
+            self.combo.clone(),
+            time::format(&self.start_time),
+            time::format(&self.end_time),
+            self.count,
+        );
+        db.call_blocking(move |conn| {
+            conn.execute(
+                "INSERT INTO combo_storms (combo, start_time, end_time, count) VALUES (?1, ?2, ?3, ?4)",
+                params,
+            )?;
+            Ok(())
+        })
     }
 }
 
@@ -75,6 +320,12 @@ pub struct Session {
     pub total_keys: i64,
 }
 
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Session {
     pub fn new() -> Self {
         Self {
@@ -86,31 +337,86 @@ impl Session {
     }
 
     pub fn start(&mut self, db: &DbConnection) -> Result<i64> {
-        let conn = db.lock().unwrap();
-        conn.execute(
-            "INSERT INTO sessions (start_time, total_keys) VALUES (?1, ?2)",
-            (self.start_time.to_rfc3339(), self.total_keys),
-        )?;
-        let id = conn.last_insert_rowid();
+        let params = (time::format(&self.start_time), self.total_keys);
+        let id = db.call_blocking(move |conn| {
+            conn.execute(
+                "INSERT INTO sessions (start_time, total_keys) VALUES (?1, ?2)",
+                params,
+            )?;
+            Ok(conn.last_insert_rowid())
+        })?;
         self.id = Some(id);
         Ok(id)
     }
 
+    /// Close the session at its `end_time`, or now when none was set
     pub fn end(&mut self, db: &DbConnection) -> Result<()> {
-        self.end_time = Some(Local::now());
+        let end_time = *self.end_time.get_or_insert_with(Local::now);
         if let Some(id) = self.id {
-            let conn = db.lock().unwrap();
-            conn.execute(
-                "UPDATE sessions SET end_time = ?1, total_keys = ?2 WHERE id = ?3",
-                (self.end_time.unwrap().to_rfc3339(), self.total_keys, id),
-            )?;
+            let params = (time::format(&end_time), self.total_keys, id);
+            db.call_blocking(move |conn| {
+                conn.execute(
+                    "UPDATE sessions SET end_time = ?1, total_keys = ?2 WHERE id = ?3",
+                    params,
+                )?;
+                Ok(())
+            })?;
         }
         Ok(())
     }
 
+    /// The session currently being recorded, if the newest session is still open
+    pub fn current(db: &DbConnection) -> Result<Option<Self>> {
+        let latest = db.read(|conn| {
+            Ok(conn.query_row(
+                "SELECT id, start_time, end_time, total_keys FROM sessions ORDER BY id DESC LIMIT 1",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, i64>(3)?,
+                    ))
+                },
+            ))
+        })?;
+
+        match latest {
+            Ok((id, start_time, None, total_keys)) => Ok(Some(Self {
+                id: Some(id),
+                start_time: time::parse(&start_time)?,
+                end_time: None,
+                total_keys,
+            })),
+            Ok(_) | Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     pub fn increment_keys(&mut self) {
         self.total_keys += 1;
     }
+
+    /// Close sessions left open by a listener that crashed or was killed, at their last
+    /// recorded key press (or their start, when nothing was recorded), and count their
+    /// keys. Only call this before a listener starts recording, since the session of a
+    /// listener that is still running is open too. Returns how many were closed.
+    pub fn close_orphaned(db: &DbConnection) -> Result<usize> {
+        db.call_blocking(|conn| {
+            Ok(conn.execute(
+                "UPDATE sessions SET
+                     end_time = COALESCE(
+                         (SELECT timestamp FROM key_events WHERE session_id = sessions.id
+                          ORDER BY seq DESC LIMIT 1),
+                         start_time
+                     ),
+                     total_keys = (SELECT COUNT(*) FROM key_events WHERE session_id = sessions.id)
+                 WHERE end_time IS NULL",
+                [],
+            )?)
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,11 +434,326 @@ impl TypingSample {
     }
 
     pub fn save(&self, db: &DbConnection) -> Result<()> {
-        let conn = db.lock().unwrap();
-        conn.execute(
-            "INSERT INTO typing_samples (chars_per_minute, timestamp) VALUES (?1, ?2)",
-            (self.chars_per_minute, self.timestamp.to_rfc3339()),
-        )?;
-        Ok(())
+        let params = (
+            self.chars_per_minute,
+            time::format(&self.timestamp),
+            self.timestamp.timestamp_millis(),
+        );
+        db.call_blocking(move |conn| {
+            conn.prepare_cached(
+                "INSERT INTO typing_samples (chars_per_minute, timestamp, timestamp_ms)
+                 VALUES (?1, ?2, ?3)",
+            )?
+            .execute(params)?;
+            Ok(())
+        })
+    }
+}
+
+/// A sustained run of fast typing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypingBurst {
+    pub start_time: DateTime<Local>,
+    pub end_time: DateTime<Local>,
+    pub keys: i64,
+    /// Fastest speed over the detection window, in characters per minute
+    pub peak_cpm: f64,
+}
+
+impl TypingBurst {
+    pub fn new(
+        start_time: DateTime<Local>,
+        end_time: DateTime<Local>,
+        keys: i64,
+        peak_cpm: f64,
+    ) -> Self {
+        Self {
+            start_time,
+            end_time,
+            keys,
+            peak_cpm,
+        }
+    }
+
+    pub fn duration_ms(&self) -> i64 {
+        (self.end_time - self.start_time).num_milliseconds()
+    }
+
+    /// Speed over the whole burst, in characters per minute
+    pub fn average_cpm(&self) -> f64 {
+        match self.duration_ms() {
+            0 => self.peak_cpm,
+            ms => self.keys as f64 * 60_000.0 / ms as f64,
+        }
+    }
+
+    pub fn save(&self, db: &DbConnection) -> Result<()> {
+        let params = (
+            self.keys,
+            self.duration_ms(),
+            self.peak_cpm,
+            self.average_cpm(),
+            time::format(&self.start_time),
+            self.start_time.timestamp_millis(),
+        );
+        db.call_blocking(move |conn| {
+            conn.execute(
+                "INSERT INTO typing_bursts
+                     (keys, duration_ms, peak_cpm, average_cpm, timestamp, timestamp_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params,
+            )?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyBigram {
+    pub session_id: i64,
+    pub first_key: String,
+    pub second_key: String,
+    pub count: i64,
+}
+
+impl KeyBigram {
+    pub fn new(session_id: i64, first_key: String, second_key: String, count: i64) -> Self {
+        Self {
+            session_id,
+            first_key,
+            second_key,
+            count,
+        }
+    }
+
+    /// Add this bigram's count to the stored total for its session
+    pub fn save(&self, db: &DbConnection) -> Result<()> {
+        let params = (
+            self.session_id,
+            self.first_key.clone(),
+            self.second_key.clone(),
+            self.count,
+        );
+        db.call_blocking(move |conn| {
+            conn.prepare_cached(
+                "INSERT INTO key_bigrams (session_id, first_key, second_key, count)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (session_id, first_key, second_key)
+                 DO UPDATE SET count = count + excluded.count",
+            )?
+            .execute(params)?;
+            Ok(())
+        })
+    }
+
+    /// Replace a session's bigram counts with exact counts derived from its key events
+    pub fn reconcile_session(db: &DbConnection, session_id: i64) -> Result<()> {
+        db.call_blocking(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "DELETE FROM key_bigrams WHERE session_id = ?1",
+                [session_id],
+            )?;
+            tx.execute(
+                "INSERT INTO key_bigrams (session_id, first_key, second_key, count)
+                 SELECT ?1, prev_key, key_name, COUNT(*) FROM (
+                     SELECT LAG(key_name) OVER (ORDER BY seq) AS prev_key, key_name
+                     FROM key_events WHERE session_id = ?1
+                 )
+                 WHERE prev_key IS NOT NULL
+                 GROUP BY prev_key, key_name",
+                [session_id],
+            )?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub session_id: Option<i64>,
+    pub events: i64,
+    pub p50_us: i64,
+    pub p99_us: i64,
+    pub max_us: i64,
+    pub timestamp: DateTime<Local>,
+}
+
+impl LatencyReport {
+    pub fn new(session_id: Option<i64>, summary: &LatencySummary) -> Self {
+        Self {
+            session_id,
+            events: summary.events as i64,
+            p50_us: summary.p50_us as i64,
+            p99_us: summary.p99_us as i64,
+            max_us: summary.max_us as i64,
+            timestamp: Local::now(),
+        }
+    }
+
+    pub fn save(&self, db: &DbConnection) -> Result<()> {
+        let params = (
+            self.session_id,
+            self.events,
+            self.p50_us,
+            self.p99_us,
+            self.max_us,
+            time::format(&self.timestamp),
+        );
+        db.call_blocking(move |conn| {
+            conn.execute(
+                "INSERT INTO latency_reports (session_id, events, p50_us, p99_us, max_us, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params,
+            )?;
+            Ok(())
+        })
+    }
+
+    /// The most recently recorded reports, newest first
+    pub fn recent(db: &DbConnection, limit: usize) -> Result<Vec<Self>> {
+        db.read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT session_id, events, p50_us, p99_us, max_us, timestamp
+             FROM latency_reports ORDER BY timestamp DESC LIMIT ?1",
+            )?;
+            let reports = stmt
+                .query_map([limit as i64], |row| {
+                    let timestamp: String = row.get(5)?;
+                    Ok(Self {
+                        session_id: row.get(0)?,
+                        events: row.get(1)?,
+                        p50_us: row.get(2)?,
+                        p99_us: row.get(3)?,
+                        max_us: row.get(4)?,
+                        timestamp: time::parse(&timestamp).unwrap_or_else(|_| Local::now()),
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(reports)
+        })
+    }
+}
+
+/// A suggested break and whether it was taken
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakReminder {
+    pub session_id: Option<i64>,
+    /// When the first reminder was shown
+    pub reminded_at: DateTime<Local>,
+    /// Minutes of continuous typing when the first reminder was shown
+    pub streak_minutes: i64,
+    /// Reminders shown before the break, counting the first
+    pub reminders: i64,
+    pub taken: bool,
+    /// Time of the last key before the break
+    pub break_started_at: Option<DateTime<Local>>,
+    pub break_secs: Option<i64>,
+}
+
+impl BreakReminder {
+    pub fn new(session_id: Option<i64>, reminded_at: DateTime<Local>, streak_minutes: i64) -> Self {
+        Self {
+            session_id,
+            reminded_at,
+            streak_minutes,
+            reminders: 1,
+            taken: false,
+            break_started_at: None,
+            break_secs: None,
+        }
+    }
+
+    pub fn save(&self, db: &DbConnection) -> Result<()> {
+        let params = (
+            self.session_id,
+            time::format(&self.reminded_at),
+            self.streak_minutes,
+            self.reminders,
+            self.taken,
+            self.break_started_at.map(|t| time::format(&t)),
+            self.break_secs,
+        );
+        db.call_blocking(move |conn| {
+            conn.execute(
+                "INSERT INTO break_reminders
+                     (session_id, reminded_at, streak_minutes, reminders, taken, break_started_at, break_secs)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params,
+            )?;
+            Ok(())
+        })
+    }
+}
+
+/// A recorded answer to the privacy summary shown by `kitmap listen`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Consent {
+    /// Ids of the kinds of data the summary listed
+    pub scopes: Vec<String>,
+    /// The summary exactly as it was shown
+    pub summary: String,
+    pub accepted: bool,
+    /// `prompt` when answered interactively, `--yes` when given on the command line
+    pub confirmed_by: String,
+    pub timestamp: DateTime<Local>,
+}
+
+impl Consent {
+    pub fn new(scopes: Vec<String>, summary: String, accepted: bool, confirmed_by: &str) -> Self {
+        Self {
+            scopes,
+            summary,
+            accepted,
+            confirmed_by: confirmed_by.to_string(),
+            timestamp: Local::now(),
+        }
+    }
+
+    pub fn save(&self, db: &DbConnection) -> Result<()> {
+        let params = (
+            self.scopes.join(","),
+            self.summary.clone(),
+            self.accepted,
+            self.confirmed_by.clone(),
+            time::format(&self.timestamp),
+        );
+        db.call_blocking(move |conn| {
+            conn.execute(
+                "INSERT INTO consents (scopes, summary, accepted, confirmed_by, timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params,
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Every consent that was given, oldest first
+    pub fn accepted(db: &DbConnection) -> Result<Vec<Self>> {
+        db.read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT scopes, summary, confirmed_by, timestamp FROM consents
+             WHERE accepted = 1 ORDER BY id",
+            )?;
+            let consents = stmt
+                .query_map([], |row| {
+                    let scopes: String = row.get(0)?;
+                    let timestamp: String = row.get(3)?;
+                    Ok(Self {
+                        scopes: scopes
+                            .split(',')
+                            .filter(|s| !s.is_empty())
+                            .map(str::to_string)
+                            .collect(),
+                        summary: row.get(1)?,
+                        accepted: true,
+                        confirmed_by: row.get(2)?,
+                        timestamp: time::parse(&timestamp).unwrap_or_else(|_| Local::now()),
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(consents)
+        })
     }
 }
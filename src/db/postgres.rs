@@ -0,0 +1,438 @@
+use crate::config::PostgresConfig;
+use crate::db::models::{
+    BreakReminder, ComboStorm, KeyBigram, KeyChord, KeyCombo, KeyEvent, LatencyReport, Session,
+    TypingBurst, TypingSample,
+};
+use crate::db::store::EventStore;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Local};
+use postgres::{Client, NoTls};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Tables shared by every machine, keyed by host name and each machine's local session id
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sessions (
+        host TEXT NOT NULL,
+        id BIGINT NOT NULL,
+        start_time TIMESTAMPTZ NOT NULL,
+        end_time TIMESTAMPTZ,
+        total_keys BIGINT NOT NULL DEFAULT 0,
+        PRIMARY KEY (host, id)
+    );
+    CREATE TABLE IF NOT EXISTS key_events (
+        id BIGSERIAL PRIMARY KEY,
+        host TEXT NOT NULL,
+        session_id BIGINT,
+        seq BIGINT NOT NULL,
+        key_code TEXT NOT NULL,
+        key_name TEXT NOT NULL,
+        is_modifier BOOLEAN NOT NULL,
+        timestamp TIMESTAMPTZ NOT NULL,
+        workspace TEXT,
+        monitor TEXT,
+        remote BOOLEAN,
+        project TEXT,
+        composed TEXT,
+        app TEXT
+    );
+    ALTER TABLE key_events ADD COLUMN IF NOT EXISTS project TEXT;
+    ALTER TABLE key_events ADD COLUMN IF NOT EXISTS composed TEXT;
+    ALTER TABLE key_events ADD COLUMN IF NOT EXISTS app TEXT;
+    CREATE INDEX IF NOT EXISTS idx_key_events_host_time ON key_events(host, timestamp);
+    CREATE INDEX IF NOT EXISTS idx_key_events_session ON key_events(host, session_id, seq);
+    CREATE TABLE IF NOT EXISTS key_combos (
+        id BIGSERIAL PRIMARY KEY,
+        host TEXT NOT NULL,
+        combo TEXT NOT NULL,
+        timestamp TIMESTAMPTZ NOT NULL,
+        app TEXT
+    );
+    ALTER TABLE key_combos ADD COLUMN IF NOT EXISTS app TEXT;
+    CREATE INDEX IF NOT EXISTS idx_key_combos_host_time ON key_combos(host, timestamp);
+    CREATE TABLE IF NOT EXISTS key_chords (
+        id BIGSERIAL PRIMARY KEY,
+        host TEXT NOT NULL,
+        chord TEXT NOT NULL,
+        key_count BIGINT NOT NULL,
+        spread_ms BIGINT NOT NULL,
+        timestamp TIMESTAMPTZ NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_key_chords_host_time ON key_chords(host, timestamp);
+    CREATE TABLE IF NOT EXISTS combo_storms (
+        id BIGSERIAL PRIMARY KEY,
+        host TEXT NOT NULL,
+        combo TEXT NOT NULL,
+        start_time TIMESTAMPTZ NOT NULL,
+        end_time TIMESTAMPTZ NOT NULL,
+        count BIGINT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS typing_samples (
+        id BIGSERIAL PRIMARY KEY,
+        host TEXT NOT NULL,
+        chars_per_minute DOUBLE PRECISION NOT NULL,
+        timestamp TIMESTAMPTZ NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS typing_bursts (
+        id BIGSERIAL PRIMARY KEY,
+        host TEXT NOT NULL,
+        keys BIGINT NOT NULL,
+        duration_ms BIGINT NOT NULL,
+        peak_cpm DOUBLE PRECISION NOT NULL,
+        average_cpm DOUBLE PRECISION NOT NULL,
+        timestamp TIMESTAMPTZ NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS key_bigrams (
+        host TEXT NOT NULL,
+        session_id BIGINT NOT NULL,
+        first_key TEXT NOT NULL,
+        second_key TEXT NOT NULL,
+        count BIGINT NOT NULL,
+        PRIMARY KEY (host, session_id, first_key, second_key)
+    );
+    CREATE TABLE IF NOT EXISTS latency_reports (
+        id BIGSERIAL PRIMARY KEY,
+        host TEXT NOT NULL,
+        session_id BIGINT,
+        events BIGINT NOT NULL,
+        p50_us BIGINT NOT NULL,
+        p99_us BIGINT NOT NULL,
+        max_us BIGINT NOT NULL,
+        timestamp TIMESTAMPTZ NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS break_reminders (
+        id BIGSERIAL PRIMARY KEY,
+        host TEXT NOT NULL,
+        session_id BIGINT,
+        reminded_at TIMESTAMPTZ NOT NULL,
+        streak_minutes BIGINT NOT NULL,
+        reminders BIGINT NOT NULL,
+        taken BOOLEAN NOT NULL,
+        break_started_at TIMESTAMPTZ,
+        break_secs BIGINT
+    );
+";
+
+/// How long to wait before reconnecting after the server becomes unreachable
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long `flush` waits for queued writes before giving up
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A write waiting to be sent to the server
+type Write = Box<dyn FnOnce(&mut Client) -> Result<(), postgres::Error> + Send>;
+
+/// A central Postgres database shared by several machines.
+///
+/// Writes are queued and sent from a background thread so a slow network never
+/// delays the key listener. Writes made while the server is unreachable are dropped.
+pub struct PostgresStore {
+    host: String,
+    writes: Sender<Write>,
+}
+
+impl PostgresStore {
+    /// Connect to the server, create the tables if needed and start the writer thread
+    pub fn connect(config: &PostgresConfig) -> Result<Self> {
+        if config.url.is_empty() {
+            bail!("Set `url` under [postgres] in the config file to a Postgres connection string");
+        }
+
+        let mut client = open(&config.url)?;
+        client
+            .batch_execute(SCHEMA)
+            .context("Failed to create the Postgres tables")?;
+
+        let (writes, queue) = mpsc::channel::<Write>();
+        let url = config.url.clone();
+        thread::spawn(move || {
+            let mut client = Some(client);
+            let mut retry_at = Instant::now();
+
+            for write in queue {
+                if client.as_ref().is_none_or(Client::is_closed) {
+                    if Instant::now() < retry_at {
+                        continue;
+                    }
+                    match open(&url) {
+                        Ok(c) => client = Some(c),
+                        Err(e) => {
+                            eprintln!("\n{:#}", e);
+                            client = None;
+                            retry_at = Instant::now() + RECONNECT_INTERVAL;
+                            continue;
+                        }
+                    }
+                }
+
+                if let Some(client) = client.as_mut() {
+                    if let Err(e) = write(client) {
+                        eprintln!("\nFailed to write to Postgres: {}", e);
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            host: config.host_name.clone().unwrap_or_else(crate::db::hostname),
+            writes,
+        })
+    }
+
+    /// Queue a write, passing it this machine's host name
+    fn queue(
+        &self,
+        write: impl FnOnce(&mut Client, &str) -> Result<(), postgres::Error> + Send + 'static,
+    ) -> Result<()> {
+        let host = self.host.clone();
+        self.writes
+            .send(Box::new(move |client| write(client, &host)))
+            .map_err(|_| anyhow!("The Postgres writer has stopped"))
+    }
+}
+
+fn open(url: &str) -> Result<Client> {
+    Client::connect(url, NoTls).context("Failed to connect to Postgres")
+}
+
+impl EventStore for PostgresStore {
+    fn start_session(&mut self, session: &mut Session) -> Result<()> {
+        let Some(id) = session.id else {
+            bail!("Postgres sessions must be numbered by the local database first");
+        };
+        let (start_time, total_keys) = (session.start_time, session.total_keys);
+        self.queue(move |client, host| {
+            client.execute(
+                "INSERT INTO sessions (host, id, start_time, total_keys) VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (host, id) DO UPDATE SET start_time = excluded.start_time",
+                &[&host, &id, &start_time, &total_keys],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn end_session(&mut self, session: &mut Session) -> Result<()> {
+        let Some(id) = session.id else {
+            return Ok(());
+        };
+        let end_time = session.end_time.unwrap_or_else(Local::now);
+        let total_keys = session.total_keys;
+        self.queue(move |client, host| {
+            client.execute(
+                "UPDATE sessions SET end_time = $1, total_keys = $2 WHERE host = $3 AND id = $4",
+                &[&end_time, &total_keys, &host, &id],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn save_key_event(&mut self, event: &KeyEvent) -> Result<()> {
+        let event = event.clone();
+        self.queue(move |client, host| {
+            client.execute(
+                "INSERT INTO key_events (host, session_id, seq, key_code, key_name, is_modifier, timestamp, workspace, monitor, remote, project, composed, app)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+                &[
+                    &host,
+                    &event.session_id,
+                    &event.seq,
+                    &event.key_code,
+                    &event.key_name,
+                    &event.is_modifier,
+                    &event.timestamp,
+                    &event.workspace,
+                    &event.monitor,
+                    &event.remote,
+                    &event.project,
+                    &event.composed,
+                    &event.app,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn save_combo(&mut self, combo: &KeyCombo) -> Result<()> {
+        let combo = combo.clone();
+        self.queue(move |client, host| {
+            client.execute(
+                "INSERT INTO key_combos (host, combo, timestamp, app) VALUES ($1, $2, $3, $4)",
+                &[&host, &combo.combo, &combo.timestamp, &combo.app],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn delete_combos_since(&mut self, combo: &str, since: DateTime<Local>) -> Result<()> {
+        let combo = combo.to_string();
+        self.queue(move |client, host| {
+            client.execute(
+                "DELETE FROM key_combos WHERE host = $1 AND combo = $2 AND timestamp >= $3",
+                &[&host, &combo, &since],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn save_combo_storm(&mut self, storm: &ComboStorm) -> Result<()> {
+        let storm = storm.clone();
+        self.queue(move |client, host| {
+            client.execute(
+                "INSERT INTO combo_storms (host, combo, start_time, end_time, count)
+                 VALUES ($1, $2, $3, $4, $5)",
+                &[
+                    &host,
+                    &storm.combo,
+                    &storm.start_time,
+                    &storm.end_time,
+                    &storm.count,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn save_chord(&mut self, chord: &KeyChord) -> Result<()> {
+        let chord = chord.clone();
+        self.queue(move |client, host| {
+            client.execute(
+                "INSERT INTO key_chords (host, chord, key_count, spread_ms, timestamp)
+                 VALUES ($1, $2, $3, $4, $5)",
+                &[
+                    &host,
+                    &chord.chord,
+                    &chord.key_count,
+                    &chord.spread_ms,
+                    &chord.timestamp,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn save_typing_sample(&mut self, sample: &TypingSample) -> Result<()> {
+        let sample = sample.clone();
+        self.queue(move |client, host| {
+            client.execute(
+                "INSERT INTO typing_samples (host, chars_per_minute, timestamp) VALUES ($1, $2, $3)",
+                &[&host, &sample.chars_per_minute, &sample.timestamp],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn save_typing_burst(&mut self, burst: &TypingBurst) -> Result<()> {
+        let burst = burst.clone();
+        self.queue(move |client, host| {
+            client.execute(
+                "INSERT INTO typing_bursts
+                     (host, keys, duration_ms, peak_cpm, average_cpm, timestamp)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                &[
+                    &host,
+                    &burst.keys,
+                    &burst.duration_ms(),
+                    &burst.peak_cpm,
+                    &burst.average_cpm(),
+                    &burst.start_time,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn save_bigram(&mut self, bigram: &KeyBigram) -> Result<()> {
+        let bigram = bigram.clone();
+        self.queue(move |client, host| {
+            client.execute(
+                "INSERT INTO key_bigrams (host, session_id, first_key, second_key, count)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (host, session_id, first_key, second_key)
+                 DO UPDATE SET count = key_bigrams.count + excluded.count",
+                &[
+                    &host,
+                    &bigram.session_id,
+                    &bigram.first_key,
+                    &bigram.second_key,
+                    &bigram.count,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn reconcile_bigrams(&mut self, session_id: i64) -> Result<()> {
+        self.queue(move |client, host| {
+            let mut tx = client.transaction()?;
+            tx.execute(
+                "DELETE FROM key_bigrams WHERE host = $1 AND session_id = $2",
+                &[&host, &session_id],
+            )?;
+            tx.execute(
+                "INSERT INTO key_bigrams (host, session_id, first_key, second_key, count)
+                 SELECT $1, $2, prev_key, key_name, COUNT(*) FROM (
+                     SELECT LAG(key_name) OVER (ORDER BY seq) AS prev_key, key_name
+                     FROM key_events WHERE host = $1 AND session_id = $2
+                 ) pairs
+                 WHERE prev_key IS NOT NULL
+                 GROUP BY prev_key, key_name",
+                &[&host, &session_id],
+            )?;
+            tx.commit()
+        })
+    }
+
+    fn save_latency_report(&mut self, report: &LatencyReport) -> Result<()> {
+        let report = report.clone();
+        self.queue(move |client, host| {
+            client.execute(
+                "INSERT INTO latency_reports (host, session_id, events, p50_us, p99_us, max_us, timestamp)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                &[
+                    &host,
+                    &report.session_id,
+                    &report.events,
+                    &report.p50_us,
+                    &report.p99_us,
+                    &report.max_us,
+                    &report.timestamp,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn save_break_reminder(&mut self, reminder: &BreakReminder) -> Result<()> {
+        let reminder = reminder.clone();
+        self.queue(move |client, host| {
+            client.execute(
+                "INSERT INTO break_reminders
+                     (host, session_id, reminded_at, streak_minutes, reminders, taken, break_started_at, break_secs)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                &[
+                    &host,
+                    &reminder.session_id,
+                    &reminder.reminded_at,
+                    &reminder.streak_minutes,
+                    &reminder.reminders,
+                    &reminder.taken,
+                    &reminder.break_started_at,
+                    &reminder.break_secs,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let (done, flushed) = mpsc::channel();
+        self.queue(move |_, _| {
+            let _ = done.send(());
+            Ok(())
+        })?;
+        flushed
+            .recv_timeout(FLUSH_TIMEOUT)
+            .context("Timed out waiting for Postgres writes")
+    }
+}
@@ -0,0 +1,229 @@
+use crate::db::models::KeyCategory;
+use crate::db::schema::{MILLIS_TABLES, TIMESTAMP_COLUMNS};
+use anyhow::{bail, Result};
+use rusqlite::Connection;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::path::Path;
+
+/// Columns of `key_events` other than `id`, copied when its rows are shuffled
+const KEY_EVENT_COLUMNS: &str = "key_code, key_name, is_modifier, timestamp, timestamp_ms, hour, \
+     day_of_week, session_id, seq, workspace, monitor, remote, source, project, category, composed, \
+     app, released_ms";
+
+/// What was redacted in a sanitized copy
+#[derive(Debug, Clone, Default)]
+pub struct SanitizeSummary {
+    pub key_events: i64,
+    /// Distinct letter and number keys given pseudonyms
+    pub keys_hashed: usize,
+    /// Distinct combos before and after generalizing
+    pub combos_before: usize,
+    pub combos_after: usize,
+    /// Distinct workspace and project names given pseudonyms
+    pub contexts_hashed: usize,
+}
+
+/// Write a copy of the database at `output` that can be shared without exposing typed content.
+///
+/// Letter and number keys, workspaces and projects get pseudonyms salted per run, so the
+/// copy keeps its frequency structure but can't be matched against other copies.
+/// Timestamps are coarsened to the hour, key events are shuffled within each hour so
+/// their order doesn't spell out text, combos keep only modifier and key class, and
+/// chords only key classes. Composed text and key release times are dropped.
+pub fn sanitize(source: &Connection, output: &Path) -> Result<SanitizeSummary> {
+    if output.exists() {
+        bail!("{} already exists", output.display());
+    }
+
+    source.execute("VACUUM INTO ?1", [output.to_string_lossy()])?;
+    let mut conn = Connection::open(output)?;
+    let salt = RandomState::new();
+    let mut summary = SanitizeSummary::default();
+
+    let tx = conn.transaction()?;
+
+    // Letter and number identities
+    let keys = distinct(&tx, "SELECT DISTINCT key_name FROM key_events")?;
+    for key in keys {
+        let Some(class) = key_class(&key) else {
+            continue;
+        };
+        let pseudonym = format!("{}-{:08x}", class, salt.hash_one(&key) as u32);
+        tx.execute(
+            "UPDATE key_events SET key_name = ?2, key_code = ?2 WHERE key_name = ?1",
+            (&key, &pseudonym),
+        )?;
+        tx.execute(
+            "UPDATE key_bigrams SET first_key = ?2 WHERE first_key = ?1",
+            (&key, &pseudonym),
+        )?;
+        tx.execute(
+            "UPDATE key_bigrams SET second_key = ?2 WHERE second_key = ?1",
+            (&key, &pseudonym),
+        )?;
+        summary.keys_hashed += 1;
+    }
+
+    // Workspace and project names can name clients or documents
+    for (column, label) in [("workspace", "Workspace"), ("project", "Project")] {
+        let names = distinct(
+            &tx,
+            &format!(
+                "SELECT DISTINCT {0} FROM key_events WHERE {0} IS NOT NULL",
+                column
+            ),
+        )?;
+        for name in names {
+            let pseudonym = format!("{}-{:08x}", label, salt.hash_one(&name) as u32);
+            tx.execute(
+                &format!("UPDATE key_events SET {0} = ?2 WHERE {0} = ?1", column),
+                (&name, &pseudonym),
+            )?;
+            summary.contexts_hashed += 1;
+        }
+    }
+
+    // Combos keep their modifiers and the class of their key
+    let combos = distinct(
+        &tx,
+        "SELECT combo FROM key_combos UNION SELECT combo FROM combo_storms",
+    )?;
+    let mut generalized: HashMap<String, String> = HashMap::new();
+    for combo in &combos {
+        generalized.insert(combo.clone(), generalize_combo(combo));
+    }
+    for (combo, general) in &generalized {
+        for table in ["key_combos", "combo_storms"] {
+            tx.execute(
+                &format!("UPDATE {} SET combo = ?2 WHERE combo = ?1", table),
+                (combo, general),
+            )?;
+        }
+    }
+    summary.combos_before = combos.len();
+    summary.combos_after = {
+        let mut unique: Vec<&String> = generalized.values().collect();
+        unique.sort();
+        unique.dedup();
+        unique.len()
+    };
+
+    // Chords keep only the class of each key; stenography strokes spell out words
+    let chords = distinct(&tx, "SELECT DISTINCT chord FROM key_chords")?;
+    for chord in chords {
+        tx.execute(
+            "UPDATE key_chords SET chord = ?2 WHERE chord = ?1",
+            (&chord, generalize_chord(&chord)),
+        )?;
+    }
+
+    // Composed characters are the typed text itself, and how long keys are held
+    // identifies who typed them
+    tx.execute(
+        "UPDATE key_events SET composed = NULL, released_ms = NULL",
+        [],
+    )?;
+
+    // The audit log names users and imported file paths
+    tx.execute("DELETE FROM audit_log", [])?;
+
+    for (table, column) in TIMESTAMP_COLUMNS {
+        tx.execute(
+            &format!(
+                "UPDATE {0} SET {1} = substr({1}, 1, 13) || ':00:00.000Z' WHERE {1} IS NOT NULL",
+                table, column
+            ),
+            [],
+        )?;
+    }
+    for table in MILLIS_TABLES {
+        tx.execute(
+            &format!(
+                "UPDATE {} SET timestamp_ms = timestamp_ms / 3600000 * 3600000",
+                table
+            ),
+            [],
+        )?;
+    }
+
+    // Row ids and sequence numbers preserve typing order, so rebuild both tables with
+    // the rows shuffled within each hour
+    tx.execute_batch(&format!(
+        "CREATE TEMP TABLE shuffled_events AS
+             SELECT {0} FROM key_events ORDER BY timestamp_ms, random();
+         DELETE FROM key_events;
+         DELETE FROM sqlite_sequence WHERE name IN ('key_events', 'key_combos');
+         INSERT INTO key_events ({0}) SELECT {0} FROM shuffled_events ORDER BY rowid;
+         UPDATE key_events SET seq = NULL;
+         DROP TABLE shuffled_events;
+
+         CREATE TEMP TABLE shuffled_combos AS
+             SELECT combo, timestamp, timestamp_ms, app FROM key_combos
+             ORDER BY timestamp_ms, random();
+         DELETE FROM key_combos;
+         INSERT INTO key_combos (combo, timestamp, timestamp_ms, app)
+             SELECT combo, timestamp, timestamp_ms, app FROM shuffled_combos ORDER BY rowid;
+         DROP TABLE shuffled_combos;",
+        KEY_EVENT_COLUMNS
+    ))?;
+
+    summary.key_events = tx.query_row("SELECT COUNT(*) FROM key_events", [], |row| row.get(0))?;
+    tx.commit()?;
+
+    // Rewrite the file so none of the original values linger in freed pages
+    conn.execute_batch("VACUUM;")?;
+
+    Ok(summary)
+}
+
+fn distinct(conn: &Connection, sql: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(sql)?;
+    let values = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(values)
+}
+
+/// `Letter` or `Digit` for keys that reveal typed content, `None` for everything else
+pub(crate) fn key_class(key: &str) -> Option<&'static str> {
+    match KeyCategory::of(key) {
+        KeyCategory::Letter => Some("Letter"),
+        KeyCategory::Digit => Some("Digit"),
+        KeyCategory::Other => None,
+    }
+}
+
+/// Reduce a combo to its modifiers (without sides) and the class of its final key,
+/// e.g. `ControlLeft+ShiftLeft+KeyT` becomes `Ctrl+Shift+Letter`
+fn generalize_combo(combo: &str) -> String {
+    let mut parts: Vec<String> = combo
+        .split('+')
+        .map(|part| {
+            let modifier = match part {
+                "ControlLeft" | "ControlRight" => Some("Ctrl"),
+                "ShiftLeft" | "ShiftRight" => Some("Shift"),
+                "MetaLeft" | "MetaRight" => Some("Meta"),
+                _ => None,
+            };
+            modifier
+                .or_else(|| key_class(part))
+                .unwrap_or(part)
+                .to_string()
+        })
+        .collect();
+    parts.dedup();
+    parts.join("+")
+}
+
+/// Reduce a chord to the classes of its keys, e.g. `KeyS+KeyT+Num1` becomes
+/// `Digit+Letter+Letter`
+fn generalize_chord(chord: &str) -> String {
+    let mut parts: Vec<&str> = chord
+        .split('+')
+        .map(|key| key_class(key).unwrap_or(key))
+        .collect();
+    parts.sort_unstable();
+    parts.join("+")
+}
@@ -2,7 +2,9 @@ use anyhow::Result;
 use rusqlite::Connection;
 
 pub fn create_tables(conn: &Connection) -> Result<()> {
-    // Key events table - stores individual key presses
+    // Key events table - stores individual key presses. `hour` and `day_of_week` are the
+    // local time where the key was recorded, kept for older versions; stats work them out
+    // from `timestamp_ms` in the viewer's timezone instead.
     conn.execute(
         "CREATE TABLE IF NOT EXISTS key_events (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -11,7 +13,20 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
             is_modifier INTEGER NOT NULL DEFAULT 0,
             timestamp TEXT NOT NULL,
             hour INTEGER NOT NULL,
-            day_of_week INTEGER NOT NULL
+            day_of_week INTEGER NOT NULL,
+            session_id INTEGER,
+            seq INTEGER,
+            workspace TEXT,
+            monitor TEXT,
+            remote INTEGER,
+            source TEXT,
+            project TEXT,
+            category TEXT,
+            timestamp_ms INTEGER,
+            composed TEXT,
+            app TEXT,
+            released_ms INTEGER,
+            month TEXT GENERATED ALWAYS AS (substr(timestamp, 1, 7)) VIRTUAL
         )",
         [],
     )?;
@@ -21,7 +36,24 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
         "CREATE TABLE IF NOT EXISTS key_combos (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             combo TEXT NOT NULL,
-            timestamp TEXT NOT NULL
+            timestamp TEXT NOT NULL,
+            timestamp_ms INTEGER,
+            app TEXT,
+            month TEXT GENERATED ALWAYS AS (substr(timestamp, 1, 7)) VIRTUAL
+        )",
+        [],
+    )?;
+
+    // Key chords - non-modifier keys pressed down together within a short window
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS key_chords (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chord TEXT NOT NULL,
+            key_count INTEGER NOT NULL,
+            spread_ms INTEGER NOT NULL,
+            timestamp TEXT NOT NULL,
+            timestamp_ms INTEGER NOT NULL,
+            month TEXT GENERATED ALWAYS AS (substr(timestamp, 1, 7)) VIRTUAL
         )",
         [],
     )?;
@@ -42,30 +74,835 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
         "CREATE TABLE IF NOT EXISTS typing_samples (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             chars_per_minute REAL NOT NULL,
+            timestamp TEXT NOT NULL,
+            timestamp_ms INTEGER,
+            month TEXT GENERATED ALWAYS AS (substr(timestamp, 1, 7)) VIRTUAL
+        )",
+        [],
+    )?;
+
+    // Typing bursts - sustained runs of fast typing, timestamped when they started
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS typing_bursts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            keys INTEGER NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            peak_cpm REAL NOT NULL,
+            average_cpm REAL NOT NULL,
+            timestamp TEXT NOT NULL,
+            timestamp_ms INTEGER NOT NULL,
+            month TEXT GENERATED ALWAYS AS (substr(timestamp, 1, 7)) VIRTUAL
+        )",
+        [],
+    )?;
+
+    // Imported activity - key presses counted by other trackers, which don't say which keys
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS imported_activity (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source TEXT NOT NULL,
+            presses INTEGER NOT NULL,
+            timestamp TEXT NOT NULL,
+            timestamp_ms INTEGER NOT NULL,
+            month TEXT GENERATED ALWAYS AS (substr(timestamp, 1, 7)) VIRTUAL
+        )",
+        [],
+    )?;
+
+    // Key bigrams - counts of consecutive key pairs per session
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS key_bigrams (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER NOT NULL,
+            first_key TEXT NOT NULL,
+            second_key TEXT NOT NULL,
+            count INTEGER NOT NULL DEFAULT 0,
+            UNIQUE (session_id, first_key, second_key)
+        )",
+        [],
+    )?;
+
+    // Combo storms - summarized bursts of a combo firing at automated rates
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS combo_storms (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            combo TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            count INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    // Callback latency - handling time summaries from sessions recorded with --measure-latency
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS latency_reports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER,
+            events INTEGER NOT NULL,
+            p50_us INTEGER NOT NULL,
+            p99_us INTEGER NOT NULL,
+            max_us INTEGER NOT NULL,
             timestamp TEXT NOT NULL
         )",
         [],
     )?;
 
-    // Create indexes for better query performance
+    // Break reminders - when a break was suggested and whether it was taken
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS break_reminders (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER,
+            reminded_at TEXT NOT NULL,
+            streak_minutes INTEGER NOT NULL,
+            reminders INTEGER NOT NULL,
+            taken INTEGER NOT NULL,
+            break_started_at TEXT,
+            break_secs INTEGER
+        )",
+        [],
+    )?;
+
+    // Privacy consents - answers to the summary shown before recording, kept across resets
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS consents (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            scopes TEXT NOT NULL,
+            summary TEXT NOT NULL,
+            accepted INTEGER NOT NULL,
+            confirmed_by TEXT NOT NULL,
+            timestamp TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Audit log - resets, imports, consents and config edits, kept across resets
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp TEXT NOT NULL,
+            action TEXT NOT NULL,
+            details TEXT NOT NULL,
+            user TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Fingerprints of each config section, to notice edits for the audit log
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS config_fingerprints (
+            section TEXT PRIMARY KEY,
+            fingerprint TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Daily and weekly targets set with `kitmap goals`
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS goals (
+            metric TEXT NOT NULL,
+            period TEXT NOT NULL,
+            target REAL NOT NULL,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (metric, period)
+        )",
+        [],
+    )?;
+
+    // Bring databases created by older versions up to date
+    migrate(conn)?;
+
+    create_minute_totals(conn)?;
+    create_key_counts(conn)?;
+    create_stats_cache(conn)?;
+
+    // Create indexes for better query performance. The composite indexes also serve
+    // lookups by their first column, which had single-column indexes of their own.
+    // Time ranges are looked up by epoch milliseconds, and whole months by their partition key.
     conn.execute_batch(
-        "CREATE INDEX IF NOT EXISTS idx_key_events_key_name ON key_events(key_name);
-         CREATE INDEX IF NOT EXISTS idx_key_events_timestamp ON key_events(timestamp);
-         CREATE INDEX IF NOT EXISTS idx_key_events_hour ON key_events(hour);
+        "DROP INDEX IF EXISTS idx_key_events_key_name;
+         DROP INDEX IF EXISTS idx_key_events_timestamp;
+         CREATE INDEX IF NOT EXISTS idx_key_events_key_name_time ON key_events(key_name, timestamp_ms);
+         CREATE INDEX IF NOT EXISTS idx_key_events_time_modifier ON key_events(timestamp_ms, is_modifier);
+         CREATE INDEX IF NOT EXISTS idx_key_events_category ON key_events(category);
+         CREATE INDEX IF NOT EXISTS idx_key_events_session_seq ON key_events(session_id, seq);
          CREATE INDEX IF NOT EXISTS idx_key_combos_combo ON key_combos(combo);
-         CREATE INDEX IF NOT EXISTS idx_typing_samples_timestamp ON typing_samples(timestamp);",
+         CREATE INDEX IF NOT EXISTS idx_key_combos_time ON key_combos(timestamp_ms);
+         CREATE INDEX IF NOT EXISTS idx_key_chords_time ON key_chords(timestamp_ms);
+         CREATE INDEX IF NOT EXISTS idx_typing_samples_time ON typing_samples(timestamp_ms);
+         CREATE INDEX IF NOT EXISTS idx_typing_bursts_time ON typing_bursts(timestamp_ms);
+         CREATE INDEX IF NOT EXISTS idx_key_events_month ON key_events(month);
+         CREATE INDEX IF NOT EXISTS idx_key_combos_month ON key_combos(month);
+         CREATE INDEX IF NOT EXISTS idx_key_chords_month ON key_chords(month);
+         CREATE INDEX IF NOT EXISTS idx_typing_samples_month ON typing_samples(month);
+         CREATE INDEX IF NOT EXISTS idx_typing_bursts_month ON typing_bursts(month);
+         CREATE INDEX IF NOT EXISTS idx_imported_activity_time ON imported_activity(timestamp_ms);
+         CREATE INDEX IF NOT EXISTS idx_imported_activity_month ON imported_activity(month);
+         CREATE INDEX IF NOT EXISTS idx_imported_activity_source ON imported_activity(source);",
+    )?;
+
+    Ok(())
+}
+
+/// Add columns introduced after the original schema to existing databases
+fn migrate(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "key_events", "session_id", "INTEGER")?;
+
+    if add_column_if_missing(conn, "key_events", "seq", "INTEGER")? {
+        // Rows recorded before sequence numbers existed keep their insertion order
+        conn.execute("UPDATE key_events SET seq = id WHERE seq IS NULL", [])?;
+    }
+
+    add_column_if_missing(conn, "key_events", "workspace", "TEXT")?;
+    add_column_if_missing(conn, "key_events", "monitor", "TEXT")?;
+    add_column_if_missing(conn, "key_events", "remote", "INTEGER")?;
+    add_column_if_missing(conn, "key_events", "source", "TEXT")?;
+    add_column_if_missing(conn, "key_events", "project", "TEXT")?;
+
+    if add_column_if_missing(conn, "key_events", "category", "TEXT")? {
+        // Same classification as `KeyCategory::of`, for keys recorded before the column
+        conn.execute(
+            "UPDATE key_events SET category = CASE
+                 WHEN key_name GLOB 'Key[A-Za-z]' THEN 'letter'
+                 WHEN key_name GLOB 'Num[0-9]' OR key_name GLOB 'Kp[0-9]' THEN 'digit'
+                 ELSE 'other'
+             END",
+            [],
+        )?;
+    }
+
+    add_column_if_missing(conn, "key_events", "composed", "TEXT")?;
+    add_column_if_missing(conn, "key_events", "app", "TEXT")?;
+    add_column_if_missing(conn, "key_combos", "app", "TEXT")?;
+    add_column_if_missing(conn, "key_events", "released_ms", "INTEGER")?;
+
+    migrate_to_utc(conn)?;
+
+    for table in MILLIS_TABLES {
+        add_column_if_missing(conn, table, "month", MONTH_COLUMN)?;
+    }
+    for (table, column, millis) in DERIVED_MILLIS {
+        add_column_if_missing(conn, table, millis, &derived_millis_column(column))?;
+    }
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_sessions_start ON sessions(start_ms);
+         CREATE INDEX IF NOT EXISTS idx_combo_storms_start ON combo_storms(start_ms);",
+    )?;
+
+    Ok(())
+}
+
+/// Every timestamp column, as (table, column)
+pub const TIMESTAMP_COLUMNS: &[(&str, &str)] = &[
+    ("key_events", "timestamp"),
+    ("key_combos", "timestamp"),
+    ("key_chords", "timestamp"),
+    ("sessions", "start_time"),
+    ("sessions", "end_time"),
+    ("typing_samples", "timestamp"),
+    ("typing_bursts", "timestamp"),
+    ("imported_activity", "timestamp"),
+    ("combo_storms", "start_time"),
+    ("combo_storms", "end_time"),
+    ("latency_reports", "timestamp"),
+    ("break_reminders", "reminded_at"),
+    ("break_reminders", "break_started_at"),
+    ("consents", "timestamp"),
+    ("audit_log", "timestamp"),
+    ("goals", "updated_at"),
+];
+
+/// Tables whose `timestamp` is also stored as epoch milliseconds in `timestamp_ms`
+pub const MILLIS_TABLES: &[&str] = &[
+    "key_events",
+    "key_combos",
+    "key_chords",
+    "typing_samples",
+    "typing_bursts",
+    "imported_activity",
+];
+
+/// Timestamp columns of the tables outside `MILLIS_TABLES`, with the column holding the
+/// same instant as epoch milliseconds, as (table, column, millis column). These rows are
+/// written too rarely for the recording path to fill them in, so SQLite derives them.
+pub const DERIVED_MILLIS: &[(&str, &str, &str)] = &[
+    ("sessions", "start_time", "start_ms"),
+    ("sessions", "end_time", "end_ms"),
+    ("combo_storms", "start_time", "start_ms"),
+    ("combo_storms", "end_time", "end_ms"),
+    ("latency_reports", "timestamp", "timestamp_ms"),
+    ("break_reminders", "reminded_at", "reminded_ms"),
+    ("break_reminders", "break_started_at", "break_started_ms"),
+    ("consents", "timestamp", "timestamp_ms"),
+    ("audit_log", "timestamp", "timestamp_ms"),
+    ("goals", "updated_at", "updated_ms"),
+];
+
+/// Epoch milliseconds generated from the timestamp in `column`, null when it is
+fn derived_millis_column(column: &str) -> String {
+    format!(
+        "INTEGER GENERATED ALWAYS AS (
+             CAST(strftime('%s', {0}) AS INTEGER) * 1000
+                 + CAST(substr(strftime('%f', {0}), 4) AS INTEGER)
+         ) VIRTUAL",
+        column
+    )
+}
+
+/// Partition key of the tables in `MILLIS_TABLES`: the UTC month each row was recorded
+/// in, e.g. 2024-01, so a month is found and pruned through its index alone
+const MONTH_COLUMN: &str = "TEXT GENERATED ALWAYS AS (substr(timestamp, 1, 7)) VIRTUAL";
+
+/// Rewrite timestamps recorded as local time with a UTC offset in UTC, and fill in
+/// epoch milliseconds, for databases from before timestamps were stored in UTC
+fn migrate_to_utc(conn: &Connection) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    if !add_column_if_missing(&tx, "key_events", "timestamp_ms", "INTEGER")? {
+        return Ok(());
+    }
+    add_column_if_missing(&tx, "key_combos", "timestamp_ms", "INTEGER")?;
+    add_column_if_missing(&tx, "typing_samples", "timestamp_ms", "INTEGER")?;
+
+    // Per-minute totals and stored stats are keyed by the old timestamps, so they are
+    // rebuilt once the rewrite is done. Indexes on the rewritten column are replaced too.
+    tx.execute_batch(
+        "DROP TRIGGER IF EXISTS minute_totals_insert;
+         DROP TRIGGER IF EXISTS minute_totals_delete;
+         DROP TRIGGER IF EXISTS minute_totals_update;
+         DROP TABLE IF EXISTS minute_totals;
+         DROP INDEX IF EXISTS idx_key_events_key_name_timestamp;
+         DROP INDEX IF EXISTS idx_key_events_timestamp_modifier;
+         DROP INDEX IF EXISTS idx_key_events_month;
+         DROP INDEX IF EXISTS idx_key_events_hour;
+         DROP INDEX IF EXISTS idx_typing_samples_timestamp;",
+    )?;
+    for (table, event) in STATS_CACHE_INVALIDATIONS {
+        tx.execute(
+            &format!(
+                "DROP TRIGGER IF EXISTS stats_cache_{}_{}",
+                table,
+                event.to_lowercase()
+            ),
+            [],
+        )?;
+    }
+    tx.execute("DROP TABLE IF EXISTS stats_cache", [])?;
+
+    // SQLite applies the recorded offset when formatting, and assignments all read the
+    // old value, so the milliseconds come from the same instant as the UTC text
+    for table in MILLIS_TABLES {
+        tx.execute(
+            &format!(
+                "UPDATE {} SET
+                     timestamp_ms = CAST(strftime('%s', timestamp) AS INTEGER) * 1000
+                         + CAST(substr(strftime('%f', timestamp), 4) AS INTEGER),
+                     timestamp = COALESCE(strftime('{}', timestamp), timestamp)",
+                table, UTC_FORMAT
+            ),
+            [],
+        )?;
+    }
+    for (table, column) in TIMESTAMP_COLUMNS {
+        if MILLIS_TABLES.contains(table) {
+            continue;
+        }
+        tx.execute(
+            &format!(
+                "UPDATE {0} SET {1} = COALESCE(strftime('{2}', {1}), {1}) WHERE {1} IS NOT NULL",
+                table, column, UTC_FORMAT
+            ),
+            [],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// SQLite format of stored timestamps, matching `db::time::format`
+const UTC_FORMAT: &str = "%Y-%m-%dT%H:%M:%fZ";
+
+/// Per-minute key counts kept up to date by triggers on `key_events`, so status bars
+/// polling every few seconds don't have to scan the day's key events
+fn create_minute_totals(conn: &Connection) -> Result<()> {
+    let exists: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'minute_totals'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    // Minutes are the UTC minute the keys were recorded in, e.g. 2024-01-31T09:05
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS minute_totals (
+             minute TEXT PRIMARY KEY,
+             keys INTEGER NOT NULL DEFAULT 0,
+             typed_keys INTEGER NOT NULL DEFAULT 0
+         );
+
+         CREATE TRIGGER IF NOT EXISTS minute_totals_insert AFTER INSERT ON key_events
+         BEGIN
+             INSERT INTO minute_totals (minute, keys, typed_keys)
+             VALUES (substr(NEW.timestamp, 1, 16), 1, NEW.is_modifier = 0)
+             ON CONFLICT (minute) DO UPDATE SET
+                 keys = keys + 1,
+                 typed_keys = typed_keys + excluded.typed_keys;
+         END;
+
+         CREATE TRIGGER IF NOT EXISTS minute_totals_delete AFTER DELETE ON key_events
+         BEGIN
+             UPDATE minute_totals SET
+                 keys = keys - 1,
+                 typed_keys = typed_keys - (OLD.is_modifier = 0)
+             WHERE minute = substr(OLD.timestamp, 1, 16);
+             DELETE FROM minute_totals WHERE minute = substr(OLD.timestamp, 1, 16) AND keys <= 0;
+         END;
+
+         CREATE TRIGGER IF NOT EXISTS minute_totals_update
+         AFTER UPDATE OF timestamp, is_modifier ON key_events
+         BEGIN
+             UPDATE minute_totals SET
+                 keys = keys - 1,
+                 typed_keys = typed_keys - (OLD.is_modifier = 0)
+             WHERE minute = substr(OLD.timestamp, 1, 16);
+             DELETE FROM minute_totals WHERE minute = substr(OLD.timestamp, 1, 16) AND keys <= 0;
+             INSERT INTO minute_totals (minute, keys, typed_keys)
+             VALUES (substr(NEW.timestamp, 1, 16), 1, NEW.is_modifier = 0)
+             ON CONFLICT (minute) DO UPDATE SET
+                 keys = keys + 1,
+                 typed_keys = typed_keys + excluded.typed_keys;
+         END;",
+    )?;
+
+    if exists == 0 {
+        // Fill in the keys recorded before the rollup existed
+        conn.execute(
+            "INSERT INTO minute_totals (minute, keys, typed_keys)
+             SELECT substr(timestamp, 1, 16), COUNT(*), SUM(is_modifier = 0)
+             FROM key_events GROUP BY 1",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// All-time presses per key kept up to date by triggers on `key_events`, so the heatmap
+/// and top keys don't have to group every key event
+fn create_key_counts(conn: &Connection) -> Result<()> {
+    let exists: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'key_counts'",
+        [],
+        |row| row.get(0),
     )?;
 
+    // `typed` counts the presses recorded as non-modifier keys
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS key_counts (
+             key_name TEXT PRIMARY KEY,
+             count INTEGER NOT NULL DEFAULT 0,
+             typed INTEGER NOT NULL DEFAULT 0
+         );
+
+         CREATE TRIGGER IF NOT EXISTS key_counts_insert AFTER INSERT ON key_events
+         BEGIN
+             INSERT INTO key_counts (key_name, count, typed)
+             VALUES (NEW.key_name, 1, NEW.is_modifier = 0)
+             ON CONFLICT (key_name) DO UPDATE SET
+                 count = count + 1,
+                 typed = typed + excluded.typed;
+         END;
+
+         CREATE TRIGGER IF NOT EXISTS key_counts_delete AFTER DELETE ON key_events
+         BEGIN
+             UPDATE key_counts SET
+                 count = count - 1,
+                 typed = typed - (OLD.is_modifier = 0)
+             WHERE key_name = OLD.key_name;
+             DELETE FROM key_counts WHERE key_name = OLD.key_name AND count <= 0;
+         END;
+
+         CREATE TRIGGER IF NOT EXISTS key_counts_update
+         AFTER UPDATE OF key_name, is_modifier ON key_events
+         BEGIN
+             UPDATE key_counts SET
+                 count = count - 1,
+                 typed = typed - (OLD.is_modifier = 0)
+             WHERE key_name = OLD.key_name;
+             DELETE FROM key_counts WHERE key_name = OLD.key_name AND count <= 0;
+             INSERT INTO key_counts (key_name, count, typed)
+             VALUES (NEW.key_name, 1, NEW.is_modifier = 0)
+             ON CONFLICT (key_name) DO UPDATE SET
+                 count = count + 1,
+                 typed = typed + excluded.typed;
+         END;",
+    )?;
+
+    if exists == 0 {
+        // Fill in the keys recorded before the counts existed
+        conn.execute(
+            "INSERT INTO key_counts (key_name, count, typed)
+             SELECT key_name, COUNT(*), SUM(is_modifier = 0) FROM key_events GROUP BY key_name",
+            [],
+        )?;
+    }
+
     Ok(())
 }
 
+/// Changes that make stored stats stale without recording a new key event, which the
+/// stats cache is keyed by, as (table, trigger event)
+const STATS_CACHE_INVALIDATIONS: &[(&str, &str)] = &[
+    ("key_events", "DELETE"),
+    ("key_events", "UPDATE"),
+    ("key_combos", "INSERT"),
+    ("key_combos", "DELETE"),
+    ("key_chords", "INSERT"),
+    ("key_chords", "DELETE"),
+    ("key_bigrams", "INSERT"),
+    ("key_bigrams", "UPDATE"),
+    ("key_bigrams", "DELETE"),
+    ("sessions", "INSERT"),
+    ("sessions", "UPDATE"),
+    ("typing_samples", "INSERT"),
+    ("typing_bursts", "INSERT"),
+    ("typing_bursts", "DELETE"),
+    ("goals", "INSERT"),
+    ("goals", "UPDATE"),
+    ("goals", "DELETE"),
+];
+
+/// The last calculated overall stats, reused until new key events are recorded or
+/// triggers clear them because other data changed
+fn create_stats_cache(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stats_cache (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            latest_id INTEGER NOT NULL,
+            fingerprint TEXT NOT NULL,
+            stats TEXT NOT NULL,
+            computed_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    for (table, event) in STATS_CACHE_INVALIDATIONS {
+        conn.execute(
+            &format!(
+                "CREATE TRIGGER IF NOT EXISTS stats_cache_{0}_{1} AFTER {2} ON {0}
+                 BEGIN
+                     DELETE FROM stats_cache;
+                 END",
+                table,
+                event.to_lowercase(),
+                event
+            ),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Add a column to a table unless it already exists, returning whether it was added
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<bool> {
+    let exists: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_xinfo(?1) WHERE name = ?2",
+        (table, column),
+        |row| row.get(0),
+    )?;
+
+    if exists > 0 {
+        return Ok(false);
+    }
+
+    conn.execute(
+        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+        [],
+    )?;
+    Ok(true)
+}
+
+/// Sessions that started before the month bound as ?1 and have no key events from it on
+pub const PRUNED_SESSIONS: &str = "start_time < ?1 AND NOT EXISTS (
+     SELECT 1 FROM key_events WHERE session_id = sessions.id AND month >= ?1
+ )";
+
+/// Delete everything recorded before the UTC `month` (YYYY-MM), a month partition at a
+/// time. The per-row triggers on the deleted rows are dropped while they go, and the
+/// totals they would have kept are updated once instead.
+pub fn prune_before(conn: &Connection, month: &str) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute_batch(
+        "DROP TRIGGER IF EXISTS minute_totals_delete;
+         DROP TRIGGER IF EXISTS key_counts_delete;",
+    )?;
+    for (table, event) in STATS_CACHE_INVALIDATIONS {
+        if *event == "DELETE" {
+            tx.execute(
+                &format!("DROP TRIGGER IF EXISTS stats_cache_{}_delete", table),
+                [],
+            )?;
+        }
+    }
+
+    tx.execute(
+        "UPDATE key_counts SET
+             count = count - pruned.presses,
+             typed = typed - pruned.typed_presses
+         FROM (
+             SELECT key_name, COUNT(*) AS presses, SUM(is_modifier = 0) AS typed_presses
+             FROM key_events WHERE month < ?1 GROUP BY key_name
+         ) AS pruned
+         WHERE key_counts.key_name = pruned.key_name",
+        [month],
+    )?;
+    tx.execute("DELETE FROM key_counts WHERE count <= 0", [])?;
+    // Minutes are UTC too, so they split at the same month boundary
+    tx.execute("DELETE FROM minute_totals WHERE minute < ?1", [month])?;
+
+    // Sessions are matched before their key events go, along with the rows recorded in them
+    tx.execute(
+        &format!(
+            "CREATE TEMP TABLE pruned_sessions AS SELECT id FROM sessions WHERE {}",
+            PRUNED_SESSIONS
+        ),
+        [month],
+    )?;
+    for table in MILLIS_TABLES {
+        tx.execute(&format!("DELETE FROM {} WHERE month < ?1", table), [month])?;
+    }
+    tx.execute_batch(
+        "DELETE FROM key_bigrams WHERE session_id IN (SELECT id FROM temp.pruned_sessions);
+         DELETE FROM latency_reports WHERE session_id IN (SELECT id FROM temp.pruned_sessions);
+         DELETE FROM break_reminders WHERE session_id IN (SELECT id FROM temp.pruned_sessions);
+         DELETE FROM sessions WHERE id IN (SELECT id FROM temp.pruned_sessions);
+         DROP TABLE temp.pruned_sessions;",
+    )?;
+    tx.execute("DELETE FROM combo_storms WHERE start_time < ?1", [month])?;
+    tx.execute("DELETE FROM latency_reports WHERE timestamp < ?1", [month])?;
+    tx.execute(
+        "DELETE FROM break_reminders WHERE reminded_at < ?1",
+        [month],
+    )?;
+
+    tx.execute("DELETE FROM stats_cache", [])?;
+    create_minute_totals(&tx)?;
+    create_key_counts(&tx)?;
+    create_stats_cache(&tx)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Delete all recorded activity. Privacy consents and the audit log are kept so the
+/// record of what was agreed to and changed stays auditable.
 pub fn clear_all_data(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         "DELETE FROM key_events;
          DELETE FROM key_combos;
+         DELETE FROM key_chords;
          DELETE FROM sessions;
          DELETE FROM typing_samples;
+         DELETE FROM typing_bursts;
+         DELETE FROM imported_activity;
+         DELETE FROM key_bigrams;
+         DELETE FROM combo_storms;
+         DELETE FROM latency_reports;
+         DELETE FROM break_reminders;
+         DELETE FROM minute_totals;
+         DELETE FROM key_counts;
+         DELETE FROM stats_cache;
          VACUUM;",
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tables as the first release created them, with local times and UTC offsets
+    fn old_database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE key_events (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 key_code TEXT NOT NULL,
+                 key_name TEXT NOT NULL,
+                 is_modifier INTEGER NOT NULL DEFAULT 0,
+                 timestamp TEXT NOT NULL,
+                 hour INTEGER NOT NULL,
+                 day_of_week INTEGER NOT NULL
+             );
+             CREATE TABLE key_combos (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 combo TEXT NOT NULL,
+                 timestamp TEXT NOT NULL
+             );
+             CREATE TABLE sessions (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 start_time TEXT NOT NULL,
+                 end_time TEXT,
+                 total_keys INTEGER NOT NULL DEFAULT 0
+             );
+             CREATE TABLE typing_samples (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 chars_per_minute REAL NOT NULL,
+                 timestamp TEXT NOT NULL
+             );
+             CREATE INDEX idx_key_events_timestamp ON key_events(timestamp);
+
+             INSERT INTO sessions (start_time, end_time, total_keys) VALUES
+                 ('2024-03-10T00:00:00-05:00', '2024-03-10T01:30:00.250-05:00', 1),
+                 ('2024-07-01T11:00:00+02:00', NULL, 1);
+             INSERT INTO key_events (key_code, key_name, timestamp, hour, day_of_week) VALUES
+                 ('KeyA', 'KeyA', '2024-03-10T01:30:00.250-05:00', 1, 0),
+                 ('KeyB', 'KeyB', '2024-07-01T12:00:00+02:00', 12, 1);
+             INSERT INTO key_combos (combo, timestamp) VALUES
+                 ('Ctrl+C', '2024-07-01T12:00:00+02:00');
+             INSERT INTO typing_samples (chars_per_minute, timestamp) VALUES
+                 (250.0, '2024-03-10T01:30:00.250-05:00');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn times(conn: &Connection, sql: &str) -> Vec<(Option<String>, Option<i64>)> {
+        let mut stmt = conn.prepare(sql).unwrap();
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)));
+        rows.unwrap().collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    fn utc(text: &str, millis: i64) -> (Option<String>, Option<i64>) {
+        (Some(text.to_string()), Some(millis))
+    }
+
+    #[test]
+    fn migrates_local_times_to_utc_with_epoch_millis() {
+        let conn = old_database();
+        create_tables(&conn).unwrap();
+
+        let events = "SELECT timestamp, timestamp_ms FROM key_events ORDER BY id";
+        let expected_events = vec![
+            utc("2024-03-10T06:30:00.250Z", 1_710_052_200_250),
+            utc("2024-07-01T10:00:00.000Z", 1_719_828_000_000),
+        ];
+        assert_eq!(times(&conn, events), expected_events);
+        assert_eq!(
+            times(&conn, "SELECT timestamp, timestamp_ms FROM key_combos"),
+            vec![utc("2024-07-01T10:00:00.000Z", 1_719_828_000_000)]
+        );
+        assert_eq!(
+            times(&conn, "SELECT timestamp, timestamp_ms FROM typing_samples"),
+            vec![utc("2024-03-10T06:30:00.250Z", 1_710_052_200_250)]
+        );
+        assert_eq!(
+            times(
+                &conn,
+                "SELECT start_time, start_ms FROM sessions ORDER BY id"
+            ),
+            vec![
+                utc("2024-03-10T05:00:00.000Z", 1_710_046_800_000),
+                utc("2024-07-01T09:00:00.000Z", 1_719_824_400_000),
+            ]
+        );
+        assert_eq!(
+            times(&conn, "SELECT end_time, end_ms FROM sessions ORDER BY id"),
+            vec![
+                utc("2024-03-10T06:30:00.250Z", 1_710_052_200_250),
+                (None, None)
+            ]
+        );
+
+        let months: Vec<String> = conn
+            .prepare("SELECT month FROM key_events ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(months, ["2024-03", "2024-07"]);
+
+        // Opening the migrated database again leaves it as it is
+        create_tables(&conn).unwrap();
+        assert_eq!(times(&conn, events), expected_events);
+    }
+
+    #[test]
+    fn derives_millis_for_rows_written_as_text() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO combo_storms (combo, start_time, end_time, count)
+             VALUES ('Alt+Tab', '2024-07-01T10:00:00.000Z', '2024-07-01T10:00:01.500Z', 40)",
+            [],
+        )
+        .unwrap();
+        assert_eq!(
+            times(&conn, "SELECT end_time, end_ms FROM combo_storms"),
+            vec![utc("2024-07-01T10:00:01.500Z", 1_719_828_001_500)]
+        );
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn prunes_whole_months_with_their_sessions() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO sessions (id, start_time, end_time) VALUES
+                 (1, '2024-02-10T09:00:00.000Z', '2024-02-10T10:00:00.000Z'),
+                 (2, '2024-02-29T23:00:00.000Z', '2024-03-01T01:00:00.000Z'),
+                 (3, '2024-03-05T09:00:00.000Z', NULL);
+             INSERT INTO key_events (key_code, key_name, timestamp, hour, day_of_week, session_id)
+             VALUES
+                 ('KeyA', 'KeyA', '2024-02-10T09:30:00.000Z', 9, 5, 1),
+                 ('KeyA', 'KeyA', '2024-02-29T23:30:00.000Z', 23, 3, 2),
+                 ('KeyB', 'KeyB', '2024-03-01T00:30:00.000Z', 0, 4, 2),
+                 ('KeyA', 'KeyA', '2024-03-05T09:30:00.000Z', 9, 1, 3);
+             INSERT INTO key_combos (combo, timestamp) VALUES
+                 ('Ctrl+C', '2024-02-10T09:30:00.000Z'),
+                 ('Ctrl+V', '2024-03-05T09:30:00.000Z');
+             INSERT INTO key_bigrams (session_id, first_key, second_key, count) VALUES
+                 (1, 'KeyA', 'KeyB', 4),
+                 (3, 'KeyA', 'KeyB', 1);
+             INSERT INTO combo_storms (combo, start_time, end_time, count) VALUES
+                 ('Alt+Tab', '2024-02-10T09:40:00.000Z', '2024-02-10T09:40:01.000Z', 40);",
+        )
+        .unwrap();
+
+        prune_before(&conn, "2024-03").unwrap();
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM key_events"), 2);
+        // A session running into the kept month stays, with its bigrams
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM sessions WHERE id IN (2, 3)"),
+            2
+        );
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM sessions"), 2);
+        assert_eq!(count(&conn, "SELECT SUM(count) FROM key_bigrams"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM key_combos"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM combo_storms"), 0);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT count FROM key_counts WHERE key_name = 'KeyA'"
+            ),
+            1
+        );
+        assert_eq!(count(&conn, "SELECT SUM(keys) FROM minute_totals"), 2);
+
+        // The totals keep following key events afterwards
+        conn.execute("DELETE FROM key_events WHERE key_name = 'KeyB'", [])
+            .unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM key_counts"), 1);
+        assert_eq!(count(&conn, "SELECT SUM(keys) FROM minute_totals"), 1);
+    }
+}
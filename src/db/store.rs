@@ -0,0 +1,341 @@
+use crate::db::models::{
+    BreakReminder, ComboStorm, KeyBigram, KeyChord, KeyCombo, KeyEvent, KeyRelease, LatencyReport,
+    Session, TypingBurst, TypingSample,
+};
+use crate::db::DbConnection;
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::time::{Duration, Instant};
+
+/// How many times per commit interval `kitmap listen` commits batched key events that
+/// have waited long enough, so they land even when no more keys arrive
+pub const COMMIT_CHECKS_PER_INTERVAL: u32 = 4;
+
+/// Longest a key event waits in a batch collected for `interval` before it is committed
+pub fn commit_deadline(interval: Duration) -> Duration {
+    interval + interval / COMMIT_CHECKS_PER_INTERVAL
+}
+
+/// Destination for everything `kitmap listen` records.
+///
+/// SQLite is the default backend; other backends (such as the optional central
+/// Postgres database) receive the same writes.
+pub trait EventStore: Send {
+    /// Open a session, assigning its id when the backend is the one that numbers sessions
+    fn start_session(&mut self, session: &mut Session) -> Result<()>;
+
+    /// Close a session, stamping its end time and final key count
+    fn end_session(&mut self, session: &mut Session) -> Result<()>;
+
+    fn save_key_event(&mut self, event: &KeyEvent) -> Result<()>;
+
+    /// Stamp when a saved key press was released. Backends that don't keep release
+    /// times ignore it.
+    fn save_key_release(&mut self, _release: &KeyRelease) -> Result<()> {
+        Ok(())
+    }
+
+    fn save_combo(&mut self, combo: &KeyCombo) -> Result<()>;
+
+    /// Remove occurrences of a combo since the given time once they turn out to be a storm
+    fn delete_combos_since(&mut self, combo: &str, since: DateTime<Local>) -> Result<()>;
+
+    fn save_combo_storm(&mut self, storm: &ComboStorm) -> Result<()>;
+
+    fn save_chord(&mut self, chord: &KeyChord) -> Result<()>;
+
+    fn save_typing_sample(&mut self, sample: &TypingSample) -> Result<()>;
+
+    fn save_typing_burst(&mut self, burst: &TypingBurst) -> Result<()>;
+
+    /// Add a bigram's count to its session total
+    fn save_bigram(&mut self, bigram: &KeyBigram) -> Result<()>;
+
+    /// Replace a session's bigram counts with exact counts derived from its key events
+    fn reconcile_bigrams(&mut self, session_id: i64) -> Result<()>;
+
+    fn save_latency_report(&mut self, report: &LatencyReport) -> Result<()>;
+
+    fn save_break_reminder(&mut self, reminder: &BreakReminder) -> Result<()>;
+
+    /// Commit writes held back for batching once they have waited out the batch, without
+    /// waiting for the next write to notice
+    fn commit_due(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Wait until queued writes have reached the backend
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The local SQLite database every other command reads from
+pub struct SqliteStore {
+    db: DbConnection,
+    /// How long key events are collected before being committed together
+    commit_interval: Duration,
+    pending: Vec<KeyEvent>,
+    /// Releases of key presses, stamped once the pending presses are committed
+    pending_releases: Vec<KeyRelease>,
+    /// When the oldest of the pending key events and releases was recorded
+    first_pending: Option<Instant>,
+}
+
+impl SqliteStore {
+    /// A store that commits every key event as soon as it is recorded
+    pub fn new(db: DbConnection) -> Self {
+        Self {
+            db,
+            commit_interval: Duration::ZERO,
+            pending: Vec::new(),
+            pending_releases: Vec::new(),
+            first_pending: None,
+        }
+    }
+
+    /// Collect key events for `interval` and commit them in one transaction, so
+    /// each batch rewrites the same database pages once instead of once per key
+    pub fn with_commit_interval(mut self, interval: Duration) -> Self {
+        self.commit_interval = interval;
+        self
+    }
+
+    fn commit_pending(&mut self) -> Result<()> {
+        self.first_pending = None;
+        if !self.pending.is_empty() {
+            let events = std::mem::take(&mut self.pending);
+            KeyEvent::save_all(&self.db, events)?;
+        }
+        if !self.pending_releases.is_empty() {
+            let releases = std::mem::take(&mut self.pending_releases);
+            KeyRelease::save_all(&self.db, releases)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the oldest pending write has waited a full commit interval
+    fn batch_due(&self) -> bool {
+        self.first_pending
+            .is_some_and(|first| first.elapsed() >= self.commit_interval)
+    }
+}
+
+impl EventStore for SqliteStore {
+    fn start_session(&mut self, session: &mut Session) -> Result<()> {
+        session.start(&self.db)?;
+        Ok(())
+    }
+
+    fn end_session(&mut self, session: &mut Session) -> Result<()> {
+        self.commit_pending()?;
+        session.end(&self.db)
+    }
+
+    fn save_key_event(&mut self, event: &KeyEvent) -> Result<()> {
+        if self.commit_interval.is_zero() {
+            return event.save(&self.db);
+        }
+        self.pending.push(event.clone());
+        self.first_pending.get_or_insert_with(Instant::now);
+        if self.batch_due() {
+            self.commit_pending()?;
+        }
+        Ok(())
+    }
+
+    fn save_key_release(&mut self, release: &KeyRelease) -> Result<()> {
+        if self.commit_interval.is_zero() {
+            return KeyRelease::save_all(&self.db, vec![release.clone()]);
+        }
+        self.pending_releases.push(release.clone());
+        self.first_pending.get_or_insert_with(Instant::now);
+        Ok(())
+    }
+
+    fn save_combo(&mut self, combo: &KeyCombo) -> Result<()> {
+        combo.save(&self.db)
+    }
+
+    fn delete_combos_since(&mut self, combo: &str, since: DateTime<Local>) -> Result<()> {
+        KeyCombo::delete_since(&self.db, combo, since)?;
+        Ok(())
+    }
+
+    fn save_combo_storm(&mut self, storm: &ComboStorm) -> Result<()> {
+        storm.save(&self.db)
+    }
+
+    fn save_chord(&mut self, chord: &KeyChord) -> Result<()> {
+        chord.save(&self.db)
+    }
+
+    fn save_typing_sample(&mut self, sample: &TypingSample) -> Result<()> {
+        sample.save(&self.db)
+    }
+
+    fn save_typing_burst(&mut self, burst: &TypingBurst) -> Result<()> {
+        burst.save(&self.db)
+    }
+
+    fn save_bigram(&mut self, bigram: &KeyBigram) -> Result<()> {
+        bigram.save(&self.db)
+    }
+
+    fn reconcile_bigrams(&mut self, session_id: i64) -> Result<()> {
+        // Exact counts are derived from the key events, so they must all be saved first
+        self.commit_pending()?;
+        KeyBigram::reconcile_session(&self.db, session_id)
+    }
+
+    fn save_latency_report(&mut self, report: &LatencyReport) -> Result<()> {
+        report.save(&self.db)
+    }
+
+    fn save_break_reminder(&mut self, reminder: &BreakReminder) -> Result<()> {
+        reminder.save(&self.db)
+    }
+
+    fn commit_due(&mut self) -> Result<()> {
+        if self.batch_due() {
+            self.commit_pending()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.commit_pending()
+    }
+}
+
+/// Writes to a primary store and copies every write to a secondary one.
+///
+/// Errors from the primary are returned; the mirror's are only reported, so an
+/// unreachable mirror never stops local recording.
+#[cfg(feature = "postgres")]
+pub struct MirroredStore {
+    primary: Box<dyn EventStore>,
+    mirror: Box<dyn EventStore>,
+}
+
+#[cfg(feature = "postgres")]
+impl MirroredStore {
+    pub fn new(primary: Box<dyn EventStore>, mirror: Box<dyn EventStore>) -> Self {
+        Self { primary, mirror }
+    }
+
+    fn write(&mut self, mut write: impl FnMut(&mut dyn EventStore) -> Result<()>) -> Result<()> {
+        // The primary goes first so the mirror sees the session ids it assigns
+        let result = write(self.primary.as_mut());
+        if result.is_ok() {
+            if let Err(e) = write(self.mirror.as_mut()) {
+                eprintln!("\nFailed to mirror write: {}", e);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl EventStore for MirroredStore {
+    fn start_session(&mut self, session: &mut Session) -> Result<()> {
+        self.write(|store| store.start_session(session))
+    }
+
+    fn end_session(&mut self, session: &mut Session) -> Result<()> {
+        self.write(|store| store.end_session(session))
+    }
+
+    fn save_key_event(&mut self, event: &KeyEvent) -> Result<()> {
+        self.write(|store| store.save_key_event(event))
+    }
+
+    fn save_key_release(&mut self, release: &KeyRelease) -> Result<()> {
+        self.write(|store| store.save_key_release(release))
+    }
+
+    fn save_combo(&mut self, combo: &KeyCombo) -> Result<()> {
+        self.write(|store| store.save_combo(combo))
+    }
+
+    fn delete_combos_since(&mut self, combo: &str, since: DateTime<Local>) -> Result<()> {
+        self.write(|store| store.delete_combos_since(combo, since))
+    }
+
+    fn save_combo_storm(&mut self, storm: &ComboStorm) -> Result<()> {
+        self.write(|store| store.save_combo_storm(storm))
+    }
+
+    fn save_chord(&mut self, chord: &KeyChord) -> Result<()> {
+        self.write(|store| store.save_chord(chord))
+    }
+
+    fn save_typing_sample(&mut self, sample: &TypingSample) -> Result<()> {
+        self.write(|store| store.save_typing_sample(sample))
+    }
+
+    fn save_typing_burst(&mut self, burst: &TypingBurst) -> Result<()> {
+        self.write(|store| store.save_typing_burst(burst))
+    }
+
+    fn save_bigram(&mut self, bigram: &KeyBigram) -> Result<()> {
+        self.write(|store| store.save_bigram(bigram))
+    }
+
+    fn reconcile_bigrams(&mut self, session_id: i64) -> Result<()> {
+        self.write(|store| store.reconcile_bigrams(session_id))
+    }
+
+    fn save_latency_report(&mut self, report: &LatencyReport) -> Result<()> {
+        self.write(|store| store.save_latency_report(report))
+    }
+
+    fn save_break_reminder(&mut self, reminder: &BreakReminder) -> Result<()> {
+        self.write(|store| store.save_break_reminder(reminder))
+    }
+
+    fn commit_due(&mut self) -> Result<()> {
+        self.write(|store| store.commit_due())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.write(|store| store.flush())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_memory_db;
+
+    fn saved_keys(db: &DbConnection) -> i64 {
+        db.read(|conn| Ok(conn.query_row("SELECT COUNT(*) FROM key_events", [], |row| row.get(0))?))
+            .unwrap()
+    }
+
+    #[test]
+    fn batched_keys_are_committed_once_due_without_another_key() {
+        let db = init_memory_db().unwrap();
+        let mut store =
+            SqliteStore::new(db.clone()).with_commit_interval(Duration::from_millis(50));
+        let key = KeyEvent::new("KeyA".into(), "KeyA".into(), false, None, 1);
+        store.save_key_event(&key).unwrap();
+
+        store.commit_due().unwrap();
+        assert_eq!(saved_keys(&db), 0);
+
+        std::thread::sleep(Duration::from_millis(60));
+        store.commit_due().unwrap();
+        assert_eq!(saved_keys(&db), 1);
+    }
+
+    #[test]
+    fn flush_commits_a_batch_that_isnt_due() {
+        let db = init_memory_db().unwrap();
+        let mut store = SqliteStore::new(db.clone()).with_commit_interval(Duration::from_secs(60));
+        let key = KeyEvent::new("KeyA".into(), "KeyA".into(), false, None, 1);
+        store.save_key_event(&key).unwrap();
+        store.flush().unwrap();
+        assert_eq!(saved_keys(&db), 1);
+    }
+}
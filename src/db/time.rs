@@ -0,0 +1,35 @@
+//! How points in time are stored.
+//!
+//! Timestamps are stored in UTC as RFC 3339 text with millisecond precision, e.g.
+//! `2024-01-31T08:05:00.123Z`, so they sort and compare as text no matter which
+//! timezone or machine recorded them. Every timestamp also has the same instant as epoch
+//! milliseconds, which range queries use: event tables store it in `timestamp_ms` as they
+//! record, and the other tables derive it, e.g. a session's `start_ms` from its
+//! `start_time` (see `schema::DERIVED_MILLIS`). Local days and hours are worked out when
+//! reading, in the timezone of whoever is viewing the data.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, SecondsFormat, TimeZone, Utc};
+
+/// `at` as stored in timestamp columns
+pub fn format<Tz: TimeZone>(at: &DateTime<Tz>) -> String {
+    at.with_timezone(&Utc)
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// The current time as stored in timestamp columns
+pub fn now() -> String {
+    format(&Utc::now())
+}
+
+/// The UTC minute `at` falls in, as stored in `minute_totals`
+pub fn minute<Tz: TimeZone>(at: &DateTime<Tz>) -> String {
+    at.with_timezone(&Utc).format("%Y-%m-%dT%H:%M").to_string()
+}
+
+/// A stored timestamp in local time
+pub fn parse(stored: &str) -> Result<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(stored)
+        .map(|t| t.with_timezone(&Local))
+        .map_err(|e| anyhow!("Invalid timestamp '{}': {}", stored, e))
+}
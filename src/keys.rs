@@ -0,0 +1,52 @@
+//! How keys reported by the platform are named when they are recorded.
+//!
+//! Key events store the name of the `rdev` key, e.g. `KeyA`, `ShiftLeft` or `Return`, so
+//! the same key has the same name on every platform. Keys `rdev` can't identify are
+//! stored as `Unknown(code)`.
+
+use crate::config::ComboModifier;
+#[cfg(feature = "cli")]
+use rdev::Key;
+
+/// Modifier keys that can be part of key combinations
+#[cfg(feature = "cli")]
+pub const MODIFIER_KEYS: &[Key] = &[
+    Key::ShiftLeft,
+    Key::ShiftRight,
+    Key::ControlLeft,
+    Key::ControlRight,
+    Key::Alt,
+    Key::AltGr,
+    Key::MetaLeft,
+    Key::MetaRight,
+];
+
+/// Check if a key is a modifier
+#[cfg(feature = "cli")]
+pub fn is_modifier(key: &Key) -> bool {
+    MODIFIER_KEYS.contains(key)
+}
+
+/// Which modifier a recorded modifier key name belongs to
+pub fn modifier_kind(key_name: &str) -> Option<ComboModifier> {
+    match key_name {
+        "ShiftLeft" | "ShiftRight" => Some(ComboModifier::Shift),
+        "ControlLeft" | "ControlRight" => Some(ComboModifier::Control),
+        "Alt" => Some(ComboModifier::Alt),
+        "AltGr" => Some(ComboModifier::AltGr),
+        "MetaLeft" | "MetaRight" => Some(ComboModifier::Meta),
+        _ => None,
+    }
+}
+
+/// Get a human-readable name for a key
+#[cfg(feature = "cli")]
+pub fn key_to_name(key: &Key) -> String {
+    format!("{:?}", key)
+}
+
+/// Get a simplified key code
+#[cfg(feature = "cli")]
+pub fn key_to_code(key: &Key) -> String {
+    format!("{:?}", key)
+}
@@ -0,0 +1,46 @@
+//! Read and analyze keyboard usage recorded by kitmap.
+//!
+//! The `kitmap` binary records key presses into a SQLite database. This library opens
+//! that database and computes the same statistics the CLI and dashboard show, so other
+//! tools can read kitmap data through typed queries instead of the raw tables.
+//!
+//! ```no_run
+//! use kitmap::stats::range::TimeRange;
+//! use kitmap::stats::{StatsCalculator, StatsFilter};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let db = kitmap::db::open_db_read_only()?;
+//! let filter = StatsFilter::from(TimeRange::parse_named("today")?);
+//! let today = StatsCalculator::new(db).summary(&filter)?;
+//! println!("{} keys today", today.total_keys);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! - [`db`] opens the database and holds the recorded rows
+//! - [`stats`] computes statistics over them
+//! - [`keys`] names keys the way they are recorded
+//! - [`processors`] extend the recorder with filters and forwarders
+//! - [`config`] reads the settings kitmap runs with
+
+pub mod config;
+pub mod db;
+pub mod keys;
+pub mod processors;
+pub mod stats;
+
+// Used by the kitmap binary, and not meant to be used from other crates
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub mod commands;
+#[doc(hidden)]
+pub mod context;
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub mod publish;
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub mod server;
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub mod ui;
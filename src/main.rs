@@ -1,9 +1,12 @@
-mod commands;
-mod db;
-mod stats;
-mod ui;
-
 use clap::{Parser, Subcommand};
+use context::ContextOptions;
+use db::export::{DynamicsConsent, ExportData, ExportFormat};
+use kitmap::{commands, context, db, publish, stats, ui};
+use stats::goals::{GoalMetric, GoalPeriod};
+use stats::range::Period;
+use stats::replay::ReplaySpeed;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
 use std::process;
 
 #[derive(Parser)]
@@ -14,12 +17,89 @@ use std::process;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Print without colors (also set by the NO_COLOR environment variable)
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Draw boxes, charts and keys with plain ASCII characters
+    #[arg(long, global = true)]
+    ascii: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Start listening to keyboard events and recording them
-    Listen,
+    Listen {
+        /// Tag keystrokes with the focused workspace (sway, i3 and Hyprland)
+        #[arg(long)]
+        workspaces: bool,
+        /// Tag keystrokes with the monitor showing the focused workspace (sway, i3 and Hyprland)
+        #[arg(long)]
+        monitors: bool,
+        /// Tag keystrokes typed into SSH/remote sessions, detected from window titles
+        #[arg(long)]
+        remote: bool,
+        /// Attribute keystrokes to projects parsed from IDE and terminal window titles
+        #[arg(long)]
+        projects: bool,
+        /// Tag keystrokes and shortcuts with the focused application (sway, i3 and Hyprland)
+        #[arg(long)]
+        apps: bool,
+        /// Agree to the privacy summary without being asked (the consent is still recorded)
+        #[arg(short, long)]
+        yes: bool,
+        /// Measure how long each keyboard event takes to handle and report it when the session ends
+        #[arg(long)]
+        measure_latency: bool,
+        /// Suggest breaks after long stretches of continuous typing (see [breaks] in the config file)
+        #[arg(long)]
+        breaks: bool,
+        /// Also record the character each key press produced, where the platform reports it
+        /// (dead keys and input methods)
+        #[arg(long)]
+        composed: bool,
+        /// Record non-modifier keys pressed together as chords (see [chords] in the config file)
+        #[arg(long)]
+        chords: bool,
+        /// Record when each key is released, for `kitmap export --data dynamics`
+        #[arg(long)]
+        dynamics: bool,
+        /// Show a notification with the day's totals (see [daily_summary] in the config file)
+        #[arg(long)]
+        daily_summary: bool,
+        /// Show typing stats as Discord Rich Presence (see [discord] in the config file)
+        #[arg(long)]
+        discord: bool,
+        /// Ship per-minute aggregates to InfluxDB or StatsD (see [metrics] in the config file)
+        #[arg(long)]
+        metrics: bool,
+        /// Also write each recorded key press and session change to stdout, one JSON object
+        /// per line; status messages move to stderr
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        emit: Option<commands::listen::EmitFormat>,
+        /// Write emitted events to this file or named pipe instead of stdout
+        #[arg(long, value_name = "PATH", requires = "emit")]
+        tee: Option<PathBuf>,
+        /// Publish activity summaries to the MQTT broker from the config file
+        #[cfg(feature = "mqtt")]
+        #[arg(long)]
+        mqtt: bool,
+        /// Also record into the central Postgres database from the config file
+        #[cfg(feature = "postgres")]
+        #[arg(long)]
+        postgres: bool,
+        /// Serve the gRPC control and query API (default address: 127.0.0.1:50051)
+        #[cfg(feature = "grpc")]
+        #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = "127.0.0.1:50051")]
+        grpc: Option<std::net::SocketAddr>,
+        /// Require this token on gRPC calls, as `authorization: Bearer <token>` metadata
+        #[cfg(feature = "grpc")]
+        #[arg(long, env = "KITMAP_TOKEN", requires = "grpc")]
+        grpc_token: Option<String>,
+        /// Register io.github.twlite.Kitmap on the D-Bus session bus
+        #[cfg(all(feature = "dbus", target_os = "linux"))]
+        #[arg(long)]
+        dbus: bool,
+    },
     /// Preview keyboard usage statistics and heatmap
     Preview {
         /// Open web-based visualization instead of ASCII heatmap
@@ -28,26 +108,426 @@ enum Commands {
         /// Port for the web server (default: 3456)
         #[arg(short, long, default_value = "3456")]
         port: u16,
+        /// Address to bind the web server to (default: 127.0.0.1)
+        #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+        host: IpAddr,
+        /// Require this token to access the web dashboard. The /overlay key stream and key
+        /// press downloads are only served with one; open /overlay?token=<token>
+        #[arg(long, env = "KITMAP_TOKEN")]
+        token: Option<String>,
+        /// Serve dashboard files from this directory instead of the embedded build
+        #[arg(long, value_name = "DIR")]
+        assets: Option<PathBuf>,
+        #[command(flatten)]
+        terminal: commands::preview::TerminalArgs,
+        #[cfg(feature = "tls")]
+        #[command(flatten)]
+        tls: kitmap::server::tls::TlsArgs,
+    },
+    /// Print a Markdown typing summary for a day, or add it to an Obsidian daily note
+    Report {
+        /// Write the summary into the day's Obsidian daily note
+        #[arg(long)]
+        obsidian: bool,
+        /// Obsidian vault directory (defaults to `vault` under [obsidian] in the config)
+        #[arg(long)]
+        vault: Option<PathBuf>,
+        /// Day to summarize (default: today), e.g. yesterday or 2024-01-31
+        #[arg(long)]
+        date: Option<String>,
+        /// Summarize typing by calendar month across all recorded years instead
+        #[arg(long, conflicts_with_all = ["obsidian", "vault", "date"])]
+        seasonality: bool,
+        /// Summarize the week, month or year containing --date against the one before
+        #[arg(long, value_enum, conflicts_with_all = ["obsidian", "vault", "seasonality"])]
+        period: Option<Period>,
+    },
+    /// Show whether a listener is running and recording, or print a status bar module
+    Status {
+        /// Status bar to format the output for (default: a summary of the recorder)
+        #[arg(short, long, value_enum)]
+        format: Option<publish::status_bar::StatusFormat>,
+    },
+    /// Stop the running listener recording until `kitmap resume`
+    Pause,
+    /// Resume recording after `kitmap pause`
+    Resume,
+    /// Show the running listener's session, or start a new one
+    Session {
+        /// End the current session and start a new one
+        #[arg(long)]
+        new: bool,
+    },
+    /// Print overall stats as plain values, JSON or CSV for scripts and status bars
+    Stats {
+        /// Print a JSON object
+        #[arg(long, conflicts_with = "csv")]
+        json: bool,
+        /// Print a header row and one row of values
+        #[arg(long)]
+        csv: bool,
+        /// Only calculate and print these fields, e.g. total_keys,top_keys (default: all)
+        #[arg(long, value_delimiter = ',', value_name = "FIELD")]
+        fields: Vec<String>,
+    },
+    /// Print the most pressed keys and most used combos
+    Top {
+        /// Number of keys to list
+        #[arg(long, default_value_t = 10)]
+        keys: usize,
+        /// Number of combos to list
+        #[arg(long, default_value_t = 10)]
+        combos: usize,
+        /// Time span as START..END, or just START (e.g. 7d, 2024-01-01..2024-02-01)
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Chart average typing speed per day over the last weeks
+    Speed {
+        /// Number of weeks to chart, ending today
+        #[arg(long, default_value_t = 4)]
+        weeks: usize,
+    },
+    /// Show key presses per day over the last year as a calendar
+    Calendar,
+    /// Animate the heatmap filling up with key presses over time
+    Replay {
+        /// Recorded time played back per second, e.g. 6h/s, 1d/s or 1w/s
+        #[arg(long, default_value = "1d/s")]
+        speed: ReplaySpeed,
+        /// Time span to replay as START..END, or just START (e.g. 30d, 2024-01-01..2024-02-01)
+        #[arg(long)]
+        since: Option<String>,
+        /// Record the replay to this asciinema cast file instead of playing it
+        #[arg(long, value_name = "FILE")]
+        cast: Option<PathBuf>,
     },
-    /// Reset all recorded keyboard data
+    /// Compare totals, typing speed and top keys of two time ranges
+    Compare {
+        /// Earlier range: today, yesterday, this/last week, month or year, or START..END
+        #[arg(long)]
+        a: String,
+        /// Later range, in the same forms as --a
+        #[arg(long)]
+        b: String,
+        /// Number of top keys to list
+        #[arg(long, default_value_t = 10)]
+        keys: usize,
+    },
+    /// Export recorded data as CSV or JSON
+    Export {
+        /// Output format (ics requires --data blocks, activitywatch requires --data keys)
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Which data to export
+        #[arg(short, long, value_enum, default_value_t = ExportData::Keys)]
+        data: ExportData,
+        /// Time span as START..END, or just START (e.g. 7d, 2024-01-01..2024-02-01)
+        #[arg(short, long)]
+        range: Option<String>,
+        /// Idle minutes that end an activity block (for --data blocks)
+        #[arg(long, default_value_t = stats::blocks::DEFAULT_BLOCK_GAP_MINUTES)]
+        gap: i64,
+        /// Agree to exporting key press and release times, which can identify you
+        /// (required for --data dynamics)
+        #[arg(long)]
+        consent_timing: bool,
+        /// Also export which letter or digit each key is, which lets the typed text be
+        /// read back (for --data dynamics)
+        #[arg(long, requires = "consent_timing")]
+        consent_keys: bool,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import keyboard history recorded by another tool
+    Import {
+        /// Format of the file to import
+        #[arg(short, long, value_enum)]
+        format: commands::import::ImportFormat,
+        /// File to import
+        file: PathBuf,
+    },
+    /// Merge another kitmap database into this one, e.g. from another computer
+    Merge {
+        /// Database file to merge in; it is not modified
+        file: PathBuf,
+    },
+    /// Reset all recorded keyboard data, or only some of it
     Reset {
         /// Skip confirmation prompt
         #[arg(short, long)]
         force: bool,
+        /// Only delete data recorded before this time, e.g. 2024-03-06 or 30d
+        #[arg(long)]
+        before: Option<String>,
+        /// Only delete data recorded at or after this time, e.g. 2024-03-05 or 7d
+        #[arg(long)]
+        after: Option<String>,
+        /// Only delete these kinds of data (default: all)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        only: Vec<commands::reset::ResetTable>,
+    },
+    /// Show resets, imports, privacy consents and config edits over time
+    Audit {
+        /// Number of most recent entries to show
+        #[arg(short, long, default_value = "50")]
+        limit: usize,
+    },
+    /// Write a copy of the database with typed content redacted, for bug reports or research
+    Sanitize {
+        /// Where to write the sanitized database
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Overwrite the output file if it exists
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Show progress towards daily and weekly typing goals, or set and remove them
+    Goals {
+        #[command(subcommand)]
+        command: Option<GoalsCommand>,
+    },
+    /// Show the database path, or analyze how it uses disk space
+    Db {
+        #[command(subcommand)]
+        command: Option<DbCommand>,
+    },
+    /// Benchmark how much latency recording adds to each keyboard event
+    Bench {
+        /// Number of synthetic key presses to replay
+        #[arg(long, default_value = "10000")]
+        events: usize,
+    },
+    /// Check input capture permissions, database health and disk space
+    Doctor,
+    /// Print the JSON Schema of an output format
+    Schema {
+        /// Output format to describe
+        #[arg(value_enum, required_unless_present = "check")]
+        name: Option<commands::schema::SchemaName>,
+        /// Check all schemas for breaking changes against the snapshots in this directory
+        #[arg(long, value_name = "DIR", conflicts_with = "name")]
+        check: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Estimate bytes written per keystroke and suggest storage settings
+    Analyze,
+    /// Checkpoint the write-ahead log and refresh query planner statistics
+    Maintain {
+        /// Also rebuild the file to return free pages to the file system
+        #[arg(long)]
+        vacuum: bool,
+    },
+    /// Delete everything recorded before a month
+    Prune {
+        /// First month to keep, as YYYY-MM
+        #[arg(long, value_name = "YYYY-MM")]
+        before: String,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GoalsCommand {
+    /// Set or replace a goal
+    Set {
+        /// What the goal measures
+        #[arg(value_enum)]
+        metric: GoalMetric,
+        /// Keys, active minutes or words per minute to reach
+        target: f64,
+        /// How often progress starts over
+        #[arg(short, long, value_enum, default_value = "daily")]
+        period: GoalPeriod,
+    },
+    /// Remove a goal
+    Remove {
+        /// What the goal measures
+        #[arg(value_enum)]
+        metric: GoalMetric,
+        /// Period of the goal to remove
+        #[arg(short, long, value_enum, default_value = "daily")]
+        period: GoalPeriod,
     },
-    /// Show the database path
-    Db,
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
+    ui::plain::configure(cli.no_color, cli.ascii);
 
     let result = match cli.command {
-        Commands::Listen => commands::listen::run().await,
-        Commands::Preview { web, port } => commands::preview::run(web, port).await,
-        Commands::Reset { force } => commands::reset::run(force).await,
-        Commands::Db => commands::db::run().await,
+        Commands::Listen {
+            workspaces,
+            monitors,
+            remote,
+            projects,
+            apps,
+            yes,
+            measure_latency,
+            breaks,
+            composed,
+            chords,
+            dynamics,
+            daily_summary,
+            discord,
+            metrics,
+            emit,
+            tee,
+            #[cfg(feature = "mqtt")]
+            mqtt,
+            #[cfg(feature = "postgres")]
+            postgres,
+            #[cfg(feature = "grpc")]
+            grpc,
+            #[cfg(feature = "grpc")]
+            grpc_token,
+            #[cfg(all(feature = "dbus", target_os = "linux"))]
+            dbus,
+        } => {
+            let context = ContextOptions {
+                workspaces,
+                monitors,
+                remote,
+                projects,
+                apps,
+            };
+            commands::listen::run(commands::listen::ListenOptions {
+                context,
+                yes,
+                measure_latency,
+                breaks,
+                daily_summary,
+                discord,
+                metrics,
+                composed,
+                chords,
+                dynamics,
+                emit,
+                tee,
+                #[cfg(feature = "mqtt")]
+                mqtt,
+                #[cfg(feature = "postgres")]
+                postgres,
+                #[cfg(feature = "grpc")]
+                grpc,
+                #[cfg(feature = "grpc")]
+                grpc_token,
+                #[cfg(all(feature = "dbus", target_os = "linux"))]
+                dbus,
+            })
+            .await
+        }
+        Commands::Preview {
+            web,
+            port,
+            host,
+            token,
+            assets,
+            terminal,
+            #[cfg(feature = "tls")]
+            tls,
+        } => {
+            commands::preview::run(
+                web,
+                host,
+                port,
+                token,
+                assets,
+                terminal,
+                #[cfg(feature = "tls")]
+                tls,
+            )
+            .await
+        }
+        Commands::Report {
+            obsidian,
+            vault,
+            date,
+            seasonality,
+            period,
+        } => commands::report::run(obsidian, vault, date, seasonality, period).await,
+        Commands::Status { format } => commands::status::run(format).await,
+        Commands::Pause => commands::control::pause().await,
+        Commands::Resume => commands::control::resume().await,
+        Commands::Session { new } => commands::control::session(new).await,
+        Commands::Stats { json, csv, fields } => {
+            let output = if json {
+                commands::stats::StatsOutput::Json
+            } else if csv {
+                commands::stats::StatsOutput::Csv
+            } else {
+                commands::stats::StatsOutput::Text
+            };
+            commands::stats::run(output, fields).await
+        }
+        Commands::Top {
+            keys,
+            combos,
+            since,
+        } => commands::top::run(keys, combos, since.as_deref()).await,
+        Commands::Speed { weeks } => commands::speed::run(weeks).await,
+        Commands::Calendar => commands::calendar::run().await,
+        Commands::Replay { speed, since, cast } => {
+            commands::replay::run(speed, since.as_deref(), cast.as_deref()).await
+        }
+        Commands::Compare { a, b, keys } => commands::compare::run(&a, &b, keys).await,
+        Commands::Export {
+            format,
+            data,
+            range,
+            gap,
+            consent_timing,
+            consent_keys,
+            output,
+        } => {
+            let consent = DynamicsConsent {
+                timing: consent_timing,
+                keys: consent_keys,
+            };
+            commands::export::run(format, data, range, gap, consent, output).await
+        }
+        Commands::Import { format, file } => commands::import::run(format, file).await,
+        Commands::Merge { file } => commands::merge::run(&file).await,
+        Commands::Reset {
+            force,
+            before,
+            after,
+            only,
+        } => commands::reset::run(force, before, after, only).await,
+        Commands::Audit { limit } => commands::audit::run(limit).await,
+        Commands::Sanitize { output, force } => commands::sanitize::run(&output, force).await,
+        Commands::Goals { command: None } => commands::goals::run().await,
+        Commands::Goals {
+            command:
+                Some(GoalsCommand::Set {
+                    metric,
+                    target,
+                    period,
+                }),
+        } => commands::goals::set(metric, target, period).await,
+        Commands::Goals {
+            command: Some(GoalsCommand::Remove { metric, period }),
+        } => commands::goals::remove(metric, period).await,
+        Commands::Db { command: None } => commands::db::run().await,
+        Commands::Db {
+            command: Some(DbCommand::Analyze),
+        } => commands::db::analyze().await,
+        Commands::Db {
+            command: Some(DbCommand::Maintain { vacuum }),
+        } => commands::db::maintain(vacuum).await,
+        Commands::Db {
+            command: Some(DbCommand::Prune { before, force }),
+        } => commands::db::prune(&before, force).await,
+        Commands::Bench { events } => commands::bench::run(events).await,
+        Commands::Doctor => commands::doctor::run().await,
+        Commands::Schema { name, check } => commands::schema::run(name, check).await,
     };
 
     if let Err(e) = result {
@@ -0,0 +1,36 @@
+use super::{jsonl::JsonLines, options, EventProcessor};
+use crate::config::ProcessorConfig;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::process::{Command, Stdio};
+use std::thread;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CommandOptions {
+    /// Program to start and its arguments
+    command: Vec<String>,
+}
+
+/// Start a program and write every event to its standard input as a line of JSON, so
+/// aggregators and forwarders can be written in any language
+pub fn from_config(config: &ProcessorConfig) -> Result<Box<dyn EventProcessor>> {
+    let CommandOptions { command } = options(config)?;
+    let Some((program, args)) = command.split_first() else {
+        bail!("Set `command` to the program to start and its arguments");
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to start `{}`", program))?;
+    let stdin = child.stdin.take().expect("stdin is piped");
+    thread::spawn(move || child.wait());
+
+    Ok(Box::new(JsonLines::spawn(
+        format!("`{}`", program),
+        move || Ok(stdin),
+    )))
+}
@@ -0,0 +1,52 @@
+use super::{options, EventProcessor, Verdict};
+use crate::config::ProcessorConfig;
+use crate::context::glob_match;
+use crate::db::models::KeyEvent;
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FilterOptions {
+    /// Key names never recorded (`*` wildcards, case-insensitive)
+    #[serde(default)]
+    keys: Vec<String>,
+    /// Projects whose keys are never recorded, when project tracking is enabled
+    #[serde(default)]
+    projects: Vec<String>,
+}
+
+/// Drops key presses of some keys, or typed in some projects, before they are recorded
+pub struct KeyFilter {
+    keys: Vec<String>,
+    projects: Vec<String>,
+}
+
+impl KeyFilter {
+    pub fn from_config(config: &ProcessorConfig) -> Result<Box<dyn EventProcessor>> {
+        let FilterOptions { keys, projects } = options(config)?;
+        if keys.is_empty() && projects.is_empty() {
+            bail!("Set `keys` or `projects` to the patterns whose key presses are dropped");
+        }
+        Ok(Box::new(Self { keys, projects }))
+    }
+}
+
+impl EventProcessor for KeyFilter {
+    fn process(&mut self, event: &mut KeyEvent) -> Result<Verdict> {
+        let key = self
+            .keys
+            .iter()
+            .any(|pattern| glob_match(pattern, &event.key_name));
+        let project = event.project.as_deref().is_some_and(|project| {
+            self.projects
+                .iter()
+                .any(|pattern| glob_match(pattern, project))
+        });
+        Ok(if key || project {
+            Verdict::Drop
+        } else {
+            Verdict::Keep
+        })
+    }
+}
@@ -0,0 +1,105 @@
+use super::{EventProcessor, Verdict};
+use crate::db::models::{KeyEvent, Session};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Sender, SyncSender};
+use std::thread;
+use std::time::Duration;
+
+/// How long the end of a session waits for its lines to be written, as the listener may
+/// exit right after
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+enum Message {
+    Line(String),
+    /// Answered once every line before it was written
+    Flush(SyncSender<()>),
+}
+
+/// Writes every event as a line of JSON.
+///
+/// Key presses are the recorded key event with `"event": "key_press"`; sessions are
+/// `session_started` and `session_ended` events with their `session_id` and `timestamp`.
+/// Lines are written from a separate thread, so a slow reader never holds up recording.
+pub struct JsonLines {
+    messages: Sender<Message>,
+}
+
+impl JsonLines {
+    /// Write lines to what `open` returns. It is opened on the writing thread, so opening a
+    /// named pipe that has no reader yet doesn't hold up recording either.
+    pub fn spawn<W, F>(destination: String, open: F) -> Self
+    where
+        W: Write,
+        F: FnOnce() -> io::Result<W> + Send + 'static,
+    {
+        let (messages, queue) = mpsc::channel::<Message>();
+        thread::spawn(move || {
+            let mut writer = match open() {
+                Ok(writer) => writer,
+                Err(e) => {
+                    eprintln!("\nFailed to open {} for events: {}", destination, e);
+                    return;
+                }
+            };
+            for message in queue {
+                match message {
+                    Message::Line(line) => {
+                        let written = writeln!(writer, "{}", line).and_then(|()| writer.flush());
+                        if let Err(e) = written {
+                            eprintln!("\n{} stopped reading events: {}", destination, e);
+                            return;
+                        }
+                    }
+                    Message::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+        Self { messages }
+    }
+
+    fn send(&self, line: Value) {
+        // A closed channel means the reader went away, which was already reported
+        let _ = self.messages.send(Message::Line(line.to_string()));
+    }
+
+    /// Wait a moment for the lines sent so far to be written
+    fn flush(&self) {
+        let (done, flushed) = mpsc::sync_channel(1);
+        if self.messages.send(Message::Flush(done)).is_ok() {
+            let _ = flushed.recv_timeout(FLUSH_TIMEOUT);
+        }
+    }
+}
+
+impl EventProcessor for JsonLines {
+    fn process(&mut self, event: &mut KeyEvent) -> Result<Verdict> {
+        let mut line = serde_json::to_value(&*event)?;
+        line["event"] = json!("key_press");
+        self.send(line);
+        Ok(Verdict::Keep)
+    }
+
+    fn session_started(&mut self, session: &Session) -> Result<()> {
+        self.send(json!({
+            "event": "session_started",
+            "session_id": session.id,
+            "timestamp": session.start_time,
+        }));
+        Ok(())
+    }
+
+    fn session_ended(&mut self, session: &Session) -> Result<()> {
+        self.send(json!({
+            "event": "session_ended",
+            "session_id": session.id,
+            "timestamp": session.end_time,
+            "total_keys": session.total_keys,
+        }));
+        self.flush();
+        Ok(())
+    }
+}
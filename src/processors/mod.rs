@@ -0,0 +1,101 @@
+//! Event processors extend the recorder without changing it.
+//!
+//! `kitmap listen` hands every key event to the processors listed under `[[processors]]`
+//! in the config, in order, before the event is recorded. A processor can change the
+//! event, drop it, or pass it on elsewhere. Each entry names its `kind`, which is looked
+//! up in a [`Registry`], and the rest of the entry configures it:
+//!
+//! ```toml
+//! [[processors]]
+//! kind = "filter"
+//! keys = ["F13", "Unknown(*)"]
+//!
+//! [[processors]]
+//! kind = "command"
+//! command = ["python3", "forward.py"]
+//! ```
+
+pub mod command;
+pub mod filter;
+pub mod jsonl;
+
+use crate::config::ProcessorConfig;
+use crate::db::models::{KeyEvent, Session};
+use anyhow::{anyhow, Context, Result};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+
+/// What happens to a key event after a processor has seen it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Record the event and hand it to the next processor
+    Keep,
+    /// Forget the event: it isn't recorded, counted or seen by later processors
+    Drop,
+}
+
+/// Sees each key event before it is recorded, along with the sessions it belongs to
+pub trait EventProcessor: Send {
+    /// Inspect or change a key press. Its context and composed text are already filled in.
+    fn process(&mut self, event: &mut KeyEvent) -> Result<Verdict>;
+
+    /// A recording session started, when listening starts or resumes after a pause
+    fn session_started(&mut self, _session: &Session) -> Result<()> {
+        Ok(())
+    }
+
+    /// A recording session ended, when listening stops or pauses
+    fn session_ended(&mut self, _session: &Session) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Builds a processor from its `[[processors]]` entry
+pub type Factory = fn(&ProcessorConfig) -> Result<Box<dyn EventProcessor>>;
+
+/// Processor kinds by the name `kind` refers to them with
+#[derive(Default)]
+pub struct Registry {
+    factories: BTreeMap<String, Factory>,
+}
+
+impl Registry {
+    /// A registry of the processors that come with kitmap
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::default();
+        registry.register("filter", filter::KeyFilter::from_config);
+        registry.register("command", command::from_config);
+        registry
+    }
+
+    /// Make a processor available as `kind`, replacing any registered under that name
+    pub fn register(&mut self, kind: &str, factory: Factory) {
+        self.factories.insert(kind.to_string(), factory);
+    }
+
+    /// Build the configured processors, in the order they are listed
+    pub fn build(&self, configs: &[ProcessorConfig]) -> Result<Vec<Box<dyn EventProcessor>>> {
+        configs
+            .iter()
+            .map(|config| {
+                let factory = self.factories.get(&config.kind).ok_or_else(|| {
+                    anyhow!(
+                        "Unknown processor kind `{}` in [[processors]]; available kinds: {}",
+                        config.kind,
+                        self.factories
+                            .keys()
+                            .map(String::as_str)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+                factory(config).with_context(|| format!("Invalid `{}` processor", config.kind))
+            })
+            .collect()
+    }
+}
+
+/// The settings of a processor entry, as the processor's own settings type
+pub fn options<T: DeserializeOwned>(config: &ProcessorConfig) -> Result<T> {
+    Ok(toml::Value::Table(config.options.clone()).try_into()?)
+}
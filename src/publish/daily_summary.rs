@@ -0,0 +1,89 @@
+use crate::config::{DailySummaryConfig, ScoreConfig};
+use crate::db::DbConnection;
+use crate::publish::{compact, notify};
+use crate::stats::calculator::DaySummary;
+use crate::stats::{StatsCalculator, StatsFilter};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime, TimeZone};
+use std::time::Duration;
+
+/// How often the clock is checked. Polling instead of sleeping until the notification
+/// time keeps the schedule right across suspend and clock changes.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The configured notification time
+pub fn parse_time(config: &DailySummaryConfig) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(&config.time, "%H:%M").with_context(|| {
+        format!(
+            "Invalid time \"{}\" under [daily_summary]; expected HH:MM",
+            config.time
+        )
+    })
+}
+
+/// Show a desktop notification with the day's totals at `time` every day.
+///
+/// Days without any keys are skipped. A notification missed while the computer was
+/// asleep is shown on wake, for the day it belongs to.
+pub async fn run(time: NaiveTime, score: ScoreConfig, db: DbConnection) {
+    let mut next = next_occurrence(time, Local::now());
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let now = Local::now();
+        if now < next {
+            continue;
+        }
+        let date = next.date_naive();
+        next = next_occurrence(time, now);
+
+        let calculator = StatsCalculator::new(db.clone()).with_score_config(score.clone());
+        match tokio::task::spawn_blocking(move || {
+            calculator.day_summary(&StatsFilter::default(), date)
+        })
+        .await
+        {
+            Ok(Ok(summary)) if summary.total_keys > 0 => {
+                let (title, body) = message(&summary);
+                notify::desktop(&title, &body);
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => eprintln!("\nFailed to summarize the day's typing: {}", e),
+            Err(_) => return,
+        }
+    }
+}
+
+/// First time after `after` that the local clock shows `time`
+fn next_occurrence(time: NaiveTime, after: DateTime<Local>) -> DateTime<Local> {
+    let mut date = after.date_naive();
+    loop {
+        // Skipped when the time falls in a daylight saving gap
+        if let Some(at) = Local.from_local_datetime(&date.and_time(time)).earliest() {
+            if at > after {
+                return at;
+            }
+        }
+        date += ChronoDuration::days(1);
+    }
+}
+
+/// Notification title and body for a day
+fn message(summary: &DaySummary) -> (String, String) {
+    let metrics = summary.score.as_ref().map(|s| &s.metrics);
+    let mut parts = vec![
+        format!("{} keys", compact(summary.total_keys)),
+        format!("{} active min", metrics.map_or(0, |m| m.active_minutes)),
+        format!("{:.0} WPM", metrics.map_or(0.0, |m| m.wpm)),
+    ];
+    if let Some(key) = summary.top_keys.first() {
+        parts.push(format!("top key {}", key.key_name));
+    }
+
+    (
+        format!("⌨️ Typing summary for {}", summary.date),
+        parts.join(" · "),
+    )
+}
@@ -0,0 +1,192 @@
+use crate::config::DiscordConfig;
+use crate::db::DbConnection;
+use crate::publish::{compact, ActivitySnapshot};
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use serde_json::{json, Value};
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::Duration;
+
+/// Discord IPC opcode for the initial handshake
+const OP_HANDSHAKE: u32 = 0;
+
+/// Discord IPC opcode for commands and their responses
+const OP_FRAME: u32 = 1;
+
+/// How long to wait before looking for Discord again when it isn't running
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Discord accepts at most one presence update per this interval
+const MIN_UPDATE_INTERVAL_SECS: u64 = 15;
+
+trait IpcStream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> IpcStream for T {}
+
+/// Open the IPC socket (or named pipe) of a running Discord client
+fn connect_ipc() -> io::Result<Box<dyn IpcStream>> {
+    #[cfg(unix)]
+    {
+        let mut dirs: Vec<String> = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .collect();
+        dirs.push("/tmp".to_string());
+
+        for dir in &dirs {
+            // Flatpak and Snap installs put the socket in their own subdirectory
+            for sub in ["", "app/com.discordapp.Discord/", "snap.discord/"] {
+                for i in 0..10 {
+                    let path = format!("{}/{}discord-ipc-{}", dir, sub, i);
+                    if let Ok(stream) = std::os::unix::net::UnixStream::connect(&path) {
+                        return Ok(Box::new(stream));
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    for i in 0..10 {
+        let path = format!(r"\\.\pipe\discord-ipc-{}", i);
+        if let Ok(pipe) = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+        {
+            return Ok(Box::new(pipe));
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "Discord is not running",
+    ))
+}
+
+/// A connection to the local Discord client's Rich Presence IPC
+struct DiscordClient {
+    stream: Box<dyn IpcStream>,
+    nonce: u64,
+}
+
+impl DiscordClient {
+    fn connect(client_id: &str) -> Result<Self> {
+        let mut client = Self {
+            stream: connect_ipc()?,
+            nonce: 0,
+        };
+        client.send(OP_HANDSHAKE, &json!({ "v": 1, "client_id": client_id }))?;
+        let (_, ready) = client.recv()?;
+        if ready["evt"] != "READY" {
+            bail!("Discord rejected the connection: {}", ready);
+        }
+        Ok(client)
+    }
+
+    fn send(&mut self, op: u32, payload: &Value) -> Result<()> {
+        let body = serde_json::to_vec(payload)?;
+        let mut frame = Vec::with_capacity(8 + body.len());
+        frame.extend_from_slice(&op.to_le_bytes());
+        frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&body);
+        self.stream.write_all(&frame)?;
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<(u32, Value)> {
+        let mut header = [0u8; 8];
+        self.stream.read_exact(&mut header)?;
+        let op = u32::from_le_bytes(header[..4].try_into()?);
+        let len = u32::from_le_bytes(header[4..].try_into()?) as usize;
+
+        let mut body = vec![0u8; len];
+        self.stream.read_exact(&mut body)?;
+        Ok((op, serde_json::from_slice(&body)?))
+    }
+
+    fn set_activity(&mut self, activity: Value) -> Result<()> {
+        self.nonce += 1;
+        self.send(
+            OP_FRAME,
+            &json!({
+                "cmd": "SET_ACTIVITY",
+                "args": { "pid": std::process::id(), "activity": activity },
+                "nonce": self.nonce.to_string(),
+            }),
+        )?;
+
+        let (_, response) = self.recv()?;
+        if response["evt"] == "ERROR" {
+            bail!("{}", response["data"]["message"]);
+        }
+        Ok(())
+    }
+}
+
+/// Build the presence shown on the user's Discord profile from the shared stats
+fn activity(snapshot: &ActivitySnapshot, config: &DiscordConfig) -> Value {
+    let mut activity = json!({});
+
+    if config.show_keys_today {
+        activity["details"] = json!(format!("{} keys today", compact(snapshot.keys_today)));
+    }
+
+    let mut state = Vec::new();
+    if config.show_wpm && snapshot.wpm > 0.0 {
+        state.push(format!("{:.0} WPM", snapshot.wpm));
+    }
+    if config.show_session && snapshot.session_minutes > 0.0 {
+        state.push(format!(
+            "{} keys this session",
+            compact(snapshot.session_keys)
+        ));
+        let started = Local::now().timestamp() - (snapshot.session_minutes * 60.0) as i64;
+        activity["timestamps"] = json!({ "start": started });
+    }
+    if !state.is_empty() {
+        activity["state"] = json!(state.join(" · "));
+    }
+
+    activity
+}
+
+/// Keep the Discord presence updated on a background thread until the process exits,
+/// reconnecting whenever Discord is restarted
+pub fn spawn(config: DiscordConfig, db: DbConnection) -> Result<()> {
+    if config.client_id.is_empty() {
+        bail!(
+            "Set `client_id` under [discord] in the config file to the ID of a Discord application"
+        );
+    }
+    let interval = Duration::from_secs(config.interval_secs.max(MIN_UPDATE_INTERVAL_SECS));
+
+    thread::spawn(move || {
+        let mut client: Option<DiscordClient> = None;
+        loop {
+            if client.is_none() {
+                client = DiscordClient::connect(&config.client_id).ok();
+                if client.is_none() {
+                    thread::sleep(RECONNECT_INTERVAL);
+                    continue;
+                }
+            }
+
+            let update = ActivitySnapshot::capture_current(&db)
+                .context("Failed to summarize activity")
+                .and_then(|snapshot| {
+                    let activity = activity(&snapshot, &config);
+                    client.as_mut().map_or(Ok(()), |c| c.set_activity(activity))
+                });
+            if let Err(e) = update {
+                eprintln!("\nDiscord presence update failed: {}", e);
+                client = None;
+            }
+
+            thread::sleep(interval);
+        }
+    });
+
+    Ok(())
+}
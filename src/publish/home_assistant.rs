@@ -0,0 +1,118 @@
+use crate::config::MqttConfig;
+use serde_json::{json, Value};
+
+/// A kitmap value exposed as a Home Assistant entity
+struct Entity {
+    /// Home Assistant platform, `sensor` or `binary_sensor`
+    component: &'static str,
+    /// Snapshot field, also the state subtopic
+    field: &'static str,
+    name: &'static str,
+    icon: &'static str,
+    unit: Option<&'static str>,
+    state_class: Option<&'static str>,
+}
+
+const ENTITIES: &[Entity] = &[
+    Entity {
+        component: "sensor",
+        field: "keys_today",
+        name: "Keys today",
+        icon: "mdi:keyboard",
+        unit: Some("keys"),
+        state_class: Some("total_increasing"),
+    },
+    Entity {
+        component: "sensor",
+        field: "wpm",
+        name: "Typing speed",
+        icon: "mdi:speedometer",
+        unit: Some("WPM"),
+        state_class: Some("measurement"),
+    },
+    Entity {
+        component: "sensor",
+        field: "keys_last_minute",
+        name: "Keys last minute",
+        icon: "mdi:keyboard-outline",
+        unit: Some("keys"),
+        state_class: Some("measurement"),
+    },
+    Entity {
+        component: "sensor",
+        field: "session_keys",
+        name: "Session keys",
+        icon: "mdi:timer-outline",
+        unit: Some("keys"),
+        state_class: Some("measurement"),
+    },
+    Entity {
+        component: "binary_sensor",
+        field: "typing_active",
+        name: "Typing active",
+        icon: "mdi:keyboard-variant",
+        unit: None,
+        state_class: None,
+    },
+];
+
+/// Topic Home Assistant watches for kitmap's online/offline availability
+pub fn availability_topic(config: &MqttConfig) -> String {
+    format!("{}/status", config.topic.trim_end_matches('/'))
+}
+
+/// MQTT discovery messages registering kitmap's sensors with Home Assistant, as
+/// `(topic, payload)` pairs to publish retained
+pub fn discovery_messages(config: &MqttConfig) -> Vec<(String, String)> {
+    let topic = config.topic.trim_end_matches('/');
+    let prefix = config.discovery_prefix.trim_end_matches('/');
+    let node_id = format!("kitmap_{}", sanitize(&config.client_id));
+    let device = json!({
+        "identifiers": [node_id],
+        "name": "kitmap",
+        "manufacturer": "kitmap",
+        "model": "Keyboard activity tracker",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+
+    ENTITIES
+        .iter()
+        .map(|entity| {
+            let mut payload = json!({
+                "name": entity.name,
+                "unique_id": format!("{}_{}", node_id, entity.field),
+                "state_topic": format!("{}/{}", topic, entity.field),
+                "availability_topic": availability_topic(config),
+                "icon": entity.icon,
+                "device": device,
+            });
+            if let Some(unit) = entity.unit {
+                payload["unit_of_measurement"] = Value::from(unit);
+            }
+            if let Some(state_class) = entity.state_class {
+                payload["state_class"] = Value::from(state_class);
+            }
+
+            (
+                format!(
+                    "{}/{}/{}/{}/config",
+                    prefix, entity.component, node_id, entity.field
+                ),
+                payload.to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Restrict an identifier to the characters allowed in discovery topics
+fn sanitize(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,83 @@
+use crate::config::HookConfig;
+use crate::db::DbConnection;
+use crate::publish::webhooks::Milestone;
+use crate::publish::ActivitySnapshot;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Start every hook subscribed to a milestone, without waiting for them to finish.
+///
+/// Commands run through the shell with the milestone and `snapshot` in `KITMAP_*`
+/// environment variables. Failures are reported but never stop recording.
+pub fn run(hooks: &[HookConfig], milestone: &Milestone, snapshot: &ActivitySnapshot) {
+    for hook in hooks {
+        if !hook.events.is_empty() && !hook.events.contains(&milestone.event) {
+            continue;
+        }
+
+        let child = shell(&hook.command)
+            .envs(environment(milestone, snapshot))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn();
+        let command = hook.command.clone();
+        match child {
+            Ok(mut child) => {
+                thread::spawn(move || match child.wait() {
+                    Ok(status) if !status.success() => {
+                        eprintln!("\nHook `{}` failed with {}", command, status)
+                    }
+                    Ok(_) => {}
+                    Err(e) => eprintln!("\nHook `{}` failed: {}", command, e),
+                });
+            }
+            Err(e) => eprintln!("\nFailed to run hook `{}`: {}", command, e),
+        }
+    }
+}
+
+/// Start the hooks subscribed to a milestone with the activity of the session being recorded
+pub fn run_current(hooks: &[HookConfig], milestone: &Milestone, db: &DbConnection) {
+    if hooks.is_empty() {
+        return;
+    }
+    match ActivitySnapshot::capture_current(db) {
+        Ok(snapshot) => run(hooks, milestone, &snapshot),
+        Err(e) => eprintln!("\nFailed to summarize activity for hooks: {}", e),
+    }
+}
+
+/// The variables a hook's command sees
+fn environment(milestone: &Milestone, snapshot: &ActivitySnapshot) -> Vec<(&'static str, String)> {
+    vec![
+        ("KITMAP_EVENT", milestone.event_name()),
+        ("KITMAP_MESSAGE", milestone.message.clone()),
+        ("KITMAP_VALUE", milestone.value_text()),
+        ("KITMAP_TIMESTAMP", milestone.timestamp.clone()),
+        ("KITMAP_KEYS_TODAY", snapshot.keys_today.to_string()),
+        (
+            "KITMAP_KEYS_LAST_MINUTE",
+            snapshot.keys_last_minute.to_string(),
+        ),
+        ("KITMAP_WPM", format!("{:.1}", snapshot.wpm)),
+        ("KITMAP_SESSION_KEYS", snapshot.session_keys.to_string()),
+        (
+            "KITMAP_SESSION_MINUTES",
+            format!("{:.0}", snapshot.session_minutes),
+        ),
+    ]
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
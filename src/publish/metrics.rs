@@ -0,0 +1,198 @@
+use crate::config::{MetricsConfig, MetricsProtocol};
+use crate::db::DbConnection;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use std::net::UdpSocket;
+use std::time::Duration;
+
+/// How long the InfluxDB write endpoint may take to respond
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Activity recorded during one emission window
+#[derive(Debug, Clone)]
+pub struct Aggregate {
+    pub end: DateTime<Local>,
+    pub keys: i64,
+    pub combos: i64,
+    /// Non-modifier keys
+    pub typed_keys: i64,
+    pub backspaces: i64,
+    /// Words (five typed keys) per minute over the window
+    pub wpm: f64,
+}
+
+impl Aggregate {
+    /// Summarize the activity recorded between `start` and `end`
+    pub fn capture(
+        db: &DbConnection,
+        start: DateTime<Local>,
+        end: DateTime<Local>,
+    ) -> Result<Self> {
+        db.read(|conn| {
+            let range = (start.timestamp_millis(), end.timestamp_millis());
+
+            let (keys, typed_keys, backspaces): (i64, i64, i64) = conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(is_modifier = 0), 0), COALESCE(SUM(key_name = 'Backspace'), 0)
+                 FROM key_events WHERE timestamp_ms >= ?1 AND timestamp_ms < ?2",
+                range,
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+            let combos: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM key_combos WHERE timestamp_ms >= ?1 AND timestamp_ms < ?2",
+                range,
+                |row| row.get(0),
+            )?;
+
+            let minutes = (end - start).num_milliseconds() as f64 / 60_000.0;
+            Ok(Self {
+                end,
+                keys,
+                combos,
+                typed_keys,
+                backspaces,
+                wpm: if minutes > 0.0 {
+                    typed_keys as f64 / 5.0 / minutes
+                } else {
+                    0.0
+                },
+            })
+        })
+    }
+
+    /// One InfluxDB line protocol point, timestamped at the end of the window
+    pub fn influx_line(&self, measurement: &str, tags: &[(String, String)]) -> String {
+        let mut line = escape(measurement, ", ");
+        for (key, value) in tags {
+            line.push_str(&format!(",{}={}", escape(key, ",= "), escape(value, ",= ")));
+        }
+        line.push_str(&format!(
+            " keys={}i,combos={}i,typed_keys={}i,backspaces={}i,wpm={:.2} {}",
+            self.keys,
+            self.combos,
+            self.typed_keys,
+            self.backspaces,
+            self.wpm,
+            self.end.timestamp_nanos_opt().unwrap_or_default()
+        ));
+        line
+    }
+
+    /// StatsD counters for the window's activity and a gauge for its typing speed
+    pub fn statsd_lines(&self, prefix: &str) -> Vec<String> {
+        vec![
+            format!("{}.keys:{}|c", prefix, self.keys),
+            format!("{}.combos:{}|c", prefix, self.combos),
+            format!("{}.typed_keys:{}|c", prefix, self.typed_keys),
+            format!("{}.backspaces:{}|c", prefix, self.backspaces),
+            format!("{}.wpm:{:.2}|g", prefix, self.wpm),
+        ]
+    }
+}
+
+/// Backslash-escape the characters line protocol treats as delimiters
+fn escape(text: &str, special: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Ships recorded activity to InfluxDB or StatsD
+pub struct MetricsEmitter {
+    config: MetricsConfig,
+    tags: Vec<(String, String)>,
+    socket: Option<UdpSocket>,
+}
+
+impl MetricsEmitter {
+    pub fn new(config: MetricsConfig) -> Result<Self> {
+        let socket = match config.protocol {
+            MetricsProtocol::Influx => {
+                if config.url.is_empty() {
+                    bail!("Set `url` under [metrics] in the config file to an InfluxDB write endpoint");
+                }
+                None
+            }
+            MetricsProtocol::Statsd => {
+                let socket = UdpSocket::bind("0.0.0.0:0")?;
+                socket
+                    .connect(&config.statsd_addr)
+                    .with_context(|| format!("Invalid StatsD address {}", config.statsd_addr))?;
+                Some(socket)
+            }
+        };
+
+        let mut tags: Vec<(String, String)> = config
+            .tags
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        if !config.tags.contains_key("host") {
+            tags.push(("host".to_string(), crate::db::hostname()));
+        }
+        // Line protocol asks for tags sorted by key for the fastest writes
+        tags.sort();
+
+        Ok(Self {
+            config,
+            tags,
+            socket,
+        })
+    }
+
+    pub fn send(&self, aggregate: &Aggregate) -> Result<()> {
+        match &self.socket {
+            Some(socket) => {
+                for line in aggregate.statsd_lines(&self.config.prefix) {
+                    socket.send(line.as_bytes())?;
+                }
+            }
+            None => {
+                let mut request = ureq::post(&self.config.url)
+                    .timeout(WRITE_TIMEOUT)
+                    .set("Content-Type", "text/plain; charset=utf-8");
+                if let Some(token) = &self.config.token {
+                    request = request.set("Authorization", &format!("Token {}", token));
+                }
+                request.send_string(&aggregate.influx_line(&self.config.prefix, &self.tags))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Ship an aggregate of the activity recorded during each `interval_secs` window
+/// until the process exits.
+///
+/// Windows end `lag` in the past so key events still waiting in a batched commit
+/// are counted in their own window.
+pub async fn run(emitter: MetricsEmitter, db: DbConnection, lag: Duration) {
+    let period = Duration::from_secs(emitter.config.interval_secs.max(1));
+    let lag = chrono::Duration::from_std(lag).unwrap_or_default();
+    let emitter = std::sync::Arc::new(emitter);
+    let mut interval = tokio::time::interval(period);
+    let mut start = Local::now() - lag;
+
+    // The first tick completes immediately; every later one closes a window
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let end = Local::now() - lag;
+
+        let (db, emitter) = (db.clone(), emitter.clone());
+        let sent = tokio::task::spawn_blocking(move || {
+            let aggregate = Aggregate::capture(&db, start, end)?;
+            emitter.send(&aggregate)
+        })
+        .await;
+
+        if let Ok(Err(e)) = sent {
+            eprintln!("\nFailed to ship metrics: {}", e);
+        }
+        start = end;
+    }
+}
@@ -0,0 +1,95 @@
+pub mod daily_summary;
+pub mod discord;
+#[cfg(feature = "mqtt")]
+pub mod home_assistant;
+pub mod hooks;
+pub mod metrics;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod notify;
+pub mod status_bar;
+pub mod webhooks;
+
+use crate::db::{models::Session, DbConnection};
+use anyhow::Result;
+use chrono::{Duration, Local};
+use serde::Serialize;
+
+/// A point-in-time summary of the current recording session, shared by publishers
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivitySnapshot {
+    pub timestamp: String,
+    /// Keys pressed during the last minute
+    pub keys_last_minute: i64,
+    /// Words (five non-modifier keystrokes) typed during the last minute
+    pub wpm: f64,
+    /// Keys recorded since local midnight
+    pub keys_today: i64,
+    /// Keys recorded since the session started
+    pub session_keys: i64,
+    pub session_minutes: f64,
+}
+
+impl ActivitySnapshot {
+    /// Summarize the session currently being recorded; session values are zero while paused
+    pub fn capture_current(db: &DbConnection) -> Result<Self> {
+        match Session::current(db)? {
+            Some(session) => Self::capture(db, &session),
+            None => {
+                let mut snapshot = Self::capture(db, &Session::new())?;
+                snapshot.session_minutes = 0.0;
+                Ok(snapshot)
+            }
+        }
+    }
+
+    /// Summarize the session's recent activity from the database
+    pub fn capture(db: &DbConnection, session: &Session) -> Result<Self> {
+        let now = Local::now();
+        let minute_ago = (now - Duration::minutes(1)).timestamp_millis();
+        db.read(|conn| {
+            let (keys_last_minute, typed_last_minute): (i64, i64) = conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(is_modifier = 0), 0) FROM key_events
+             WHERE session_id = ?1 AND timestamp_ms >= ?2",
+                (session.id, minute_ago),
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+
+            let midnight = now
+                .date_naive()
+                .and_hms_opt(0, 0, 0)
+                .and_then(|t| t.and_local_timezone(Local).earliest())
+                .unwrap_or(now);
+            let keys_today: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM key_events WHERE timestamp_ms >= ?1",
+                [midnight.timestamp_millis()],
+                |row| row.get(0),
+            )?;
+
+            let session_keys: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM key_events WHERE session_id = ?1",
+                [session.id],
+                |row| row.get(0),
+            )?;
+
+            Ok(Self {
+                timestamp: now.to_rfc3339(),
+                keys_last_minute,
+                wpm: typed_last_minute as f64 / 5.0,
+                keys_today,
+                session_keys,
+                session_minutes: (now - session.start_time).num_seconds() as f64 / 60.0,
+            })
+        })
+    }
+}
+
+/// Shorten large counts, e.g. 128k or 1.2M
+pub fn compact(count: i64) -> String {
+    match count {
+        n if n >= 1_000_000 => format!("{:.1}M", n as f64 / 1_000_000.0),
+        n if n >= 10_000 => format!("{:.0}k", n as f64 / 1_000.0),
+        n if n >= 1_000 => format!("{:.1}k", n as f64 / 1_000.0),
+        n => n.to_string(),
+    }
+}
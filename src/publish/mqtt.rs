@@ -0,0 +1,119 @@
+use crate::config::MqttConfig;
+use crate::db::DbConnection;
+use crate::publish::home_assistant;
+use crate::publish::ActivitySnapshot;
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use std::time::Duration;
+
+/// Publish an activity snapshot to the broker every `interval_secs` until the process exits.
+///
+/// The full snapshot goes to `<topic>/summary` as JSON and each value to its own
+/// `<topic>/<field>` subtopic, for automations that only care about one number.
+/// With Home Assistant integration enabled, the values are also announced as sensors
+/// through MQTT discovery whenever the connection is (re)established.
+pub async fn run(config: MqttConfig, db: DbConnection) {
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.as_deref().unwrap_or_default());
+    }
+
+    if config.home_assistant {
+        options.set_last_will(LastWill::new(
+            home_assistant::availability_topic(&config),
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+    }
+
+    let (client, mut eventloop) = AsyncClient::new(options, 32);
+
+    // Drive the connection; rumqttc reconnects on the next poll after an error
+    let announcer = client.clone();
+    let announce_config = config.clone();
+    tokio::spawn(async move {
+        let mut connected = true;
+        loop {
+            match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) if announce_config.home_assistant => {
+                    connected = true;
+                    announce(&announcer, &announce_config);
+                }
+                Ok(_) => connected = true,
+                Err(e) => {
+                    if connected {
+                        eprintln!("\nMQTT connection error: {}", e);
+                        connected = false;
+                    }
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+
+    let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs.max(1)));
+    loop {
+        interval.tick().await;
+
+        let capture_db = db.clone();
+        let snapshot = match tokio::task::spawn_blocking(move || {
+            ActivitySnapshot::capture_current(&capture_db)
+        })
+        .await
+        {
+            Ok(Ok(snapshot)) => snapshot,
+            Ok(Err(e)) => {
+                eprintln!("\nFailed to summarize activity for MQTT: {}", e);
+                continue;
+            }
+            Err(_) => continue,
+        };
+
+        let topic = config.topic.trim_end_matches('/');
+        let mut messages = vec![(
+            format!("{}/summary", topic),
+            serde_json::to_string(&snapshot).unwrap_or_default(),
+        )];
+        messages.extend(
+            [
+                ("keys_last_minute", snapshot.keys_last_minute.to_string()),
+                ("wpm", format!("{:.1}", snapshot.wpm)),
+                ("keys_today", snapshot.keys_today.to_string()),
+                (
+                    "typing_active",
+                    if snapshot.keys_last_minute > 0 {
+                        "ON"
+                    } else {
+                        "OFF"
+                    }
+                    .to_string(),
+                ),
+                ("session_keys", snapshot.session_keys.to_string()),
+                (
+                    "session_minutes",
+                    format!("{:.1}", snapshot.session_minutes),
+                ),
+            ]
+            .map(|(field, value)| (format!("{}/{}", topic, field), value)),
+        );
+
+        for (topic, payload) in messages {
+            // try_publish never blocks the timer when the broker is unreachable
+            let _ = client.try_publish(topic, QoS::AtLeastOnce, config.retain, payload);
+        }
+    }
+}
+
+/// Publish Home Assistant discovery configs and mark kitmap as online, both retained
+fn announce(client: &AsyncClient, config: &MqttConfig) {
+    for (topic, payload) in home_assistant::discovery_messages(config) {
+        let _ = client.try_publish(topic, QoS::AtLeastOnce, true, payload);
+    }
+    let _ = client.try_publish(
+        home_assistant::availability_topic(config),
+        QoS::AtLeastOnce,
+        true,
+        "online",
+    );
+}
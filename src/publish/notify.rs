@@ -0,0 +1,53 @@
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Show a desktop notification without waiting for it to be delivered.
+///
+/// Uses `notify-send` on Linux, AppleScript on macOS and a PowerShell balloon tip on
+/// Windows. Failures are reported but never stop recording.
+pub fn desktop(title: &str, body: &str) {
+    let mut command = notification_command(title, body);
+    thread::spawn(move || {
+        let status = command.stdout(Stdio::null()).stderr(Stdio::null()).status();
+        if !status.is_ok_and(|s| s.success()) {
+            eprintln!("\nFailed to show a desktop notification");
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(title: &str, body: &str) -> Command {
+    let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(format!(
+        "display notification \"{}\" with title \"{}\"",
+        quote(body),
+        quote(title)
+    ));
+    command
+}
+
+#[cfg(windows)]
+fn notification_command(title: &str, body: &str) -> Command {
+    let quote = |text: &str| text.replace('\'', "''");
+    let script = format!(
+        "Add-Type -AssemblyName System.Windows.Forms; \
+         $n = New-Object System.Windows.Forms.NotifyIcon; \
+         $n.Icon = [System.Drawing.SystemIcons]::Information; \
+         $n.Visible = $true; \
+         $n.ShowBalloonTip(10000, '{}', '{}', 'Info'); \
+         Start-Sleep -Seconds 10; $n.Dispose()",
+        quote(title),
+        quote(body)
+    );
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-Command", &script]);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn notification_command(title: &str, body: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name=kitmap", title, body]);
+    command
+}
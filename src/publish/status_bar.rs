@@ -0,0 +1,123 @@
+use crate::db::models::{LatencyReport, Session};
+use crate::db::time;
+use crate::db::DbConnection;
+use crate::publish::compact;
+use crate::stats::range::local_midnight;
+use anyhow::Result;
+use chrono::{Duration, Local, Timelike};
+use serde_json::json;
+
+/// Text color of the i3blocks block while nothing is being recorded
+const I3BLOCKS_STOPPED_COLOR: &str = "#888888";
+
+/// Status bars kitmap can print a module for
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum StatusFormat {
+    /// JSON for a Waybar custom module with `return-type` set to `json`
+    Waybar,
+    /// One line of text for a Polybar script module
+    Polybar,
+    /// Full text, short text and color lines for an i3blocks block
+    I3blocks,
+}
+
+/// What a status bar shows, read from the per-minute rollup rather than raw key events
+#[derive(Debug, Clone)]
+pub struct StatusSnapshot {
+    pub keys_today: i64,
+    /// Words (five typed keys) per minute over roughly the last minute
+    pub wpm: f64,
+    /// Whether a listener has a session open
+    pub recording: bool,
+    /// Minutes since the open session started
+    pub session_minutes: Option<i64>,
+    /// p99 handling latency of the most recent `--measure-latency` session
+    pub latency_p99_us: Option<i64>,
+}
+
+impl StatusSnapshot {
+    pub fn capture(db: &DbConnection) -> Result<Self> {
+        let now = Local::now();
+        let today = local_midnight(now.date_naive())?;
+        let session = Session::current(db)?;
+        let latency_p99_us = LatencyReport::recent(db, 1)?
+            .first()
+            .map(|report| report.p99_us);
+
+        db.read(|conn| {
+            let keys_today: i64 = conn.query_row(
+                "SELECT COALESCE(SUM(keys), 0) FROM minute_totals WHERE minute >= ?1",
+                [time::minute(&today)],
+                |row| row.get(0),
+            )?;
+
+            // The current minute is partial, so it's counted together with the one before
+            let typed_recently: i64 = conn.query_row(
+                "SELECT COALESCE(SUM(typed_keys), 0) FROM minute_totals
+             WHERE minute >= ?1 AND minute <= ?2",
+                (
+                    time::minute(&(now - Duration::minutes(1))),
+                    time::minute(&now),
+                ),
+                |row| row.get(0),
+            )?;
+            let minutes = 1.0 + now.second() as f64 / 60.0;
+
+            Ok(Self {
+                keys_today,
+                wpm: typed_recently as f64 / 5.0 / minutes,
+                recording: session.is_some(),
+                session_minutes: session.map(|s| (now - s.start_time).num_minutes()),
+                latency_p99_us,
+            })
+        })
+    }
+
+    /// The module's text, e.g. `⌨ 12k · 64 WPM`
+    pub fn text(&self) -> String {
+        if self.recording {
+            format!("⌨ {} · {:.0} WPM", compact(self.keys_today), self.wpm)
+        } else {
+            format!("⌨ {} · off", compact(self.keys_today))
+        }
+    }
+
+    /// Longer description for tooltips
+    pub fn tooltip(&self) -> String {
+        let mut lines = vec![format!("{} keys today", self.keys_today)];
+        match self.session_minutes {
+            Some(minutes) => lines.push(format!("Recording for {} min", minutes)),
+            None => lines.push("Not recording".to_string()),
+        }
+        if let Some(p99) = self.latency_p99_us {
+            lines.push(format!("Handling latency p99: {}µs", p99));
+        }
+        lines.join("\n")
+    }
+
+    pub fn render(&self, format: StatusFormat) -> String {
+        let state = if self.recording {
+            "recording"
+        } else {
+            "stopped"
+        };
+        match format {
+            StatusFormat::Waybar => json!({
+                "text": self.text(),
+                "tooltip": self.tooltip(),
+                "alt": state,
+                "class": state,
+            })
+            .to_string(),
+            StatusFormat::Polybar => self.text(),
+            StatusFormat::I3blocks => {
+                let short = format!("⌨ {}", compact(self.keys_today));
+                if self.recording {
+                    format!("{}\n{}", self.text(), short)
+                } else {
+                    format!("{}\n{}\n{}", self.text(), short, I3BLOCKS_STOPPED_COLOR)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use crate::config::HookConfig;
+use crate::config::{MilestoneConfig, MilestoneKind, WebhookConfig};
+use crate::db::DbConnection;
+use crate::publish::{hooks, ActivitySnapshot};
+use crate::stats::goals::{self, GoalProgress};
+use anyhow::Result;
+use chrono::Local;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// How long a webhook may take to respond before it is given up on
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A milestone announced to webhooks
+#[derive(Debug, Clone, Serialize)]
+pub struct Milestone {
+    pub event: MilestoneKind,
+    pub message: String,
+    pub value: f64,
+    pub timestamp: String,
+}
+
+impl Milestone {
+    fn new(event: MilestoneKind, message: String, value: f64) -> Self {
+        Self {
+            event,
+            message,
+            value,
+            timestamp: Local::now().to_rfc3339(),
+        }
+    }
+
+    /// Announce that recording started
+    pub fn session_started(snapshot: &ActivitySnapshot) -> Self {
+        Self::new(
+            MilestoneKind::SessionStarted,
+            format!(
+                "Typing session started with {} keys so far today",
+                snapshot.keys_today
+            ),
+            snapshot.keys_today as f64,
+        )
+    }
+
+    /// Announce the end of a recording session
+    pub fn session_ended(snapshot: &ActivitySnapshot) -> Self {
+        Self::new(
+            MilestoneKind::SessionEnded,
+            format!(
+                "Typing session ended: {} keys in {:.0} minutes",
+                snapshot.session_keys, snapshot.session_minutes
+            ),
+            snapshot.session_keys as f64,
+        )
+    }
+
+    /// Announce that a goal was met for its current period
+    fn goal_reached(goal: &GoalProgress) -> Self {
+        Self::new(
+            MilestoneKind::GoalReached,
+            format!(
+                "Reached the {} goal of {} {}",
+                goal.period.as_str(),
+                goal.metric.format(goal.target),
+                goal.metric.label()
+            ),
+            goal.current,
+        )
+    }
+
+    /// The milestone's `snake_case` name, e.g. `max_wpm`
+    pub fn event_name(&self) -> String {
+        serde_json::to_value(self.event)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    /// The value as text, without decimals when it is a whole number
+    pub fn value_text(&self) -> String {
+        if self.value.fract() == 0.0 {
+            format!("{:.0}", self.value)
+        } else {
+            format!("{:.1}", self.value)
+        }
+    }
+
+    /// Render the request body, filling `{{placeholders}}` in a custom template
+    fn payload(&self, template: Option<&str>) -> String {
+        let Some(template) = template else {
+            return serde_json::to_string(self).unwrap_or_default();
+        };
+
+        let event = self.event_name();
+        let value = self.value_text();
+
+        // Values are escaped so they can sit inside JSON strings in the template
+        template
+            .replace("{{event}}", &json_escape(&event))
+            .replace("{{message}}", &json_escape(&self.message))
+            .replace("{{value}}", &value)
+            .replace("{{timestamp}}", &json_escape(&self.timestamp))
+    }
+}
+
+/// Escape text for use inside a JSON string literal
+fn json_escape(text: &str) -> String {
+    let quoted = serde_json::to_string(text).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Detects milestones by comparing all-time totals against those seen at the last check
+pub struct MilestoneTracker {
+    total_keys_every: i64,
+    total_keys_reached: i64,
+    max_wpm: f64,
+    /// Goals met in their current period, by metric, period and the period's first day
+    goals_met: HashSet<String>,
+}
+
+impl MilestoneTracker {
+    /// Start tracking from the current totals, so milestones already passed are not re-announced
+    pub fn new(db: &DbConnection, config: &MilestoneConfig) -> Result<Self> {
+        let total_keys_every = config.total_keys_every.max(1);
+        let (total_keys, max_wpm) = current_totals(db)?;
+        let goals_met = db
+            .read(goals::progress)?
+            .iter()
+            .filter(|goal| goal.met)
+            .map(goal_key)
+            .collect();
+        Ok(Self {
+            total_keys_every,
+            total_keys_reached: total_keys / total_keys_every,
+            max_wpm,
+            goals_met,
+        })
+    }
+
+    pub fn check(&mut self, db: &DbConnection) -> Result<Vec<Milestone>> {
+        let (total_keys, max_wpm) = current_totals(db)?;
+        let mut milestones = Vec::new();
+
+        let reached = total_keys / self.total_keys_every;
+        if reached > self.total_keys_reached {
+            self.total_keys_reached = reached;
+            let keys = reached * self.total_keys_every;
+            milestones.push(Milestone::new(
+                MilestoneKind::TotalKeys,
+                format!("Reached {} total keys", keys),
+                keys as f64,
+            ));
+        }
+
+        if max_wpm > self.max_wpm {
+            // The first sample ever recorded is a baseline, not a personal best
+            if self.max_wpm > 0.0 {
+                milestones.push(Milestone::new(
+                    MilestoneKind::MaxWpm,
+                    format!(
+                        "New max typing speed: {:.0} WPM (previous best {:.0} WPM)",
+                        max_wpm, self.max_wpm
+                    ),
+                    max_wpm,
+                ));
+            }
+            self.max_wpm = max_wpm;
+        }
+
+        for goal in db.read(goals::progress)? {
+            if goal.met && self.goals_met.insert(goal_key(&goal)) {
+                milestones.push(Milestone::goal_reached(&goal));
+            }
+        }
+
+        Ok(milestones)
+    }
+}
+
+fn goal_key(goal: &GoalProgress) -> String {
+    format!(
+        "{}:{}:{}",
+        goal.metric.as_str(),
+        goal.period.as_str(),
+        goal.period_start
+    )
+}
+
+/// All-time key count and best typing speed in words (five keystrokes) per minute
+fn current_totals(db: &DbConnection) -> Result<(i64, f64)> {
+    db.read(|conn| {
+        let total_keys: i64 =
+            conn.query_row("SELECT COUNT(*) FROM key_events", [], |row| row.get(0))?;
+        let max_cpm: f64 = conn.query_row(
+            "SELECT COALESCE(MAX(chars_per_minute), 0.0) FROM typing_samples",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok((total_keys, max_cpm / 5.0))
+    })
+}
+
+/// POST a milestone to every webhook subscribed to it, logging failures.
+///
+/// This blocks until every webhook has responded or timed out.
+pub fn send(webhooks: &[WebhookConfig], milestone: &Milestone) {
+    let agent = ureq::AgentBuilder::new().timeout(WEBHOOK_TIMEOUT).build();
+
+    for webhook in webhooks {
+        if !webhook.events.is_empty() && !webhook.events.contains(&milestone.event) {
+            continue;
+        }
+
+        let mut request = agent
+            .post(&webhook.url)
+            .set("Content-Type", "application/json");
+        for (name, value) in &webhook.headers {
+            request = request.set(name, value);
+        }
+
+        if let Err(e) = request.send_string(&milestone.payload(webhook.payload.as_deref())) {
+            eprintln!("\nWebhook {} failed: {}", webhook.url, e);
+        }
+    }
+}
+
+/// Check for milestones every `check_interval_secs` and notify webhooks and run hooks
+/// until the process exits
+pub async fn run(
+    webhooks: Vec<WebhookConfig>,
+    hooks: Vec<HookConfig>,
+    config: MilestoneConfig,
+    db: DbConnection,
+) {
+    let tracker_db = db.clone();
+    let tracker_config = config.clone();
+    let mut tracker = match tokio::task::spawn_blocking(move || {
+        MilestoneTracker::new(&tracker_db, &tracker_config)
+    })
+    .await
+    {
+        Ok(Ok(tracker)) => tracker,
+        Ok(Err(e)) => {
+            eprintln!("\nFailed to start milestone tracking: {}", e);
+            return;
+        }
+        Err(_) => return,
+    };
+
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(config.check_interval_secs.max(1)));
+    // The first tick completes immediately; the baseline was just taken
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        let check_db = db.clone();
+        let webhooks = webhooks.clone();
+        let hooks = hooks.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let milestones = tracker.check(&check_db);
+            if let Ok(milestones) = &milestones {
+                for milestone in milestones {
+                    hooks::run_current(&hooks, milestone, &check_db);
+                    send(&webhooks, milestone);
+                }
+            }
+            (tracker, milestones)
+        })
+        .await;
+
+        match result {
+            Ok((returned, milestones)) => {
+                tracker = returned;
+                if let Err(e) = milestones {
+                    eprintln!("\nFailed to check milestones: {}", e);
+                }
+            }
+            Err(_) => return,
+        }
+    }
+}
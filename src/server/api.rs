@@ -0,0 +1,536 @@
+use crate::db::export::{write_export, DynamicsConsent, ExportData, ExportFormat, ExportOptions};
+use crate::server::{auth, AppState};
+use crate::stats::blocks::DEFAULT_BLOCK_GAP_MINUTES;
+use crate::stats::calculator::HeatmapMetric;
+use crate::stats::range::TimeRange;
+use crate::stats::replay::ReplayStep;
+use crate::stats::{StatsCalculator, StatsFilter};
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use chrono::NaiveDate;
+use futures_util::stream::{self, Stream, StreamExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+/// How often the live stream checks the database for new key events
+const LIVE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the key stream checks the database for new key presses
+const KEY_STREAM_POLL_INTERVAL: Duration = Duration::from_millis(150);
+
+/// How often the key stream sends updated rates while no keys are pressed
+const KEY_STREAM_IDLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most keys sent in a single key stream event
+const KEY_STREAM_BATCH: usize = 50;
+
+/// Size an export is sent in, as it is written
+const EXPORT_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Export chunks written ahead of a client that reads them slowly
+const EXPORT_CHUNKS_AHEAD: usize = 4;
+
+/// Default number of rows returned by list endpoints
+pub(crate) const DEFAULT_LIMIT: usize = 20;
+
+/// Upper bound on rows returned by list endpoints
+pub(crate) const MAX_LIMIT: usize = 1000;
+
+pub async fn get_stats(State(state): State<Arc<AppState>>) -> Response {
+    match tokio::task::spawn_blocking(move || state.current_stats()).await {
+        Ok(Ok((_, stats))) => Json(stats).into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Push fresh stats to the browser over SSE whenever new key events are recorded
+pub async fn stream_stats(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let mut ticker = interval(LIVE_POLL_INTERVAL);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let updates = stream::unfold(
+        (state, ticker, None),
+        |(state, mut ticker, last_seen): (Arc<AppState>, Interval, Option<i64>)| async move {
+            loop {
+                ticker.tick().await;
+
+                let app_state = state.clone();
+                let update = tokio::task::spawn_blocking(move || app_state.current_stats()).await;
+
+                match update {
+                    Ok(Ok((latest_id, _))) if last_seen == Some(latest_id) => continue,
+                    Ok(Ok((latest_id, stats))) => {
+                        let event = Event::default().event("stats").json_data(&stats);
+                        return Some((event, (state, ticker, Some(latest_id))));
+                    }
+                    Ok(Err(e)) => eprintln!("Failed to refresh live stats: {}", e),
+                    Err(e) => eprintln!("Live stats task failed: {}", e),
+                }
+            }
+        },
+    );
+
+    Sse::new(updates).keep_alive(KeepAlive::default())
+}
+
+/// Push each key press, with live keys-per-second and WPM rates, as it is recorded.
+/// Only keys pressed after the client connects are sent.
+pub async fn stream_keys(State(state): State<Arc<AppState>>) -> Response {
+    if let Some(refused) = auth::require_configured_token(&state, "The live key stream") {
+        return refused;
+    }
+    let mut ticker = interval(KEY_STREAM_POLL_INTERVAL);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let updates = stream::unfold(
+        (state, ticker, None, Instant::now()),
+        |(state, mut ticker, mut last_id, last_sent): (
+            Arc<AppState>,
+            Interval,
+            Option<i64>,
+            Instant,
+        )| async move {
+            loop {
+                ticker.tick().await;
+
+                let app_state = state.clone();
+                let update = tokio::task::spawn_blocking(move || {
+                    let calculator = app_state.calculator();
+                    let after_id = match last_id {
+                        Some(id) => id,
+                        None => calculator.latest_event_id()?,
+                    };
+                    calculator.live_keys(after_id, KEY_STREAM_BATCH)
+                })
+                .await;
+
+                match update {
+                    Ok(Ok(live)) => {
+                        last_id = Some(live.last_id);
+                        // Keep the rates decaying on screen even while nothing is typed
+                        if live.keys.is_empty() && last_sent.elapsed() < KEY_STREAM_IDLE_INTERVAL {
+                            continue;
+                        }
+                        let event = Event::default().event("keys").json_data(&live);
+                        return Some((event, (state, ticker, last_id, Instant::now())));
+                    }
+                    Ok(Err(e)) => eprintln!("Failed to read live keys: {}", e),
+                    Err(e) => eprintln!("Live keys task failed: {}", e),
+                }
+            }
+        },
+    );
+
+    Sse::new(updates)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RangeQuery {
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+}
+
+impl RangeQuery {
+    fn range(&self) -> anyhow::Result<TimeRange> {
+        TimeRange::parse(self.since.as_deref(), self.until.as_deref())
+    }
+
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HourlyQuery {
+    date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LimitQuery {
+    limit: Option<usize>,
+}
+
+/// `GET /api/keys?since=&until=&limit=`
+pub async fn get_keys(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RangeQuery>,
+) -> Response {
+    let range = match query.range() {
+        Ok(range) => range,
+        Err(e) => return bad_request(e),
+    };
+    let limit = query.limit();
+
+    run_query(state, move |calc| {
+        calc.top_keys(&StatsFilter::from(range), limit)
+    })
+    .await
+}
+
+/// `GET /api/combos?since=&until=&limit=`
+pub async fn get_combos(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RangeQuery>,
+) -> Response {
+    let range = match query.range() {
+        Ok(range) => range,
+        Err(e) => return bad_request(e),
+    };
+    let limit = query.limit();
+
+    run_query(state, move |calc| {
+        calc.top_combos(&StatsFilter::from(range), limit)
+    })
+    .await
+}
+
+/// `GET /api/chords?since=&until=&limit=`
+pub async fn get_chords(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RangeQuery>,
+) -> Response {
+    let range = match query.range() {
+        Ok(range) => range,
+        Err(e) => return bad_request(e),
+    };
+    let limit = query.limit();
+
+    run_query(state, move |calc| {
+        calc.top_chords(&StatsFilter::from(range), limit)
+    })
+    .await
+}
+
+/// `GET /api/hourly?date=YYYY-MM-DD`, covering all recorded days when no date is given
+pub async fn get_hourly(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HourlyQuery>,
+) -> Response {
+    let range = match query.date.as_deref() {
+        Some(date) => {
+            let parsed = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| anyhow::anyhow!("Invalid date '{}': expected YYYY-MM-DD", date))
+                .and_then(TimeRange::day);
+            match parsed {
+                Ok(range) => range,
+                Err(e) => return bad_request(e),
+            }
+        }
+        None => TimeRange::default(),
+    };
+
+    run_query(state, move |calc| calc.hourly(&StatsFilter::from(range))).await
+}
+
+/// `GET /api/sessions?limit=`
+pub async fn get_sessions(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LimitQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    run_query(state, move |calc| {
+        calc.recent_sessions(&StatsFilter::default(), limit)
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HeatmapQuery {
+    #[serde(default)]
+    metric: HeatmapMetric,
+    since: Option<String>,
+    until: Option<String>,
+}
+
+/// `GET /api/heatmap?metric=count|avg_hour&since=&until=`
+pub async fn get_heatmap(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HeatmapQuery>,
+) -> Response {
+    let range = match TimeRange::parse(query.since.as_deref(), query.until.as_deref()) {
+        Ok(range) => range,
+        Err(e) => return bad_request(e),
+    };
+    let metric = query.metric;
+
+    run_query(state, move |calc| {
+        calc.key_heatmap(metric, &StatsFilter::from(range))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayQuery {
+    #[serde(default)]
+    step: ReplayStep,
+    since: Option<String>,
+    until: Option<String>,
+}
+
+/// `GET /api/replay?step=day|hour&since=&until=`
+pub async fn get_replay(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ReplayQuery>,
+) -> Response {
+    let range = match TimeRange::parse(query.since.as_deref(), query.until.as_deref()) {
+        Ok(range) => range,
+        Err(e) => return bad_request(e),
+    };
+    let step = query.step;
+
+    run_query(state, move |calc| {
+        calc.replay(&StatsFilter::from(range), step)
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeasonalityQuery {
+    /// Number of most pressed keys listed per month
+    top: Option<usize>,
+}
+
+/// `GET /api/seasonality?top=`
+pub async fn get_seasonality(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SeasonalityQuery>,
+) -> Response {
+    let top = query.top.unwrap_or(5).min(MAX_LIMIT);
+
+    run_query(state, move |calc| {
+        calc.seasonality(&StatsFilter::default(), top)
+    })
+    .await
+}
+
+/// `GET /api/rolls`
+pub async fn get_rolls(State(state): State<Arc<AppState>>) -> Response {
+    run_query(state, |calc| calc.roll_analysis(&StatsFilter::default())).await
+}
+
+/// `GET /api/sequences?limit=`
+pub async fn get_sequences(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LimitQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    run_query(state, move |calc| {
+        calc.key_sequences(&StatsFilter::default(), limit)
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScoreQuery {
+    days: Option<usize>,
+}
+
+/// `GET /api/score?days=`
+pub async fn get_score(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ScoreQuery>,
+) -> Response {
+    let days = query.days.map(|d| d.clamp(1, MAX_LIMIT));
+
+    run_query(state, move |calc| {
+        calc.daily_scores(&StatsFilter::default(), days)
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpeedQuery {
+    /// Number of weeks, ending today
+    weeks: Option<usize>,
+}
+
+/// `GET /api/speed?weeks=`
+pub async fn get_speed(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SpeedQuery>,
+) -> Response {
+    let weeks = query.weeks.unwrap_or(4).clamp(1, MAX_LIMIT);
+
+    run_query(state, move |calc| {
+        calc.daily_speed(&StatsFilter::default(), weeks)
+    })
+    .await
+}
+
+/// `GET /api/calendar`
+pub async fn get_calendar(State(state): State<Arc<AppState>>) -> Response {
+    run_query(state, |calc| {
+        calc.activity_calendar(&StatsFilter::default())
+    })
+    .await
+}
+
+/// Endpoints the server refuses depending on how it was started, so clients can hide them
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Capabilities {
+    /// Whether `export` serves `data=keys`, which needs the dashboard to require a token
+    pub key_export: bool,
+    /// Whether `keys/stream` sends key presses, which needs the dashboard to require a token
+    pub key_stream: bool,
+}
+
+/// `GET /api/capabilities`
+pub async fn get_capabilities(State(state): State<Arc<AppState>>) -> Response {
+    let token_required = state.token().is_some();
+    Json(Capabilities {
+        key_export: token_required,
+        key_stream: token_required,
+    })
+    .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    format: ExportFormat,
+    #[serde(default)]
+    data: ExportData,
+    /// Time span as `START..END` or just `START`
+    range: Option<String>,
+    /// Idle minutes that end an activity block
+    gap: Option<i64>,
+}
+
+/// `GET /api/export?format=csv|json|ics&data=keys|combos|blocks&range=&gap=`
+pub async fn get_export(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    let range = match query.range.as_deref().map(TimeRange::parse_span) {
+        Some(Ok(range)) => range,
+        Some(Err(e)) => return bad_request(e),
+        None => TimeRange::default(),
+    };
+    let options = ExportOptions {
+        data: query.data,
+        format: query.format,
+        range,
+        block_gap_minutes: query.gap.unwrap_or(DEFAULT_BLOCK_GAP_MINUTES),
+        dynamics_consent: DynamicsConsent::default(),
+    };
+    if let Err(e) = options.validate() {
+        return bad_request(e);
+    }
+    if options.data == ExportData::Keys {
+        if let Some(refused) = auth::require_configured_token(&state, "The key press export") {
+            return refused;
+        }
+    }
+    let (format, data) = (options.format, options.data);
+
+    // Rows are written off the async runtime and sent on as they come, so large exports
+    // aren't held in memory. A failure after the first chunk cuts the download short.
+    let (chunks, mut received) = mpsc::channel(EXPORT_CHUNKS_AHEAD);
+    tokio::task::spawn_blocking(move || {
+        let mut out = ChunkWriter::new(chunks.clone());
+        let written = state.db.read(|conn| {
+            write_export(conn, &options, &mut out)?;
+            out.flush()?;
+            Ok(())
+        });
+        if let Err(e) = written {
+            let _ = chunks.blocking_send(Err(io::Error::other(e.to_string())));
+        }
+    });
+
+    let first = match received.recv().await {
+        Some(Err(e)) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        first => first,
+    };
+    let rest = stream::unfold(received, |mut received| async move {
+        received.recv().await.map(|chunk| (chunk, received))
+    });
+
+    let disposition = format!(
+        "attachment; filename=\"kitmap-{}.{}\"",
+        data.name(),
+        format.extension()
+    );
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(stream::iter(first).chain(rest)),
+    )
+        .into_response()
+}
+
+/// Collects an export into chunks sent to the response body
+struct ChunkWriter {
+    chunks: mpsc::Sender<io::Result<Vec<u8>>>,
+    buffer: Vec<u8>,
+}
+
+impl ChunkWriter {
+    fn new(chunks: mpsc::Sender<io::Result<Vec<u8>>>) -> Self {
+        Self {
+            chunks,
+            buffer: Vec::with_capacity(EXPORT_CHUNK_BYTES),
+        }
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= EXPORT_CHUNK_BYTES {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Send the buffered bytes, waiting while the client is behind
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buffer, Vec::with_capacity(EXPORT_CHUNK_BYTES));
+        self.chunks.blocking_send(Ok(chunk)).map_err(|_| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "The export download was closed")
+        })
+    }
+}
+
+/// Run a blocking stats query off the async runtime and serialize its result
+async fn run_query<T, F>(state: Arc<AppState>, query: F) -> Response
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce(&StatsCalculator) -> anyhow::Result<T> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(move || query(&state.calculator())).await {
+        Ok(Ok(result)) => Json(result).into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+fn bad_request(e: anyhow::Error) -> Response {
+    (StatusCode::BAD_REQUEST, e.to_string()).into_response()
+}
@@ -0,0 +1,78 @@
+use crate::server::AppState;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Response},
+};
+use include_dir::{include_dir, Dir};
+use std::path::Component;
+use std::sync::Arc;
+
+// Embed the web dist directory into the binary
+static WEB_DIST: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/web/dist");
+
+// Minimal live key display for use as an OBS browser source
+static OVERLAY_HTML: &str = include_str!("overlay.html");
+
+/// Transparent page showing recently pressed keys and live rates, for streaming overlays.
+/// The key stream it reads needs a token, so without one it only shows how to add it.
+pub async fn serve_overlay() -> Html<&'static str> {
+    Html(OVERLAY_HTML)
+}
+
+pub async fn serve_index(State(state): State<Arc<AppState>>) -> Response {
+    match load_asset(&state, "index.html").await {
+        Some(contents) => Html(contents).into_response(),
+        None => (StatusCode::NOT_FOUND, "Index not found").into_response(),
+    }
+}
+
+pub async fn serve_static(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<String>,
+) -> Response {
+    let content_type = if path.ends_with(".js") {
+        "application/javascript"
+    } else if path.ends_with(".css") {
+        "text/css"
+    } else if path.ends_with(".svg") {
+        "image/svg+xml"
+    } else if path.ends_with(".png") {
+        "image/png"
+    } else if path.ends_with(".ico") {
+        "image/x-icon"
+    } else {
+        "application/octet-stream"
+    };
+
+    let asset_path = format!("assets/{}", path);
+
+    match load_asset(&state, &asset_path).await {
+        Some(contents) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, content_type)],
+            contents,
+        )
+            .into_response(),
+        None => (StatusCode::NOT_FOUND, "Not Found").into_response(),
+    }
+}
+
+/// Read an asset from the override directory when one is set, otherwise from the
+/// embedded bundle. Override files are read on every request so frontend changes
+/// show up without restarting.
+async fn load_asset(state: &AppState, path: &str) -> Option<Vec<u8>> {
+    match &state.assets_dir {
+        Some(dir) => {
+            let relative = std::path::Path::new(path);
+            let inside_dir = relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+            if !inside_dir {
+                return None;
+            }
+            tokio::fs::read(dir.join(relative)).await.ok()
+        }
+        None => WEB_DIST.get_file(path).map(|file| file.contents().to_vec()),
+    }
+}
@@ -0,0 +1,121 @@
+use crate::server::AppState;
+use axum::{
+    extract::{Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Name of the cookie used to remember a token passed in the query string
+const TOKEN_COOKIE: &str = "kitmap_token";
+
+/// Reject requests that don't carry the configured token.
+///
+/// The token is accepted as a bearer token, as the password of HTTP basic auth,
+/// in a `token` query parameter, or in the cookie set after a successful query login.
+pub async fn require_token(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(expected) = state.token() else {
+        return next.run(req).await;
+    };
+
+    // Decoded like any other query parameter, so tokens with reserved characters match
+    let from_query = Query::<HashMap<String, String>>::try_from_uri(req.uri())
+        .ok()
+        .and_then(|Query(mut params)| params.remove("token"));
+
+    let query_valid = from_query
+        .as_deref()
+        .is_some_and(|t| tokens_match(t, expected));
+
+    if !query_valid && !headers_carry_token(req.headers(), expected) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"kitmap\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    }
+
+    let mut response = next.run(req).await;
+    if query_valid {
+        let cookie = format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Strict",
+            TOKEN_COOKIE, expected
+        );
+        if let Ok(value) = cookie.parse() {
+            response.headers_mut().insert(header::SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+/// Refuse endpoints that send raw key presses, which could be read back as typed text,
+/// unless the dashboard requires a token
+pub fn require_configured_token(state: &AppState, what: &str) -> Option<Response> {
+    if state.token().is_some() {
+        return None;
+    }
+    Some(
+        (
+            StatusCode::FORBIDDEN,
+            format!(
+                "{} is only served when the dashboard requires a token; restart it with --token",
+                what
+            ),
+        )
+            .into_response(),
+    )
+}
+
+/// Check the Authorization and Cookie headers for the expected token
+fn headers_carry_token(headers: &HeaderMap, expected: &str) -> bool {
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+
+    if let Some(auth) = authorization {
+        if let Some(bearer) = auth.strip_prefix("Bearer ") {
+            if tokens_match(bearer.trim(), expected) {
+                return true;
+            }
+        }
+
+        if let Some(basic) = auth.strip_prefix("Basic ") {
+            let credentials = BASE64
+                .decode(basic.trim())
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok());
+
+            if let Some((_, password)) = credentials.as_deref().and_then(|c| c.split_once(':')) {
+                if tokens_match(password, expected) {
+                    return true;
+                }
+            }
+        }
+    }
+
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|c| c.trim().split_once('='))
+        .any(|(name, value)| name == TOKEN_COOKIE && tokens_match(value, expected))
+}
+
+/// Compare tokens in constant time to avoid leaking how much of a guess was right
+pub(crate) fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
@@ -0,0 +1,224 @@
+//! The control socket `kitmap listen` serves next to the database, through which
+//! `kitmap pause`, `resume`, `session` and `status` reach the running listener.
+//!
+//! Each request and reply is a line of JSON, e.g. `{"command":"pause"}` answered by
+//! `{"status":{...}}` or `{"error":"..."}`.
+
+use crate::db::get_socket_path;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// How long a command waits for the listener to answer. Ending a session waits for
+/// queued writes, so this is generous.
+#[cfg_attr(not(unix), allow(dead_code))]
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether this process serves the socket, and so removes it when it stops
+static SERVING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    Pause,
+    Resume,
+    /// End the current session and start another
+    NewSession,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Reply {
+    Status(ListenerStatus),
+    Error(String),
+}
+
+/// What the running listener is doing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerStatus {
+    pub pid: u32,
+    pub recording: bool,
+    /// Why recording is paused: `requested`, `screen_locked` or `asleep`
+    pub paused_because: Vec<String>,
+    /// The open session; none while paused
+    pub session: Option<SessionStatus>,
+    /// Keys recorded since the listener started
+    pub keys_recorded: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStatus {
+    pub id: Option<i64>,
+    pub started: DateTime<Local>,
+    pub keys: u64,
+}
+
+impl ListenerStatus {
+    /// Why recording is paused, for people, e.g. `screen locked, kitmap pause`
+    pub fn pause_causes(&self) -> String {
+        self.paused_because
+            .iter()
+            .map(|reason| match reason.as_str() {
+                "requested" => "kitmap pause",
+                "screen_locked" => "screen locked",
+                "asleep" => "computer asleep",
+                other => other,
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The running listener, as the control socket and the APIs built on it control it.
+/// Calls may block on the database.
+pub trait Recorder: Send + Sync + 'static {
+    fn status(&self) -> ListenerStatus;
+    /// Stop recording until `resume` is called
+    fn pause(&self) -> Result<()>;
+    fn resume(&self) -> Result<()>;
+    /// End the current session and start another right away
+    fn new_session(&self) -> Result<()>;
+}
+
+/// Answer a request, replying with the listener's status once it is carried out
+fn answer(recorder: &impl Recorder, request: Request) -> Reply {
+    let result = match request {
+        Request::Status => Ok(()),
+        Request::Pause => recorder.pause(),
+        Request::Resume => recorder.resume(),
+        Request::NewSession => recorder.new_session(),
+    };
+    match result {
+        Ok(()) => Reply::Status(recorder.status()),
+        Err(e) => Reply::Error(format!("{:#}", e)),
+    }
+}
+
+/// Serve the control socket until the process exits. Fails if another listener already
+/// serves it, or on platforms without Unix domain sockets.
+pub fn spawn(recorder: impl Recorder) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use anyhow::Context;
+
+        let path = get_socket_path()?;
+        if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+            bail!("another listener already serves {}", path.display());
+        }
+        // Left behind by a listener that was killed
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).context("Failed to remove the old control socket"),
+        }
+        let listener = tokio::net::UnixListener::bind(&path)
+            .with_context(|| format!("Failed to listen on {}", path.display()))?;
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        SERVING.store(true, Ordering::SeqCst);
+        tokio::spawn(serve(listener, std::sync::Arc::new(recorder)));
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = recorder;
+        bail!("control commands need Unix domain sockets")
+    }
+}
+
+#[cfg(unix)]
+async fn serve<R: Recorder>(listener: tokio::net::UnixListener, recorder: std::sync::Arc<R>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let recorder = recorder.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = converse(stream, recorder).await {
+                        eprintln!("\nControl socket connection failed: {:#}", e);
+                    }
+                });
+            }
+            Err(e) => {
+                eprintln!("\nControl socket stopped: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+/// Answer each request on a connection until the command hangs up
+#[cfg(unix)]
+async fn converse<R: Recorder>(
+    stream: tokio::net::UnixStream,
+    recorder: std::sync::Arc<R>,
+) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let reply = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let recorder = recorder.clone();
+                tokio::task::spawn_blocking(move || answer(&*recorder, request)).await?
+            }
+            Err(e) => Reply::Error(format!("Invalid request: {}", e)),
+        };
+        let mut line = serde_json::to_string(&reply)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Delete the control socket, if this process serves it
+pub fn remove_socket() {
+    if !SERVING.load(Ordering::SeqCst) {
+        return;
+    }
+    if let Ok(path) = get_socket_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Send a request to the running listener and return its status afterwards, or `None`
+/// when no listener serves the control socket
+pub fn send(request: Request) -> Result<Option<ListenerStatus>> {
+    #[cfg(unix)]
+    {
+        use anyhow::Context;
+        use std::io::{BufRead, BufReader, ErrorKind, Write};
+
+        let path = get_socket_path()?;
+        let mut stream = match std::os::unix::net::UnixStream::connect(&path) {
+            Ok(stream) => stream,
+            Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+                return Ok(None)
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to connect to {}", path.display()))
+            }
+        };
+        stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        writeln!(stream, "{}", serde_json::to_string(&request)?)?;
+
+        let mut line = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut line)
+            .context("The listener didn't answer")?;
+        match serde_json::from_str(&line).context("The listener sent an invalid reply")? {
+            Reply::Status(status) => Ok(Some(status)),
+            Reply::Error(message) => bail!(message),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = request;
+        Ok(None)
+    }
+}
@@ -0,0 +1,221 @@
+//! The D-Bus service `kitmap listen --dbus` registers on the session bus, so desktop
+//! extensions and scripts can show what kitmap records and pause it.
+//!
+//! The object at `/io/github/twlite/Kitmap` implements `io.github.twlite.Kitmap1`:
+//! read-only properties with the recording state and current stats, `Pause`, `Resume`
+//! and `NewSession` methods, and `SessionStarted` and `SessionEnded` signals.
+
+use crate::db::DbConnection;
+use crate::publish::ActivitySnapshot;
+use crate::server::control::{ListenerStatus, Recorder};
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use std::time::Duration;
+use zbus::object_server::SignalEmitter;
+use zbus::{fdo, interface};
+
+pub const BUS_NAME: &str = "io.github.twlite.Kitmap";
+const OBJECT_PATH: &str = "/io/github/twlite/Kitmap";
+
+/// How often properties are refreshed, and changes signalled
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+struct KitmapObject {
+    recorder: Arc<dyn Recorder>,
+    db: DbConnection,
+    status: ListenerStatus,
+    keys_today: i64,
+    keys_last_minute: i64,
+    wpm: f64,
+}
+
+impl KitmapObject {
+    /// Run a call on the recorder off the async runtime, then refresh the properties
+    async fn control(
+        &mut self,
+        emitter: &SignalEmitter<'_>,
+        call: impl FnOnce(&dyn Recorder) -> Result<()> + Send + 'static,
+    ) -> fdo::Result<()> {
+        let recorder = self.recorder.clone();
+        tokio::task::spawn_blocking(move || call(&*recorder))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?
+            .map_err(|e| fdo::Error::Failed(format!("{:#}", e)))?;
+        self.refresh(emitter)
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("{:#}", e)))
+    }
+
+    /// Read the listener's state and the current stats, signalling what changed
+    async fn refresh(&mut self, emitter: &SignalEmitter<'_>) -> Result<()> {
+        let recorder = self.recorder.clone();
+        let db = self.db.clone();
+        let (status, activity) = tokio::task::spawn_blocking(move || {
+            let status = recorder.status();
+            ActivitySnapshot::capture_current(&db).map(|activity| (status, activity))
+        })
+        .await??;
+        let previous = std::mem::replace(&mut self.status, status);
+
+        let session_id = |status: &ListenerStatus| status.session.as_ref().and_then(|s| s.id);
+        if session_id(&previous) != session_id(&self.status) {
+            if let Some(ended) = &previous.session {
+                if let Some(id) = ended.id {
+                    Self::session_ended(emitter, id, ended.keys).await?;
+                }
+            }
+            if let Some(id) = session_id(&self.status) {
+                Self::session_started(emitter, id).await?;
+            }
+            self.session_id_changed(emitter).await?;
+            self.session_started_at_changed(emitter).await?;
+        }
+        if previous.recording != self.status.recording {
+            self.recording_changed(emitter).await?;
+        }
+        if previous.paused_because != self.status.paused_because {
+            self.paused_because_changed(emitter).await?;
+        }
+        if previous.session.map(|s| s.keys) != self.status.session.as_ref().map(|s| s.keys) {
+            self.session_keys_changed(emitter).await?;
+        }
+        if previous.keys_recorded != self.status.keys_recorded {
+            self.keys_recorded_changed(emitter).await?;
+        }
+
+        if self.keys_today != activity.keys_today {
+            self.keys_today = activity.keys_today;
+            self.keys_today_changed(emitter).await?;
+        }
+        if self.keys_last_minute != activity.keys_last_minute {
+            self.keys_last_minute = activity.keys_last_minute;
+            self.keys_last_minute_changed(emitter).await?;
+        }
+        if self.wpm != activity.wpm {
+            self.wpm = activity.wpm;
+            self.wpm_changed(emitter).await?;
+        }
+        Ok(())
+    }
+}
+
+#[interface(name = "io.github.twlite.Kitmap1")]
+impl KitmapObject {
+    /// Stop recording until `Resume` is called
+    async fn pause(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.control(&emitter, |recorder| recorder.pause()).await
+    }
+
+    /// Undo `Pause`. Recording stays paused while the screen is locked or the computer sleeps.
+    async fn resume(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.control(&emitter, |recorder| recorder.resume()).await
+    }
+
+    /// End the current session and start another
+    async fn new_session(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.control(&emitter, |recorder| recorder.new_session())
+            .await
+    }
+
+    #[zbus(property)]
+    fn recording(&self) -> bool {
+        self.status.recording
+    }
+
+    /// Why recording is paused: `requested`, `screen_locked` or `asleep`
+    #[zbus(property)]
+    fn paused_because(&self) -> Vec<String> {
+        self.status.paused_because.clone()
+    }
+
+    /// Id of the open session; 0 while paused
+    #[zbus(property)]
+    fn session_id(&self) -> i64 {
+        self.status.session.as_ref().and_then(|s| s.id).unwrap_or(0)
+    }
+
+    /// RFC 3339; empty while paused
+    #[zbus(property)]
+    fn session_started_at(&self) -> String {
+        self.status
+            .session
+            .as_ref()
+            .map(|s| s.started.to_rfc3339())
+            .unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    fn session_keys(&self) -> u64 {
+        self.status.session.as_ref().map_or(0, |s| s.keys)
+    }
+
+    /// Keys recorded since the listener started
+    #[zbus(property)]
+    fn keys_recorded(&self) -> u64 {
+        self.status.keys_recorded
+    }
+
+    /// Keys recorded since local midnight
+    #[zbus(property)]
+    fn keys_today(&self) -> i64 {
+        self.keys_today
+    }
+
+    #[zbus(property)]
+    fn keys_last_minute(&self) -> i64 {
+        self.keys_last_minute
+    }
+
+    /// Words (five typed keys) per minute over the last minute
+    #[zbus(property)]
+    fn wpm(&self) -> f64 {
+        self.wpm
+    }
+
+    #[zbus(signal)]
+    async fn session_started(emitter: &SignalEmitter<'_>, id: i64) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn session_ended(emitter: &SignalEmitter<'_>, id: i64, keys: u64) -> zbus::Result<()>;
+}
+
+/// Register the service on the session bus and keep its properties current until the
+/// process exits
+pub async fn serve(recorder: impl Recorder, db: DbConnection) -> Result<()> {
+    let object = KitmapObject {
+        status: recorder.status(),
+        recorder: Arc::new(recorder),
+        db,
+        keys_today: 0,
+        keys_last_minute: 0,
+        wpm: 0.0,
+    };
+    let connection = zbus::connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, object)?
+        .build()
+        .await
+        .context("Failed to register on the session bus")?;
+    let object = connection
+        .object_server()
+        .interface::<_, KitmapObject>(OBJECT_PATH)
+        .await?;
+
+    let mut ticks = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        ticks.tick().await;
+        let emitter = object.signal_emitter();
+        if let Err(e) = object.get_mut().await.refresh(emitter).await {
+            eprintln!("\nFailed to refresh D-Bus properties: {:#}", e);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Endpoints for the Grafana JSON datasource plugin (and the older SimpleJSON plugin).
+//!
+//! Point the datasource at `http://<host>:<port>/api/v1/grafana`.
+
+use crate::server::AppState;
+use crate::stats::range::TimeRange;
+use crate::stats::series::{fill_gaps, SeriesMetric};
+use crate::stats::StatsFilter;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Local, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Bucket width used when Grafana doesn't send an interval
+const DEFAULT_INTERVAL_MS: i64 = 60_000;
+
+/// Upper bound on points returned per series; wider intervals are used beyond it
+const MAX_POINTS: i64 = 10_000;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryRequest {
+    range: QueryRange,
+    interval_ms: Option<i64>,
+    #[serde(default)]
+    targets: Vec<QueryTarget>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueryRange {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueryTarget {
+    target: Option<String>,
+    #[serde(default)]
+    hide: bool,
+}
+
+/// One series in a query response, as Grafana expects it
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct GrafanaSeries {
+    pub target: String,
+    /// `[value, unix_milliseconds]` pairs, oldest first
+    pub datapoints: Vec<(f64, i64)>,
+}
+
+/// `GET /api/grafana/`, used by Grafana to test the datasource connection
+pub async fn health() -> &'static str {
+    "OK"
+}
+
+/// `POST /api/grafana/metrics`, listing the metrics offered in the query editor
+pub async fn metrics() -> Json<Value> {
+    Json(
+        SeriesMetric::ALL
+            .iter()
+            .map(|m| json!({ "label": m.label(), "value": m.name() }))
+            .collect(),
+    )
+}
+
+/// `POST /api/grafana/search`, the SimpleJSON plugin's metric list
+pub async fn search() -> Json<Vec<&'static str>> {
+    Json(SeriesMetric::ALL.iter().map(|m| m.name()).collect())
+}
+
+/// `POST /api/grafana/metric-payload-options`; metrics take no extra options
+pub async fn metric_payload_options() -> Json<Vec<Value>> {
+    Json(Vec::new())
+}
+
+/// `POST /api/grafana/query`, returning one time series per requested metric
+pub async fn query(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<QueryRequest>,
+) -> Response {
+    let mut metrics = Vec::new();
+    for target in request.targets.iter().filter(|t| !t.hide) {
+        let Some(name) = target.target.as_deref().filter(|t| !t.is_empty()) else {
+            continue;
+        };
+        match SeriesMetric::parse(name) {
+            Some(metric) => metrics.push(metric),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown metric '{}'", name),
+                )
+                    .into_response()
+            }
+        }
+    }
+
+    let start = request.range.from.timestamp();
+    let end = request.range.to.timestamp();
+    let span = (end - start).max(1);
+    let interval = (request.interval_ms.unwrap_or(DEFAULT_INTERVAL_MS) / 1000)
+        .max(1)
+        .max(span / MAX_POINTS + 1);
+    let filter = StatsFilter::from(TimeRange {
+        since: Some(request.range.from.with_timezone(&Local)),
+        until: Some(request.range.to.with_timezone(&Local)),
+    });
+
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<GrafanaSeries>> {
+        let calculator = state.calculator();
+        metrics
+            .into_iter()
+            .map(|metric| {
+                let mut points = calculator.time_series(metric, &filter, interval)?;
+                if metric.is_count() {
+                    points = fill_gaps(points, start, end, interval)?;
+                }
+                Ok(GrafanaSeries {
+                    target: metric.name().to_string(),
+                    datapoints: points
+                        .into_iter()
+                        .map(|p| (p.value, p.time * 1000))
+                        .collect(),
+                })
+            })
+            .collect()
+    })
+    .await;
+
+    match result {
+        Ok(Ok(series)) => Json(series).into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
@@ -0,0 +1,162 @@
+use crate::server::AppState;
+use crate::stats::calculator::{ActivityBucket, ComboStats, HourlyStats, KeyStats, SessionStats};
+use crate::stats::range::{TimeBucket, TimeRange};
+use crate::stats::{StatsCalculator, StatsFilter};
+use async_graphql::{
+    http::GraphiQLSource, Context, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject,
+};
+use axum::{
+    response::{Html, IntoResponse},
+    routing::get,
+    Extension, Json, Router,
+};
+use std::sync::Arc;
+
+/// Default number of rows returned by list fields
+const DEFAULT_LIMIT: usize = 20;
+
+/// Upper bound on rows returned by list fields
+const MAX_LIMIT: usize = 1000;
+
+pub type KitmapSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Headline totals across all recorded data
+#[derive(SimpleObject)]
+pub struct Totals {
+    pub total_keys: i64,
+    pub total_combos: i64,
+    pub total_sessions: i64,
+    pub total_time_minutes: f64,
+    pub unique_keys_used: i64,
+    pub average_typing_speed: f64,
+    pub max_typing_speed: f64,
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Headline totals across all recorded data
+    async fn totals(&self, ctx: &Context<'_>) -> async_graphql::Result<Totals> {
+        let state = ctx.data::<Arc<AppState>>()?.clone();
+        let (_, stats) = tokio::task::spawn_blocking(move || state.current_stats()).await??;
+
+        Ok(Totals {
+            total_keys: stats.total_keys,
+            total_combos: stats.total_combos,
+            total_sessions: stats.total_sessions,
+            total_time_minutes: stats.total_time_minutes,
+            unique_keys_used: stats.unique_keys_used,
+            average_typing_speed: stats.average_typing_speed,
+            max_typing_speed: stats.max_typing_speed,
+        })
+    }
+
+    /// Most pressed keys, optionally within a time range
+    async fn keys(
+        &self,
+        ctx: &Context<'_>,
+        since: Option<String>,
+        until: Option<String>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<KeyStats>> {
+        let range = TimeRange::parse(since.as_deref(), until.as_deref())?;
+        let limit = clamp_limit(limit);
+        run_query(ctx, move |calc| {
+            calc.top_keys(&StatsFilter::from(range), limit)
+        })
+        .await
+    }
+
+    /// Most used key combos, optionally within a time range
+    async fn combos(
+        &self,
+        ctx: &Context<'_>,
+        since: Option<String>,
+        until: Option<String>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<ComboStats>> {
+        let range = TimeRange::parse(since.as_deref(), until.as_deref())?;
+        let limit = clamp_limit(limit);
+        run_query(ctx, move |calc| {
+            calc.top_combos(&StatsFilter::from(range), limit)
+        })
+        .await
+    }
+
+    /// Most recent recording sessions, newest first
+    async fn sessions(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<SessionStats>> {
+        let limit = clamp_limit(limit);
+        run_query(ctx, move |calc| {
+            calc.recent_sessions(&StatsFilter::default(), limit)
+        })
+        .await
+    }
+
+    /// Key presses per hour of day, optionally within a time range
+    async fn hourly(
+        &self,
+        ctx: &Context<'_>,
+        since: Option<String>,
+        until: Option<String>,
+    ) -> async_graphql::Result<Vec<HourlyStats>> {
+        let range = TimeRange::parse(since.as_deref(), until.as_deref())?;
+        run_query(ctx, move |calc| calc.hourly(&StatsFilter::from(range))).await
+    }
+
+    /// Key presses grouped into hour, day, week or month buckets
+    async fn activity(
+        &self,
+        ctx: &Context<'_>,
+        bucket: TimeBucket,
+        since: Option<String>,
+        until: Option<String>,
+    ) -> async_graphql::Result<Vec<ActivityBucket>> {
+        let range = TimeRange::parse(since.as_deref(), until.as_deref())?;
+        run_query(ctx, move |calc| {
+            calc.activity(&StatsFilter::from(range), bucket)
+        })
+        .await
+    }
+}
+
+fn clamp_limit(limit: Option<usize>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+/// Run a blocking stats query off the async runtime
+async fn run_query<T, F>(ctx: &Context<'_>, query: F) -> async_graphql::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&StatsCalculator) -> anyhow::Result<T> + Send + 'static,
+{
+    let state = ctx.data::<Arc<AppState>>()?.clone();
+    let result = tokio::task::spawn_blocking(move || query(&state.calculator())).await??;
+    Ok(result)
+}
+
+/// Routes serving the GraphQL endpoint and the GraphiQL explorer at `/graphql`
+pub fn routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let schema: KitmapSchema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .finish();
+
+    Router::new()
+        .route("/graphql", get(graphiql).post(execute))
+        .layer(Extension(schema))
+}
+
+async fn execute(
+    Extension(schema): Extension<KitmapSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
@@ -0,0 +1,293 @@
+//! The gRPC API served by `kitmap listen --grpc`, described by `proto/kitmap/v1/kitmap.proto`.
+//!
+//! The messages below mirror the proto file field for field; keep them in sync.
+
+// tonic requires `Status` as the error of handlers and interceptors, large as it is
+#![allow(clippy::result_large_err)]
+
+use crate::db::DbConnection;
+use crate::server::api::{DEFAULT_LIMIT, MAX_LIMIT};
+use crate::server::auth::tokens_match;
+use crate::server::control::{ListenerStatus, Recorder};
+use crate::stats::range::TimeRange;
+use crate::stats::{StatsCalculator, StatsFilter};
+use anyhow::Result;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/kitmap.v1.Kitmap.rs"));
+}
+
+use generated::kitmap_server::{Kitmap, KitmapServer};
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StatusRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PauseRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ResumeRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StatusReply {
+    #[prost(bool, tag = "1")]
+    pub recording: bool,
+    /// Why recording is paused: `requested`, `screen_locked` or `asleep`
+    #[prost(string, repeated, tag = "2")]
+    pub paused_because: Vec<String>,
+    #[prost(int64, optional, tag = "3")]
+    pub session_id: Option<i64>,
+    /// RFC 3339; empty while paused
+    #[prost(string, tag = "4")]
+    pub session_started: String,
+    #[prost(uint64, tag = "5")]
+    pub session_keys: u64,
+    /// Keys recorded since the listener started
+    #[prost(uint64, tag = "6")]
+    pub keys_recorded: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RangeRequest {
+    #[prost(string, tag = "1")]
+    pub since: String,
+    #[prost(string, tag = "2")]
+    pub until: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SummaryReply {
+    #[prost(int64, tag = "1")]
+    pub total_keys: i64,
+    #[prost(int64, tag = "2")]
+    pub total_combos: i64,
+    #[prost(int64, tag = "3")]
+    pub active_minutes: i64,
+    #[prost(double, tag = "4")]
+    pub average_typing_speed: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TopRequest {
+    #[prost(message, optional, tag = "1")]
+    pub range: Option<RangeRequest>,
+    #[prost(uint32, tag = "2")]
+    pub limit: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct KeyCount {
+    #[prost(string, tag = "1")]
+    pub key_name: String,
+    #[prost(int64, tag = "2")]
+    pub count: i64,
+    #[prost(double, tag = "3")]
+    pub percentage: f64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TopKeysReply {
+    #[prost(message, repeated, tag = "1")]
+    pub keys: Vec<KeyCount>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ComboCount {
+    #[prost(string, tag = "1")]
+    pub combo: String,
+    #[prost(int64, tag = "2")]
+    pub count: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TopCombosReply {
+    #[prost(message, repeated, tag = "1")]
+    pub combos: Vec<ComboCount>,
+}
+
+impl From<ListenerStatus> for StatusReply {
+    fn from(status: ListenerStatus) -> Self {
+        let session = status.session;
+        Self {
+            recording: status.recording,
+            paused_because: status.paused_because,
+            session_id: session.as_ref().and_then(|s| s.id),
+            session_started: session
+                .as_ref()
+                .map(|s| s.started.to_rfc3339())
+                .unwrap_or_default(),
+            session_keys: session.map_or(0, |s| s.keys),
+            keys_recorded: status.keys_recorded,
+        }
+    }
+}
+
+struct KitmapService<R> {
+    recorder: Arc<R>,
+    db: DbConnection,
+}
+
+impl<R: Recorder> KitmapService<R> {
+    /// Run a call on the recorder off the async runtime, then report its status
+    async fn control(
+        &self,
+        call: impl FnOnce(&R) -> Result<()> + Send + 'static,
+    ) -> Result<Response<StatusReply>, Status> {
+        let recorder = self.recorder.clone();
+        tokio::task::spawn_blocking(move || {
+            call(&recorder)?;
+            Ok(recorder.status().into())
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map(Response::new)
+        .map_err(|e: anyhow::Error| Status::internal(e.to_string()))
+    }
+
+    async fn query<T: Send + 'static>(
+        &self,
+        query: impl FnOnce(&StatsCalculator) -> Result<T> + Send + 'static,
+    ) -> Result<T, Status> {
+        let calculator = StatsCalculator::new(self.db.clone());
+        tokio::task::spawn_blocking(move || query(&calculator))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+#[tonic::async_trait]
+impl<R: Recorder> Kitmap for KitmapService<R> {
+    async fn status(
+        &self,
+        _request: Request<StatusRequest>,
+    ) -> Result<Response<StatusReply>, Status> {
+        self.control(|_| Ok(())).await
+    }
+
+    async fn pause(
+        &self,
+        _request: Request<PauseRequest>,
+    ) -> Result<Response<StatusReply>, Status> {
+        self.control(R::pause).await
+    }
+
+    async fn resume(
+        &self,
+        _request: Request<ResumeRequest>,
+    ) -> Result<Response<StatusReply>, Status> {
+        self.control(R::resume).await
+    }
+
+    async fn summary(
+        &self,
+        request: Request<RangeRequest>,
+    ) -> Result<Response<SummaryReply>, Status> {
+        let range = time_range(Some(request.get_ref()))?;
+        let summary = self
+            .query(move |calc| calc.summary(&StatsFilter::from(range)))
+            .await?;
+        Ok(Response::new(SummaryReply {
+            total_keys: summary.total_keys,
+            total_combos: summary.total_combos,
+            active_minutes: summary.active_minutes,
+            average_typing_speed: summary.average_typing_speed,
+        }))
+    }
+
+    async fn top_keys(
+        &self,
+        request: Request<TopRequest>,
+    ) -> Result<Response<TopKeysReply>, Status> {
+        let request = request.into_inner();
+        let range = time_range(request.range.as_ref())?;
+        let limit = limit(request.limit);
+        let keys = self
+            .query(move |calc| calc.top_keys(&StatsFilter::from(range), limit))
+            .await?;
+        Ok(Response::new(TopKeysReply {
+            keys: keys
+                .into_iter()
+                .map(|key| KeyCount {
+                    key_name: key.key_name,
+                    count: key.count,
+                    percentage: key.percentage,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn top_combos(
+        &self,
+        request: Request<TopRequest>,
+    ) -> Result<Response<TopCombosReply>, Status> {
+        let request = request.into_inner();
+        let range = time_range(request.range.as_ref())?;
+        let limit = limit(request.limit);
+        let combos = self
+            .query(move |calc| calc.top_combos(&StatsFilter::from(range), limit))
+            .await?;
+        Ok(Response::new(TopCombosReply {
+            combos: combos
+                .into_iter()
+                .map(|combo| ComboCount {
+                    combo: combo.combo,
+                    count: combo.count,
+                })
+                .collect(),
+        }))
+    }
+}
+
+/// The range a request asks for, where empty bounds are open
+fn time_range(range: Option<&RangeRequest>) -> Result<TimeRange, Status> {
+    TimeRange::parse(
+        range.map(|r| r.since.as_str()).filter(|t| !t.is_empty()),
+        range.map(|r| r.until.as_str()).filter(|t| !t.is_empty()),
+    )
+    .map_err(|e| Status::invalid_argument(e.to_string()))
+}
+
+fn limit(requested: u32) -> usize {
+    match requested {
+        0 => DEFAULT_LIMIT,
+        n => (n as usize).min(MAX_LIMIT),
+    }
+}
+
+/// Serve the API on `addr` until the process exits. With a `token`, calls must carry it
+/// as `authorization: Bearer <token>` metadata.
+pub async fn serve(
+    addr: SocketAddr,
+    token: Option<String>,
+    recorder: impl Recorder,
+    db: DbConnection,
+) -> Result<()> {
+    let service = KitmapService {
+        recorder: Arc::new(recorder),
+        db,
+    };
+    let authorize = move |request: Request<()>| -> Result<Request<()>, Status> {
+        let Some(expected) = token.as_deref() else {
+            return Ok(request);
+        };
+        let given = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match given {
+            Some(given) if tokens_match(given, expected) => Ok(request),
+            _ => Err(Status::unauthenticated("Missing or wrong token")),
+        }
+    };
+
+    tonic::transport::Server::builder()
+        .add_service(KitmapServer::with_interceptor(service, authorize))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,402 @@
+pub mod api;
+pub mod assets;
+pub mod auth;
+pub mod control;
+#[cfg(all(feature = "dbus", target_os = "linux"))]
+pub mod dbus;
+pub mod grafana;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "tls")]
+pub mod tls;
+pub mod version;
+
+use crate::config::{EffortConfig, ScoreConfig};
+use crate::db::DbConnection;
+use crate::stats::board::Board;
+use crate::stats::calculator::AllStats;
+use crate::stats::custom::CustomMetric;
+use crate::stats::{StatsCalculator, StatsFilter};
+use anyhow::Result;
+use axum::{
+    http::HeaderValue,
+    middleware,
+    routing::{get, post},
+    Router,
+};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+pub struct AppState {
+    db: DbConnection,
+    /// Token required to access the dashboard, if any
+    token: Option<String>,
+    /// Formula used for productivity scores
+    score: ScoreConfig,
+    /// Layout typing effort is estimated for
+    effort: EffortConfig,
+    /// Keyboard fingers and effort are estimated for
+    board: Board,
+    /// Statistics added to the overall stats
+    metrics: Vec<Arc<dyn CustomMetric>>,
+    /// Serve dashboard files from this directory instead of the embedded bundle
+    assets_dir: Option<PathBuf>,
+    /// Other origins browsers may read the API from
+    allowed_origins: Vec<HeaderValue>,
+    /// Last calculated stats, keyed by the newest key event id they include
+    cache: Mutex<Option<(i64, AllStats)>>,
+}
+
+impl AppState {
+    pub fn new(db: DbConnection, token: Option<String>) -> Self {
+        Self {
+            db,
+            token,
+            score: ScoreConfig::default(),
+            effort: EffortConfig::default(),
+            board: Board::standard(),
+            metrics: Vec::new(),
+            assets_dir: None,
+            allowed_origins: Vec::new(),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Use a custom productivity score formula
+    pub fn with_score_config(mut self, score: ScoreConfig) -> Self {
+        self.score = score;
+        self
+    }
+
+    /// Estimate typing effort for a different layout
+    pub fn with_effort_config(mut self, effort: EffortConfig) -> Self {
+        self.effort = effort;
+        self
+    }
+
+    /// Assign fingers and estimate effort for a keyboard other than a standard one
+    pub fn with_board(mut self, board: Board) -> Self {
+        self.board = board;
+        self
+    }
+
+    /// Add statistics to the overall stats
+    pub fn with_metrics(mut self, metrics: Vec<Arc<dyn CustomMetric>>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Serve dashboard files from disk, for working on the frontend without rebuilding
+    pub fn with_assets_dir(mut self, assets_dir: Option<PathBuf>) -> Self {
+        self.assets_dir = assets_dir;
+        self
+    }
+
+    /// Let pages from these origins read the API, which browsers otherwise only allow the
+    /// dashboard's own pages to do
+    pub fn with_allowed_origins(mut self, origins: Vec<HeaderValue>) -> Self {
+        self.allowed_origins = origins;
+        self
+    }
+
+    /// Seed the stats cache with stats already calculated at startup
+    pub fn with_cached_stats(self, latest_id: i64, stats: AllStats) -> Self {
+        *self.cache.lock().unwrap() = Some((latest_id, stats));
+        self
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    pub fn calculator(&self) -> StatsCalculator {
+        StatsCalculator::new(self.db.clone())
+            .with_score_config(self.score.clone())
+            .with_effort_config(self.effort.clone())
+            .with_board(self.board.clone())
+            .with_metrics(self.metrics.clone())
+    }
+
+    /// Get up-to-date stats, recalculating only when new key events were recorded
+    pub fn current_stats(&self) -> Result<(i64, AllStats)> {
+        let calculator = self.calculator();
+        let latest_id = calculator.latest_event_id()?;
+
+        if let Some((cached_id, stats)) = self.cache.lock().unwrap().as_ref() {
+            if *cached_id == latest_id {
+                return Ok((latest_id, stats.clone()));
+            }
+        }
+
+        let stats = calculator.calculate_all_cached(&StatsFilter::default())?;
+        *self.cache.lock().unwrap() = Some((latest_id, stats.clone()));
+        Ok((latest_id, stats))
+    }
+}
+
+/// Build the dashboard router with all API and asset routes
+pub fn router(state: Arc<AppState>) -> Router {
+    // Without allowed origins no CORS headers are sent, so other sites open in the same
+    // browser can't read stats or live keys
+    let cors = CorsLayer::new()
+        .allow_origin(AllowOrigin::list(state.allowed_origins.clone()))
+        .allow_methods(Any)
+        .allow_headers(Any);
+
+    let api = Router::new()
+        .route("/stats", get(api::get_stats))
+        .route("/stats/stream", get(api::stream_stats))
+        .route("/keys", get(api::get_keys))
+        .route("/keys/stream", get(api::stream_keys))
+        .route("/combos", get(api::get_combos))
+        .route("/chords", get(api::get_chords))
+        .route("/hourly", get(api::get_hourly))
+        .route("/heatmap", get(api::get_heatmap))
+        .route("/replay", get(api::get_replay))
+        .route("/sessions", get(api::get_sessions))
+        .route("/score", get(api::get_score))
+        .route("/speed", get(api::get_speed))
+        .route("/calendar", get(api::get_calendar))
+        .route("/seasonality", get(api::get_seasonality))
+        .route("/rolls", get(api::get_rolls))
+        .route("/sequences", get(api::get_sequences))
+        .route("/export", get(api::get_export))
+        .route("/capabilities", get(api::get_capabilities))
+        .route("/grafana", get(grafana::health))
+        .route("/grafana/", get(grafana::health))
+        .route("/grafana/metrics", post(grafana::metrics))
+        .route(
+            "/grafana/metric-payload-options",
+            post(grafana::metric_payload_options),
+        )
+        .route("/grafana/search", post(grafana::search))
+        .route("/grafana/query", post(grafana::query))
+        .layer(middleware::from_fn(version::negotiate));
+
+    let router = Router::new()
+        .route("/", get(assets::serve_index))
+        .route("/overlay", get(assets::serve_overlay))
+        .nest(&format!("/api/v{}", version::API_VERSION), api.clone())
+        .nest(
+            "/api",
+            api.layer(middleware::from_fn(version::deprecated_alias)),
+        )
+        .route("/assets/*path", get(assets::serve_static));
+
+    #[cfg(feature = "graphql")]
+    let router = router.merge(graphql::routes(state.clone()));
+
+    router
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_token,
+        ))
+        .layer(cors)
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_memory_db;
+    use crate::stats::calculator::KeyStats;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use tower::ServiceExt;
+
+    fn dashboard(token: Option<&str>) -> Router {
+        recorded_dashboard(token, "")
+    }
+
+    /// A dashboard over a database holding what `sql` inserts
+    fn recorded_dashboard(token: Option<&str>, sql: &str) -> Router {
+        let db = init_memory_db().unwrap();
+        let sql = sql.to_string();
+        db.call_blocking(move |conn| Ok(conn.execute_batch(&sql)?))
+            .unwrap();
+        router(Arc::new(AppState::new(db, token.map(String::from))))
+    }
+
+    async fn get(router: &Router, uri: &str) -> (StatusCode, Vec<u8>) {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, body.to_vec())
+    }
+
+    /// The download links the dashboard shows, as page.tsx builds them from the capabilities
+    async fn download_links(router: &Router, query: &str) -> Vec<String> {
+        let (status, body) = get(router, &format!("/api/v1/capabilities{}", query)).await;
+        assert_eq!(status, StatusCode::OK);
+        let capabilities: api::Capabilities = serde_json::from_slice(&body).unwrap();
+        let data = if capabilities.key_export {
+            "keys"
+        } else {
+            "combos"
+        };
+        ["csv", "json"]
+            .iter()
+            .map(|format| format!("/api/v1/export?format={}&data={}", format, data))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn serves_the_download_links_without_a_token() {
+        let router = dashboard(None);
+        for link in download_links(&router, "").await {
+            assert_eq!(get(&router, &link).await.0, StatusCode::OK, "{}", link);
+        }
+        let (status, _) = get(&router, "/api/v1/export?format=csv").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn serves_key_downloads_with_a_token() {
+        let router = dashboard(Some("secret"));
+        let links = download_links(&router, "?token=secret").await;
+        assert!(links.iter().all(|link| link.ends_with("data=keys")));
+        for link in links {
+            let (status, _) = get(&router, &format!("{}&token=secret", link)).await;
+            assert_eq!(status, StatusCode::OK, "{}", link);
+        }
+    }
+
+    /// Key `Key0` to `Key29` on 1 March 2024, each pressed one more time than the last,
+    /// and `KeyZ` once in February
+    const PRESSES: &str = "
+        WITH RECURSIVE keys(n) AS (SELECT 0 UNION ALL SELECT n + 1 FROM keys WHERE n < 29),
+            presses(n, i) AS (
+                SELECT n, 0 FROM keys
+                UNION ALL SELECT n, i + 1 FROM presses WHERE i < n
+            )
+        INSERT INTO key_events (key_code, key_name, timestamp, timestamp_ms, hour, day_of_week)
+        SELECT 'Key' || n, 'Key' || n, '2024-03-01T12:00:00.000Z', 1709294400000 + i, 12, 4
+        FROM presses;
+        INSERT INTO key_events (key_code, key_name, timestamp, timestamp_ms, hour, day_of_week)
+        VALUES ('KeyZ', 'KeyZ', '2024-02-01T12:00:00.000Z', 1706788800000, 12, 3);";
+
+    async fn top_keys(router: &Router, query: &str) -> Vec<String> {
+        let (status, body) = get(router, &format!("/api/v1/keys{}", query)).await;
+        assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+        let keys: Vec<KeyStats> = serde_json::from_slice(&body).unwrap();
+        keys.into_iter().map(|key| key.key_name).collect()
+    }
+
+    #[tokio::test]
+    async fn clamps_limits_and_narrows_by_range() {
+        let router = recorded_dashboard(None, PRESSES);
+        assert_eq!(top_keys(&router, "").await.len(), api::DEFAULT_LIMIT);
+        assert_eq!(top_keys(&router, "?limit=0").await, ["Key29"]);
+        assert_eq!(top_keys(&router, "?limit=1000000").await.len(), 31);
+        assert_eq!(
+            top_keys(&router, "?until=2024-02-15&limit=5").await,
+            ["KeyZ"]
+        );
+        assert!(!top_keys(&router, "?since=2024-02-15&limit=100")
+            .await
+            .contains(&"KeyZ".to_string()));
+
+        let (status, _) = get(&router, "/api/v1/keys?since=sometime").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get(&router, "/api/v1/keys?limit=-1").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    async fn status_with(router: &Router, uri: &str, header: Option<(&str, &str)>) -> StatusCode {
+        let mut request = Request::get(uri);
+        if let Some((name, value)) = header {
+            request = request.header(name, value);
+        }
+        let request = request.body(Body::empty()).unwrap();
+        router.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn accepts_the_token_however_it_is_sent() {
+        let router = dashboard(Some("s3cr+t/="));
+        let uri = "/api/v1/capabilities";
+        let basic = format!("Basic {}", BASE64.encode("kitmap:s3cr+t/="));
+        for header in [
+            ("authorization", "Bearer s3cr+t/="),
+            ("authorization", basic.as_str()),
+            ("cookie", "theme=dark; kitmap_token=s3cr+t/="),
+        ] {
+            assert_eq!(
+                status_with(&router, uri, Some(header)).await,
+                StatusCode::OK,
+                "{:?}",
+                header
+            );
+        }
+
+        // A token in the query is decoded, and remembered in a cookie
+        let request = Request::get(format!("{}?token=s3cr%2Bt%2F%3D", uri))
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        assert!(cookie.starts_with("kitmap_token=s3cr+t/=;"), "{}", cookie);
+    }
+
+    #[tokio::test]
+    async fn refuses_requests_without_the_token() {
+        let router = dashboard(Some("secret"));
+        let request = Request::get("/api/v1/stats").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+
+        for header in [
+            ("authorization", "Bearer secreT"),
+            ("authorization", "Bearer secret2"),
+            ("cookie", "kitmap_token=guess"),
+        ] {
+            assert_eq!(
+                status_with(&router, "/", Some(header)).await,
+                StatusCode::UNAUTHORIZED,
+                "{:?}",
+                header
+            );
+        }
+        assert_eq!(
+            status_with(&router, "/overlay?token=secre", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert!(auth::tokens_match("secret", "secret"));
+        assert!(!auth::tokens_match("", "secret"));
+    }
+
+    #[tokio::test]
+    async fn streams_exports_larger_than_a_chunk() {
+        let router = recorded_dashboard(
+            None,
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 5000)
+             INSERT INTO key_combos (combo, timestamp, timestamp_ms)
+             SELECT 'Ctrl+Shift+T', '2024-03-01T12:00:00.000Z', 1709294400000 + i FROM n;",
+        );
+        let request = Request::get("/api/v1/export?format=csv&data=combos")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.len() > 64 * 1024);
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        let rows: Vec<&str> = text.lines().collect();
+        assert_eq!(rows.len(), 5001);
+        assert!(rows[5000].contains("Ctrl+Shift+T"), "{}", rows[5000]);
+
+        let (status, body) = get(&router, "/api/v1/export?format=json&data=combos").await;
+        assert_eq!(status, StatusCode::OK);
+        let combos: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(combos.len(), 5000);
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::{bail, Context, Result};
+use axum_server::tls_rustls::RustlsConfig;
+use directories::ProjectDirs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+/// HTTPS options for the web server
+#[derive(Debug, Clone, clap::Args)]
+pub struct TlsArgs {
+    /// Serve over HTTPS with a self-signed certificate generated on first use
+    #[arg(long)]
+    pub tls: bool,
+    /// PEM certificate chain to serve over HTTPS
+    #[arg(long, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key for --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+}
+
+impl TlsArgs {
+    pub fn enabled(&self) -> bool {
+        self.tls || self.tls_cert.is_some()
+    }
+
+    /// Load the configured certificate, or a self-signed one valid for `host`
+    pub async fn rustls_config(&self, host: IpAddr) -> Result<RustlsConfig> {
+        // Several rustls providers may be linked in; settle on ring before any config is built
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        let (cert, key) = match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => (cert.clone(), key.clone()),
+            (None, None) => self_signed_certificate(host)?,
+            _ => bail!("--tls-cert and --tls-key must be given together"),
+        };
+
+        RustlsConfig::from_pem_file(&cert, &key)
+            .await
+            .with_context(|| format!("Failed to load TLS certificate {}", cert.display()))
+    }
+}
+
+/// Get (or create) a self-signed certificate for `host` in the data directory.
+///
+/// The certificate is reused across runs so the browser exception only has to be
+/// accepted once per host.
+fn self_signed_certificate(host: IpAddr) -> Result<(PathBuf, PathBuf)> {
+    let dir = ProjectDirs::from("com", "twilight", "kitmap")
+        .map(|dirs| dirs.data_dir().join("tls"))
+        .unwrap_or_else(|| PathBuf::from("tls"));
+    std::fs::create_dir_all(&dir)?;
+
+    let name = host.to_string().replace(':', "_");
+    let cert_path = dir.join(format!("{}-cert.pem", name));
+    let key_path = dir.join(format!("{}-key.pem", name));
+
+    if cert_path.exists() && key_path.exists() {
+        return Ok((cert_path, key_path));
+    }
+
+    let mut names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    if !host.is_unspecified() && !names.contains(&host.to_string()) {
+        names.push(host.to_string());
+    }
+
+    let certified = rcgen::generate_simple_self_signed(names)?;
+    std::fs::write(&cert_path, certified.cert.pem())?;
+    std::fs::write(&key_path, certified.key_pair.serialize_pem())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok((cert_path, key_path))
+}
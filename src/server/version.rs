@@ -0,0 +1,59 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// Current major version of the HTTP API, served under `/api/v1`
+pub const API_VERSION: u32 = 1;
+
+/// Header clients send to request an API version, and responses carry to confirm it
+pub static VERSION_HEADER: HeaderName = HeaderName::from_static("kitmap-api-version");
+
+/// Reject requests for API versions this server cannot speak and tag every
+/// response with the version that produced it.
+pub async fn negotiate(req: Request, next: Next) -> Response {
+    if let Some(requested) = req.headers().get(&VERSION_HEADER) {
+        let supported = requested
+            .to_str()
+            .ok()
+            .and_then(|v| v.trim().trim_start_matches('v').parse::<u32>().ok())
+            == Some(API_VERSION);
+
+        if !supported {
+            return (
+                StatusCode::NOT_ACCEPTABLE,
+                [(VERSION_HEADER.clone(), HeaderValue::from(API_VERSION))],
+                format!(
+                    "Unsupported API version; this server speaks version {}",
+                    API_VERSION
+                ),
+            )
+                .into_response();
+        }
+    }
+
+    let mut response = next.run(req).await;
+    response
+        .headers_mut()
+        .insert(VERSION_HEADER.clone(), HeaderValue::from(API_VERSION));
+    response
+}
+
+/// Mark responses from the unversioned `/api` paths as deprecated in favor of `/api/v1`
+pub async fn deprecated_alias(req: Request, next: Next) -> Response {
+    let successor = format!(
+        "</api/v{}{}>; rel=\"successor-version\"",
+        API_VERSION,
+        req.uri().path().trim_start_matches("/api")
+    );
+
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.insert("link", link);
+    }
+    response
+}
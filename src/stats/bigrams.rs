@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+/// A tracked bigram with its estimated count and maximum overestimation
+#[derive(Debug, Clone, Copy)]
+struct Counter {
+    count: u64,
+    error: u64,
+}
+
+/// Space-saving top-K sketch of key bigrams.
+///
+/// Memory is bounded by `capacity` no matter how much is typed. Frequent bigrams
+/// are always retained; when the sketch is full, the least frequent entry is
+/// evicted and its count inherited by the newcomer, so counts are upper bounds.
+pub struct BigramSketch {
+    capacity: usize,
+    counters: HashMap<(String, String), Counter>,
+}
+
+impl BigramSketch {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            counters: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Record that `second` was pressed directly after `first`
+    pub fn record(&mut self, first: &str, second: &str) {
+        let bigram = (first.to_string(), second.to_string());
+
+        if let Some(counter) = self.counters.get_mut(&bigram) {
+            counter.count += 1;
+            return;
+        }
+
+        if self.counters.len() < self.capacity {
+            self.counters.insert(bigram, Counter { count: 1, error: 0 });
+            return;
+        }
+
+        // Evict the least frequent bigram and let the new one take over its count
+        let evicted = self
+            .counters
+            .iter()
+            .min_by_key(|(_, c)| c.count)
+            .map(|(k, c)| (k.clone(), c.count));
+
+        if let Some((evicted_key, min_count)) = evicted {
+            self.counters.remove(&evicted_key);
+            self.counters.insert(
+                bigram,
+                Counter {
+                    count: min_count + 1,
+                    error: min_count,
+                },
+            );
+        }
+    }
+
+    /// Take all tracked bigrams, using the guaranteed lower bound of each count
+    pub fn drain(&mut self) -> Vec<(String, String, u64)> {
+        self.counters
+            .drain()
+            .map(|((first, second), c)| (first, second, c.count - c.error))
+            .filter(|(_, _, count)| *count > 0)
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.counters.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(sketch: &mut BigramSketch) -> HashMap<(String, String), u64> {
+        sketch
+            .drain()
+            .into_iter()
+            .map(|(first, second, count)| ((first, second), count))
+            .collect()
+    }
+
+    #[test]
+    fn counts_exactly_below_capacity() {
+        let mut sketch = BigramSketch::new(4);
+        for _ in 0..3 {
+            sketch.record("t", "h");
+        }
+        sketch.record("h", "e");
+
+        let counts = counts(&mut sketch);
+        assert_eq!(counts[&("t".into(), "h".into())], 3);
+        assert_eq!(counts[&("h".into(), "e".into())], 1);
+        assert!(sketch.is_empty());
+    }
+
+    #[test]
+    fn keeps_frequent_bigrams_when_full() {
+        let mut sketch = BigramSketch::new(2);
+        for _ in 0..10 {
+            sketch.record("t", "h");
+        }
+        for second in ["a", "b", "c", "d"] {
+            sketch.record("x", second);
+        }
+
+        let counts = counts(&mut sketch);
+        // Each newcomer evicted the last one; only its own press is a guaranteed count
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&("t".into(), "h".into())], 10);
+        assert_eq!(counts[&("x".into(), "d".into())], 1);
+    }
+
+    #[test]
+    fn clear_forgets_everything() {
+        let mut sketch = BigramSketch::new(0);
+        sketch.record("a", "b");
+        assert!(!sketch.is_empty());
+        sketch.clear();
+        assert!(sketch.is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Duration, Local};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Default idle time that ends an active typing block
+pub const DEFAULT_BLOCK_GAP_MINUTES: i64 = 5;
+
+/// Blocks shorter than this are dropped as noise (a stray keystroke or two)
+const MIN_BLOCK_SECONDS: i64 = 60;
+
+/// A continuous period of typing without long pauses
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ActivityBlock {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub keys: i64,
+}
+
+impl ActivityBlock {
+    pub fn duration_minutes(&self) -> f64 {
+        (self.end - self.start).num_seconds() as f64 / 60.0
+    }
+}
+
+/// Group ordered key timestamps into blocks, starting a new block after `gap` of idleness
+pub fn detect_blocks(
+    timestamps: impl IntoIterator<Item = DateTime<Local>>,
+    gap: Duration,
+) -> Vec<ActivityBlock> {
+    let mut blocks = Vec::new();
+    let mut current: Option<ActivityBlock> = None;
+
+    for at in timestamps {
+        match current.as_mut() {
+            Some(block) if at - block.end <= gap => {
+                block.end = at;
+                block.keys += 1;
+            }
+            _ => {
+                blocks.extend(current.take());
+                current = Some(ActivityBlock {
+                    start: at,
+                    end: at,
+                    keys: 1,
+                });
+            }
+        }
+    }
+    blocks.extend(current);
+
+    blocks.retain(|b| (b.end - b.start).num_seconds() >= MIN_BLOCK_SECONDS);
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minutes: i64, seconds: i64) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 1, 31, 9, 0, 0).unwrap()
+            + Duration::minutes(minutes)
+            + Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn splits_blocks_at_long_pauses() {
+        let timestamps = [at(0, 0), at(1, 0), at(2, 0), at(10, 0), at(12, 0)];
+        let blocks = detect_blocks(timestamps, Duration::minutes(5));
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!((blocks[0].start, blocks[0].end), (at(0, 0), at(2, 0)));
+        assert_eq!(blocks[0].keys, 3);
+        assert_eq!((blocks[1].start, blocks[1].end), (at(10, 0), at(12, 0)));
+        assert_eq!(blocks[1].keys, 2);
+        assert_eq!(blocks[0].duration_minutes(), 2.0);
+    }
+
+    #[test]
+    fn a_pause_of_exactly_the_gap_continues_the_block() {
+        let blocks = detect_blocks([at(0, 0), at(5, 0)], Duration::minutes(5));
+        assert_eq!(blocks.len(), 1);
+    }
+
+    #[test]
+    fn drops_blocks_shorter_than_a_minute() {
+        let timestamps = [at(0, 0), at(0, 30), at(20, 0), at(21, 0)];
+        let blocks = detect_blocks(timestamps, Duration::minutes(5));
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].start, at(20, 0));
+        assert!(detect_blocks([], Duration::minutes(5)).is_empty());
+    }
+}
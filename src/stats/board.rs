@@ -0,0 +1,313 @@
+use crate::config::{get_config_path, KeyboardConfig};
+use crate::db::models::KeyCategory;
+use crate::stats::effort::{standard_effort, standard_finger, Finger, Hand};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// Keys of a standard ANSI board, one row at a time from the function keys down, by
+/// their recorded names
+const STANDARD_ROWS: &[&[&str]] = &[
+    &[
+        "Escape", "F1", "F2", "F3", "F4", "F5", "F6", "F7", "F8", "F9", "F10", "F11", "F12",
+    ],
+    &[
+        "BackQuote",
+        "Num1",
+        "Num2",
+        "Num3",
+        "Num4",
+        "Num5",
+        "Num6",
+        "Num7",
+        "Num8",
+        "Num9",
+        "Num0",
+        "Minus",
+        "Equal",
+        "Backspace",
+    ],
+    &[
+        "Tab",
+        "KeyQ",
+        "KeyW",
+        "KeyE",
+        "KeyR",
+        "KeyT",
+        "KeyY",
+        "KeyU",
+        "KeyI",
+        "KeyO",
+        "KeyP",
+        "LeftBracket",
+        "RightBracket",
+        "BackSlash",
+    ],
+    &[
+        "CapsLock",
+        "KeyA",
+        "KeyS",
+        "KeyD",
+        "KeyF",
+        "KeyG",
+        "KeyH",
+        "KeyJ",
+        "KeyK",
+        "KeyL",
+        "SemiColon",
+        "Quote",
+        "Return",
+    ],
+    &[
+        "ShiftLeft",
+        "KeyZ",
+        "KeyX",
+        "KeyC",
+        "KeyV",
+        "KeyB",
+        "KeyN",
+        "KeyM",
+        "Comma",
+        "Dot",
+        "Slash",
+        "ShiftRight",
+    ],
+    &[
+        "ControlLeft",
+        "MetaLeft",
+        "Alt",
+        "Space",
+        "AltGr",
+        "MetaRight",
+        "ControlRight",
+    ],
+];
+
+/// Index of the home row in `STANDARD_ROWS`
+const STANDARD_HOME_ROW: usize = 3;
+
+/// Finger a key of a layout definition is typed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FingerAssignment {
+    LeftPinky,
+    LeftRing,
+    LeftMiddle,
+    LeftIndex,
+    LeftThumb,
+    RightThumb,
+    RightIndex,
+    RightMiddle,
+    RightRing,
+    RightPinky,
+}
+
+impl FingerAssignment {
+    fn new(hand: Hand, finger: Finger) -> Self {
+        match (hand, finger) {
+            (Hand::Left, Finger::Pinky) => FingerAssignment::LeftPinky,
+            (Hand::Left, Finger::Ring) => FingerAssignment::LeftRing,
+            (Hand::Left, Finger::Middle) => FingerAssignment::LeftMiddle,
+            (Hand::Left, Finger::Index) => FingerAssignment::LeftIndex,
+            (Hand::Left, Finger::Thumb) => FingerAssignment::LeftThumb,
+            (Hand::Right, Finger::Thumb) => FingerAssignment::RightThumb,
+            (Hand::Right, Finger::Index) => FingerAssignment::RightIndex,
+            (Hand::Right, Finger::Middle) => FingerAssignment::RightMiddle,
+            (Hand::Right, Finger::Ring) => FingerAssignment::RightRing,
+            (Hand::Right, Finger::Pinky) => FingerAssignment::RightPinky,
+        }
+    }
+
+    pub fn hand_finger(self) -> (Hand, Finger) {
+        match self {
+            FingerAssignment::LeftPinky => (Hand::Left, Finger::Pinky),
+            FingerAssignment::LeftRing => (Hand::Left, Finger::Ring),
+            FingerAssignment::LeftMiddle => (Hand::Left, Finger::Middle),
+            FingerAssignment::LeftIndex => (Hand::Left, Finger::Index),
+            FingerAssignment::LeftThumb => (Hand::Left, Finger::Thumb),
+            FingerAssignment::RightThumb => (Hand::Right, Finger::Thumb),
+            FingerAssignment::RightIndex => (Hand::Right, Finger::Index),
+            FingerAssignment::RightMiddle => (Hand::Right, Finger::Middle),
+            FingerAssignment::RightRing => (Hand::Right, Finger::Ring),
+            FingerAssignment::RightPinky => (Hand::Right, Finger::Pinky),
+        }
+    }
+}
+
+/// One key of a layout definition, or a gap when `key` is left out
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BoardKey {
+    /// Name the key is recorded under, e.g. `KeyA` or `Space`
+    pub key: Option<String>,
+    /// Text shown on the key in the heatmap, instead of one derived from `key`
+    pub label: Option<String>,
+    /// Width in characters; keys default to a width based on their name
+    pub width: Option<usize>,
+    pub finger: Option<FingerAssignment>,
+    /// Effort of a press, in home-row index finger presses, instead of the estimate
+    /// from the finger and row
+    pub effort: Option<f64>,
+}
+
+/// Name and category given to a key that is recorded without a useful name, such as
+/// `Unknown(179)` from a media or function layer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyName {
+    /// Name shown in stats and on the heatmap
+    pub name: Option<String>,
+    /// Whether the key counts as a letter, digit or other key, instead of the category
+    /// of its recorded name
+    pub category: Option<KeyCategory>,
+}
+
+/// A physical keyboard: where its keys are and which finger types each of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Board {
+    /// Index of the row the fingers rest on
+    pub home_row: usize,
+    /// Keys from the top row down, each row from left to right
+    pub rows: Vec<Vec<BoardKey>>,
+    /// Names and categories of recorded keys, by recorded name
+    #[serde(default)]
+    pub names: BTreeMap<String, KeyName>,
+}
+
+impl Board {
+    /// A standard ANSI board typed by a touch typist
+    pub fn standard() -> Self {
+        let rows = STANDARD_ROWS
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&key| BoardKey {
+                        key: Some(key.to_string()),
+                        finger: standard_finger(key)
+                            .map(|(hand, finger)| FingerAssignment::new(hand, finger)),
+                        effort: standard_effort(key),
+                        ..Default::default()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            home_row: STANDARD_HOME_ROW,
+            rows,
+            names: BTreeMap::new(),
+        }
+    }
+
+    /// The board set under `[keyboard]`, or a standard one, with the key names from
+    /// the config taking precedence over those of the layout definition
+    pub fn from_config(config: &KeyboardConfig) -> Result<Self> {
+        let mut board = match &config.definition {
+            Some(definition) => Self::load(definition)?,
+            None => Self::standard(),
+        };
+        board.names.extend(config.keys.clone());
+        Ok(board)
+    }
+
+    /// Load a layout definition from the `layouts` directory next to the config file,
+    /// by file name with or without its `.toml` or `.json` extension, or by path
+    pub fn load(definition: &str) -> Result<Self> {
+        let dir = layouts_dir()?;
+        let path = ["", ".toml", ".json"]
+            .iter()
+            .map(|extension| dir.join(format!("{}{}", definition, extension)))
+            .find(|path| path.is_file())
+            .with_context(|| {
+                format!(
+                    "No layout definition \"{}\" found in {}",
+                    definition,
+                    dir.display()
+                )
+            })?;
+
+        // The cause is part of the message, as only the outermost error is printed
+        let invalid =
+            |e: &dyn Display| anyhow!("Invalid layout definition {}: {}", path.display(), e);
+        let contents = std::fs::read_to_string(&path)?;
+        let board: Self = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&contents).map_err(|e| invalid(&e))?
+        } else {
+            toml::from_str(&contents).map_err(|e| invalid(&e))?
+        };
+
+        board.validate().map_err(|e| invalid(&e))?;
+        Ok(board)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.home_row >= self.rows.len() {
+            bail!(
+                "home_row is {} but there are only {} rows",
+                self.home_row,
+                self.rows.len()
+            );
+        }
+        for key in self.rows.iter().flatten() {
+            if key.key.is_none() && key.width.is_none() {
+                bail!("Gaps between keys need a width");
+            }
+            if let Some(effort) = key.effort {
+                if !(effort > 0.0 && effort.is_finite()) {
+                    bail!("Effort must be a positive number");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Row index and definition of a recorded key
+    pub fn key(&self, key_name: &str) -> Option<(usize, &BoardKey)> {
+        self.rows.iter().enumerate().find_map(|(row, keys)| {
+            keys.iter()
+                .find(|key| key.key.as_deref() == Some(key_name))
+                .map(|key| (row, key))
+        })
+    }
+
+    /// Name a recorded key is shown under
+    pub fn display_name<'a>(&'a self, key_name: &'a str) -> &'a str {
+        self.names
+            .get(key_name)
+            .and_then(|key| key.name.as_deref())
+            .unwrap_or(key_name)
+    }
+
+    /// Category a recorded key is counted in
+    pub fn category_of(&self, key_name: &str) -> KeyCategory {
+        self.names
+            .get(key_name)
+            .and_then(|key| key.category)
+            .unwrap_or_else(|| KeyCategory::of(key_name))
+    }
+
+    /// Hand and finger a recorded key is typed with, if the board assigns one.
+    ///
+    /// Shift is left out: it's held down together with the next key rather than typed
+    /// before it.
+    pub fn finger_of(&self, key_name: &str) -> Option<(Hand, Finger)> {
+        if matches!(key_name, "ShiftLeft" | "ShiftRight") {
+            return None;
+        }
+        self.key(key_name)?
+            .1
+            .finger
+            .map(FingerAssignment::hand_finger)
+    }
+}
+
+/// Directory layout definitions are loaded from
+fn layouts_dir() -> Result<PathBuf> {
+    let config_path = get_config_path()?;
+    Ok(config_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("layouts"))
+}
@@ -0,0 +1,107 @@
+use crate::config::BreaksConfig;
+use crate::db::models::BreakReminder;
+use chrono::{DateTime, Duration, Local};
+
+/// What the listener should do after a key press
+#[derive(Debug, PartialEq)]
+pub enum BreakDecision {
+    Continue,
+    /// Suggest a break after `streak_minutes` of continuous typing
+    Remind {
+        streak_minutes: i64,
+        repeat: bool,
+    },
+}
+
+/// Tracks stretches of continuous typing and whether suggested breaks were taken.
+///
+/// A pause of at least `break_minutes` ends a stretch, and counts as the suggested
+/// break when one is pending. Screen locks need no special handling: the locked time
+/// is simply a pause between key presses.
+pub struct BreakTracker {
+    typing: Duration,
+    break_length: Duration,
+    repeat: Duration,
+    streak_start: Option<DateTime<Local>>,
+    last_key: Option<DateTime<Local>>,
+    pending: Option<BreakReminder>,
+    last_reminder: Option<DateTime<Local>>,
+    finished: Vec<BreakReminder>,
+    /// Reminder episodes and breaks taken since the tracker started
+    pub reminded: usize,
+    pub taken: usize,
+}
+
+impl BreakTracker {
+    pub fn new(config: &BreaksConfig) -> Self {
+        Self {
+            typing: Duration::minutes(config.typing_minutes.max(1) as i64),
+            break_length: Duration::minutes(config.break_minutes.max(1) as i64),
+            repeat: Duration::minutes(config.repeat_minutes.max(1) as i64),
+            streak_start: None,
+            last_key: None,
+            pending: None,
+            last_reminder: None,
+            finished: Vec::new(),
+            reminded: 0,
+            taken: 0,
+        }
+    }
+
+    /// Observe a key press and decide whether a break should be suggested
+    pub fn observe(&mut self, session_id: Option<i64>, at: DateTime<Local>) -> BreakDecision {
+        if let Some(last) = self.last_key {
+            if at - last >= self.break_length {
+                if let Some(mut reminder) = self.pending.take() {
+                    reminder.taken = true;
+                    reminder.break_started_at = Some(last);
+                    reminder.break_secs = Some((at - last).num_seconds());
+                    self.finished.push(reminder);
+                    self.taken += 1;
+                }
+                self.streak_start = None;
+            }
+        }
+        let streak_start = *self.streak_start.get_or_insert(at);
+        self.last_key = Some(at);
+
+        let streak_minutes = (at - streak_start).num_minutes();
+        match self.pending.as_mut() {
+            None if at - streak_start >= self.typing => {
+                self.pending = Some(BreakReminder::new(session_id, at, streak_minutes));
+                self.last_reminder = Some(at);
+                self.reminded += 1;
+                BreakDecision::Remind {
+                    streak_minutes,
+                    repeat: false,
+                }
+            }
+            Some(reminder)
+                if self
+                    .last_reminder
+                    .is_some_and(|last| at - last >= self.repeat) =>
+            {
+                reminder.reminders += 1;
+                self.last_reminder = Some(at);
+                BreakDecision::Remind {
+                    streak_minutes,
+                    repeat: true,
+                }
+            }
+            _ => BreakDecision::Continue,
+        }
+    }
+
+    /// Reminders whose outcome is known, removed from the tracker
+    pub fn drain_finished(&mut self) -> Vec<BreakReminder> {
+        std::mem::take(&mut self.finished)
+    }
+
+    /// Close the pending reminder, if any, as not taken, and return every finished reminder
+    pub fn finish(&mut self) -> Vec<BreakReminder> {
+        if let Some(reminder) = self.pending.take() {
+            self.finished.push(reminder);
+        }
+        self.drain_finished()
+    }
+}
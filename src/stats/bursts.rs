@@ -0,0 +1,89 @@
+use crate::config::BurstsConfig;
+use crate::db::models::TypingBurst;
+use chrono::{DateTime, Duration, Local};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Trailing window typing speed is measured over while looking for bursts
+const BURST_WINDOW_MS: i64 = 5000;
+
+/// A recorded burst, as listed in stats
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BurstStats {
+    /// When the burst started, RFC 3339 in local time
+    pub started: String,
+    pub duration_secs: f64,
+    pub keys: i64,
+    /// Fastest speed over the window used for detection, in characters per minute
+    pub peak_cpm: f64,
+    /// Speed over the whole burst, in characters per minute
+    pub average_cpm: f64,
+}
+
+/// Finds sustained runs of fast typing, which say more about how fast someone types
+/// than the fastest 10-second sample, set by a single lucky moment
+pub struct BurstDetector {
+    min_cpm: f64,
+    min_duration: Duration,
+    recent: VecDeque<DateTime<Local>>,
+    active: Option<TypingBurst>,
+}
+
+impl BurstDetector {
+    pub fn new(config: &BurstsConfig) -> Self {
+        Self {
+            min_cpm: config.min_cpm,
+            min_duration: Duration::seconds(config.min_secs as i64),
+            recent: VecDeque::new(),
+            active: None,
+        }
+    }
+
+    fn window() -> Duration {
+        Duration::milliseconds(BURST_WINDOW_MS)
+    }
+
+    /// Observe a key press, returning the burst it ended, if any
+    pub fn observe(&mut self, at: DateTime<Local>) -> Option<TypingBurst> {
+        self.recent.push_back(at);
+        while let Some(&t) = self.recent.front() {
+            if at - t > Self::window() {
+                self.recent.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let cpm = self.recent.len() as f64 * 60_000.0 / BURST_WINDOW_MS as f64;
+        if cpm < self.min_cpm {
+            return self.finish();
+        }
+
+        match self.active.as_mut() {
+            Some(burst) => {
+                burst.end_time = at;
+                burst.keys += 1;
+                burst.peak_cpm = burst.peak_cpm.max(cpm);
+            }
+            None => {
+                let start = self.recent.front().copied().unwrap_or(at);
+                self.active = Some(TypingBurst::new(start, at, self.recent.len() as i64, cpm));
+            }
+        }
+        None
+    }
+
+    /// End the active burst, returning it if it lasted long enough to count
+    pub fn finish(&mut self) -> Option<TypingBurst> {
+        self.active
+            .take()
+            .filter(|burst| burst.end_time - burst.start_time >= self.min_duration)
+    }
+
+    /// Forget recent keys and any active burst, e.g. while recording is paused
+    pub fn reset(&mut self) {
+        self.recent.clear();
+        self.active = None;
+    }
+}
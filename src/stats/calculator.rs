@@ -1,43 +1,372 @@
+use crate::config::{EffortConfig, ScoreConfig};
+use crate::db::models::KeyCategory;
+use crate::db::time;
 use crate::db::DbConnection;
-use anyhow::Result;
+use crate::stats::board::Board;
+use crate::stats::bursts::BurstStats;
+use crate::stats::calendar::{self, CalendarDay, CALENDAR_WEEKS};
+use crate::stats::custom::{self, CustomMetric, CustomMetricValue};
+use crate::stats::effort::{DailyEffort, RowUsage};
+use crate::stats::filter::StatsFilter;
+use crate::stats::goals::{self, GoalProgress};
+use crate::stats::hands::HandAlternation;
+#[cfg(feature = "graphql")]
+use crate::stats::range::TimeBucket;
+use crate::stats::range::{
+    local_midnight, TimeRange, LOCAL_DAY, LOCAL_HOUR, LOCAL_HOUR_START, LOCAL_MONTH, LOCAL_WEEKDAY,
+    LOCAL_YEAR,
+};
+use crate::stats::replay::{Replay, ReplayFrame, ReplayStep};
+use crate::stats::rhythm::{KeyIntervals, MAX_INTERVAL_MS};
+use crate::stats::rolls::RollAnalysis;
+use crate::stats::score::{DailyMetrics, DailyScore};
+use crate::stats::sequences::KeySequences;
+use crate::stats::series::{self, SeriesMetric, SeriesPoint};
+use crate::stats::shortcuts::AppShortcuts;
+use crate::stats::speed::{DailySpeed, SpeedDistribution};
+use crate::stats::storms::ComboStormStats;
+use crate::stats::weekend::{DayTypeStats, WeekdayWeekend};
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::TAU;
+use std::sync::Arc;
+use std::thread::{Scope, ScopedJoinHandle};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Trailing window, in seconds, that live keys-per-second rates are averaged over
+const LIVE_KPS_WINDOW_SECS: i64 = 3;
+
+/// Days of the week, in the order of `LOCAL_WEEKDAY`
+const DAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 pub struct KeyStats {
     pub key_name: String,
     pub count: i64,
     pub percentage: f64,
+    /// Character an `Unknown` key most often composed, when composed characters are recorded
+    pub composed: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl KeyStats {
+    /// The composed character for keys that have one, otherwise the key name
+    pub fn label(&self) -> &str {
+        self.composed.as_deref().unwrap_or(&self.key_name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 pub struct ComboStats {
     pub combo: String,
     pub count: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How often a chord of non-modifier keys was pressed
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ChordStats {
+    pub chord: String,
+    pub key_count: i64,
+    pub count: i64,
+    /// Average milliseconds between the chord's first and last key going down
+    pub avg_spread_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BigramStats {
+    pub first_key: String,
+    pub second_key: String,
+    pub count: i64,
+}
+
+/// Key presses attributed to one value of a context dimension (workspace, monitor, ...)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ContextStats {
+    pub name: String,
+    pub count: i64,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 pub struct HourlyStats {
     pub hour: i32,
     pub count: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What the keyboard heatmap colors keys by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatmapMetric {
+    /// Number of presses
+    #[default]
+    Count,
+    /// Average local hour of day the key is pressed at
+    AvgHour,
+}
+
+/// A key's heatmap value under the requested metric
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeyHeat {
+    pub key_name: String,
+    pub count: i64,
+    /// Press count, or for `avg_hour` the mean hour of use in `[0, 24)`.
+    /// Hours are averaged around the clock, so keys used at 23:00 and 01:00 average to midnight.
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DailyStats {
     pub day: String,
     pub count: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Key presses in each hour of one day of the week
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WeekdayHourStats {
+    pub day: String,
+    /// Presses in each local hour, from midnight
+    pub hours: Vec<i64>,
+}
+
+/// Key presses within one time bucket
+#[cfg(feature = "graphql")]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct ActivityBucket {
+    pub start: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct SessionStats {
+    pub id: i64,
+    pub start_time: String,
+    pub end_time: Option<String>,
+    pub total_keys: i64,
+    pub duration_minutes: Option<f64>,
+}
+
+/// Activity on a single day
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DaySummary {
+    pub date: String,
+    pub total_keys: i64,
+    pub total_combos: i64,
+    /// Productivity score, absent when nothing was typed that day
+    pub score: Option<DailyScore>,
+    pub top_keys: Vec<KeyStats>,
+    pub top_combos: Vec<ComboStats>,
+}
+
+/// Typing within one time range, for comparing periods
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RangeSummary {
+    pub total_keys: i64,
+    pub total_combos: i64,
+    /// Minutes with at least one key press
+    pub active_minutes: i64,
+    /// Average typing speed in characters per minute
+    pub average_typing_speed: f64,
+    pub most_active_day: Option<DailyStats>,
+    /// Every key pressed in the range, most pressed first
+    pub keys: Vec<KeyStats>,
+}
+
+/// Keys pressed since an earlier poll, with the current typing rates
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LiveKeys {
+    /// Id of the newest key event seen; pass it to the next poll
+    pub last_id: i64,
+    /// Newly pressed keys, oldest first
+    pub keys: Vec<String>,
+    /// Keys per second over the last few seconds
+    pub kps: f64,
+    /// Words (five non-modifier keystrokes) per minute over the last minute
+    pub wpm: f64,
+}
+
+/// Typing in one calendar month, aggregated across every recorded year
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MonthSeasonality {
+    /// Month number, 1 = January
+    pub month: u32,
+    pub name: String,
+    pub total_keys: i64,
+    /// Number of years with activity in this month
+    pub years: i64,
+    /// Keys per year that had activity in this month
+    pub average_keys: f64,
+    /// `average_keys` relative to the mean of all months with activity; 1.0 is a typical month
+    pub index: f64,
+    pub top_keys: Vec<KeyStats>,
+}
+
+/// Seasonal typing patterns by calendar month
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Seasonality {
+    /// Years with recorded activity, oldest first
+    pub years: Vec<i32>,
+    /// All twelve months, January first
+    pub months: Vec<MonthSeasonality>,
+}
+
+/// Groups of `AllStats` fields that can be calculated without the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StatsSection {
+    /// Keyboard heatmap of key frequencies
+    Heatmap,
+    /// Totals, most pressed key and combo, and when recording started and ended
+    Overview,
+    /// Special keys, key categories, row usage and hand alternation
+    Keys,
+    /// Typing speed, most active hour and day, and hourly activity
+    Activity,
+    /// Top keys, combos and chords tables, and combo storms
+    Top,
+    /// Workspaces, monitors, projects and remote vs local keys
+    Contexts,
+    /// Productivity scores, typing effort and goals
+    Progress,
+    /// Weekday and weekend typing side by side
+    Weekend,
+    /// Rolls and redirects
+    Rolls,
+    /// Most likely next keys and most common 3-key sequences
+    Sequences,
+}
+
+impl StatsSection {
+    pub const ALL: &'static [StatsSection] = &[
+        StatsSection::Heatmap,
+        StatsSection::Overview,
+        StatsSection::Keys,
+        StatsSection::Activity,
+        StatsSection::Top,
+        StatsSection::Contexts,
+        StatsSection::Progress,
+        StatsSection::Weekend,
+        StatsSection::Rolls,
+        StatsSection::Sequences,
+    ];
+}
+
+/// Every `AllStats` field with the section that calculates it. Fields without a section
+/// are totals, calculated whichever sections are asked for.
+pub const STATS_FIELDS: &[(&str, Option<StatsSection>)] = &[
+    ("total_keys", None),
+    ("total_combos", None),
+    ("total_chords", None),
+    ("total_sessions", None),
+    ("total_time_minutes", None),
+    ("most_pressed_key", Some(StatsSection::Overview)),
+    ("most_pressed_combo", Some(StatsSection::Overview)),
+    ("top_keys", Some(StatsSection::Top)),
+    ("top_combos", Some(StatsSection::Top)),
+    ("top_chords", Some(StatsSection::Top)),
+    ("combo_storms", Some(StatsSection::Top)),
+    ("top_bigrams", Some(StatsSection::Keys)),
+    ("hand_alternation", Some(StatsSection::Keys)),
+    ("top_workspaces", Some(StatsSection::Contexts)),
+    ("top_monitors", Some(StatsSection::Contexts)),
+    ("top_projects", Some(StatsSection::Contexts)),
+    ("app_shortcuts", Some(StatsSection::Contexts)),
+    ("daily_scores", Some(StatsSection::Progress)),
+    ("daily_effort", Some(StatsSection::Progress)),
+    ("goals", Some(StatsSection::Progress)),
+    ("remote_keys_count", Some(StatsSection::Contexts)),
+    ("local_keys_count", Some(StatsSection::Contexts)),
+    ("spacebar_count", Some(StatsSection::Keys)),
+    ("enter_count", Some(StatsSection::Keys)),
+    ("backspace_count", Some(StatsSection::Keys)),
+    ("delete_count", Some(StatsSection::Keys)),
+    ("escape_count", Some(StatsSection::Keys)),
+    ("tab_count", Some(StatsSection::Keys)),
+    ("arrow_keys_count", Some(StatsSection::Keys)),
+    ("modifier_keys_count", Some(StatsSection::Keys)),
+    ("letter_keys_count", Some(StatsSection::Keys)),
+    ("number_keys_count", Some(StatsSection::Keys)),
+    ("special_keys_count", Some(StatsSection::Keys)),
+    ("row_usage", Some(StatsSection::Keys)),
+    ("hourly_distribution", Some(StatsSection::Activity)),
+    ("daily_distribution", Some(StatsSection::Activity)),
+    ("weekday_hours", Some(StatsSection::Activity)),
+    ("most_active_hour", Some(StatsSection::Activity)),
+    ("most_active_day", Some(StatsSection::Activity)),
+    ("average_keys_per_session", None),
+    ("average_typing_speed", Some(StatsSection::Activity)),
+    ("max_typing_speed", Some(StatsSection::Activity)),
+    ("speed_distribution", Some(StatsSection::Activity)),
+    ("top_bursts", Some(StatsSection::Activity)),
+    ("key_intervals", Some(StatsSection::Activity)),
+    ("weekday_weekend", Some(StatsSection::Weekend)),
+    ("key_frequency_map", Some(StatsSection::Heatmap)),
+    ("first_recorded", Some(StatsSection::Overview)),
+    ("last_recorded", Some(StatsSection::Overview)),
+    ("unique_keys_used", None),
+    ("keys_per_minute_avg", None),
+    ("custom_metrics", None),
+    ("unfiltered", None),
+];
+
+/// `AllStats` fields counted from data that only records when it happened, left empty
+/// when the filter narrows key events by anything else
+const TIMED_FIELDS: &[&str] = &[
+    "total_chords",
+    "top_chords",
+    "combo_storms",
+    "average_typing_speed",
+    "max_typing_speed",
+    "speed_distribution",
+    "top_bursts",
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct AllStats {
     pub total_keys: i64,
     pub total_combos: i64,
+    /// Chords recorded by `kitmap listen --chords`
+    pub total_chords: i64,
     pub total_sessions: i64,
     pub total_time_minutes: f64,
     pub most_pressed_key: Option<KeyStats>,
     pub most_pressed_combo: Option<ComboStats>,
     pub top_keys: Vec<KeyStats>,
     pub top_combos: Vec<ComboStats>,
+    pub top_chords: Vec<ChordStats>,
+    /// Combos that fired at automated rates, such as a stuck key or a macro, most presses
+    /// first. Their presses are kept out of the combo counts.
+    pub combo_storms: Vec<ComboStormStats>,
+    pub top_bigrams: Vec<BigramStats>,
+    /// How often consecutive keys switch hands, over all recorded bigrams
+    pub hand_alternation: HandAlternation,
+    pub top_workspaces: Vec<ContextStats>,
+    pub top_monitors: Vec<ContextStats>,
+    pub top_projects: Vec<ContextStats>,
+    /// Shortcuts used in each application, most typed in first
+    pub app_shortcuts: Vec<AppShortcuts>,
+    /// Productivity score per day, oldest first
+    pub daily_scores: Vec<DailyScore>,
+    /// Typing effort per day over the same days as the scores, oldest first
+    pub daily_effort: Vec<DailyEffort>,
+    /// Progress towards each goal set with `kitmap goals`
+    pub goals: Vec<GoalProgress>,
+    pub remote_keys_count: i64,
+    pub local_keys_count: i64,
     pub spacebar_count: i64,
     pub enter_count: i64,
     pub backspace_count: i64,
@@ -49,256 +378,1243 @@ pub struct AllStats {
     pub letter_keys_count: i64,
     pub number_keys_count: i64,
     pub special_keys_count: i64,
+    /// Where non-modifier presses land on the keyboard
+    pub row_usage: RowUsage,
     pub hourly_distribution: Vec<HourlyStats>,
     pub daily_distribution: Vec<DailyStats>,
+    /// Key presses by day of the week and hour, Monday first
+    pub weekday_hours: Vec<WeekdayHourStats>,
     pub most_active_hour: Option<HourlyStats>,
     pub most_active_day: Option<DailyStats>,
     pub average_keys_per_session: f64,
     pub average_typing_speed: f64,
     pub max_typing_speed: f64,
+    /// Histogram and percentiles of the typing speed samples
+    pub speed_distribution: SpeedDistribution,
+    /// Fastest sustained bursts of typing, by average speed
+    pub top_bursts: Vec<BurstStats>,
+    /// Histogram, percentiles and hourly medians of the time between key presses
+    pub key_intervals: KeyIntervals,
+    /// Volume, speed, active hours and top keys on weekdays and on weekends
+    pub weekday_weekend: WeekdayWeekend,
     pub key_frequency_map: HashMap<String, i64>,
     pub first_recorded: Option<String>,
     pub last_recorded: Option<String>,
     pub unique_keys_used: i64,
     pub keys_per_minute_avg: f64,
+    /// Values of the metrics added with `StatsCalculator::with_metrics`, in the order they
+    /// were added
+    pub custom_metrics: Vec<CustomMetricValue>,
+    /// Fields left empty because what they are counted from doesn't record what the filter
+    /// narrows, such as chords when it narrows apps
+    pub unfiltered: Vec<String>,
 }
 
 pub struct StatsCalculator {
     db: DbConnection,
+    score: ScoreConfig,
+    effort: EffortConfig,
+    board: Board,
+    metrics: Vec<Arc<dyn CustomMetric>>,
 }
 
 impl StatsCalculator {
     pub fn new(db: DbConnection) -> Self {
-        Self { db }
+        Self {
+            db,
+            score: ScoreConfig::default(),
+            effort: EffortConfig::default(),
+            board: Board::standard(),
+            metrics: Vec::new(),
+        }
     }
 
-    pub fn calculate_all(&self) -> Result<AllStats> {
-        let conn = self.db.lock().unwrap();
+    /// Use a custom productivity score formula instead of the default one
+    pub fn with_score_config(mut self, score: ScoreConfig) -> Self {
+        self.score = score;
+        self
+    }
 
-        // Total keys
-        let total_keys: i64 =
-            conn.query_row("SELECT COUNT(*) FROM key_events", [], |row| row.get(0))?;
+    /// Estimate typing effort for the layout set in the config
+    pub fn with_effort_config(mut self, effort: EffortConfig) -> Self {
+        self.effort = effort;
+        self
+    }
 
-        // Total combos
-        let total_combos: i64 =
-            conn.query_row("SELECT COUNT(*) FROM key_combos", [], |row| row.get(0))?;
+    /// Assign fingers and estimate effort for a keyboard other than a standard one
+    pub fn with_board(mut self, board: Board) -> Self {
+        self.board = board;
+        self
+    }
 
-        // Total sessions
-        let total_sessions: i64 =
-            conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+    /// Add statistics to the overall stats, computed after the built-in ones
+    pub fn with_metrics(
+        mut self,
+        metrics: impl IntoIterator<Item = Arc<dyn CustomMetric>>,
+    ) -> Self {
+        self.metrics.extend(metrics);
+        self
+    }
 
-        // Total time from sessions (in minutes)
-        let total_time_minutes: f64 = conn.query_row(
-            "SELECT COALESCE(
-                SUM(
-                    CAST((julianday(end_time) - julianday(start_time)) * 24 * 60 AS REAL)
-                ), 0.0
-            ) FROM sessions WHERE end_time IS NOT NULL",
-            [],
-            |row| row.get(0),
-        )?;
+    /// Get the id of the newest key event, used to detect when new data lands
+    pub fn latest_event_id(&self) -> Result<i64> {
+        self.db.read(|conn| {
+            let id: i64 =
+                conn.query_row("SELECT COALESCE(MAX(id), 0) FROM key_events", [], |row| {
+                    row.get(0)
+                })?;
+            Ok(id)
+        })
+    }
 
-        // Most pressed key
-        let most_pressed_key = self.get_most_pressed_key(&conn)?;
-
-        // Most pressed combo
-        let most_pressed_combo = self.get_most_pressed_combo(&conn)?;
-
-        // Top 20 keys
-        let top_keys = self.get_top_keys(&conn, 20, total_keys)?;
-
-        // Top 10 combos
-        let top_combos = self.get_top_combos(&conn, 10)?;
-
-        // Special key counts
-        let spacebar_count = self.get_key_count(&conn, "Space")?;
-        let enter_count =
-            self.get_key_count(&conn, "Return")? + self.get_key_count(&conn, "Enter")?;
-        let backspace_count = self.get_key_count(&conn, "Backspace")?;
-        let delete_count = self.get_key_count(&conn, "Delete")?;
-        let escape_count = self.get_key_count(&conn, "Escape")?;
-        let tab_count = self.get_key_count(&conn, "Tab")?;
-
-        // Arrow keys count
-        let arrow_keys_count = self.get_key_count(&conn, "UpArrow")?
-            + self.get_key_count(&conn, "DownArrow")?
-            + self.get_key_count(&conn, "LeftArrow")?
-            + self.get_key_count(&conn, "RightArrow")?;
-
-        // Modifier keys count
-        let modifier_keys_count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM key_events WHERE is_modifier = 1",
-            [],
-            |row| row.get(0),
-        )?;
+    /// Overall stats like `calculate_all`, reusing the ones stored by an earlier call while
+    /// no key events have been recorded since. Triggers clear the stored stats when
+    /// other data they depend on changes.
+    pub fn calculate_all_cached(&self, filter: &StatsFilter) -> Result<AllStats> {
+        self.calculate_sections_cached(filter, StatsSection::ALL)
+    }
 
-        // Letter keys count
-        let letter_keys_count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM key_events WHERE key_name GLOB '[A-Za-z]'",
-            [],
-            |row| row.get(0),
-        )?;
+    /// Stats like `calculate_sections`, taken from the stored overall stats while they are
+    /// current. Only unfiltered overall stats are stored, so anything else is calculated
+    /// without storing it.
+    pub fn calculate_sections_cached(
+        &self,
+        filter: &StatsFilter,
+        sections: &[StatsSection],
+    ) -> Result<AllStats> {
+        if !filter.is_empty() {
+            return self.calculate_sections(filter, sections);
+        }
 
-        // Number keys count
-        let number_keys_count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM key_events WHERE key_name GLOB '[0-9]' OR key_name LIKE 'Num%' OR key_name LIKE 'Key%'",
-            [],
-            |row| row.get(0),
-        )?;
+        let latest_id = self.latest_event_id()?;
+        // Scores, effort and goals depend on the config and on what day it is
+        let fingerprint = serde_json::to_string(&(
+            Local::now().date_naive(),
+            &self.score,
+            &self.effort,
+            &self.board,
+        ))?;
 
-        // Special keys count (everything else)
-        let special_keys_count =
-            total_keys - letter_keys_count - number_keys_count - modifier_keys_count;
+        let cached: Option<String> = self.db.read(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT stats FROM stats_cache WHERE latest_id = ?1 AND fingerprint = ?2",
+                    (latest_id, &fingerprint),
+                    |row| row.get(0),
+                )
+                .optional()?)
+        })?;
+        // Stats stored by a version with different fields are recalculated
+        if let Some(Ok(mut stats)) = cached.map(|json| serde_json::from_str::<AllStats>(&json)) {
+            // Custom metrics may read anything, so they are never taken from the store
+            stats.custom_metrics = self
+                .db
+                .read(|conn| custom::values(&self.metrics, conn, filter))?;
+            return Ok(stats);
+        }
 
-        // Hourly distribution
-        let hourly_distribution = self.get_hourly_distribution(&conn)?;
+        if StatsSection::ALL
+            .iter()
+            .any(|section| !sections.contains(section))
+        {
+            return self.calculate_sections(filter, sections);
+        }
 
-        // Daily distribution
-        let daily_distribution = self.get_daily_distribution(&conn)?;
+        let stats = self.calculate_all(filter)?;
+        // A database opened read-only can't keep the cache
+        if self.db.is_read_only() {
+            return Ok(stats);
+        }
+        let params = (
+            latest_id,
+            fingerprint,
+            serde_json::to_string(&stats)?,
+            time::now(),
+        );
+        self.db.call_blocking(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO stats_cache (id, latest_id, fingerprint, stats, computed_at)
+                 VALUES (1, ?1, ?2, ?3, ?4)",
+                params,
+            )?;
+            Ok(())
+        })?;
+        Ok(stats)
+    }
 
-        // Most active hour
-        let most_active_hour = hourly_distribution.iter().max_by_key(|h| h.count).cloned();
+    /// Most pressed keys, with percentages of the presses the filter keeps
+    pub fn top_keys(&self, filter: &StatsFilter, limit: usize) -> Result<Vec<KeyStats>> {
+        self.db.read(|conn| {
+            let counts = self.get_key_counts(conn, filter)?;
+            self.top_keys_of(conn, &counts, limit)
+        })
+    }
 
-        // Most active day
-        let most_active_day = daily_distribution.iter().max_by_key(|d| d.count).cloned();
+    /// Most used combos
+    pub fn top_combos(&self, filter: &StatsFilter, limit: usize) -> Result<Vec<ComboStats>> {
+        self.db
+            .read(|conn| self.get_top_combos(conn, filter, limit))
+    }
 
-        // Average keys per session
-        let average_keys_per_session = if total_sessions > 0 {
-            total_keys as f64 / total_sessions as f64
-        } else {
-            0.0
+    /// Shortcut use in the `limit` applications with the most key presses
+    pub fn app_shortcuts(&self, filter: &StatsFilter, limit: usize) -> Result<Vec<AppShortcuts>> {
+        self.db
+            .read(|conn| self.get_app_shortcuts(conn, filter, limit))
+    }
+
+    /// Most used chords
+    pub fn top_chords(&self, filter: &StatsFilter, limit: usize) -> Result<Vec<ChordStats>> {
+        self.db
+            .read(|conn| self.get_top_chords(conn, filter, limit))
+    }
+
+    /// Fastest sustained bursts of typing, by average speed
+    pub fn top_bursts(&self, filter: &StatsFilter, limit: usize) -> Result<Vec<BurstStats>> {
+        self.db
+            .read(|conn| self.get_top_bursts(conn, filter, limit))
+    }
+
+    /// Key presses per hour of day
+    pub fn hourly(&self, filter: &StatsFilter) -> Result<Vec<HourlyStats>> {
+        self.db
+            .read(|conn| self.get_hourly_distribution(conn, filter))
+    }
+
+    /// Key presses grouped into time buckets, oldest first
+    #[cfg(feature = "graphql")]
+    pub fn activity(
+        &self,
+        filter: &StatsFilter,
+        bucket: TimeBucket,
+    ) -> Result<Vec<ActivityBucket>> {
+        let condition = filter.key_events();
+        self.db.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} as bucket, COUNT(*) FROM key_events WHERE {}
+             GROUP BY bucket ORDER BY bucket",
+                bucket.sql_expr(),
+                condition.sql()
+            ))?;
+
+            let buckets = stmt.query_map(condition.params(), |row| {
+                Ok(ActivityBucket {
+                    start: row.get(0)?,
+                    count: row.get(1)?,
+                })
+            })?;
+
+            Ok(buckets.filter_map(|b| b.ok()).collect())
+        })
+    }
+
+    /// A metric aggregated into `interval_secs` buckets aligned to the Unix epoch, oldest first.
+    /// Intervals of whole days follow local calendar days instead, starting at local midnight.
+    /// Buckets without recorded data are omitted.
+    pub fn time_series(
+        &self,
+        metric: SeriesMetric,
+        filter: &StatsFilter,
+        interval_secs: i64,
+    ) -> Result<Vec<SeriesPoint>> {
+        let (table, aggregate) = metric.source();
+        let condition = match table {
+            "key_events" => filter.key_events(),
+            "key_combos" => filter.combos(),
+            _ => filter.timed()?,
+        };
+        let days = series::interval_days(interval_secs);
+        // Whole-day buckets are numbered by local day since 1970-01-01 (Julian day 2440587.5)
+        let bucket = match days {
+            Some(days) => format!(
+                "CAST(julianday(timestamp, 'localtime') - 2440587.5 AS INTEGER) / {0} * {0}",
+                days
+            ),
+            None => format!("timestamp_ms / 1000 / {0} * {0}", interval_secs.max(1)),
         };
 
-        // Typing speed statistics
-        let (average_typing_speed, max_typing_speed) = self.get_typing_speed_stats(&conn)?;
+        let mut sql = format!(
+            "SELECT {} as bucket, {} as value FROM {} WHERE {} GROUP BY bucket",
+            bucket,
+            aggregate,
+            table,
+            condition.sql()
+        );
+        let mut params = condition.values();
+        // Key presses imported from other trackers count too, where the filter allows
+        if let Some(imported) = filter.imported().filter(|_| metric == SeriesMetric::Keys) {
+            sql = format!(
+                "SELECT bucket, SUM(value) FROM (
+                     {} UNION ALL
+                     SELECT {} as bucket, SUM(presses) FROM imported_activity WHERE {}
+                     GROUP BY bucket
+                 ) GROUP BY bucket",
+                sql,
+                bucket,
+                imported.sql()
+            );
+            params.extend(imported.values());
+        }
 
-        // Key frequency map for heatmap
-        let key_frequency_map = self.get_key_frequency_map(&conn)?;
+        self.db.read(|conn| {
+            let mut stmt = conn.prepare(&format!("{} ORDER BY bucket", sql))?;
 
-        // First and last recorded timestamps
-        let first_recorded = self.get_first_recorded(&conn)?;
-        let last_recorded = self.get_last_recorded(&conn)?;
+            let points = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, f64>(1)?))
+            })?;
 
-        // Unique keys used
-        let unique_keys_used: i64 = conn.query_row(
-            "SELECT COUNT(DISTINCT key_name) FROM key_events",
-            [],
-            |row| row.get(0),
-        )?;
+            points
+                .filter_map(|p| p.ok())
+                .map(|(bucket, value)| {
+                    let time = match days {
+                        Some(_) => series::local_day_start(bucket)?,
+                        None => bucket,
+                    };
+                    Ok(SeriesPoint { time, value })
+                })
+                .collect()
+        })
+    }
 
-        // Keys per minute average
-        let keys_per_minute_avg = if total_time_minutes > 0.0 {
-            total_keys as f64 / total_time_minutes
-        } else {
-            0.0
+    /// Per-key heatmap values, most pressed keys first
+    pub fn key_heatmap(&self, metric: HeatmapMetric, filter: &StatsFilter) -> Result<Vec<KeyHeat>> {
+        let condition = filter.key_events();
+        self.db.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT key_name, {} as hour, COUNT(*) FROM key_events WHERE {}
+             GROUP BY key_name, hour",
+                LOCAL_HOUR,
+                condition.sql()
+            ))?;
+
+            let rows = stmt.query_map(condition.params(), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?;
+
+            let mut hours_by_key: HashMap<String, [i64; 24]> = HashMap::new();
+            for (key_name, hour, count) in rows.filter_map(|r| r.ok()) {
+                hours_by_key.entry(key_name).or_insert([0; 24])[hour.rem_euclid(24) as usize] +=
+                    count;
+            }
+
+            let mut heat: Vec<KeyHeat> = hours_by_key
+                .into_iter()
+                .map(|(key_name, hours)| {
+                    let count = hours.iter().sum();
+                    let value = match metric {
+                        HeatmapMetric::Count => count as f64,
+                        HeatmapMetric::AvgHour => circular_mean_hour(&hours),
+                    };
+                    KeyHeat {
+                        key_name,
+                        count,
+                        value,
+                    }
+                })
+                .collect();
+            heat.sort_by(|a, b| b.count.cmp(&a.count).then(a.key_name.cmp(&b.key_name)));
+
+            Ok(heat)
+        })
+    }
+
+    /// Keys recorded after event `after_id`, plus rates over the trailing window
+    pub fn live_keys(&self, after_id: i64, limit: usize) -> Result<LiveKeys> {
+        self.db.read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, key_name FROM key_events WHERE id > ?1 ORDER BY id DESC LIMIT ?2",
+            )?;
+            let mut recent = stmt
+                .query_map((after_id, limit as i64), |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })?
+                .filter_map(|r| r.ok())
+                .collect::<Vec<_>>();
+            recent.reverse();
+
+            let now = chrono::Local::now();
+            let kps_since =
+                (now - chrono::Duration::seconds(LIVE_KPS_WINDOW_SECS)).timestamp_millis();
+            let wpm_since = (now - chrono::Duration::minutes(1)).timestamp_millis();
+            let (window_keys, typed_last_minute): (i64, i64) = conn.query_row(
+                "SELECT COALESCE(SUM(timestamp_ms >= ?1), 0), COALESCE(SUM(is_modifier = 0), 0)
+             FROM key_events WHERE timestamp_ms >= ?2",
+                (kps_since, wpm_since),
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+
+            Ok(LiveKeys {
+                last_id: recent.last().map_or(after_id, |(id, _)| *id),
+                keys: recent.into_iter().map(|(_, key)| key).collect(),
+                kps: window_keys as f64 / LIVE_KPS_WINDOW_SECS as f64,
+                wpm: typed_last_minute as f64 / 5.0,
+            })
+        })
+    }
+
+    /// Key usage by calendar month across all years, with each month's most pressed keys
+    pub fn seasonality(&self, filter: &StatsFilter, top_keys: usize) -> Result<Seasonality> {
+        let condition = filter.key_events();
+        self.db.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} as month, {} as year, COUNT(*)
+             FROM key_events WHERE {} GROUP BY month, year",
+                LOCAL_MONTH,
+                LOCAL_YEAR,
+                condition.sql()
+            ))?;
+            let month_years = stmt
+                .query_map(condition.params(), |row| {
+                    Ok((
+                        row.get::<_, u32>(0)?,
+                        row.get::<_, i32>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                })?
+                .filter_map(|r| r.ok())
+                .collect::<Vec<_>>();
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} as month, key_name, COUNT(*) as cnt
+             FROM key_events WHERE {} GROUP BY month, key_name ORDER BY month, cnt DESC, key_name",
+                LOCAL_MONTH,
+                condition.sql()
+            ))?;
+            let mut keys_by_month: HashMap<u32, Vec<(String, i64)>> = HashMap::new();
+            for (month, key_name, count) in stmt
+                .query_map(condition.params(), |row| {
+                    Ok((
+                        row.get::<_, u32>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                })?
+                .filter_map(|r| r.ok())
+            {
+                keys_by_month
+                    .entry(month)
+                    .or_default()
+                    .push((key_name, count));
+            }
+
+            let mut years: Vec<i32> = month_years.iter().map(|(_, year, _)| *year).collect();
+            years.sort_unstable();
+            years.dedup();
+
+            let mut months: Vec<MonthSeasonality> = (1..=12)
+                .map(|month| {
+                    let (total_keys, active_years) = month_years
+                        .iter()
+                        .filter(|(m, _, _)| *m == month)
+                        .fold((0, 0), |(keys, years), (_, _, count)| {
+                            (keys + count, years + 1)
+                        });
+                    let top_keys = keys_by_month
+                        .get(&month)
+                        .map(|keys| {
+                            self.name_keys(
+                                keys.iter()
+                                    .take(top_keys)
+                                    .map(|(key_name, count)| KeyStats {
+                                        key_name: key_name.clone(),
+                                        count: *count,
+                                        percentage: *count as f64 / total_keys.max(1) as f64
+                                            * 100.0,
+                                        composed: None,
+                                    })
+                                    .collect(),
+                            )
+                        })
+                        .unwrap_or_default();
+
+                    MonthSeasonality {
+                        month,
+                        name: NaiveDate::from_ymd_opt(2000, month, 1)
+                            .map(|d| d.format("%B").to_string())
+                            .unwrap_or_default(),
+                        total_keys,
+                        years: active_years,
+                        average_keys: if active_years > 0 {
+                            total_keys as f64 / active_years as f64
+                        } else {
+                            0.0
+                        },
+                        index: 0.0,
+                        top_keys,
+                    }
+                })
+                .collect();
+
+            let active: Vec<f64> = months
+                .iter()
+                .filter(|m| m.years > 0)
+                .map(|m| m.average_keys)
+                .collect();
+            if !active.is_empty() {
+                let typical = active.iter().sum::<f64>() / active.len() as f64;
+                for month in months.iter_mut().filter(|m| m.years > 0) {
+                    month.index = month.average_keys / typical;
+                }
+            }
+
+            Ok(Seasonality { years, months })
+        })
+    }
+
+    /// Rolls, redirects and the other layout-analysis categories of recorded bigrams
+    /// and trigrams. Trigrams are read from the raw key sequence of each session, so
+    /// this is kept out of `calculate_all`.
+    pub fn roll_analysis(&self, filter: &StatsFilter) -> Result<RollAnalysis> {
+        self.db.read(|conn| {
+            let bigrams = self.get_bigrams(conn, filter)?;
+            let trigrams = self.get_trigrams(conn, filter)?;
+            Ok(RollAnalysis::new(&bigrams, &trigrams, &self.board))
+        })
+    }
+
+    /// Most likely next keys after the `limit` most followed keys, and the `limit` most
+    /// common 3-key sequences. Trigrams are read like in `roll_analysis`, so this is also
+    /// kept out of `calculate_all`.
+    pub fn key_sequences(&self, filter: &StatsFilter, limit: usize) -> Result<KeySequences> {
+        self.db.read(|conn| {
+            let bigrams = self.get_bigrams(conn, filter)?;
+            let trigrams = self.get_trigrams(conn, filter)?;
+            Ok(KeySequences::new(&bigrams, &trigrams, limit))
+        })
+    }
+
+    /// Most recent recording sessions with key presses the filter keeps, newest first
+    pub fn recent_sessions(&self, filter: &StatsFilter, limit: usize) -> Result<Vec<SessionStats>> {
+        let condition = filter.sessions();
+        self.db.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT id, start_time, end_time, total_keys,
+                    (julianday(end_time) - julianday(start_time)) * 24 * 60
+             FROM sessions WHERE {} ORDER BY id DESC LIMIT ?",
+                condition.sql()
+            ))?;
+
+            let sessions =
+                stmt.query_map(condition.params_with([Value::from(limit as i64)]), |row| {
+                    Ok(SessionStats {
+                        id: row.get(0)?,
+                        start_time: local_timestamp(row.get(1)?),
+                        end_time: row.get::<_, Option<String>>(2)?.map(local_timestamp),
+                        total_keys: row.get(3)?,
+                        duration_minutes: row.get(4)?,
+                    })
+                })?;
+
+            Ok(sessions.filter_map(|s| s.ok()).collect())
+        })
+    }
+
+    /// Productivity score for the most recent days with activity, oldest first.
+    /// Defaults to the configured history length.
+    pub fn daily_scores(
+        &self,
+        filter: &StatsFilter,
+        days: Option<usize>,
+    ) -> Result<Vec<DailyScore>> {
+        self.db.read(|conn| {
+            self.get_daily_scores(conn, filter, days.unwrap_or(self.score.history_days))
+        })
+    }
+
+    /// Average typing speed on every day of the last `weeks` weeks up to today, oldest
+    /// first. The weeks replace the filter's time range.
+    pub fn daily_speed(&self, filter: &StatsFilter, weeks: usize) -> Result<Vec<DailySpeed>> {
+        let today = Local::now().date_naive();
+        let days = weeks.max(1).saturating_mul(7);
+        let first = i64::try_from(days - 1)
+            .ok()
+            .and_then(Duration::try_days)
+            .and_then(|back| today.checked_sub_signed(back))
+            .ok_or_else(|| anyhow!("{} weeks reach too far into the past", weeks))?;
+        let tomorrow = today
+            .succ_opt()
+            .ok_or_else(|| anyhow!("Date {} is out of range", today))?;
+        let filter = filter.clone().with_range(TimeRange {
+            since: Some(local_midnight(first)?),
+            until: Some(local_midnight(tomorrow)?),
+        });
+        let condition = filter.timed()?;
+
+        let speeds: HashMap<String, (i64, f64)> = self.db.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} as day, COUNT(*), AVG(chars_per_minute) FROM typing_samples
+                 WHERE {} GROUP BY day",
+                LOCAL_DAY,
+                condition.sql()
+            ))?;
+            let rows = stmt.query_map(condition.params(), |row| {
+                Ok((row.get(0)?, (row.get(1)?, row.get(2)?)))
+            })?;
+            Ok(rows.filter_map(|r| r.ok()).collect())
+        })?;
+
+        Ok(first
+            .iter_days()
+            .take(days)
+            .map(|date| {
+                let date = date.format("%Y-%m-%d").to_string();
+                match speeds.get(&date) {
+                    Some(&(samples, average)) => DailySpeed::new(date, samples, Some(average)),
+                    None => DailySpeed::new(date, 0, None),
+                }
+            })
+            .collect())
+    }
+
+    /// Key presses on every day of the last year, from the Monday `CALENDAR_WEEKS` weeks
+    /// ago up to today, oldest first. The year replaces the filter's time range.
+    pub fn activity_calendar(&self, filter: &StatsFilter) -> Result<Vec<CalendarDay>> {
+        let today = Local::now().date_naive();
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let first = monday - Duration::weeks(CALENDAR_WEEKS as i64 - 1);
+        let filter = filter.clone().with_range(TimeRange {
+            since: Some(local_midnight(first)?),
+            until: Some(local_midnight(today + Duration::days(1))?),
+        });
+        let condition = filter.key_events();
+
+        let mut counts: HashMap<String, i64> = self.db.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} as day, COUNT(*) FROM key_events WHERE {} GROUP BY day",
+                LOCAL_DAY,
+                condition.sql()
+            ))?;
+            let rows = stmt.query_map(condition.params(), |row| Ok((row.get(0)?, row.get(1)?)))?;
+            Ok(rows.filter_map(|r| r.ok()).collect())
+        })?;
+        // Key presses imported from other trackers count too, where the filter allows
+        if let Some(imported) = filter.imported() {
+            let imported: Vec<(String, i64)> = self.db.read(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {} as day, SUM(presses) FROM imported_activity WHERE {} GROUP BY day",
+                    LOCAL_DAY,
+                    imported.sql()
+                ))?;
+                let rows =
+                    stmt.query_map(imported.params(), |row| Ok((row.get(0)?, row.get(1)?)))?;
+                Ok(rows.filter_map(|r| r.ok()).collect())
+            })?;
+            for (day, presses) in imported {
+                *counts.entry(day).or_default() += presses;
+            }
+        }
+
+        Ok(calendar::shade(
+            first
+                .iter_days()
+                .take_while(|date| *date <= today)
+                .map(|date| {
+                    let date = date.format("%Y-%m-%d").to_string();
+                    let count = counts.get(&date).copied().unwrap_or(0);
+                    (date, count)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Key presses per key in each local hour or day with any, oldest first
+    pub fn replay(&self, filter: &StatsFilter, step: ReplayStep) -> Result<Replay> {
+        let condition = filter.key_events();
+        let bucket = match step {
+            ReplayStep::Hour => LOCAL_HOUR_START.to_string(),
+            ReplayStep::Day => format!("{} || ' 00:00:00'", LOCAL_DAY),
         };
 
-        Ok(AllStats {
+        let frames = self.db.read(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} as bucket, key_name, COUNT(*) FROM key_events WHERE {}
+                 GROUP BY bucket, key_name ORDER BY bucket",
+                bucket,
+                condition.sql()
+            ))?;
+            let rows = stmt.query_map(condition.params(), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?;
+
+            let mut frames: Vec<ReplayFrame> = Vec::new();
+            for (bucket, key_name, count) in rows.filter_map(|r| r.ok()) {
+                let Ok(start) = NaiveDateTime::parse_from_str(&bucket, "%Y-%m-%d %H:%M:%S") else {
+                    continue;
+                };
+                match frames.last_mut() {
+                    Some(frame) if frame.start == start => {
+                        frame.keys.insert(key_name, count);
+                    }
+                    _ => frames.push(ReplayFrame {
+                        start,
+                        keys: BTreeMap::from([(key_name, count)]),
+                    }),
+                }
+            }
+            Ok(frames)
+        })?;
+
+        Ok(Replay { step, frames })
+    }
+
+    /// Summary of a single local calendar day, used for reports. The day replaces the
+    /// filter's time range.
+    pub fn day_summary(&self, filter: &StatsFilter, date: NaiveDate) -> Result<DaySummary> {
+        let filter = filter.clone().with_range(TimeRange::day(date)?);
+        let events = filter.key_events();
+        let combos = filter.combos();
+
+        let (total_keys, total_combos, score) = self.db.read(|conn| {
+            let total_keys: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM key_events WHERE {}", events.sql()),
+                events.params(),
+                |row| row.get(0),
+            )?;
+            let total_combos: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM key_combos WHERE {}", combos.sql()),
+                combos.params(),
+                |row| row.get(0),
+            )?;
+            let score = self.get_daily_scores(conn, &filter, 1)?.pop();
+            Ok((total_keys, total_combos, score))
+        })?;
+
+        Ok(DaySummary {
+            date: date.format("%Y-%m-%d").to_string(),
             total_keys,
             total_combos,
-            total_sessions,
-            total_time_minutes,
-            most_pressed_key,
-            most_pressed_combo,
-            top_keys,
-            top_combos,
-            spacebar_count,
-            enter_count,
-            backspace_count,
-            delete_count,
-            escape_count,
-            tab_count,
-            arrow_keys_count,
-            modifier_keys_count,
-            letter_keys_count,
-            number_keys_count,
-            special_keys_count,
-            hourly_distribution,
-            daily_distribution,
-            most_active_hour,
-            most_active_day,
-            average_keys_per_session,
-            average_typing_speed,
-            max_typing_speed,
-            key_frequency_map,
-            first_recorded,
-            last_recorded,
-            unique_keys_used,
-            keys_per_minute_avg,
+            score,
+            top_keys: self.top_keys(&filter, 5)?,
+            top_combos: self.top_combos(&filter, 5)?,
         })
     }
 
-    fn get_most_pressed_key(&self, conn: &rusqlite::Connection) -> Result<Option<KeyStats>> {
-        let total: i64 = conn.query_row("SELECT COUNT(*) FROM key_events", [], |row| row.get(0))?;
+    /// Totals, typing speed, busiest day and every key's count
+    pub fn summary(&self, filter: &StatsFilter) -> Result<RangeSummary> {
+        let events = filter.key_events();
+        let combos = filter.combos();
 
-        let result: Option<(String, i64)> = conn
-            .query_row(
-                "SELECT key_name, COUNT(*) as cnt FROM key_events 
-             GROUP BY key_name ORDER BY cnt DESC LIMIT 1",
-                [],
-                |row| Ok((row.get(0)?, row.get(1)?)),
-            )
-            .ok();
+        self.db.read(|conn| {
+            let counts = self.get_key_counts(conn, filter)?;
+            let active_minutes: i64 = conn.query_row(
+                &format!(
+                    "SELECT COUNT(DISTINCT timestamp_ms / 60000) FROM key_events WHERE {}",
+                    events.sql()
+                ),
+                events.params(),
+                |row| row.get(0),
+            )?;
+            let total_combos: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM key_combos WHERE {}", combos.sql()),
+                combos.params(),
+                |row| row.get(0),
+            )?;
+            let (average_typing_speed, _) = self.get_typing_speed_stats(conn, filter)?;
+            let most_active_day = conn
+                .query_row(
+                    &format!(
+                        "SELECT {} as day, COUNT(*) as cnt FROM key_events WHERE {}
+                         GROUP BY day ORDER BY cnt DESC, day LIMIT 1",
+                        LOCAL_DAY,
+                        events.sql()
+                    ),
+                    events.params(),
+                    |row| {
+                        Ok(DailyStats {
+                            day: row.get(0)?,
+                            count: row.get(1)?,
+                        })
+                    },
+                )
+                .optional()?;
 
-        Ok(result.map(|(key_name, count)| KeyStats {
-            key_name,
-            count,
-            percentage: if total > 0 {
-                (count as f64 / total as f64) * 100.0
-            } else {
-                0.0
-            },
-        }))
+            Ok(RangeSummary {
+                total_keys: counts.iter().map(|key| key.count).sum(),
+                total_combos,
+                active_minutes,
+                average_typing_speed,
+                most_active_day,
+                keys: self.top_keys_of(conn, &counts, counts.len())?,
+            })
+        })
     }
 
-    fn get_most_pressed_combo(&self, conn: &rusqlite::Connection) -> Result<Option<ComboStats>> {
-        let result: Option<(String, i64)> = conn
-            .query_row(
-                "SELECT combo, COUNT(*) as cnt FROM key_combos 
-             GROUP BY combo ORDER BY cnt DESC LIMIT 1",
-                [],
-                |row| Ok((row.get(0)?, row.get(1)?)),
+    pub fn calculate_all(&self, filter: &StatsFilter) -> Result<AllStats> {
+        self.calculate_sections(filter, StatsSection::ALL)
+    }
+
+    /// Overall stats with only the fields of `sections` calculated. Totals are always
+    /// calculated; every other field not in a requested section is left empty.
+    /// Goals always track the current day and week, whatever the filter.
+    pub fn calculate_sections(
+        &self,
+        filter: &StatsFilter,
+        sections: &[StatsSection],
+    ) -> Result<AllStats> {
+        let wants = |section| sections.contains(&section);
+        // Data recording only when it happened can't follow a filter narrowing key events
+        let follows_timed = !filter.narrows_keys();
+
+        std::thread::scope(|scope| {
+            let queries = ParallelQueries {
+                scope,
+                db: &self.db,
+                sections,
+            };
+
+            // Bigrams, for the top 10 and hand alternation
+            let bigrams = queries.spawn(StatsSection::Keys, |conn| self.get_bigrams(conn, filter));
+
+            // Top 10 workspaces
+            let top_workspaces = queries.spawn(StatsSection::Contexts, |conn| {
+                self.get_top_contexts(conn, filter, "workspace", 10)
+            });
+
+            // Key presses per monitor
+            let top_monitors = queries.spawn(StatsSection::Contexts, |conn| {
+                self.get_top_contexts(conn, filter, "monitor", 10)
+            });
+
+            // Top 10 projects
+            let top_projects = queries.spawn(StatsSection::Contexts, |conn| {
+                self.get_top_contexts(conn, filter, "project", 10)
+            });
+
+            // Shortcut use in the 10 most typed in apps
+            let app_shortcuts = queries.spawn(StatsSection::Contexts, |conn| {
+                self.get_app_shortcuts(conn, filter, 10)
+            });
+
+            // Productivity score history
+            let daily_scores = queries.spawn(StatsSection::Progress, |conn| {
+                self.get_daily_scores(conn, filter, self.score.history_days)
+            });
+
+            // Typing effort over the same days
+            let daily_effort = queries.spawn(StatsSection::Progress, |conn| {
+                self.get_daily_effort(conn, filter, self.score.history_days)
+            });
+
+            // Remote (SSH) vs local keys, counting only keys recorded with remote tracking
+            let remote_local_counts = queries.spawn(StatsSection::Contexts, |conn| {
+                let condition = filter.key_events();
+                Ok(conn.query_row(
+                    &format!(
+                        "SELECT COALESCE(SUM(remote = 1), 0), COALESCE(SUM(remote = 0), 0)
+                         FROM key_events WHERE {}",
+                        condition.sql()
+                    ),
+                    condition.params(),
+                    |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+                )?)
+            });
+
+            // Hourly distribution
+            let hourly_distribution = queries.spawn(StatsSection::Activity, |conn| {
+                self.get_hourly_distribution(conn, filter)
+            });
+
+            // Daily distribution
+            let daily_distribution = queries.spawn(StatsSection::Activity, |conn| {
+                self.get_daily_distribution(conn, filter)
+            });
+            let weekday_hours = queries.spawn(StatsSection::Activity, |conn| {
+                self.get_weekday_hours(conn, filter)
+            });
+
+            // Typing speed histogram and percentiles
+            let speed_distribution = queries.spawn(StatsSection::Activity, |conn| {
+                when(follows_timed, || self.get_speed_distribution(conn, filter))
+            });
+
+            // Weekdays against weekends
+            let weekday_weekend = queries.spawn(StatsSection::Weekend, |conn| {
+                self.get_weekday_weekend(conn, filter, 5)
+            });
+
+            // Time between consecutive key presses, read from each session's key sequence
+            let key_intervals = queries.spawn(StatsSection::Activity, |conn| {
+                self.get_key_intervals(conn, filter)
+            });
+
+            // Queries served by indexes and rollup tables run on this thread in the meantime
+            self.db.read(|conn| {
+                let combos = filter.combos();
+                let sessions = filter.sessions();
+
+                // Presses per key, which the key totals and counts are derived from
+                let counts = self.get_key_counts(conn, filter)?;
+                let count_of = |names: &[&str]| -> i64 {
+                    counts
+                        .iter()
+                        .filter(|key| names.contains(&key.key_name.as_str()))
+                        .map(|key| key.count)
+                        .sum()
+                };
+
+                // Total keys
+                let total_keys: i64 = counts.iter().map(|key| key.count).sum();
+
+                // Total combos
+                let total_combos: i64 = conn.query_row(
+                    &format!("SELECT COUNT(*) FROM key_combos WHERE {}", combos.sql()),
+                    combos.params(),
+                    |row| row.get(0),
+                )?;
+
+                // Total chords
+                let total_chords: i64 = when(follows_timed, || {
+                    let timed = filter.timed()?;
+                    Ok(conn.query_row(
+                        &format!("SELECT COUNT(*) FROM key_chords WHERE {}", timed.sql()),
+                        timed.params(),
+                        |row| row.get(0),
+                    )?)
+                })?;
+
+                // Total sessions
+                let total_sessions: i64 = conn.query_row(
+                    &format!("SELECT COUNT(*) FROM sessions WHERE {}", sessions.sql()),
+                    sessions.params(),
+                    |row| row.get(0),
+                )?;
+
+                // Total time from sessions (in minutes)
+                let total_time_minutes: f64 = conn.query_row(
+                    &format!(
+                        "SELECT COALESCE(
+                        SUM(
+                            CAST((julianday(end_time) - julianday(start_time)) * 24 * 60 AS REAL)
+                        ), 0.0
+                    ) FROM sessions WHERE end_time IS NOT NULL AND {}",
+                        sessions.sql()
+                    ),
+                    sessions.params(),
+                    |row| row.get(0),
+                )?;
+
+                // Most pressed key
+                let most_pressed_key = when(wants(StatsSection::Overview), || {
+                    Ok(self.top_keys_of(conn, &counts, 1)?.into_iter().next())
+                })?;
+
+                // Most pressed combo
+                let most_pressed_combo = when(wants(StatsSection::Overview), || {
+                    Ok(self.get_top_combos(conn, filter, 1)?.into_iter().next())
+                })?;
+
+                // Top 20 keys
+                let top_keys = when(wants(StatsSection::Top), || {
+                    self.top_keys_of(conn, &counts, 20)
+                })?;
+
+                // Top 10 combos
+                let top_combos = when(wants(StatsSection::Top), || {
+                    self.get_top_combos(conn, filter, 10)
+                })?;
+
+                // Top 10 chords
+                let top_chords = when(wants(StatsSection::Top) && follows_timed, || {
+                    self.get_top_chords(conn, filter, 10)
+                })?;
+
+                // Top 10 combo storms
+                let combo_storms = when(wants(StatsSection::Top) && follows_timed, || {
+                    self.get_combo_storms(conn, filter, 10)
+                })?;
+
+                // Top 10 bursts of fast typing
+                let top_bursts = when(wants(StatsSection::Activity) && follows_timed, || {
+                    self.get_top_bursts(conn, filter, 10)
+                })?;
+
+                // Goal progress in the current day and week
+                let goals = when(wants(StatsSection::Progress), || goals::progress(conn))?;
+
+                // Special key counts
+                let keys_section = wants(StatsSection::Keys);
+                let special_key_count =
+                    |names: &[&str]| if keys_section { count_of(names) } else { 0 };
+                let spacebar_count = special_key_count(&["Space"]);
+                let enter_count = special_key_count(&["Return", "Enter"]);
+                let backspace_count = special_key_count(&["Backspace"]);
+                let delete_count = special_key_count(&["Delete"]);
+                let escape_count = special_key_count(&["Escape"]);
+                let tab_count = special_key_count(&["Tab"]);
+                let arrow_keys_count =
+                    special_key_count(&["UpArrow", "DownArrow", "LeftArrow", "RightArrow"]);
+
+                // Modifier, letter and number keys, in the categories the config puts them
+                let (modifier_keys_count, letter_keys_count, number_keys_count) = if keys_section {
+                    let category_count = |category| -> i64 {
+                        counts
+                            .iter()
+                            .filter(|key| self.board.category_of(&key.key_name) == category)
+                            .map(|key| key.count)
+                            .sum()
+                    };
+                    (
+                        counts.iter().map(|key| key.count - key.typed).sum(),
+                        category_count(KeyCategory::Letter),
+                        category_count(KeyCategory::Digit),
+                    )
+                } else {
+                    (0, 0, 0)
+                };
+
+                // Row usage of non-modifier keys
+                let row_usage = when(keys_section, || {
+                    let typed: Vec<(String, i64)> = counts
+                        .iter()
+                        .filter(|key| key.typed > 0)
+                        .map(|key| (key.key_name.clone(), key.typed))
+                        .collect();
+                    Ok(RowUsage::new(&typed))
+                })?;
+
+                // Average keys per session
+                let average_keys_per_session = if total_sessions > 0 {
+                    total_keys as f64 / total_sessions as f64
+                } else {
+                    0.0
+                };
+
+                // Typing speed statistics
+                let (average_typing_speed, max_typing_speed) =
+                    when(wants(StatsSection::Activity) && follows_timed, || {
+                        self.get_typing_speed_stats(conn, filter)
+                    })?;
+
+                // Key frequency map for heatmap
+                let key_frequency_map = when(wants(StatsSection::Heatmap), || {
+                    Ok(counts
+                        .iter()
+                        .map(|key| (key.key_name.clone(), key.count))
+                        .collect())
+                })?;
+
+                // First and last recorded timestamps
+                let (first_recorded, last_recorded) = when(wants(StatsSection::Overview), || {
+                    Ok((
+                        self.get_first_recorded(conn, filter)?,
+                        self.get_last_recorded(conn, filter)?,
+                    ))
+                })?;
+
+                // Unique keys used
+                let unique_keys_used = counts.len() as i64;
+
+                // Keys per minute average
+                let keys_per_minute_avg = if total_time_minutes > 0.0 {
+                    total_keys as f64 / total_time_minutes
+                } else {
+                    0.0
+                };
+
+                let (remote_keys_count, local_keys_count) = join(remote_local_counts)?;
+
+                // Special keys count (everything else)
+                let special_keys_count = if keys_section {
+                    total_keys - letter_keys_count - number_keys_count - modifier_keys_count
+                } else {
+                    0
+                };
+
+                let hourly_distribution = join(hourly_distribution)?;
+                let daily_distribution = join(daily_distribution)?;
+
+                // Most active hour
+                let most_active_hour = hourly_distribution.iter().max_by_key(|h| h.count).cloned();
+
+                // Most active day
+                let most_active_day = daily_distribution.iter().max_by_key(|d| d.count).cloned();
+
+                let bigrams = join(bigrams)?;
+
+                // Metrics added on top of these
+                let custom_metrics = custom::values(&self.metrics, conn, filter)?;
+
+                let unfiltered = if follows_timed {
+                    Vec::new()
+                } else {
+                    TIMED_FIELDS
+                        .iter()
+                        .filter(|&&field| {
+                            STATS_FIELDS
+                                .iter()
+                                .any(|&(name, section)| name == field && section.is_none_or(wants))
+                        })
+                        .map(|field| field.to_string())
+                        .collect()
+                };
+
+                Ok(AllStats {
+                    total_keys,
+                    total_combos,
+                    total_chords,
+                    total_sessions,
+                    total_time_minutes,
+                    most_pressed_key,
+                    most_pressed_combo,
+                    top_keys,
+                    top_combos,
+                    top_chords,
+                    combo_storms,
+                    top_bigrams: top_bigrams(&bigrams, 10),
+                    hand_alternation: HandAlternation::new(&bigrams, &self.board),
+                    top_workspaces: join(top_workspaces)?,
+                    top_monitors: join(top_monitors)?,
+                    top_projects: join(top_projects)?,
+                    app_shortcuts: join(app_shortcuts)?,
+                    daily_scores: join(daily_scores)?,
+                    daily_effort: join(daily_effort)?,
+                    goals,
+                    remote_keys_count,
+                    local_keys_count,
+                    spacebar_count,
+                    enter_count,
+                    backspace_count,
+                    delete_count,
+                    escape_count,
+                    tab_count,
+                    arrow_keys_count,
+                    modifier_keys_count,
+                    letter_keys_count,
+                    number_keys_count,
+                    special_keys_count,
+                    row_usage,
+                    hourly_distribution,
+                    daily_distribution,
+                    weekday_hours: join(weekday_hours)?,
+                    most_active_hour,
+                    most_active_day,
+                    average_keys_per_session,
+                    average_typing_speed,
+                    max_typing_speed,
+                    speed_distribution: join(speed_distribution)?,
+                    top_bursts,
+                    key_intervals: join(key_intervals)?,
+                    weekday_weekend: join(weekday_weekend)?,
+                    key_frequency_map,
+                    first_recorded,
+                    last_recorded,
+                    unique_keys_used,
+                    keys_per_minute_avg,
+                    custom_metrics,
+                    unfiltered,
+                })
+            })
+        })
+    }
+
+    /// Presses of each key the filter keeps, most pressed first. Without a filter they
+    /// come from the `key_counts` rollup.
+    fn get_key_counts(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<Vec<KeyCount>> {
+        let condition = filter.key_events();
+        let sql = if filter.is_empty() {
+            "SELECT key_name, count, typed FROM key_counts ORDER BY count DESC".to_string()
+        } else {
+            format!(
+                "SELECT key_name, COUNT(*) as cnt, SUM(is_modifier = 0) FROM key_events
+                 WHERE {} GROUP BY key_name ORDER BY cnt DESC",
+                condition.sql()
             )
-            .ok();
+        };
+        let mut stmt = conn.prepare(&sql)?;
+
+        let counts = stmt.query_map(condition.params(), |row| {
+            Ok(KeyCount {
+                key_name: row.get(0)?,
+                count: row.get(1)?,
+                typed: row.get(2)?,
+            })
+        })?;
 
-        Ok(result.map(|(combo, count)| ComboStats { combo, count }))
+        Ok(counts.filter_map(|k| k.ok()).collect())
     }
 
-    fn get_top_keys(
+    /// The `limit` most pressed of `counts`, with percentages of all of them
+    fn top_keys_of(
         &self,
         conn: &rusqlite::Connection,
+        counts: &[KeyCount],
         limit: usize,
-        total: i64,
     ) -> Result<Vec<KeyStats>> {
-        let mut stmt = conn.prepare(
-            "SELECT key_name, COUNT(*) as cnt FROM key_events 
-             GROUP BY key_name ORDER BY cnt DESC LIMIT ?1",
-        )?;
-
-        let keys = stmt.query_map([limit as i64], |row| {
-            let key_name: String = row.get(0)?;
-            let count: i64 = row.get(1)?;
-            Ok(KeyStats {
-                key_name,
-                count,
+        let total: i64 = counts.iter().map(|key| key.count).sum();
+        let mut keys: Vec<KeyStats> = counts
+            .iter()
+            .take(self.fetch_limit(limit))
+            .map(|key| KeyStats {
+                key_name: key.key_name.clone(),
+                count: key.count,
                 percentage: if total > 0 {
-                    (count as f64 / total as f64) * 100.0
+                    (key.count as f64 / total as f64) * 100.0
                 } else {
                     0.0
                 },
+                composed: None,
             })
-        })?;
+            .collect();
 
-        Ok(keys.filter_map(|k| k.ok()).collect())
+        label_unknown_keys(conn, &mut keys)?;
+        let mut keys = self.name_keys(keys);
+        keys.truncate(limit);
+        Ok(keys)
     }
 
-    fn get_top_combos(&self, conn: &rusqlite::Connection, limit: usize) -> Result<Vec<ComboStats>> {
-        let mut stmt = conn.prepare(
-            "SELECT combo, COUNT(*) as cnt FROM key_combos 
-             GROUP BY combo ORDER BY cnt DESC LIMIT ?1",
-        )?;
+    /// Keys to read for the `limit` most pressed keys, with room for keys the config
+    /// gives the same name, which are added up afterwards
+    fn fetch_limit(&self, limit: usize) -> usize {
+        limit + self.board.names.len()
+    }
+
+    /// Show keys under the names given to them in the config, adding up keys given
+    /// the same name
+    fn name_keys(&self, keys: Vec<KeyStats>) -> Vec<KeyStats> {
+        let mut named: Vec<KeyStats> = Vec::with_capacity(keys.len());
+        for mut key in keys {
+            let name = self.board.display_name(&key.key_name);
+            if name != key.key_name {
+                key.key_name = name.to_string();
+                key.composed = None;
+            }
+            match named.iter_mut().find(|k| k.key_name == key.key_name) {
+                Some(existing) => {
+                    existing.count += key.count;
+                    existing.percentage += key.percentage;
+                }
+                None => named.push(key),
+            }
+        }
+        named.sort_by_key(|key| std::cmp::Reverse(key.count));
+        named
+    }
 
-        let combos = stmt.query_map([limit as i64], |row| {
+    fn get_top_combos(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+        limit: usize,
+    ) -> Result<Vec<ComboStats>> {
+        let condition = filter.combos();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT combo, COUNT(*) as cnt FROM key_combos WHERE {}
+             GROUP BY combo ORDER BY cnt DESC LIMIT ?",
+            condition.sql()
+        ))?;
+
+        let combos = stmt.query_map(condition.params_with([Value::from(limit as i64)]), |row| {
             Ok(ComboStats {
                 combo: row.get(0)?,
                 count: row.get(1)?,
@@ -308,22 +1624,255 @@ impl StatsCalculator {
         Ok(combos.filter_map(|c| c.ok()).collect())
     }
 
-    fn get_key_count(&self, conn: &rusqlite::Connection, key_name: &str) -> Result<i64> {
-        let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM key_events WHERE key_name = ?1",
-            [key_name],
+    fn get_top_chords(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+        limit: usize,
+    ) -> Result<Vec<ChordStats>> {
+        let condition = filter.timed()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT chord, key_count, COUNT(*) as cnt, AVG(spread_ms) FROM key_chords
+             WHERE {} GROUP BY chord ORDER BY cnt DESC LIMIT ?",
+            condition.sql()
+        ))?;
+
+        let chords = stmt.query_map(
+            condition.params_with([Value::from(limit as i64)]),
+            chord_stats,
+        )?;
+
+        Ok(chords.filter_map(|c| c.ok()).collect())
+    }
+
+    fn get_combo_storms(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+        limit: usize,
+    ) -> Result<Vec<ComboStormStats>> {
+        let condition = filter.storms()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT combo, COUNT(*), SUM(count) as presses, MAX(end_time) FROM combo_storms
+             WHERE {} GROUP BY combo ORDER BY presses DESC LIMIT ?",
+            condition.sql()
+        ))?;
+
+        let storms = stmt.query_map(condition.params_with([Value::from(limit as i64)]), |row| {
+            Ok(ComboStormStats {
+                combo: row.get(0)?,
+                storms: row.get(1)?,
+                count: row.get(2)?,
+                last_seen: local_timestamp(row.get(3)?),
+            })
+        })?;
+
+        Ok(storms.filter_map(|s| s.ok()).collect())
+    }
+
+    fn get_top_bursts(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+        limit: usize,
+    ) -> Result<Vec<BurstStats>> {
+        let condition = filter.timed()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT timestamp, duration_ms, keys, peak_cpm, average_cpm FROM typing_bursts
+             WHERE {} ORDER BY average_cpm DESC LIMIT ?",
+            condition.sql()
+        ))?;
+
+        let bursts = stmt.query_map(condition.params_with([Value::from(limit as i64)]), |row| {
+            Ok(BurstStats {
+                started: local_timestamp(row.get(0)?),
+                duration_secs: row.get::<_, i64>(1)? as f64 / 1000.0,
+                keys: row.get(2)?,
+                peak_cpm: row.get(3)?,
+                average_cpm: row.get(4)?,
+            })
+        })?;
+
+        Ok(bursts.filter_map(|b| b.ok()).collect())
+    }
+
+    /// Consecutive key pairs as (first, second, count). Counted from the key events the
+    /// filter keeps when `key_bigrams` can't follow it.
+    fn get_bigrams(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<Vec<(String, String, i64)>> {
+        let (sql, condition) = if filter.counts_bigrams_from_events() {
+            let condition = filter.key_events();
+            let sql = format!(
+                "SELECT first_key, second_key, COUNT(*) FROM (
+                 SELECT LAG(key_name) OVER w as first_key, key_name as second_key
+                 FROM key_events
+                 WHERE session_id IS NOT NULL AND seq IS NOT NULL AND {}
+                 WINDOW w AS (PARTITION BY session_id ORDER BY seq)
+             )
+             WHERE first_key IS NOT NULL
+             GROUP BY first_key, second_key",
+                condition.sql()
+            );
+            (sql, condition)
+        } else {
+            let condition = filter.bigrams();
+            let sql = format!(
+                "SELECT first_key, second_key, SUM(count) FROM key_bigrams WHERE {}
+             GROUP BY first_key, second_key",
+                condition.sql()
+            );
+            (sql, condition)
+        };
+
+        let mut stmt = conn.prepare(&sql)?;
+        let bigrams = stmt.query_map(condition.params(), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+
+        Ok(bigrams.filter_map(|b| b.ok()).collect())
+    }
+
+    /// Runs of three non-modifier keys as (first, second, third, count), read from the raw
+    /// key sequence of each session
+    fn get_trigrams(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<Vec<(String, String, String, i64)>> {
+        let condition = filter.key_events();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT first_key, second_key, third_key, COUNT(*) FROM (
+                 SELECT LAG(key_name, 2) OVER w as first_key,
+                        LAG(key_name, 1) OVER w as second_key,
+                        key_name as third_key
+                 FROM key_events
+                 WHERE is_modifier = 0 AND session_id IS NOT NULL AND seq IS NOT NULL AND {}
+                 WINDOW w AS (PARTITION BY session_id ORDER BY seq)
+             )
+             WHERE first_key IS NOT NULL
+             GROUP BY first_key, second_key, third_key",
+            condition.sql()
+        ))?;
+        let trigrams = stmt.query_map(condition.params(), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?;
+
+        Ok(trigrams.filter_map(|t| t.ok()).collect())
+    }
+
+    /// Key presses grouped by a context column of `key_events`, ignoring untagged events
+    fn get_top_contexts(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+        column: &str,
+        limit: usize,
+    ) -> Result<Vec<ContextStats>> {
+        let condition = filter.key_events();
+        let total: i64 = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM key_events WHERE {} IS NOT NULL AND {}",
+                column,
+                condition.sql()
+            ),
+            condition.params(),
             |row| row.get(0),
         )?;
-        Ok(count)
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {0}, COUNT(*) as cnt FROM key_events WHERE {0} IS NOT NULL AND {1}
+             GROUP BY {0} ORDER BY cnt DESC LIMIT ?",
+            column,
+            condition.sql()
+        ))?;
+
+        let contexts =
+            stmt.query_map(condition.params_with([Value::from(limit as i64)]), |row| {
+                let name: String = row.get(0)?;
+                let count: i64 = row.get(1)?;
+                Ok(ContextStats {
+                    name,
+                    count,
+                    percentage: if total > 0 {
+                        (count as f64 / total as f64) * 100.0
+                    } else {
+                        0.0
+                    },
+                })
+            })?;
+
+        Ok(contexts.filter_map(|c| c.ok()).collect())
     }
 
-    fn get_hourly_distribution(&self, conn: &rusqlite::Connection) -> Result<Vec<HourlyStats>> {
-        let mut stmt = conn.prepare(
-            "SELECT hour, COUNT(*) as cnt FROM key_events 
+    /// Key presses and combos per application, with each application's 5 most used
+    /// combos. Only keys and combos recorded with app tracking count.
+    fn get_app_shortcuts(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+        limit: usize,
+    ) -> Result<Vec<AppShortcuts>> {
+        let events = filter.key_events();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT app, COUNT(*) as cnt FROM key_events WHERE app IS NOT NULL AND {}
+             GROUP BY app ORDER BY cnt DESC LIMIT ?",
+            events.sql()
+        ))?;
+        let apps: Vec<(String, i64)> = stmt
+            .query_map(events.params_with([Value::from(limit as i64)]), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .filter_map(|a| a.ok())
+            .collect();
+
+        let timed = filter.combos();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT app, combo, COUNT(*) as cnt FROM key_combos WHERE app IS NOT NULL AND {}
+             GROUP BY app, combo ORDER BY cnt DESC",
+            timed.sql()
+        ))?;
+        let mut combos: HashMap<String, Vec<ComboStats>> = HashMap::new();
+        for row in stmt.query_map(timed.params(), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                ComboStats {
+                    combo: row.get(1)?,
+                    count: row.get(2)?,
+                },
+            ))
+        })? {
+            let (app, combo) = row?;
+            combos.entry(app).or_default().push(combo);
+        }
+
+        Ok(apps
+            .into_iter()
+            .map(|(app, keys)| {
+                let mut top_combos = combos.remove(&app).unwrap_or_default();
+                let total = top_combos.iter().map(|c| c.count).sum();
+                top_combos.truncate(5);
+                AppShortcuts::new(app, keys, total, top_combos)
+            })
+            .collect())
+    }
+
+    fn get_hourly_distribution(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<Vec<HourlyStats>> {
+        let condition = filter.key_events();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} as hour, COUNT(*) as cnt FROM key_events WHERE {}
              GROUP BY hour ORDER BY hour",
-        )?;
+            LOCAL_HOUR,
+            condition.sql()
+        ))?;
 
-        let hours = stmt.query_map([], |row| {
+        let hours = stmt.query_map(condition.params(), |row| {
             Ok(HourlyStats {
                 hour: row.get(0)?,
                 count: row.get(1)?,
@@ -344,27 +1893,142 @@ impl StatsCalculator {
             .collect())
     }
 
-    fn get_daily_distribution(&self, conn: &rusqlite::Connection) -> Result<Vec<DailyStats>> {
-        let mut stmt = conn.prepare(
-            "SELECT day_of_week, COUNT(*) as cnt FROM key_events 
+    /// Typing effort for the `days` most recent days with key presses the filter keeps
+    fn get_daily_effort(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+        days: usize,
+    ) -> Result<Vec<DailyEffort>> {
+        let condition = filter.key_events();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {1} as day, key_name, COUNT(*) FROM key_events
+             WHERE {0} AND {1} >= COALESCE((
+                 SELECT MIN(day) FROM (
+                     SELECT DISTINCT {1} as day FROM key_events
+                     WHERE {0} ORDER BY day DESC LIMIT ?
+                 )
+             ), '')
+             GROUP BY day, key_name",
+            condition.sql(),
+            LOCAL_DAY
+        ))?;
+
+        let mut counts: BTreeMap<String, Vec<(String, i64)>> = BTreeMap::new();
+        // The condition appears twice, the second time followed by the limit
+        let params = condition.params_with(
+            condition
+                .values()
+                .into_iter()
+                .chain([Value::from(days as i64)]),
+        );
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+        for (day, key, count) in rows.filter_map(|r| r.ok()) {
+            counts.entry(day).or_default().push((key, count));
+        }
+
+        Ok(counts
+            .into_iter()
+            .map(|(day, keys)| DailyEffort::new(day, &keys, self.effort.layout, &self.board))
+            .collect())
+    }
+
+    fn get_daily_scores(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+        days: usize,
+    ) -> Result<Vec<DailyScore>> {
+        let combo_condition = filter.combos();
+        let mut combo_stmt = conn.prepare(&format!(
+            "SELECT {} as day, COUNT(*) FROM key_combos WHERE {}
+             GROUP BY day",
+            LOCAL_DAY,
+            combo_condition.sql()
+        ))?;
+        let combos: HashMap<String, i64> = combo_stmt
+            .query_map(combo_condition.params(), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .filter_map(|c| c.ok())
+            .collect();
+
+        let condition = filter.key_events();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} as day,
+                    COUNT(DISTINCT timestamp_ms / 60000),
+                    SUM(is_modifier = 0),
+                    SUM(key_name IN ('Backspace', 'Delete'))
+             FROM key_events WHERE {}
+             GROUP BY day ORDER BY day DESC LIMIT ?",
+            LOCAL_DAY,
+            condition.sql()
+        ))?;
+
+        let rows = stmt.query_map(condition.params_with([Value::from(days as i64)]), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?;
+
+        let mut scores: Vec<DailyScore> = rows
+            .filter_map(|r| r.ok())
+            .map(|(date, active_minutes, typed, corrections)| {
+                let combo_count = combos.get(&date).copied().unwrap_or(0);
+                let per_typed = |count: i64| {
+                    if typed > 0 {
+                        count as f64 / typed as f64
+                    } else {
+                        0.0
+                    }
+                };
+
+                let metrics = DailyMetrics {
+                    active_minutes,
+                    wpm: if active_minutes > 0 {
+                        typed as f64 / 5.0 / active_minutes as f64
+                    } else {
+                        0.0
+                    },
+                    error_rate: per_typed(corrections),
+                    combo_usage: per_typed(combo_count),
+                };
+                DailyScore::new(date, metrics, &self.score)
+            })
+            .collect();
+
+        scores.reverse();
+        Ok(scores)
+    }
+
+    fn get_daily_distribution(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<Vec<DailyStats>> {
+        let condition = filter.key_events();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} as day_of_week, COUNT(*) as cnt FROM key_events WHERE {}
              GROUP BY day_of_week ORDER BY day_of_week",
-        )?;
+            LOCAL_WEEKDAY,
+            condition.sql()
+        ))?;
 
-        let days = stmt.query_map([], |row| {
+        let days = stmt.query_map(condition.params(), |row| {
             let day_num: i32 = row.get(0)?;
             let count: i64 = row.get(1)?;
             Ok((day_num, count))
         })?;
 
-        let day_names = [
-            "Monday",
-            "Tuesday",
-            "Wednesday",
-            "Thursday",
-            "Friday",
-            "Saturday",
-            "Sunday",
-        ];
         let mut day_map: HashMap<i32, i64> = HashMap::new();
         for d in days.filter_map(|d| d.ok()) {
             day_map.insert(d.0, d.1);
@@ -372,58 +2036,353 @@ impl StatsCalculator {
 
         Ok((0..7)
             .map(|d| DailyStats {
-                day: day_names[d as usize].to_string(),
+                day: DAY_NAMES[d as usize].to_string(),
                 count: *day_map.get(&d).unwrap_or(&0),
             })
             .collect())
     }
 
-    fn get_typing_speed_stats(&self, conn: &rusqlite::Connection) -> Result<(f64, f64)> {
-        let avg: f64 = conn.query_row(
-            "SELECT COALESCE(AVG(chars_per_minute), 0.0) FROM typing_samples",
-            [],
-            |row| row.get(0),
-        )?;
+    fn get_weekday_hours(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<Vec<WeekdayHourStats>> {
+        let condition = filter.key_events();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} as day_of_week, {} as hour, COUNT(*) as cnt FROM key_events WHERE {}
+             GROUP BY day_of_week, hour",
+            LOCAL_WEEKDAY,
+            LOCAL_HOUR,
+            condition.sql()
+        ))?;
 
-        let max: f64 = conn.query_row(
-            "SELECT COALESCE(MAX(chars_per_minute), 0.0) FROM typing_samples",
-            [],
-            |row| row.get(0),
+        let cells = stmt.query_map(condition.params(), |row| {
+            let day_num: usize = row.get(0)?;
+            let hour: usize = row.get(1)?;
+            let count: i64 = row.get(2)?;
+            Ok((day_num, hour, count))
+        })?;
+
+        let mut grid: Vec<WeekdayHourStats> = DAY_NAMES
+            .iter()
+            .map(|day| WeekdayHourStats {
+                day: day.to_string(),
+                hours: vec![0; 24],
+            })
+            .collect();
+        for (day_num, hour, count) in cells.filter_map(|c| c.ok()) {
+            if day_num < 7 && hour < 24 {
+                grid[day_num].hours[hour] = count;
+            }
+        }
+
+        Ok(grid)
+    }
+
+    fn get_typing_speed_stats(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<(f64, f64)> {
+        let condition = filter.timed()?;
+        let speeds = conn.query_row(
+            &format!(
+                "SELECT COALESCE(AVG(chars_per_minute), 0.0), COALESCE(MAX(chars_per_minute), 0.0)
+                 FROM typing_samples WHERE {}",
+                condition.sql()
+            ),
+            condition.params(),
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )?;
 
-        Ok((avg, max))
+        Ok(speeds)
     }
 
-    fn get_key_frequency_map(&self, conn: &rusqlite::Connection) -> Result<HashMap<String, i64>> {
-        let mut stmt =
-            conn.prepare("SELECT key_name, COUNT(*) as cnt FROM key_events GROUP BY key_name")?;
+    fn get_speed_distribution(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<SpeedDistribution> {
+        let condition = filter.timed()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT chars_per_minute FROM typing_samples WHERE {} ORDER BY chars_per_minute",
+            condition.sql()
+        ))?;
+        let speeds = stmt
+            .query_map(condition.params(), |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<f64>>>()?;
+
+        Ok(SpeedDistribution::new(&speeds))
+    }
 
-        let keys = stmt.query_map([], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    /// Weekday and weekend stats, each with its `top` most pressed keys
+    fn get_weekday_weekend(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+        top: usize,
+    ) -> Result<WeekdayWeekend> {
+        // Index 0 is weekdays, 1 weekends
+        let mut active_days = [0; 2];
+        let mut hourly = [[0; 24]; 2];
+        let condition = filter.key_events();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} as day, {} as hour, COUNT(*) FROM key_events WHERE {}
+             GROUP BY day, hour ORDER BY day",
+            LOCAL_DAY,
+            LOCAL_HOUR,
+            condition.sql()
+        ))?;
+        let cells = stmt.query_map(condition.params(), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, usize>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
         })?;
+        let mut last_day = None;
+        for (day, hour, count) in cells.filter_map(|c| c.ok()) {
+            let Ok(date) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") else {
+                continue;
+            };
+            let kind = (date.weekday().num_days_from_monday() >= 5) as usize;
+            if last_day != Some(date) {
+                active_days[kind] += 1;
+                last_day = Some(date);
+            }
+            if hour < 24 {
+                hourly[kind][hour] += count;
+            }
+        }
+
+        let mut top_keys: [Vec<(String, i64)>; 2] = Default::default();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} >= 5 as weekend, key_name, COUNT(*) as cnt FROM key_events WHERE {}
+             GROUP BY weekend, key_name ORDER BY cnt DESC",
+            LOCAL_WEEKDAY,
+            condition.sql()
+        ))?;
+        let keys = stmt.query_map(condition.params(), |row| {
+            Ok((
+                row.get::<_, bool>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+        for (weekend, key_name, count) in keys.filter_map(|k| k.ok()) {
+            let keys = &mut top_keys[weekend as usize];
+            if keys.len() < top {
+                keys.push((key_name, count));
+            }
+        }
+
+        // Typing speed is left out when the filter narrows key events, which it can't follow
+        let mut average_cpm = [None; 2];
+        if let Ok(timed) = filter.timed() {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} >= 5 as weekend, AVG(chars_per_minute) FROM typing_samples
+                 WHERE {} GROUP BY weekend",
+                LOCAL_WEEKDAY,
+                timed.sql()
+            ))?;
+            let speeds = stmt.query_map(timed.params(), |row| {
+                Ok((row.get::<_, bool>(0)?, row.get::<_, f64>(1)?))
+            })?;
+            for (weekend, cpm) in speeds.filter_map(|s| s.ok()) {
+                average_cpm[weekend as usize] = Some(cpm);
+            }
+        }
+
+        let [weekday_keys, weekend_keys] = top_keys;
+        Ok(WeekdayWeekend {
+            weekday: DayTypeStats::new(active_days[0], &hourly[0], average_cpm[0], weekday_keys),
+            weekend: DayTypeStats::new(active_days[1], &hourly[1], average_cpm[1], weekend_keys),
+        })
+    }
 
-        Ok(keys.filter_map(|k| k.ok()).collect())
+    fn get_key_intervals(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<KeyIntervals> {
+        let condition = filter.key_events();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT hour, interval_ms, COUNT(*) FROM (
+                 SELECT {} as hour, timestamp_ms - LAG(timestamp_ms) OVER w as interval_ms
+                 FROM key_events
+                 WHERE session_id IS NOT NULL AND seq IS NOT NULL
+                     AND timestamp_ms IS NOT NULL AND {}
+                 WINDOW w AS (PARTITION BY session_id ORDER BY seq)
+             )
+             WHERE interval_ms >= 0 AND interval_ms < {}
+             GROUP BY hour, interval_ms",
+            LOCAL_HOUR,
+            condition.sql(),
+            MAX_INTERVAL_MS
+        ))?;
+        let counts = stmt
+            .query_map(condition.params(), |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(u32, i64, i64)>>>()?;
+
+        Ok(KeyIntervals::new(&counts))
     }
 
-    fn get_first_recorded(&self, conn: &rusqlite::Connection) -> Result<Option<String>> {
+    fn get_first_recorded(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<Option<String>> {
+        let condition = filter.key_events();
         let result: Option<String> = conn
             .query_row(
-                "SELECT timestamp FROM key_events ORDER BY timestamp ASC LIMIT 1",
-                [],
+                &format!(
+                    "SELECT timestamp FROM key_events WHERE {} ORDER BY timestamp_ms ASC LIMIT 1",
+                    condition.sql()
+                ),
+                condition.params(),
                 |row| row.get(0),
             )
             .ok();
-        Ok(result)
+        Ok(result.map(local_timestamp))
     }
 
-    fn get_last_recorded(&self, conn: &rusqlite::Connection) -> Result<Option<String>> {
+    fn get_last_recorded(
+        &self,
+        conn: &rusqlite::Connection,
+        filter: &StatsFilter,
+    ) -> Result<Option<String>> {
+        let condition = filter.key_events();
         let result: Option<String> = conn
             .query_row(
-                "SELECT timestamp FROM key_events ORDER BY timestamp DESC LIMIT 1",
-                [],
+                &format!(
+                    "SELECT timestamp FROM key_events WHERE {} ORDER BY timestamp_ms DESC LIMIT 1",
+                    condition.sql()
+                ),
+                condition.params(),
                 |row| row.get(0),
             )
             .ok();
-        Ok(result)
+        Ok(result.map(local_timestamp))
+    }
+}
+
+/// Presses of one key, of which `typed` were recorded as non-modifier presses
+struct KeyCount {
+    key_name: String,
+    count: i64,
+    typed: i64,
+}
+
+/// The `limit` most frequent of `bigrams`
+fn top_bigrams(bigrams: &[(String, String, i64)], limit: usize) -> Vec<BigramStats> {
+    let mut top: Vec<&(String, String, i64)> = bigrams.iter().collect();
+    top.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| (&a.0, &a.1).cmp(&(&b.0, &b.1))));
+    top.into_iter()
+        .take(limit)
+        .map(|(first_key, second_key, count)| BigramStats {
+            first_key: first_key.clone(),
+            second_key: second_key.clone(),
+            count: *count,
+        })
+        .collect()
+}
+
+/// Fill in the character each `Unknown` key most often composed. Keys the platform has
+/// no name for, such as dead keys and keys of non-US layouts, are otherwise
+/// indistinguishable in stats.
+fn label_unknown_keys(conn: &Connection, keys: &mut [KeyStats]) -> Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT composed FROM key_events WHERE key_name = ?1 AND composed IS NOT NULL
+         GROUP BY composed ORDER BY COUNT(*) DESC LIMIT 1",
+    )?;
+    for key in keys
+        .iter_mut()
+        .filter(|key| key.key_name.starts_with("Unknown"))
+    {
+        key.composed = stmt
+            .query_row([&key.key_name], |row| row.get(0))
+            .optional()?;
+    }
+    Ok(())
+}
+
+/// A `ChordStats` from a row of chord, key count, count and average spread
+fn chord_stats(row: &rusqlite::Row) -> rusqlite::Result<ChordStats> {
+    Ok(ChordStats {
+        chord: row.get(0)?,
+        key_count: row.get(1)?,
+        count: row.get(2)?,
+        avg_spread_ms: row.get(3)?,
+    })
+}
+
+/// A stored timestamp in local time, as RFC 3339
+fn local_timestamp(stored: String) -> String {
+    time::parse(&stored)
+        .map(|t| t.to_rfc3339())
+        .unwrap_or(stored)
+}
+
+/// Mean hour of day of counts per hour, treating the day as a circle.
+/// Each hour is weighted at its midpoint, e.g. presses during 14:00-14:59 count as 14:30.
+fn circular_mean_hour(hours: &[i64; 24]) -> f64 {
+    let (sin, cos) = hours
+        .iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(sin, cos), (hour, &count)| {
+            let angle = (hour as f64 + 0.5) / 24.0 * TAU;
+            (
+                sin + count as f64 * angle.sin(),
+                cos + count as f64 * angle.cos(),
+            )
+        });
+
+    sin.atan2(cos).rem_euclid(TAU) / TAU * 24.0
+}
+
+/// Spawns queries on threads of a scope, each on a read-only connection of its own
+struct ParallelQueries<'scope, 'env> {
+    scope: &'scope Scope<'scope, 'env>,
+    db: &'env DbConnection,
+    /// Sections whose queries are run; queries of other sections are skipped
+    sections: &'env [StatsSection],
+}
+
+impl<'scope, 'env> ParallelQueries<'scope, 'env> {
+    fn spawn<T, F>(
+        &self,
+        section: StatsSection,
+        query: F,
+    ) -> Option<ScopedJoinHandle<'scope, Result<T>>>
+    where
+        T: Send + 'scope,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'scope,
+    {
+        if !self.sections.contains(&section) {
+            return None;
+        }
+        let db = self.db;
+        Some(self.scope.spawn(move || db.read(query)))
+    }
+}
+
+/// Result of a spawned query, passing its panic on, or the default for a skipped one
+fn join<T: Default>(handle: Option<ScopedJoinHandle<'_, Result<T>>>) -> Result<T> {
+    match handle {
+        Some(handle) => handle
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+        None => Ok(T::default()),
+    }
+}
+
+/// Result of `query` if its section was requested, or the default otherwise
+fn when<T: Default>(wanted: bool, query: impl FnOnce() -> Result<T>) -> Result<T> {
+    if wanted {
+        query()
+    } else {
+        Ok(T::default())
     }
 }
@@ -0,0 +1,34 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Weeks an activity calendar covers, ending with the current one
+pub const CALENDAR_WEEKS: usize = 53;
+
+/// Shades of a calendar day, above the one for days without key presses
+const LEVELS: f64 = 4.0;
+
+/// Key presses on one local calendar day of an activity calendar
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CalendarDay {
+    pub date: String,
+    pub count: i64,
+    /// Shade from 0, for no key presses, to 4 for the busiest days. Each shade above 0
+    /// covers a quarter of the busiest day's count.
+    pub level: u8,
+}
+
+/// Shade each day's count against the busiest day's
+pub fn shade(counts: Vec<(String, i64)>) -> Vec<CalendarDay> {
+    let max = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    counts
+        .into_iter()
+        .map(|(date, count)| {
+            let level = if count > 0 {
+                (count as f64 / max as f64 * LEVELS).ceil() as u8
+            } else {
+                0
+            };
+            CalendarDay { date, count, level }
+        })
+        .collect()
+}
@@ -0,0 +1,93 @@
+use crate::config::ChordsConfig;
+use crate::db::models::KeyChord;
+use chrono::{DateTime, Duration, Local};
+use std::collections::HashSet;
+
+/// Groups non-modifier keys held down together into chords (stenography strokes,
+/// gaming inputs), kept apart from modifier combos.
+///
+/// A chord starts with the first key pressed while no other key is held, and every
+/// key must go down within the window after it, before any key comes back up. Keys
+/// that merely overlap, as in a fast roll from one key to the next, break the group
+/// instead. The chord is complete once all of its keys are released.
+pub struct ChordDetector {
+    window: Duration,
+    min_keys: usize,
+    held: HashSet<String>,
+    keys: Vec<String>,
+    started: Option<DateTime<Local>>,
+    last_press: Option<DateTime<Local>>,
+    /// Set once a key was released while others are still held
+    releasing: bool,
+    /// Set when the held keys turn out not to be a chord
+    broken: bool,
+}
+
+impl ChordDetector {
+    pub fn new(config: &ChordsConfig) -> Self {
+        Self {
+            window: Duration::milliseconds(config.window_ms as i64),
+            min_keys: config.min_keys.max(2),
+            held: HashSet::new(),
+            keys: Vec::new(),
+            started: None,
+            last_press: None,
+            releasing: false,
+            broken: false,
+        }
+    }
+
+    /// Observe a non-modifier key going down
+    pub fn press(&mut self, key: &str, at: DateTime<Local>) {
+        if self.held.contains(key) {
+            // Auto-repeat of a key that is already held
+            return;
+        }
+        if self.held.is_empty() {
+            self.reset();
+            self.started = Some(at);
+        }
+        self.held.insert(key.to_string());
+
+        let started = *self.started.get_or_insert(at);
+        if self.releasing || at - started > self.window {
+            self.broken = true;
+        } else {
+            self.keys.push(key.to_string());
+            self.last_press = Some(at);
+        }
+    }
+
+    /// Observe a non-modifier key coming up, returning the chord it completes
+    pub fn release(&mut self, key: &str) -> Option<KeyChord> {
+        if !self.held.remove(key) {
+            return None;
+        }
+        if !self.held.is_empty() {
+            self.releasing = true;
+            return None;
+        }
+
+        let started = self.started.take()?;
+        let last_press = self.last_press.take().unwrap_or(started);
+        let keys = std::mem::take(&mut self.keys);
+        if self.broken || keys.len() < self.min_keys {
+            return None;
+        }
+        Some(KeyChord::new(
+            keys,
+            started,
+            (last_press - started).num_milliseconds(),
+        ))
+    }
+
+    /// Forget held keys, for when key releases may have gone unseen
+    pub fn reset(&mut self) {
+        self.held.clear();
+        self.keys.clear();
+        self.started = None;
+        self.last_press = None;
+        self.releasing = false;
+        self.broken = false;
+    }
+}
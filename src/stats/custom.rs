@@ -0,0 +1,115 @@
+//! Statistics added on top of the built-in ones, without changing `AllStats`.
+//!
+//! Each metric registered with `StatsCalculator::with_metrics` is computed along with the
+//! overall stats and listed under `custom_metrics`, in the order it was registered. The
+//! config defines metrics as SQL queries:
+//!
+//! ```toml
+//! [[custom_metrics]]
+//! name = "Semicolons"
+//! sql = "SELECT COUNT(*) FROM key_events WHERE key_name = 'SemiColon'"
+//! ```
+
+use crate::config::CustomMetricConfig;
+use crate::stats::filter::StatsFilter;
+use anyhow::{Context, Result};
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// A statistic computed from the database
+pub trait CustomMetric: Send + Sync {
+    /// Name the metric is listed under
+    fn name(&self) -> &str;
+
+    /// The metric's value over the data the filter keeps. `conn` is read-only.
+    fn value(&self, conn: &Connection, filter: &StatsFilter) -> Result<Value>;
+}
+
+/// A custom metric's value, as listed in the overall stats
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CustomMetricValue {
+    pub name: String,
+    pub value: Value,
+}
+
+/// A metric the config defines as a query, whose value is the first column of its
+/// first row. The query can use `:since` and `:until`, the filter's time range in Unix
+/// milliseconds, which are null when the range is open.
+pub struct SqlMetric {
+    name: String,
+    sql: String,
+}
+
+impl SqlMetric {
+    pub fn new(name: impl Into<String>, sql: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            sql: sql.into(),
+        }
+    }
+}
+
+impl CustomMetric for SqlMetric {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self, conn: &Connection, filter: &StatsFilter) -> Result<Value> {
+        let mut stmt = conn.prepare(&self.sql)?;
+        for (name, bound) in [
+            (":since", filter.range.since_param()),
+            (":until", filter.range.until_param()),
+        ] {
+            if let Some(index) = stmt.parameter_index(name)? {
+                stmt.raw_bind_parameter(index, bound)?;
+            }
+        }
+        let value = stmt
+            .raw_query()
+            .next()?
+            .map(|row| row.get_ref(0).map(json_value))
+            .transpose()?;
+        Ok(value.unwrap_or(Value::Null))
+    }
+}
+
+/// The metrics `[[custom_metrics]]` defines, in the order they are listed
+pub fn from_config(configs: &[CustomMetricConfig]) -> Vec<Arc<dyn CustomMetric>> {
+    configs
+        .iter()
+        .map(|config| Arc::new(SqlMetric::new(&config.name, &config.sql)) as Arc<dyn CustomMetric>)
+        .collect()
+}
+
+/// Compute each metric in turn
+pub(crate) fn values(
+    metrics: &[Arc<dyn CustomMetric>],
+    conn: &Connection,
+    filter: &StatsFilter,
+) -> Result<Vec<CustomMetricValue>> {
+    metrics
+        .iter()
+        .map(|metric| {
+            let value = metric
+                .value(conn, filter)
+                .with_context(|| format!("Failed to compute custom metric `{}`", metric.name()))?;
+            Ok(CustomMetricValue {
+                name: metric.name().to_string(),
+                value,
+            })
+        })
+        .collect()
+}
+
+fn json_value(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+        ValueRef::Integer(n) => Value::from(n),
+        ValueRef::Real(x) => Value::from(x),
+        ValueRef::Text(text) => Value::from(String::from_utf8_lossy(text).into_owned()),
+    }
+}
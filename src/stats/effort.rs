@@ -0,0 +1,383 @@
+use crate::stats::board::Board;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Physical keys of the main block, one row at a time from the number row down, by
+/// their recorded names. The first key of each row sits in column -1, left of the
+/// column the left pinky rests on.
+const PHYSICAL_ROWS: [&[&str]; 4] = [
+    &[
+        "BackQuote",
+        "Num1",
+        "Num2",
+        "Num3",
+        "Num4",
+        "Num5",
+        "Num6",
+        "Num7",
+        "Num8",
+        "Num9",
+        "Num0",
+        "Minus",
+        "Equal",
+        "Backspace",
+    ],
+    &[
+        "Tab",
+        "KeyQ",
+        "KeyW",
+        "KeyE",
+        "KeyR",
+        "KeyT",
+        "KeyY",
+        "KeyU",
+        "KeyI",
+        "KeyO",
+        "KeyP",
+        "LeftBracket",
+        "RightBracket",
+        "BackSlash",
+    ],
+    &[
+        "CapsLock",
+        "KeyA",
+        "KeyS",
+        "KeyD",
+        "KeyF",
+        "KeyG",
+        "KeyH",
+        "KeyJ",
+        "KeyK",
+        "KeyL",
+        "SemiColon",
+        "Quote",
+        "Return",
+    ],
+    &[
+        "ShiftLeft",
+        "KeyZ",
+        "KeyX",
+        "KeyC",
+        "KeyV",
+        "KeyB",
+        "KeyN",
+        "KeyM",
+        "Comma",
+        "Dot",
+        "Slash",
+        "ShiftRight",
+    ],
+];
+
+/// Index of the home row in `PHYSICAL_ROWS`
+const HOME_ROW: usize = 2;
+
+/// Effort of the space bar, pressed by a thumb resting on it
+const THUMB_EFFORT: f64 = 1.0;
+
+/// Effort of keys off the main block (arrows, function keys, Ctrl, Alt, ...), which
+/// take the hand away from its home position
+const OFF_BLOCK_EFFORT: f64 = 3.0;
+
+/// Rows of the main block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyRow {
+    Number,
+    Top,
+    Home,
+    Bottom,
+}
+
+impl KeyRow {
+    /// Row a recorded key sits on, if it's on the main block
+    pub fn of(key_name: &str) -> Option<Self> {
+        let rows = [KeyRow::Number, KeyRow::Top, KeyRow::Home, KeyRow::Bottom];
+        Position::of(key_name).map(|position| rows[position.row])
+    }
+}
+
+/// Share of non-modifier key presses on each row, in percent
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RowUsage {
+    pub number: f64,
+    pub top: f64,
+    pub home: f64,
+    pub bottom: f64,
+    /// Keys off the four rows, such as Space, arrows and function keys
+    pub other: f64,
+}
+
+impl RowUsage {
+    /// Distribute per-key counts of non-modifier presses over the rows
+    pub fn new(counts: &[(String, i64)]) -> Self {
+        let total: i64 = counts.iter().map(|(_, count)| count).sum();
+        if total == 0 {
+            return Self::default();
+        }
+
+        let mut usage = Self::default();
+        for (key, count) in counts {
+            let percent = *count as f64 / total as f64 * 100.0;
+            match KeyRow::of(key) {
+                Some(KeyRow::Number) => usage.number += percent,
+                Some(KeyRow::Top) => usage.top += percent,
+                Some(KeyRow::Home) => usage.home += percent,
+                Some(KeyRow::Bottom) => usage.bottom += percent,
+                None => usage.other += percent,
+            }
+        }
+        usage
+    }
+}
+
+/// Layouts the same typing can be compared across
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Dvorak,
+    Colemak,
+}
+
+impl KeyboardLayout {
+    pub const ALL: [KeyboardLayout; 3] = [
+        KeyboardLayout::Qwerty,
+        KeyboardLayout::Dvorak,
+        KeyboardLayout::Colemak,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyboardLayout::Qwerty => "QWERTY",
+            KeyboardLayout::Dvorak => "Dvorak",
+            KeyboardLayout::Colemak => "Colemak",
+        }
+    }
+
+    /// Characters of each row, starting at column 0. Every layout has the same set.
+    fn rows(self) -> [&'static str; 4] {
+        match self {
+            KeyboardLayout::Qwerty => ["1234567890-=", "qwertyuiop[]", "asdfghjkl;'", "zxcvbnm,./"],
+            KeyboardLayout::Dvorak => ["1234567890[]", "',.pyfgcrl/=", "aoeuidhtns-", ";qjkxbmwvz"],
+            KeyboardLayout::Colemak => {
+                ["1234567890-=", "qwfpgjluy;[]", "arstdhneio'", "zxcvbkm,./"]
+            }
+        }
+    }
+
+    fn char_at(self, position: Position) -> Option<char> {
+        let col = usize::try_from(position.col).ok()?;
+        self.rows()[position.row].chars().nth(col)
+    }
+
+    fn position_of(self, c: char) -> Option<Position> {
+        self.rows().iter().enumerate().find_map(|(row, chars)| {
+            chars
+                .chars()
+                .position(|other| other == c)
+                .map(|col| Position {
+                    row,
+                    col: col as i32,
+                })
+        })
+    }
+}
+
+/// Hand a key is typed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+/// Finger a key is typed with, ordered from the outside of the hand inwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Finger {
+    Pinky,
+    Ring,
+    Middle,
+    Index,
+    Thumb,
+}
+
+impl Finger {
+    /// Effort multiplier for presses with this finger; weaker fingers cost more
+    fn weight(self) -> f64 {
+        match self {
+            Finger::Index | Finger::Thumb => 1.0,
+            Finger::Middle => 1.1,
+            Finger::Ring => 1.3,
+            Finger::Pinky => 1.6,
+        }
+    }
+}
+
+/// Hand and finger that touch typists use for a recorded key on a standard board, if
+/// it's on the main block
+pub fn standard_finger(key_name: &str) -> Option<(Hand, Finger)> {
+    Position::of(key_name).map(Position::finger)
+}
+
+/// Effort of a recorded key on a standard board, if it's on the main block or Space
+pub fn standard_effort(key_name: &str) -> Option<f64> {
+    if key_name == "Space" {
+        return Some(THUMB_EFFORT);
+    }
+    Position::of(key_name).map(Position::effort)
+}
+
+/// Where a key sits on the main block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    row: usize,
+    col: i32,
+}
+
+impl Position {
+    /// Physical position of a recorded key, named after its place on a QWERTY board
+    fn of(key_name: &str) -> Option<Self> {
+        PHYSICAL_ROWS.iter().enumerate().find_map(|(row, keys)| {
+            keys.iter()
+                .position(|key| *key == key_name)
+                .map(|index| Self {
+                    row,
+                    col: index as i32 - 1,
+                })
+        })
+    }
+
+    /// Recorded name of the key at this position
+    fn key_name(self) -> Option<&'static str> {
+        let index = usize::try_from(self.col + 1).ok()?;
+        PHYSICAL_ROWS[self.row].get(index).copied()
+    }
+
+    /// Hand and finger that touch typists use for this column
+    fn finger(self) -> (Hand, Finger) {
+        match self.col {
+            ..=0 => (Hand::Left, Finger::Pinky),
+            1 => (Hand::Left, Finger::Ring),
+            2 => (Hand::Left, Finger::Middle),
+            3 | 4 => (Hand::Left, Finger::Index),
+            5 | 6 => (Hand::Right, Finger::Index),
+            7 => (Hand::Right, Finger::Middle),
+            8 => (Hand::Right, Finger::Ring),
+            _ => (Hand::Right, Finger::Pinky),
+        }
+    }
+
+    /// Rows away from the home row, plus columns the finger stretches sideways
+    fn distance(self) -> f64 {
+        let rows = self.row.abs_diff(HOME_ROW) as f64;
+        let sideways = match self.col {
+            -1 | 4 | 5 => 1.0,
+            col if col >= 10 => (col - 9) as f64,
+            _ => 0.0,
+        };
+        rows + sideways
+    }
+
+    /// Relative effort of a press; 1.0 is an index finger on the home row
+    fn effort(self) -> f64 {
+        self.finger().1.weight() * (1.0 + self.distance())
+    }
+}
+
+/// Effort of pressing a key on `board`. `typed_on` is the layout the key was recorded
+/// with and `layout` the one to estimate for: letters and punctuation move to the key
+/// `layout` puts the same character on, other keys stay in place.
+///
+/// Keys the board doesn't define an effort for are estimated from their finger and
+/// their distance from the home row; thumbs and keys without a finger use fixed costs.
+pub fn key_effort(
+    key_name: &str,
+    typed_on: KeyboardLayout,
+    layout: KeyboardLayout,
+    board: &Board,
+) -> f64 {
+    let key_name = Position::of(key_name)
+        .and_then(|position| typed_on.char_at(position))
+        .and_then(|c| layout.position_of(c))
+        .and_then(Position::key_name)
+        .unwrap_or(key_name);
+
+    let Some((row, key)) = board.key(key_name) else {
+        return OFF_BLOCK_EFFORT;
+    };
+    if let Some(effort) = key.effort {
+        return effort;
+    }
+    match key.finger.map(|finger| finger.hand_finger().1) {
+        Some(Finger::Thumb) => THUMB_EFFORT,
+        Some(finger) => finger.weight() * (1.0 + row.abs_diff(board.home_row) as f64),
+        None => OFF_BLOCK_EFFORT,
+    }
+}
+
+/// Effort of one day's typing if it had been done on a given layout
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LayoutEffort {
+    pub layout: KeyboardLayout,
+    pub total: f64,
+    /// Effort per key press
+    pub average: f64,
+}
+
+/// Total and average typing effort for one local calendar day
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DailyEffort {
+    /// Day as `YYYY-MM-DD`
+    pub date: String,
+    pub keys: i64,
+    /// Layout set in the config, which the keys were typed on
+    pub layout: KeyboardLayout,
+    /// Effort on the configured layout, in home-row index finger presses
+    pub total: f64,
+    /// Effort per key press on the configured layout
+    pub average: f64,
+    /// The same keys estimated on every supported layout, including the configured one
+    pub layouts: Vec<LayoutEffort>,
+}
+
+impl DailyEffort {
+    /// Sum the effort of a day's key counts, typed on `typed_on` with `board`
+    pub fn new(
+        date: String,
+        counts: &[(String, i64)],
+        typed_on: KeyboardLayout,
+        board: &Board,
+    ) -> Self {
+        let keys: i64 = counts.iter().map(|(_, count)| count).sum();
+        let per_key = |total: f64| if keys > 0 { total / keys as f64 } else { 0.0 };
+
+        let layouts: Vec<LayoutEffort> = KeyboardLayout::ALL
+            .iter()
+            .map(|&layout| {
+                let total: f64 = counts
+                    .iter()
+                    .map(|(key, count)| key_effort(key, typed_on, layout, board) * *count as f64)
+                    .sum();
+                LayoutEffort {
+                    layout,
+                    total,
+                    average: per_key(total),
+                }
+            })
+            .collect();
+
+        let total = layouts
+            .iter()
+            .find(|effort| effort.layout == typed_on)
+            .map_or(0.0, |effort| effort.total);
+
+        Self {
+            date,
+            keys,
+            layout: typed_on,
+            total,
+            average: per_key(total),
+            layouts,
+        }
+    }
+}
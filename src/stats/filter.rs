@@ -0,0 +1,393 @@
+//! Which recorded data statistics are computed over.
+
+use crate::db::models::KeyCategory;
+use crate::stats::range::TimeRange;
+use anyhow::{bail, Result};
+use rusqlite::types::Value;
+use rusqlite::ParamsFromIter;
+
+/// Narrows statistics to part of what was recorded; the default covers everything.
+/// Each list left empty doesn't narrow anything, and each one that isn't keeps only
+/// data matching one of its entries.
+///
+/// Key events record every dimension. Combos record the app they were pressed in and
+/// when. Chords, typing speed samples, bursts and combo storms only record when they
+/// happened, so they follow the time range and the time spans of the sessions, and
+/// computations over them fail when the filter narrows anything else. Bigrams are counted
+/// per session, so they follow the sessions, and are counted from key events when the
+/// filter narrows anything else.
+///
+/// There is no device dimension: rdev reports each key event without the keyboard it came
+/// from, so presses on different keyboards can't be told apart.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsFilter {
+    pub range: TimeRange,
+    /// Recording sessions, by id
+    pub sessions: Vec<i64>,
+    pub workspaces: Vec<String>,
+    pub monitors: Vec<String>,
+    pub projects: Vec<String>,
+    /// Applications of the focused window, e.g. `firefox`
+    pub apps: Vec<String>,
+    pub categories: Vec<KeyCategory>,
+}
+
+impl From<TimeRange> for StatsFilter {
+    fn from(range: TimeRange) -> Self {
+        Self {
+            range,
+            ..Self::default()
+        }
+    }
+}
+
+impl StatsFilter {
+    pub fn with_range(mut self, range: TimeRange) -> Self {
+        self.range = range;
+        self
+    }
+
+    pub fn with_sessions(mut self, sessions: impl IntoIterator<Item = i64>) -> Self {
+        self.sessions.extend(sessions);
+        self
+    }
+
+    pub fn with_workspaces<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.workspaces.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_monitors<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.monitors.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_projects<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.projects.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_apps<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.apps.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_categories(mut self, categories: impl IntoIterator<Item = KeyCategory>) -> Self {
+        self.categories.extend(categories);
+        self
+    }
+
+    /// Whether the filter covers everything recorded, so whole-table rollups apply
+    pub fn is_empty(&self) -> bool {
+        self.range.since.is_none()
+            && self.range.until.is_none()
+            && self.sessions.is_empty()
+            && !self.narrows_keys()
+    }
+
+    /// Whether the filter narrows key events by something other than time and session,
+    /// which data recorded without key details can't follow
+    pub(crate) fn narrows_keys(&self) -> bool {
+        !(self.workspaces.is_empty()
+            && self.monitors.is_empty()
+            && self.projects.is_empty()
+            && self.apps.is_empty()
+            && self.categories.is_empty())
+    }
+
+    /// Condition on `key_events`
+    pub(crate) fn key_events(&self) -> Condition {
+        let mut condition = Condition::time(&self.range);
+        condition.one_of(
+            "session_id",
+            self.sessions.iter().map(|&id| Value::from(id)),
+        );
+        for (column, names) in [
+            ("workspace", &self.workspaces),
+            ("monitor", &self.monitors),
+            ("project", &self.projects),
+            ("app", &self.apps),
+        ] {
+            condition.one_of(column, names.iter().map(|name| Value::from(name.clone())));
+        }
+        condition.one_of(
+            "category",
+            self.categories
+                .iter()
+                .map(|category| Value::from(category.as_str().to_string())),
+        );
+        condition
+    }
+
+    /// Condition on tables recording only when something happened: `key_chords`,
+    /// `typing_samples` and `typing_bursts`. Sessions match by their time span. Fails when
+    /// the filter narrows key events any other way, which these tables can't follow.
+    pub(crate) fn timed(&self) -> Result<Condition> {
+        self.ensure_timed()?;
+        Ok(self.during())
+    }
+
+    /// Condition on the time range and the time spans of the sessions
+    fn during(&self) -> Condition {
+        let mut condition = Condition::time(&self.range);
+        if !self.sessions.is_empty() {
+            condition.push(
+                &format!(
+                    "EXISTS (SELECT 1 FROM sessions
+                     WHERE sessions.id IN ({}) AND timestamp_ms >= sessions.start_ms
+                       AND (sessions.end_ms IS NULL OR timestamp_ms <= sessions.end_ms))",
+                    placeholders(self.sessions.len())
+                ),
+                self.sessions.iter().map(|&id| Value::from(id)),
+            );
+        }
+        condition
+    }
+
+    /// Condition on `key_combos`, which also record their app
+    pub(crate) fn combos(&self) -> Condition {
+        let mut condition = self.during();
+        condition.one_of(
+            "app",
+            self.apps.iter().map(|name| Value::from(name.clone())),
+        );
+        condition
+    }
+
+    /// Condition on `imported_activity`, which only records when keys were pressed. None
+    /// when the filter narrows anything but the time range, which imports can't follow.
+    pub(crate) fn imported(&self) -> Option<Condition> {
+        (self.sessions.is_empty() && !self.narrows_keys()).then(|| Condition::time(&self.range))
+    }
+
+    /// Condition on `combo_storms`, by when each storm started. Fails like `timed`.
+    pub(crate) fn storms(&self) -> Result<Condition> {
+        self.ensure_timed()?;
+        let mut condition = Condition::default();
+        if let Some(since) = self.range.since_param() {
+            condition.push("start_ms >= ?", [Value::from(since)]);
+        }
+        if let Some(until) = self.range.until_param() {
+            condition.push("start_ms < ?", [Value::from(until)]);
+        }
+        if !self.sessions.is_empty() {
+            condition.push(
+                &format!(
+                    "EXISTS (SELECT 1 FROM sessions
+                     WHERE sessions.id IN ({}) AND combo_storms.start_ms >= sessions.start_ms
+                       AND (sessions.end_ms IS NULL OR combo_storms.start_ms <= sessions.end_ms))",
+                    placeholders(self.sessions.len())
+                ),
+                self.sessions.iter().map(|&id| Value::from(id)),
+            );
+        }
+        Ok(condition)
+    }
+
+    fn ensure_timed(&self) -> Result<()> {
+        if self.narrows_keys() {
+            bail!(
+                "Chords, typing speed, bursts and combo storms only record when they happened, \
+                 so they can't be narrowed by workspace, monitor, project, app or key category"
+            );
+        }
+        Ok(())
+    }
+
+    /// Condition on `sessions`: those with key events the filter keeps
+    pub(crate) fn sessions(&self) -> Condition {
+        let mut condition = Condition::default();
+        if !self.is_empty() {
+            let events = self.key_events();
+            condition.push(
+                &format!(
+                    "id IN (SELECT DISTINCT session_id FROM key_events WHERE {})",
+                    events.sql()
+                ),
+                events.params,
+            );
+        }
+        condition
+    }
+
+    /// Condition on `key_bigrams`, which only record their session. Applies only when
+    /// `counts_bigrams_from_events` is false.
+    pub(crate) fn bigrams(&self) -> Condition {
+        let mut condition = Condition::default();
+        condition.one_of(
+            "session_id",
+            self.sessions.iter().map(|&id| Value::from(id)),
+        );
+        condition
+    }
+
+    /// Whether bigrams have to be counted from key events, because the filter narrows
+    /// more than the sessions `key_bigrams` records
+    pub(crate) fn counts_bigrams_from_events(&self) -> bool {
+        self.range.since.is_some() || self.range.until.is_some() || self.narrows_keys()
+    }
+}
+
+/// A SQL condition and the values of its `?` parameters, in order. Without any parts,
+/// every row meets it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Condition {
+    parts: Vec<String>,
+    params: Vec<Value>,
+}
+
+impl Condition {
+    pub fn sql(&self) -> String {
+        if self.parts.is_empty() {
+            "1".to_string()
+        } else {
+            self.parts.join(" AND ")
+        }
+    }
+
+    /// Restricts `timestamp_ms` to a time range
+    fn time(range: &TimeRange) -> Self {
+        let mut condition = Self::default();
+        if let Some(since) = range.since_param() {
+            condition.push("timestamp_ms >= ?", [Value::from(since)]);
+        }
+        if let Some(until) = range.until_param() {
+            condition.push("timestamp_ms < ?", [Value::from(until)]);
+        }
+        condition
+    }
+
+    fn push(&mut self, sql: &str, params: impl IntoIterator<Item = Value>) {
+        self.parts.push(sql.to_string());
+        self.params.extend(params);
+    }
+
+    /// Keep rows whose `column` is one of `values`, unless there are none
+    fn one_of(&mut self, column: &str, values: impl ExactSizeIterator<Item = Value>) {
+        if values.len() == 0 {
+            return;
+        }
+        let sql = format!("{} IN ({})", column, placeholders(values.len()));
+        self.push(&sql, values);
+    }
+
+    /// The parameters, followed by `extra` for parameters after the condition's
+    pub fn params_with(
+        &self,
+        extra: impl IntoIterator<Item = Value>,
+    ) -> ParamsFromIter<Vec<Value>> {
+        rusqlite::params_from_iter(self.params.iter().cloned().chain(extra).collect())
+    }
+
+    /// The parameters, for statements repeating the condition
+    pub fn values(&self) -> Vec<Value> {
+        self.params.clone()
+    }
+
+    pub fn params(&self) -> ParamsFromIter<Vec<Value>> {
+        self.params_with([])
+    }
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_memory_db;
+    use crate::stats::StatsCalculator;
+    use chrono::NaiveDate;
+
+    fn day() -> TimeRange {
+        TimeRange::day(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn narrows_key_events_by_every_dimension() {
+        let filter = StatsFilter::from(day())
+            .with_sessions([3])
+            .with_workspaces(["2"])
+            .with_apps(["firefox", "kitty"])
+            .with_categories([KeyCategory::Letter]);
+        let condition = filter.key_events();
+        assert_eq!(
+            condition.sql(),
+            "timestamp_ms >= ? AND timestamp_ms < ? AND session_id IN (?) \
+             AND workspace IN (?) AND app IN (?, ?) AND category IN (?)"
+        );
+        assert_eq!(
+            condition.values()[2..],
+            [
+                Value::from(3),
+                Value::from("2".to_string()),
+                Value::from("firefox".to_string()),
+                Value::from("kitty".to_string()),
+                Value::from("letter".to_string()),
+            ]
+        );
+        assert_eq!(StatsFilter::default().key_events().sql(), "1");
+    }
+
+    #[test]
+    fn combos_follow_apps_and_sessions_by_their_span() {
+        let filter = StatsFilter::default()
+            .with_sessions([3])
+            .with_apps(["firefox"]);
+        let sql = filter.combos().sql();
+        assert!(sql.contains("sessions.id IN (?)"), "{}", sql);
+        assert!(sql.ends_with("app IN (?)"), "{}", sql);
+    }
+
+    #[test]
+    fn timed_data_refuses_filters_it_cannot_follow() {
+        let sessions = StatsFilter::from(day()).with_sessions([3]);
+        assert!(sessions.timed().is_ok());
+        assert!(sessions
+            .storms()
+            .unwrap()
+            .sql()
+            .starts_with("start_ms >= ?"));
+
+        for filter in [
+            StatsFilter::default().with_apps(["firefox"]),
+            StatsFilter::default().with_projects(["kitmap"]),
+            StatsFilter::default().with_categories([KeyCategory::Digit]),
+        ] {
+            assert!(filter.timed().is_err());
+            assert!(filter.storms().is_err());
+            assert!(filter.imported().is_none());
+        }
+    }
+
+    #[test]
+    fn leaves_out_stats_the_filter_cannot_narrow() {
+        let db = init_memory_db().unwrap();
+        db.call_blocking(|conn| {
+            conn.execute_batch(
+                "INSERT INTO key_events
+                     (key_code, key_name, timestamp, hour, day_of_week, app, timestamp_ms)
+                 VALUES ('KeyA', 'KeyA', '2024-03-01T10:00:00.000Z', 10, 4, 'firefox',
+                         1709287200000);
+                 INSERT INTO key_chords (chord, key_count, spread_ms, timestamp, timestamp_ms)
+                 VALUES ('J+K', 2, 20, '2024-03-01T10:00:01.000Z', 1709287201000);",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let stats = StatsCalculator::new(db);
+
+        let everything = stats.calculate_all(&StatsFilter::default()).unwrap();
+        assert_eq!(everything.total_chords, 1);
+        assert!(everything.unfiltered.is_empty());
+
+        let firefox = StatsFilter::default().with_apps(["firefox"]);
+        let narrowed = stats.calculate_all(&firefox).unwrap();
+        assert_eq!(narrowed.total_keys, 1);
+        assert_eq!(narrowed.total_chords, 0);
+        assert!(narrowed.top_chords.is_empty());
+        assert!(narrowed.unfiltered.contains(&"top_chords".to_string()));
+        assert!(stats.top_chords(&firefox, 10).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use crate::db::time;
+use crate::db::DbConnection;
+use crate::stats::range::local_midnight;
+use anyhow::Result;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use rusqlite::Connection;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What a goal measures
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalMetric {
+    /// Keys pressed
+    Keys,
+    /// Minutes in which at least one key was pressed
+    ActiveMinutes,
+    /// Words (five typed keys) per active minute
+    Wpm,
+}
+
+impl GoalMetric {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GoalMetric::Keys => "keys",
+            GoalMetric::ActiveMinutes => "active_minutes",
+            GoalMetric::Wpm => "wpm",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "keys" => Some(GoalMetric::Keys),
+            "active_minutes" => Some(GoalMetric::ActiveMinutes),
+            "wpm" => Some(GoalMetric::Wpm),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GoalMetric::Keys => "keys",
+            GoalMetric::ActiveMinutes => "active minutes",
+            GoalMetric::Wpm => "WPM",
+        }
+    }
+
+    /// A value of this metric for display, with a decimal only for WPM
+    pub fn format(self, value: f64) -> String {
+        match self {
+            GoalMetric::Wpm => format!("{:.1}", value),
+            _ => format!("{:.0}", value),
+        }
+    }
+}
+
+/// How often a goal's progress starts over
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalPeriod {
+    /// Each local calendar day
+    Daily,
+    /// Each week, starting on Monday
+    Weekly,
+}
+
+impl GoalPeriod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GoalPeriod::Daily => "daily",
+            GoalPeriod::Weekly => "weekly",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "daily" => Some(GoalPeriod::Daily),
+            "weekly" => Some(GoalPeriod::Weekly),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GoalPeriod::Daily => "Daily",
+            GoalPeriod::Weekly => "Weekly",
+        }
+    }
+
+    /// First day of the period containing `day`, and the first day after it
+    fn bounds(self, day: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            GoalPeriod::Daily => (day, day + Duration::days(1)),
+            GoalPeriod::Weekly => {
+                let monday = day - Duration::days(day.weekday().num_days_from_monday() as i64);
+                (monday, monday + Duration::days(7))
+            }
+        }
+    }
+}
+
+/// A target set with `kitmap goals set`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Goal {
+    pub metric: GoalMetric,
+    pub period: GoalPeriod,
+    pub target: f64,
+}
+
+/// How far along a goal is in its current period
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GoalProgress {
+    pub metric: GoalMetric,
+    pub period: GoalPeriod,
+    pub target: f64,
+    /// Value reached so far in the current period
+    pub current: f64,
+    /// `current / target`, capped at 1
+    pub progress: f64,
+    pub met: bool,
+    /// First day of the current period, as `YYYY-MM-DD`
+    pub period_start: String,
+}
+
+impl Goal {
+    /// Set a goal, replacing any earlier target for the same metric and period
+    pub fn save(&self, db: &DbConnection) -> Result<()> {
+        let params = (
+            self.metric.as_str(),
+            self.period.as_str(),
+            self.target,
+            time::now(),
+        );
+        db.call_blocking(move |conn| {
+            conn.execute(
+                "INSERT INTO goals (metric, period, target, updated_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (metric, period) DO UPDATE SET
+                     target = excluded.target,
+                     updated_at = excluded.updated_at",
+                params,
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Remove a goal, returning whether it existed
+    pub fn remove(db: &DbConnection, metric: GoalMetric, period: GoalPeriod) -> Result<bool> {
+        db.call_blocking(move |conn| {
+            let removed = conn.execute(
+                "DELETE FROM goals WHERE metric = ?1 AND period = ?2",
+                (metric.as_str(), period.as_str()),
+            )?;
+            Ok(removed > 0)
+        })
+    }
+
+    /// Every goal, daily goals first
+    pub fn all(conn: &Connection) -> Result<Vec<Self>> {
+        let mut stmt = conn.prepare("SELECT metric, period, target FROM goals")?;
+        let mut goals: Vec<Self> = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f64>(2)?,
+                ))
+            })?
+            .filter_map(|r| r.ok())
+            .filter_map(|(metric, period, target)| {
+                Some(Self {
+                    metric: GoalMetric::parse(&metric)?,
+                    period: GoalPeriod::parse(&period)?,
+                    target,
+                })
+            })
+            .collect();
+        goals.sort_by_key(|goal| (goal.period as u8, goal.metric as u8));
+        Ok(goals)
+    }
+
+    /// Progress in the period containing `today`, read from the per-minute rollup
+    pub fn progress(&self, conn: &Connection, today: NaiveDate) -> Result<GoalProgress> {
+        let (start, end) = self.period.bounds(today);
+        let (keys, active_minutes, typed_keys): (i64, i64, i64) = conn.query_row(
+            "SELECT COALESCE(SUM(keys), 0), COUNT(*), COALESCE(SUM(typed_keys), 0)
+             FROM minute_totals WHERE minute >= ?1 AND minute < ?2",
+            (
+                time::minute(&local_midnight(start)?),
+                time::minute(&local_midnight(end)?),
+            ),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+
+        let current = match self.metric {
+            GoalMetric::Keys => keys as f64,
+            GoalMetric::ActiveMinutes => active_minutes as f64,
+            GoalMetric::Wpm if active_minutes > 0 => {
+                typed_keys as f64 / 5.0 / active_minutes as f64
+            }
+            GoalMetric::Wpm => 0.0,
+        };
+        let progress = if self.target > 0.0 {
+            (current / self.target).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        Ok(GoalProgress {
+            metric: self.metric,
+            period: self.period,
+            target: self.target,
+            current,
+            progress,
+            met: current >= self.target,
+            period_start: start.format("%Y-%m-%d").to_string(),
+        })
+    }
+}
+
+/// Progress of every goal in the current periods
+pub fn progress(conn: &Connection) -> Result<Vec<GoalProgress>> {
+    let today = Local::now().date_naive();
+    Goal::all(conn)?
+        .iter()
+        .map(|goal| goal.progress(conn, today))
+        .collect()
+}
@@ -0,0 +1,42 @@
+use crate::stats::board::Board;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How often consecutive key presses switch hands
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct HandAlternation {
+    /// Bigrams whose keys both belong to a hand; Space, Shift and keys off the main
+    /// block are left out
+    pub bigrams: i64,
+    /// Bigrams typed with one key on each hand
+    pub alternating: i64,
+    /// Bigrams typed with both keys on the same hand
+    pub same_hand: i64,
+    /// Share of alternating bigrams, in percent
+    pub rate: f64,
+}
+
+impl HandAlternation {
+    /// Classify bigram counts by whether their keys are typed with different hands
+    pub fn new(bigrams: &[(String, String, i64)], board: &Board) -> Self {
+        let mut alternation = Self::default();
+        for (first, second, count) in bigrams {
+            let (Some((first_hand, _)), Some((second_hand, _))) =
+                (board.finger_of(first), board.finger_of(second))
+            else {
+                continue;
+            };
+            if first_hand == second_hand {
+                alternation.same_hand += count;
+            } else {
+                alternation.alternating += count;
+            }
+        }
+
+        alternation.bigrams = alternation.alternating + alternation.same_hand;
+        if alternation.bigrams > 0 {
+            alternation.rate = alternation.alternating as f64 / alternation.bigrams as f64 * 100.0;
+        }
+        alternation
+    }
+}
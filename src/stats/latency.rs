@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Number of most recent handling times kept for percentile estimates
+const LATENCY_WINDOW: usize = 10_000;
+
+/// Handling time distribution of keyboard event callbacks, in microseconds
+#[derive(Debug, Clone, Copy)]
+pub struct LatencySummary {
+    pub events: u64,
+    pub p50_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+/// Records how long each event callback took to return control to the input hook
+#[derive(Debug, Default)]
+pub struct LatencyRecorder {
+    window: VecDeque<u64>,
+    events: u64,
+    max_us: u64,
+}
+
+impl LatencyRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, elapsed: Duration) {
+        let micros = elapsed.as_micros() as u64;
+        if self.window.len() == LATENCY_WINDOW {
+            self.window.pop_front();
+        }
+        self.window.push_back(micros);
+        self.events += 1;
+        self.max_us = self.max_us.max(micros);
+    }
+
+    /// Percentiles over the recent window; the event count and maximum cover every event
+    pub fn summary(&self) -> Option<LatencySummary> {
+        if self.window.is_empty() {
+            return None;
+        }
+
+        let mut sorted: Vec<u64> = self.window.iter().copied().collect();
+        sorted.sort_unstable();
+
+        Some(LatencySummary {
+            events: self.events,
+            p50_us: percentile(&sorted, 0.50),
+            p99_us: percentile(&sorted, 0.99),
+            max_us: self.max_us,
+        })
+    }
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
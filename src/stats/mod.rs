@@ -1,3 +1,31 @@
+pub mod bigrams;
+pub mod blocks;
+pub mod board;
+pub mod breaks;
+pub mod bursts;
 pub mod calculator;
+pub mod calendar;
+pub mod chords;
+pub mod custom;
+pub mod effort;
+pub mod filter;
+pub mod goals;
+pub mod hands;
+pub mod latency;
+pub mod normalize;
+#[cfg(feature = "wasm")]
+pub mod plugins;
+pub mod range;
+pub mod replay;
+pub mod rhythm;
+pub mod rolls;
+pub mod score;
+pub mod sequences;
+pub mod series;
+pub mod shortcuts;
+pub mod speed;
+pub mod storms;
+pub mod weekend;
 
 pub use calculator::StatsCalculator;
+pub use filter::StatsFilter;
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+/// Letter frequencies in English text, in percent of letters
+const ENGLISH_LETTERS: [(char, f64); 26] = [
+    ('E', 12.70),
+    ('T', 9.06),
+    ('A', 8.17),
+    ('O', 7.51),
+    ('I', 6.97),
+    ('N', 6.75),
+    ('S', 6.33),
+    ('H', 6.09),
+    ('R', 5.99),
+    ('D', 4.25),
+    ('L', 4.03),
+    ('C', 2.78),
+    ('U', 2.76),
+    ('M', 2.41),
+    ('W', 2.36),
+    ('F', 2.23),
+    ('G', 2.02),
+    ('Y', 1.97),
+    ('P', 1.93),
+    ('B', 1.29),
+    ('V', 0.98),
+    ('K', 0.77),
+    ('J', 0.15),
+    ('X', 0.15),
+    ('Q', 0.10),
+    ('Z', 0.07),
+];
+
+/// Share of key presses that are letters when typing English prose
+const LETTER_SHARE: f64 = 0.76;
+
+/// Other keys used when typing English prose, as a share of key presses
+const ENGLISH_KEYS: &[(&str, f64)] = &[
+    ("Space", 0.17),
+    ("ShiftLeft", 0.01),
+    ("ShiftRight", 0.01),
+    ("Comma", 0.01),
+    ("Dot", 0.01),
+    ("Return", 0.005),
+    ("Quote", 0.003),
+];
+
+/// Expected share of keys prose rarely or never needs (navigation, Backspace, Ctrl,
+/// ...), so they stand out once pressed more than occasionally
+const RARE_KEY_SHARE: f64 = 0.001;
+
+/// Reference distributions key counts can be compared against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Normalization {
+    /// Letter and punctuation frequencies of English prose
+    English,
+}
+
+impl Normalization {
+    /// Share of all key presses a key gets in the reference
+    pub fn expected_share(self, key_name: &str) -> f64 {
+        match self {
+            Normalization::English => {
+                let letter = key_name.strip_prefix("Key").and_then(|rest| {
+                    ENGLISH_LETTERS
+                        .iter()
+                        .find(|(c, _)| rest.len() == 1 && rest.starts_with(*c))
+                });
+                if let Some((_, percent)) = letter {
+                    return percent / 100.0 * LETTER_SHARE;
+                }
+                ENGLISH_KEYS
+                    .iter()
+                    .find(|(key, _)| *key == key_name)
+                    .map_or(RARE_KEY_SHARE, |(_, share)| *share)
+            }
+        }
+    }
+
+    /// How many times more often each key was pressed than the reference expects;
+    /// 1.0 is exactly as often
+    pub fn ratios(self, counts: &HashMap<String, i64>) -> HashMap<String, f64> {
+        let total: i64 = counts.values().sum();
+        if total == 0 {
+            return HashMap::new();
+        }
+
+        counts
+            .iter()
+            .map(|(key, &count)| {
+                let share = count as f64 / total as f64;
+                (key.clone(), share / self.expected_share(key))
+            })
+            .collect()
+    }
+}
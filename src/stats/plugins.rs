@@ -0,0 +1,145 @@
+//! Sandboxed WASM plugins that add sections to reports.
+//!
+//! A plugin is a WebAssembly module that analyzes the overall stats of the period a
+//! report covers, never individual key presses. It runs in an interpreter without any
+//! host functions, so it can't read files, reach the network or tell the time, and its
+//! memory and running time are capped.
+//!
+//! A plugin exports:
+//!
+//! - `memory`, its linear memory
+//! - `kitmap_alloc(len: i32) -> i32`, returning where `len` bytes of input can be written
+//! - `kitmap_analyze(ptr: i32, len: i32) -> i64`, which reads the stats as JSON from
+//!   `ptr` and returns where its answer is: the pointer in the high 32 bits and the
+//!   length in the low 32 bits
+//!
+//! The answer is a JSON list of sections, `[{"title": "...", "body": "..."}]`, with
+//! Markdown bodies.
+
+use crate::config::{get_config_path, PluginConfig};
+use crate::stats::calculator::AllStats;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Fuel a plugin may use per report; about one unit per instruction
+const FUEL: u64 = 200_000_000;
+
+/// Largest linear memory a plugin may grow
+const MAX_MEMORY_BYTES: usize = 64 << 20;
+
+/// Largest answer read back from a plugin
+const MAX_ANSWER_BYTES: usize = 1 << 20;
+
+/// Extra section of a report, written by a plugin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportSection {
+    pub title: String,
+    /// Markdown
+    pub body: String,
+}
+
+/// A compiled plugin, ready to analyze stats
+pub struct Plugin {
+    name: String,
+    engine: Engine,
+    module: Module,
+}
+
+impl Plugin {
+    /// Compile the module at `path`, checking it exports what plugins must
+    pub fn load(path: &Path) -> Result<Self> {
+        let wasm = std::fs::read(path)
+            .with_context(|| format!("Failed to read plugin {}", path.display()))?;
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &wasm)
+            .map_err(|e| anyhow!("Plugin {} is not valid WebAssembly: {}", path.display(), e))?;
+
+        if let Some(import) = module.imports().next() {
+            bail!(
+                "Plugin {} imports `{}::{}`, but plugins can't use host functions",
+                path.display(),
+                import.module(),
+                import.name()
+            );
+        }
+        for export in ["memory", "kitmap_alloc", "kitmap_analyze"] {
+            if module.get_export(export).is_none() {
+                bail!("Plugin {} doesn't export `{}`", path.display(), export);
+            }
+        }
+
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        Ok(Self {
+            name,
+            engine,
+            module,
+        })
+    }
+
+    /// Name of the module file, without its extension
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Run the plugin on `stats` in a fresh instance, returning the sections it adds
+    pub fn analyze(&self, stats: &AllStats) -> Result<Vec<ReportSection>> {
+        let input = serde_json::to_vec(stats)?;
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_BYTES)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(FUEL).map_err(|e| anyhow!("{}", e))?;
+
+        let instance = Linker::<StoreLimits>::new(&self.engine)
+            .instantiate(&mut store, &self.module)?
+            .start(&mut store)?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| anyhow!("`memory` is not a memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "kitmap_alloc")?;
+        let analyze = instance.get_typed_func::<(i32, i32), i64>(&store, "kitmap_analyze")?;
+
+        let len = i32::try_from(input.len()).context("Stats too large for a plugin")?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory
+            .write(&mut store, ptr as u32 as usize, &input)
+            .map_err(|e| anyhow!("Failed to write the stats to {:#x}: {}", ptr, e))?;
+
+        let answer = analyze.call(&mut store, (ptr, len))? as u64;
+        let (ptr, len) = ((answer >> 32) as usize, (answer & 0xffff_ffff) as usize);
+        if len > MAX_ANSWER_BYTES {
+            bail!(
+                "Answer of {} bytes is larger than {}",
+                len,
+                MAX_ANSWER_BYTES
+            );
+        }
+        let mut output = vec![0; len];
+        memory
+            .read(&store, ptr, &mut output)
+            .map_err(|e| anyhow!("Failed to read the answer at {:#x}: {}", ptr, e))?;
+
+        serde_json::from_slice(&output).context("The answer is not a list of sections")
+    }
+}
+
+/// Compile the plugins `[[plugins]]` lists, in order
+pub fn from_config(configs: &[PluginConfig]) -> Result<Vec<Plugin>> {
+    let config_dir = get_config_path()?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(PathBuf::new);
+    configs
+        .iter()
+        .map(|config| Plugin::load(&config_dir.join(&config.path)))
+        .collect()
+}
@@ -0,0 +1,336 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, TimeZone};
+
+/// SQL condition restricting `timestamp_ms` to the range bound as ?1 (since) and ?2 (until)
+pub const RANGE_CONDITION: &str =
+    "(?1 IS NULL OR timestamp_ms >= ?1) AND (?2 IS NULL OR timestamp_ms < ?2)";
+
+/// SQL expressions for `timestamp` in the viewer's local time. Timestamps are stored in
+/// UTC, so days and hours follow the timezone of whoever is looking at the stats.
+pub const LOCAL_DAY: &str = "date(timestamp, 'localtime')";
+pub const LOCAL_HOUR: &str = "CAST(strftime('%H', timestamp, 'localtime') AS INTEGER)";
+/// Start of the local hour, e.g. `2024-01-31 14:00:00`
+pub const LOCAL_HOUR_START: &str = "strftime('%Y-%m-%d %H:00:00', timestamp, 'localtime')";
+/// Day of the week, 0 for Monday
+pub const LOCAL_WEEKDAY: &str = "(CAST(strftime('%w', timestamp, 'localtime') AS INTEGER) + 6) % 7";
+pub const LOCAL_MONTH: &str = "CAST(strftime('%m', timestamp, 'localtime') AS INTEGER)";
+pub const LOCAL_YEAR: &str = "CAST(strftime('%Y', timestamp, 'localtime') AS INTEGER)";
+
+/// An optional time window used to restrict stats queries
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeRange {
+    pub since: Option<DateTime<Local>>,
+    pub until: Option<DateTime<Local>>,
+}
+
+impl TimeRange {
+    /// Parse optional `since`/`until` bounds from user input
+    pub fn parse(since: Option<&str>, until: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            since: since.map(parse_time).transpose()?,
+            until: until.map(parse_time).transpose()?,
+        })
+    }
+
+    /// Parse a single `START..END` span, where either side may be omitted.
+    /// A bare point in time such as `7d` means "since then".
+    pub fn parse_span(span: &str) -> Result<Self> {
+        let (since, until) = match span.split_once("..") {
+            Some((since, until)) => (since.trim(), until.trim()),
+            None => (span.trim(), ""),
+        };
+
+        Self::parse(
+            Some(since).filter(|s| !s.is_empty()),
+            Some(until).filter(|s| !s.is_empty()),
+        )
+    }
+
+    /// Parse a named period such as `today`, `yesterday`, `this week` or `last month`,
+    /// falling back to a `START..END` span for anything else
+    pub fn parse_named(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let today = Local::now().date_naive();
+
+        match input {
+            "today" => return Self::day(today),
+            "yesterday" => return Self::day(today - Duration::days(1)),
+            _ => {}
+        }
+
+        let period = |name: &str| match name {
+            "week" => Some(Period::Week),
+            "month" => Some(Period::Month),
+            "year" => Some(Period::Year),
+            _ => None,
+        };
+        match input.split_once(' ') {
+            Some(("this", name)) => {
+                if let Some(period) = period(name.trim()) {
+                    return period.range(period.start(today));
+                }
+            }
+            Some(("last", name)) => {
+                if let Some(period) = period(name.trim()) {
+                    return period.range(period.previous(period.start(today)));
+                }
+            }
+            _ => {}
+        }
+
+        Self::parse_span(input)
+    }
+
+    /// The whole local calendar day of `date`
+    pub fn day(date: NaiveDate) -> Result<Self> {
+        let start = local_midnight(date)?;
+        let next = date
+            .succ_opt()
+            .ok_or_else(|| anyhow!("Date {} is out of range", date))?;
+        let end = local_midnight(next)?;
+        Ok(Self {
+            since: Some(start),
+            until: Some(end),
+        })
+    }
+
+    /// Lower bound in epoch milliseconds for SQL parameters
+    pub fn since_param(&self) -> Option<i64> {
+        self.since.map(|t| t.timestamp_millis())
+    }
+
+    /// Upper bound in epoch milliseconds for SQL parameters
+    pub fn until_param(&self) -> Option<i64> {
+        self.until.map(|t| t.timestamp_millis())
+    }
+}
+
+/// A calendar period for periodic reports. Weeks start on Monday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Period {
+    /// Monday to Sunday
+    Week,
+    /// A calendar month
+    Month,
+    /// A calendar year
+    Year,
+}
+
+impl Period {
+    /// First day of the period containing `date`
+    pub fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Self::Month => date.with_day(1).unwrap_or(date),
+            Self::Year => date.with_ordinal(1).unwrap_or(date),
+        }
+    }
+
+    /// First day of the period before the one starting on `start`
+    pub fn previous(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Week => start - Duration::days(7),
+            Self::Month => start - Months::new(1),
+            Self::Year => start - Months::new(12),
+        }
+    }
+
+    /// The whole period starting on `start`
+    pub fn range(self, start: NaiveDate) -> Result<TimeRange> {
+        let end = match self {
+            Self::Week => start + Duration::days(7),
+            Self::Month => start + Months::new(1),
+            Self::Year => start + Months::new(12),
+        };
+        Ok(TimeRange {
+            since: Some(local_midnight(start)?),
+            until: Some(local_midnight(end)?),
+        })
+    }
+
+    /// Name of the period starting on `start`, e.g. `2024-W03`, `January 2024` or `2024`
+    pub fn name(self, start: NaiveDate) -> String {
+        match self {
+            Self::Week => start.format("%G-W%V").to_string(),
+            Self::Month => start.format("%B %Y").to_string(),
+            Self::Year => start.format("%Y").to_string(),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Week => "Weekly",
+            Self::Month => "Monthly",
+            Self::Year => "Yearly",
+        }
+    }
+}
+
+/// Granularity for time-bucketed aggregates
+#[cfg(feature = "graphql")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+pub enum TimeBucket {
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+/// Local hour with its UTC offset, e.g. `2024-10-27T02:00+01:00`, so the hour repeated when
+/// DST ends is kept apart from the one before it
+#[cfg(feature = "graphql")]
+const HOUR_BUCKET: &str = "strftime('%Y-%m-%dT%H:00', timestamp, 'localtime')
+    || CASE WHEN julianday(timestamp, 'localtime') < julianday(timestamp) THEN '-' ELSE '+' END
+    || strftime('%H:%M', round(abs(julianday(timestamp, 'localtime') - julianday(timestamp)) * 1440) * 60, 'unixepoch')";
+
+#[cfg(feature = "graphql")]
+impl TimeBucket {
+    /// SQL expression mapping `timestamp` to the start of its bucket in local time
+    pub fn sql_expr(&self) -> &'static str {
+        match self {
+            Self::Hour => HOUR_BUCKET,
+            Self::Day => "date(timestamp, 'localtime')",
+            Self::Week => "date(timestamp, 'localtime', '-6 days', 'weekday 1')",
+            Self::Month => "strftime('%Y-%m', timestamp, 'localtime')",
+        }
+    }
+}
+
+/// Parse a point in time.
+///
+/// Accepts RFC 3339 timestamps, `YYYY-MM-DD` dates (local midnight), `now`, `today`,
+/// `yesterday`, and relative offsets into the past such as `30m`, `12h`, `7d` or `2w`.
+pub fn parse_time(input: &str) -> Result<DateTime<Local>> {
+    let input = input.trim();
+    let now = Local::now();
+
+    match input {
+        "now" => return Ok(now),
+        "today" => return local_midnight(now.date_naive()),
+        "yesterday" => return local_midnight(now.date_naive() - Duration::days(1)),
+        _ => {}
+    }
+
+    if let Ok(t) = DateTime::parse_from_rfc3339(input) {
+        return Ok(t.with_timezone(&Local));
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return local_midnight(date);
+    }
+
+    if let Some(offset) = parse_relative(input)? {
+        return now
+            .checked_sub_signed(offset)
+            .ok_or_else(|| anyhow!("Time '{}' is too far in the past", input));
+    }
+
+    bail!(
+        "Invalid time '{}': expected YYYY-MM-DD, an RFC 3339 timestamp, or a relative offset like 7d",
+        input
+    )
+}
+
+/// Parse a relative duration such as `45m`, `12h`, `7d` or `2w`. None when the input
+/// isn't one, and an error when it is but doesn't fit in a duration.
+pub(crate) fn parse_relative(input: &str) -> Result<Option<Duration>> {
+    let Some(unit) = input.chars().last() else {
+        return Ok(None);
+    };
+    let Ok(amount) = input[..input.len() - unit.len_utf8()].parse::<i64>() else {
+        return Ok(None);
+    };
+
+    let duration = match unit {
+        'm' => Duration::try_minutes(amount),
+        'h' => Duration::try_hours(amount),
+        'd' => Duration::try_days(amount),
+        'w' => Duration::try_weeks(amount),
+        _ => return Ok(None),
+    };
+    duration
+        .map(Some)
+        .ok_or_else(|| anyhow!("Duration '{}' is too long", input))
+}
+
+/// Local midnight at the start of `date`. Where a DST change skips midnight, the day
+/// starts at the first local time that exists.
+pub fn local_midnight(date: NaiveDate) -> Result<DateTime<Local>> {
+    let midnight = date
+        .and_hms_opt(0, 0, 0)
+        .ok_or_else(|| anyhow!("Invalid date {}", date))?;
+
+    (0..=120)
+        .step_by(15)
+        .find_map(|minutes| {
+            Local
+                .from_local_datetime(&(midnight + Duration::minutes(minutes)))
+                .earliest()
+        })
+        .ok_or_else(|| anyhow!("Local midnight does not exist on {}", date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_relative_durations() {
+        assert_eq!(parse_relative("45m").unwrap(), Some(Duration::minutes(45)));
+        assert_eq!(parse_relative("12h").unwrap(), Some(Duration::hours(12)));
+        assert_eq!(parse_relative("7d").unwrap(), Some(Duration::days(7)));
+        assert_eq!(parse_relative("2w").unwrap(), Some(Duration::weeks(2)));
+    }
+
+    #[test]
+    fn ignores_input_that_is_not_relative() {
+        for input in ["", "d", "7", "7y", "seven d", "2024-01-01"] {
+            assert_eq!(parse_relative(input).unwrap(), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_durations_that_overflow() {
+        assert!(parse_relative("99999999999999d").is_err());
+        assert!(parse_relative(&format!("{}w", i64::MAX)).is_err());
+    }
+
+    #[test]
+    fn rejects_times_too_far_in_the_past() {
+        assert!(parse_time("99999999999999d").is_err());
+        assert!(parse_time("999999999d").is_err());
+    }
+
+    #[test]
+    fn parses_points_in_time() {
+        let now = Local::now();
+        let week_ago = parse_time("7d").unwrap();
+        assert!((now - week_ago - Duration::days(7)).num_seconds().abs() < 5);
+
+        let date = parse_time("2024-01-31").unwrap();
+        assert_eq!(
+            date.date_naive(),
+            NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()
+        );
+
+        assert!(parse_time("soon").is_err());
+    }
+
+    #[test]
+    fn parses_spans() {
+        let span = TimeRange::parse_span("2024-01-01..2024-02-01").unwrap();
+        assert_eq!(
+            span.since.unwrap().date_naive(),
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+        );
+        assert_eq!(
+            span.until.unwrap().date_naive(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap()
+        );
+
+        let open = TimeRange::parse_span("2024-01-01").unwrap();
+        assert!(open.since.is_some());
+        assert!(open.until.is_none());
+    }
+}
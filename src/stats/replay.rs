@@ -0,0 +1,129 @@
+use crate::stats::range::parse_relative;
+use anyhow::{anyhow, bail, Result};
+use chrono::{Duration, NaiveDateTime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Recorded time each frame of a replay covers
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayStep {
+    Hour,
+    #[default]
+    Day,
+}
+
+impl ReplayStep {
+    pub fn duration(self) -> Duration {
+        match self {
+            Self::Hour => Duration::hours(1),
+            Self::Day => Duration::days(1),
+        }
+    }
+}
+
+/// Key presses recorded in one step of a replay
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReplayFrame {
+    /// Local time the step starts at
+    pub start: NaiveDateTime,
+    /// Key presses in the step, by key name
+    pub keys: BTreeMap<String, i64>,
+}
+
+/// Key presses step by step, oldest first, for watching a heatmap fill up over time.
+/// Steps without key presses are left out.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Replay {
+    pub step: ReplayStep,
+    pub frames: Vec<ReplayFrame>,
+}
+
+/// Fastest a replay plays, which still shows ten years of typing in a second
+const MAX_SPEED_WEEKS: i64 = 520;
+
+/// How fast a replay plays, as recorded time per second of playback, e.g. `1d/s`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplaySpeed(Duration);
+
+impl ReplaySpeed {
+    /// Recorded time played back each second
+    pub fn per_second(self) -> Duration {
+        self.0
+    }
+}
+
+impl Default for ReplaySpeed {
+    fn default() -> Self {
+        Self(Duration::days(1))
+    }
+}
+
+impl fmt::Display for ReplaySpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = self.0.num_minutes();
+        match minutes {
+            m if m % (7 * 24 * 60) == 0 => write!(f, "{}w/s", m / (7 * 24 * 60)),
+            m if m % (24 * 60) == 0 => write!(f, "{}d/s", m / (24 * 60)),
+            m if m % 60 == 0 => write!(f, "{}h/s", m / 60),
+            m => write!(f, "{}m/s", m),
+        }
+    }
+}
+
+impl FromStr for ReplaySpeed {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let amount = input.strip_suffix("/s").unwrap_or(input);
+        let duration = parse_relative(amount)?.ok_or_else(|| {
+            anyhow!(
+                "Invalid speed '{}': expected recorded time per second like 1d/s, 6h/s or 2w/s",
+                input
+            )
+        })?;
+        if duration <= Duration::zero() {
+            bail!("Replay speed must be more than zero");
+        }
+        if duration > Duration::weeks(MAX_SPEED_WEEKS) {
+            bail!("Replay speed can be at most {}w/s", MAX_SPEED_WEEKS);
+        }
+        Ok(Self(duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_speeds_with_or_without_per_second() {
+        let speed: ReplaySpeed = "6h/s".parse().unwrap();
+        assert_eq!(speed.per_second(), Duration::hours(6));
+        assert_eq!(speed, "6h".parse().unwrap());
+        assert_eq!(speed.to_string(), "6h/s");
+        assert_eq!("14d/s".parse::<ReplaySpeed>().unwrap().to_string(), "2w/s");
+        assert_eq!(ReplaySpeed::default().to_string(), "1d/s");
+    }
+
+    #[test]
+    fn rejects_zero_invalid_and_overflowing_speeds() {
+        for input in [
+            "0d/s",
+            "-1d/s",
+            "fast",
+            "1y/s",
+            "521w/s",
+            "99999999999999d/s",
+        ] {
+            assert!(input.parse::<ReplaySpeed>().is_err(), "{}", input);
+        }
+        assert!("520w/s".parse::<ReplaySpeed>().is_ok());
+    }
+}
@@ -0,0 +1,110 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Gaps between two key presses counted as typing rhythm are shorter than this, in
+/// milliseconds. Longer gaps are pauses to think or read, not rhythm.
+pub const MAX_INTERVAL_MS: i64 = 1000;
+
+/// Width of each histogram bucket, in milliseconds
+const BUCKET_MS: i64 = 50;
+
+/// How long passes between consecutive key presses of a session, a measure of typing
+/// rhythm. Per hour of day, a median that creeps up later in the day hints at fatigue.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct KeyIntervals {
+    /// Intervals shorter than a second between consecutive key presses
+    pub intervals: i64,
+    /// Median interval in milliseconds
+    pub p50_ms: i64,
+    pub p25_ms: i64,
+    pub p75_ms: i64,
+    /// Intervals per 50 ms range, shortest first, up to the range holding the longest
+    pub buckets: Vec<IntervalBucket>,
+    /// Median interval in each local hour of the day, 0 to 23
+    pub hourly: Vec<HourlyInterval>,
+}
+
+/// Intervals of at least `min_ms` and below `max_ms` milliseconds
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IntervalBucket {
+    pub min_ms: i64,
+    pub max_ms: i64,
+    pub count: i64,
+}
+
+/// Intervals between key presses in one local hour of the day
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HourlyInterval {
+    pub hour: u32,
+    pub intervals: i64,
+    /// Absent for hours without intervals
+    pub median_ms: Option<i64>,
+}
+
+impl KeyIntervals {
+    /// Distribution of intervals counted as (local hour, milliseconds, count)
+    pub fn new(counts: &[(u32, i64, i64)]) -> Self {
+        let mut overall: Vec<(i64, i64)> = Vec::new();
+        let mut by_hour: Vec<Vec<(i64, i64)>> = vec![Vec::new(); 24];
+        for &(hour, ms, count) in counts {
+            overall.push((ms, count));
+            if let Some(hour) = by_hour.get_mut(hour as usize) {
+                hour.push((ms, count));
+            }
+        }
+
+        let hourly = by_hour
+            .into_iter()
+            .enumerate()
+            .map(|(hour, mut counts)| HourlyInterval {
+                hour: hour as u32,
+                intervals: counts.iter().map(|(_, count)| count).sum(),
+                median_ms: percentile(&mut counts, 0.50),
+            })
+            .collect();
+
+        let intervals: i64 = overall.iter().map(|(_, count)| count).sum();
+        if intervals == 0 {
+            return Self {
+                hourly,
+                ..Self::default()
+            };
+        }
+
+        let mut buckets: Vec<IntervalBucket> = (0..MAX_INTERVAL_MS / BUCKET_MS)
+            .map(|bucket| IntervalBucket {
+                min_ms: bucket * BUCKET_MS,
+                max_ms: (bucket + 1) * BUCKET_MS,
+                count: 0,
+            })
+            .collect();
+        let last = buckets.len() - 1;
+        for &(ms, count) in &overall {
+            buckets[((ms.max(0) / BUCKET_MS) as usize).min(last)].count += count;
+        }
+        while buckets.last().is_some_and(|bucket| bucket.count == 0) {
+            buckets.pop();
+        }
+
+        Self {
+            intervals,
+            p50_ms: percentile(&mut overall, 0.50).unwrap_or(0),
+            p25_ms: percentile(&mut overall, 0.25).unwrap_or(0),
+            p75_ms: percentile(&mut overall, 0.75).unwrap_or(0),
+            buckets,
+            hourly,
+        }
+    }
+}
+
+/// Nearest-rank percentile of counted values, or `None` when nothing is counted
+fn percentile(counts: &mut [(i64, i64)], p: f64) -> Option<i64> {
+    counts.sort_unstable();
+    let total: i64 = counts.iter().map(|(_, count)| count).sum();
+    let rank = ((p * total as f64).ceil() as i64).max(1);
+    let mut seen = 0;
+    counts.iter().find_map(|&(value, count)| {
+        seen += count;
+        (seen >= rank).then_some(value)
+    })
+}
@@ -0,0 +1,149 @@
+use crate::stats::board::Board;
+use crate::stats::effort::{Finger, Hand};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// Standard layout-analysis categories for key pairs and triples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RollCategory {
+    /// Every key typed with the other hand than the one before it
+    Alternate,
+    /// Two keys on one hand moving from the pinky towards the index finger
+    InwardRoll,
+    /// Two keys on one hand moving from the index finger towards the pinky
+    OutwardRoll,
+    /// Three keys on one hand moving towards the index finger
+    OneHandInward,
+    /// Three keys on one hand moving towards the pinky
+    OneHandOutward,
+    /// Three keys on one hand that change direction halfway
+    Redirect,
+    /// Consecutive keys typed with the same finger, including repeats of one key
+    SameFinger,
+}
+
+impl RollCategory {
+    pub fn label(self) -> &'static str {
+        match self {
+            RollCategory::Alternate => "Alternate",
+            RollCategory::InwardRoll => "Inward roll",
+            RollCategory::OutwardRoll => "Outward roll",
+            RollCategory::OneHandInward => "One-hand inward",
+            RollCategory::OneHandOutward => "One-hand outward",
+            RollCategory::Redirect => "Redirect",
+            RollCategory::SameFinger => "Same finger",
+        }
+    }
+
+    /// Category of two consecutive keys
+    fn of_bigram(first: (Hand, Finger), second: (Hand, Finger)) -> Self {
+        if first.0 != second.0 {
+            RollCategory::Alternate
+        } else if first.1 == second.1 {
+            RollCategory::SameFinger
+        } else if first.1 < second.1 {
+            RollCategory::InwardRoll
+        } else {
+            RollCategory::OutwardRoll
+        }
+    }
+
+    /// Category of three consecutive keys
+    fn of_trigram(first: (Hand, Finger), second: (Hand, Finger), third: (Hand, Finger)) -> Self {
+        let pairs = [
+            Self::of_bigram(first, second),
+            Self::of_bigram(second, third),
+        ];
+        if pairs.contains(&RollCategory::SameFinger) {
+            return RollCategory::SameFinger;
+        }
+
+        match pairs {
+            [RollCategory::Alternate, RollCategory::Alternate] => RollCategory::Alternate,
+            [RollCategory::InwardRoll, RollCategory::InwardRoll] => RollCategory::OneHandInward,
+            [RollCategory::OutwardRoll, RollCategory::OutwardRoll] => RollCategory::OneHandOutward,
+            [RollCategory::InwardRoll, RollCategory::OutwardRoll]
+            | [RollCategory::OutwardRoll, RollCategory::InwardRoll] => RollCategory::Redirect,
+            // One pair on the same hand, the other switching hands
+            [RollCategory::Alternate, roll] | [roll, _] => roll,
+        }
+    }
+}
+
+/// How often a category occurred
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RollShare {
+    pub category: RollCategory,
+    pub count: i64,
+    /// Share of the classified n-grams, in percent
+    pub percentage: f64,
+}
+
+/// Breakdown of recorded bigrams and trigrams into rolls, redirects and the like
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RollAnalysis {
+    /// Bigrams whose keys both belong to a finger; Space, Shift and keys off the main
+    /// block are left out
+    pub bigrams: i64,
+    pub bigram_categories: Vec<RollShare>,
+    /// Trigrams whose keys all belong to a finger
+    pub trigrams: i64,
+    pub trigram_categories: Vec<RollShare>,
+}
+
+impl RollAnalysis {
+    /// Classify bigram and trigram counts, listing each category that occurred with
+    /// the most frequent first
+    pub fn new(
+        bigrams: &[(String, String, i64)],
+        trigrams: &[(String, String, String, i64)],
+        board: &Board,
+    ) -> Self {
+        let (bigrams, bigram_categories) =
+            tally(bigrams.iter().filter_map(|(first, second, count)| {
+                let category =
+                    RollCategory::of_bigram(board.finger_of(first)?, board.finger_of(second)?);
+                Some((category, *count))
+            }));
+        let (trigrams, trigram_categories) =
+            tally(trigrams.iter().filter_map(|(first, second, third, count)| {
+                let category = RollCategory::of_trigram(
+                    board.finger_of(first)?,
+                    board.finger_of(second)?,
+                    board.finger_of(third)?,
+                );
+                Some((category, *count))
+            }));
+
+        Self {
+            bigrams,
+            bigram_categories,
+            trigrams,
+            trigram_categories,
+        }
+    }
+}
+
+/// Total count and per-category shares of classified n-grams
+fn tally(classified: impl Iterator<Item = (RollCategory, i64)>) -> (i64, Vec<RollShare>) {
+    let mut shares: Vec<RollShare> = Vec::new();
+    for (category, count) in classified {
+        match shares.iter_mut().find(|share| share.category == category) {
+            Some(share) => share.count += count,
+            None => shares.push(RollShare {
+                category,
+                count,
+                percentage: 0.0,
+            }),
+        }
+    }
+
+    let total: i64 = shares.iter().map(|share| share.count).sum();
+    for share in &mut shares {
+        share.percentage = share.count as f64 / total as f64 * 100.0;
+    }
+    shares.sort_by_key(|share| Reverse(share.count));
+    (total, shares)
+}
@@ -0,0 +1,78 @@
+use crate::config::ScoreConfig;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Raw metrics for one local calendar day
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct DailyMetrics {
+    /// Minutes in which at least one key was pressed
+    pub active_minutes: i64,
+    /// Words (five keystrokes) per active minute
+    pub wpm: f64,
+    /// Share of keystrokes spent on Backspace/Delete
+    pub error_rate: f64,
+    /// Combos per non-modifier keystroke
+    pub combo_usage: f64,
+}
+
+/// The combined productivity score for one day
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DailyScore {
+    /// Day as `YYYY-MM-DD`
+    pub date: String,
+    /// Weighted score between 0 and 100
+    pub score: f64,
+    pub metrics: DailyMetrics,
+}
+
+impl DailyScore {
+    pub fn new(date: String, metrics: DailyMetrics, config: &ScoreConfig) -> Self {
+        Self {
+            date,
+            score: score(&metrics, config),
+            metrics,
+        }
+    }
+}
+
+/// Combine daily metrics into a single 0-100 score.
+///
+/// Each metric is scaled against its configured target (reaching the target earns
+/// full marks; for the error rate, the target is the rate that earns nothing), then
+/// the scaled values are averaged using the configured weights.
+pub fn score(metrics: &DailyMetrics, config: &ScoreConfig) -> f64 {
+    let weights = &config.weights;
+    let targets = &config.targets;
+
+    let parts = [
+        (
+            weights.active_minutes,
+            ratio(metrics.active_minutes as f64, targets.active_minutes),
+        ),
+        (weights.wpm, ratio(metrics.wpm, targets.wpm)),
+        (
+            weights.error_rate,
+            1.0 - ratio(metrics.error_rate, targets.error_rate),
+        ),
+        (
+            weights.combo_usage,
+            ratio(metrics.combo_usage, targets.combo_usage),
+        ),
+    ];
+
+    let total_weight: f64 = parts.iter().map(|(w, _)| w.max(0.0)).sum();
+    if total_weight <= 0.0 {
+        return 0.0;
+    }
+
+    let weighted: f64 = parts.iter().map(|(w, v)| w.max(0.0) * v).sum();
+    (weighted / total_weight) * 100.0
+}
+
+/// `value / target`, clamped to 0..=1
+fn ratio(value: f64, target: f64) -> f64 {
+    if target <= 0.0 {
+        return 0.0;
+    }
+    (value / target).clamp(0.0, 1.0)
+}
@@ -0,0 +1,107 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Most likely next keys listed for each key
+const NEXT_KEYS: usize = 3;
+
+/// A key that follows another, and how likely it is to
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NextKey {
+    pub key_name: String,
+    pub count: i64,
+    /// Chance of this key coming next, from 0 to 1
+    pub probability: f64,
+}
+
+/// The keys typed right after one key, most likely first
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeyTransitions {
+    pub key_name: String,
+    /// Times any key followed this one
+    pub count: i64,
+    pub next: Vec<NextKey>,
+}
+
+/// Three keys typed in a row
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TrigramStats {
+    pub keys: Vec<String>,
+    pub count: i64,
+    /// Share of all recorded trigrams, in percent
+    pub percentage: f64,
+}
+
+/// What tends to follow what: a first-order Markov model of the recorded key sequence,
+/// and the most common runs of three keys, which point at typed boilerplate
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct KeySequences {
+    /// The most followed keys first, each with its most likely next keys
+    pub transitions: Vec<KeyTransitions>,
+    pub trigrams: i64,
+    pub top_trigrams: Vec<TrigramStats>,
+}
+
+impl KeySequences {
+    /// Model bigram and trigram counts, listing `limit` keys and trigrams
+    pub fn new(
+        bigrams: &[(String, String, i64)],
+        trigrams: &[(String, String, String, i64)],
+        limit: usize,
+    ) -> Self {
+        let mut following: HashMap<&str, Vec<(&str, i64)>> = HashMap::new();
+        for (first, second, count) in bigrams {
+            following.entry(first).or_default().push((second, *count));
+        }
+
+        let mut transitions: Vec<KeyTransitions> = following
+            .into_iter()
+            .map(|(key, mut next)| {
+                let count: i64 = next.iter().map(|(_, count)| count).sum();
+                next.sort_by_key(|&(key, count)| (Reverse(count), key));
+                KeyTransitions {
+                    key_name: key.to_string(),
+                    count,
+                    next: next
+                        .into_iter()
+                        .take(NEXT_KEYS)
+                        .map(|(key, next_count)| NextKey {
+                            key_name: key.to_string(),
+                            count: next_count,
+                            probability: next_count as f64 / count as f64,
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        transitions.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.key_name.cmp(&b.key_name))
+        });
+        transitions.truncate(limit);
+
+        let total: i64 = trigrams.iter().map(|(_, _, _, count)| count).sum();
+        let mut top: Vec<&(String, String, String, i64)> = trigrams.iter().collect();
+        top.sort_by(|a, b| {
+            b.3.cmp(&a.3)
+                .then_with(|| (&a.0, &a.1, &a.2).cmp(&(&b.0, &b.1, &b.2)))
+        });
+        let top_trigrams = top
+            .into_iter()
+            .take(limit)
+            .map(|(first, second, third, count)| TrigramStats {
+                keys: vec![first.clone(), second.clone(), third.clone()],
+                count: *count,
+                percentage: *count as f64 / total as f64 * 100.0,
+            })
+            .collect();
+
+        Self {
+            transitions,
+            trigrams: total,
+            top_trigrams,
+        }
+    }
+}
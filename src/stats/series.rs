@@ -0,0 +1,120 @@
+use crate::stats::range::local_midnight;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate};
+
+/// Metrics that can be charted over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeriesMetric {
+    /// Key presses per bucket
+    Keys,
+    /// Key combinations per bucket
+    Combos,
+    /// Average typing speed in words (five keystrokes) per minute
+    Wpm,
+}
+
+impl SeriesMetric {
+    pub const ALL: [Self; 3] = [Self::Keys, Self::Combos, Self::Wpm];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Keys => "keys",
+            Self::Combos => "combos",
+            Self::Wpm => "wpm",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Keys => "Key presses",
+            Self::Combos => "Key combinations",
+            Self::Wpm => "Typing speed (WPM)",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    /// Counts are zero in buckets without rows; averages are simply missing there
+    pub fn is_count(&self) -> bool {
+        !matches!(self, Self::Wpm)
+    }
+
+    /// Table the metric is read from and the aggregate computed per bucket
+    pub(crate) fn source(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::Keys => ("key_events", "COUNT(*)"),
+            Self::Combos => ("key_combos", "COUNT(*)"),
+            Self::Wpm => ("typing_samples", "AVG(chars_per_minute) / 5.0"),
+        }
+    }
+}
+
+/// A metric's value within the time bucket starting at `time` (Unix seconds)
+#[derive(Debug, Clone, Copy)]
+pub struct SeriesPoint {
+    pub time: i64,
+    pub value: f64,
+}
+
+const DAY_SECS: i64 = 86_400;
+
+/// Whole days per bucket when `interval` (seconds) is a multiple of a day. Such buckets
+/// follow local calendar days, so the 23- and 25-hour days around DST changes stay whole.
+pub(crate) fn interval_days(interval: i64) -> Option<i64> {
+    (interval >= DAY_SECS && interval % DAY_SECS == 0).then_some(interval / DAY_SECS)
+}
+
+/// Start (Unix seconds) of the local day `day` days after 1970-01-01
+pub(crate) fn local_day_start(day: i64) -> Result<i64> {
+    let date = NaiveDate::default() + Duration::days(day);
+    Ok(local_midnight(date)?.timestamp())
+}
+
+/// Days from 1970-01-01 to the local date of `time` (Unix seconds)
+fn local_day(time: i64) -> Result<i64> {
+    let date = DateTime::from_timestamp(time, 0)
+        .ok_or_else(|| anyhow!("Invalid timestamp {}", time))?
+        .with_timezone(&Local)
+        .date_naive();
+    Ok((date - NaiveDate::default()).num_days())
+}
+
+/// Start of the bucket containing `time` (Unix seconds)
+fn bucket_start(time: i64, interval: i64) -> Result<i64> {
+    match interval_days(interval) {
+        Some(days) => local_day_start(local_day(time)?.div_euclid(days) * days),
+        None => Ok(time.div_euclid(interval) * interval),
+    }
+}
+
+/// Start of the bucket after the one starting at `time`
+fn next_bucket(time: i64, interval: i64) -> Result<i64> {
+    match interval_days(interval) {
+        Some(days) => local_day_start(local_day(time)? + days),
+        None => Ok(time + interval),
+    }
+}
+
+/// Insert zero-valued points for empty buckets between `start` and `end` (Unix seconds)
+pub fn fill_gaps(
+    points: Vec<SeriesPoint>,
+    start: i64,
+    end: i64,
+    interval: i64,
+) -> Result<Vec<SeriesPoint>> {
+    let mut filled = Vec::new();
+    let mut recorded = points.into_iter().peekable();
+    let mut time = bucket_start(start, interval)?;
+
+    while time < end {
+        match recorded.peek() {
+            Some(point) if point.time == time => filled.extend(recorded.next()),
+            _ => filled.push(SeriesPoint { time, value: 0.0 }),
+        }
+        time = next_bucket(time, interval)?;
+    }
+
+    Ok(filled)
+}
@@ -0,0 +1,42 @@
+use crate::stats::calculator::ComboStats;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Apps typed in with fewer shortcuts than this per 1000 key presses are flagged
+const FEW_SHORTCUTS_PER_1000_KEYS: f64 = 5.0;
+
+/// Key presses an app needs before it's flagged, so apps barely typed in aren't
+const MIN_KEYS_TO_FLAG: i64 = 500;
+
+/// Shortcuts used in one application, recorded by `kitmap listen --apps`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AppShortcuts {
+    pub app: String,
+    pub keys: i64,
+    pub combos: i64,
+    pub combos_per_1000_keys: f64,
+    /// Whether shortcuts are rare in the app for how much is typed in it, which hints at
+    /// shortcuts worth learning
+    pub few_shortcuts: bool,
+    /// Most used combos in the app
+    pub top_combos: Vec<ComboStats>,
+}
+
+impl AppShortcuts {
+    pub fn new(app: String, keys: i64, combos: i64, top_combos: Vec<ComboStats>) -> Self {
+        let combos_per_1000_keys = if keys > 0 {
+            combos as f64 * 1000.0 / keys as f64
+        } else {
+            0.0
+        };
+        Self {
+            app,
+            keys,
+            combos,
+            combos_per_1000_keys,
+            few_shortcuts: keys >= MIN_KEYS_TO_FLAG
+                && combos_per_1000_keys < FEW_SHORTCUTS_PER_1000_KEYS,
+            top_combos,
+        }
+    }
+}
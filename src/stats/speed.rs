@@ -0,0 +1,100 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Width of each histogram bucket, in characters per minute (10 WPM)
+const BUCKET_CPM: f64 = 50.0;
+
+/// How typing speed samples spread out, which the average and maximum hide: a single
+/// lucky burst sets the maximum
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SpeedDistribution {
+    /// Typing speed samples, taken every 10 seconds of typing
+    pub samples: i64,
+    /// Median speed in characters per minute
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    /// Samples per speed range, slowest first, up to the range holding the 99th
+    /// percentile. Faster samples are counted in a last, open-ended range.
+    pub buckets: Vec<SpeedBucket>,
+}
+
+/// Samples whose speed is at least `min` and below `max` characters per minute
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SpeedBucket {
+    pub min: f64,
+    /// Absent for the last range when it holds everything faster
+    pub max: Option<f64>,
+    pub count: i64,
+}
+
+impl SpeedDistribution {
+    /// Distribution of speeds in characters per minute, sorted slowest first
+    pub fn new(sorted: &[f64]) -> Self {
+        if sorted.is_empty() {
+            return Self::default();
+        }
+
+        let p99 = percentile(sorted, 0.99);
+        let bucket_of = |speed: f64| (speed.max(0.0) / BUCKET_CPM) as usize;
+        let last = bucket_of(p99);
+        let mut buckets: Vec<SpeedBucket> = (0..=last)
+            .map(|bucket| SpeedBucket {
+                min: bucket as f64 * BUCKET_CPM,
+                max: Some((bucket + 1) as f64 * BUCKET_CPM),
+                count: 0,
+            })
+            .collect();
+        for &speed in sorted {
+            let bucket = bucket_of(speed);
+            if bucket > last {
+                if buckets.len() == last + 1 {
+                    buckets.push(SpeedBucket {
+                        min: (last + 1) as f64 * BUCKET_CPM,
+                        max: None,
+                        count: 0,
+                    });
+                }
+                buckets[last + 1].count += 1;
+            } else {
+                buckets[bucket].count += 1;
+            }
+        }
+
+        Self {
+            samples: sorted.len() as i64,
+            p50: percentile(sorted, 0.50),
+            p90: percentile(sorted, 0.90),
+            p99,
+            buckets,
+        }
+    }
+}
+
+/// Average typing speed on one local calendar day
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DailySpeed {
+    pub date: String,
+    pub samples: i64,
+    /// Characters per minute; absent on days without samples
+    pub average_cpm: Option<f64>,
+    /// Words (five characters) per minute; absent on days without samples
+    pub average_wpm: Option<f64>,
+}
+
+impl DailySpeed {
+    pub fn new(date: String, samples: i64, average_cpm: Option<f64>) -> Self {
+        Self {
+            date,
+            samples,
+            average_cpm,
+            average_wpm: average_cpm.map(|cpm| cpm / 5.0),
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
@@ -0,0 +1,198 @@
+use crate::db::models::ComboStorm;
+use chrono::{DateTime, Duration, Local};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Number of identical combos inside the window that marks a storm
+const STORM_THRESHOLD: usize = 15;
+
+/// Sliding window used to measure how fast a combo is firing
+const STORM_WINDOW_MS: i64 = 1000;
+
+/// A combo's storms, as listed in stats. Their presses aren't counted with the combos.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ComboStormStats {
+    pub combo: String,
+    /// Number of separate storms
+    pub storms: i64,
+    /// Presses collapsed into the storms
+    pub count: i64,
+    /// When the latest storm ended, RFC 3339 in local time
+    pub last_seen: String,
+}
+
+/// What the listener should do with a combo it just observed
+#[derive(Debug, PartialEq)]
+pub enum StormDecision {
+    /// Ordinary usage, record the combo as usual
+    Record,
+    /// A storm just started; combos already recorded since `since` belong to it
+    Started { since: DateTime<Local> },
+    /// The combo is part of an ongoing storm and is counted there
+    Absorbed,
+}
+
+/// Detects combos firing at inhuman rates (stuck macros, automation, key repeat)
+/// so they can be collapsed into a single summarized row.
+#[derive(Default)]
+pub struct ComboStormDetector {
+    recent: VecDeque<(String, DateTime<Local>)>,
+    active: Option<ComboStorm>,
+    finished: Vec<ComboStorm>,
+}
+
+impl ComboStormDetector {
+    pub fn new() -> Self {
+        Self {
+            recent: VecDeque::new(),
+            active: None,
+            finished: Vec::new(),
+        }
+    }
+
+    fn window() -> Duration {
+        Duration::milliseconds(STORM_WINDOW_MS)
+    }
+
+    /// Observe a combo and decide how it should be recorded
+    pub fn observe(&mut self, combo: &str, at: DateTime<Local>) -> StormDecision {
+        self.expire(at);
+
+        if let Some(storm) = self.active.as_mut() {
+            if storm.combo == combo {
+                storm.count += 1;
+                storm.end_time = at;
+                return StormDecision::Absorbed;
+            }
+        }
+
+        self.recent.push_back((combo.to_string(), at));
+        while let Some((_, t)) = self.recent.front() {
+            if at - *t > Self::window() {
+                self.recent.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let same: Vec<DateTime<Local>> = self
+            .recent
+            .iter()
+            .filter(|(c, _)| c == combo)
+            .map(|(_, t)| *t)
+            .collect();
+
+        if same.len() < STORM_THRESHOLD {
+            return StormDecision::Record;
+        }
+
+        let since = same[0];
+        self.recent.retain(|(c, _)| c != combo);
+
+        if let Some(previous) = self.active.take() {
+            self.finished.push(previous);
+        }
+        self.active = Some(ComboStorm::new(
+            combo.to_string(),
+            since,
+            at,
+            same.len() as i64,
+        ));
+
+        StormDecision::Started { since }
+    }
+
+    /// End the active storm once its combo has stopped firing
+    fn expire(&mut self, now: DateTime<Local>) {
+        let expired = self
+            .active
+            .as_ref()
+            .is_some_and(|storm| now - storm.end_time > Self::window());
+
+        if expired {
+            self.finished.extend(self.active.take());
+        }
+    }
+
+    /// Take storms that have ended and are ready to be saved
+    pub fn drain_finished(&mut self, now: DateTime<Local>) -> Vec<ComboStorm> {
+        self.expire(now);
+        std::mem::take(&mut self.finished)
+    }
+
+    /// End any active storm and take everything that still needs saving
+    pub fn finish(&mut self) -> Vec<ComboStorm> {
+        self.finished.extend(self.active.take());
+        std::mem::take(&mut self.finished)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(millis: i64) -> DateTime<Local> {
+        Local
+            .timestamp_millis_opt(1_719_828_000_000 + millis)
+            .unwrap()
+    }
+
+    #[test]
+    fn records_combos_pressed_at_human_rates() {
+        let mut detector = ComboStormDetector::new();
+        for i in 0..40 {
+            assert_eq!(
+                detector.observe("Ctrl+C", at(i * 200)),
+                StormDecision::Record
+            );
+        }
+        assert!(detector.finish().is_empty());
+    }
+
+    #[test]
+    fn collapses_a_combo_firing_faster_than_anyone_types() {
+        let mut detector = ComboStormDetector::new();
+        for i in 0..14 {
+            assert_eq!(
+                detector.observe("Alt+Tab", at(i * 20)),
+                StormDecision::Record
+            );
+        }
+        assert_eq!(
+            detector.observe("Alt+Tab", at(14 * 20)),
+            StormDecision::Started { since: at(0) }
+        );
+        for i in 15..50 {
+            assert_eq!(
+                detector.observe("Alt+Tab", at(i * 20)),
+                StormDecision::Absorbed
+            );
+        }
+        // Other combos are still recorded while it storms
+        assert_eq!(detector.observe("Ctrl+S", at(990)), StormDecision::Record);
+        assert!(detector.drain_finished(at(1500)).is_empty());
+
+        let storms = detector.drain_finished(at(49 * 20 + 1001));
+        assert_eq!(storms.len(), 1);
+        assert_eq!(storms[0].combo, "Alt+Tab");
+        assert_eq!(storms[0].count, 50);
+        assert_eq!(storms[0].start_time, at(0));
+        assert_eq!(storms[0].end_time, at(49 * 20));
+
+        // Once it has ended, the combo is recorded again
+        assert_eq!(detector.observe("Alt+Tab", at(5000)), StormDecision::Record);
+    }
+
+    #[test]
+    fn finishing_saves_the_storm_still_going() {
+        let mut detector = ComboStormDetector::new();
+        for i in 0..20 {
+            detector.observe("Super+Q", at(i * 10));
+        }
+        let storms = detector.finish();
+        assert_eq!(storms.len(), 1);
+        assert_eq!(storms[0].count, 20);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::stats::calculator::KeyStats;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Typing on weekdays, Monday to Friday, or on weekends
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct DayTypeStats {
+    /// Days with at least one key press
+    pub active_days: i64,
+    pub keys: i64,
+    /// Key presses per active day
+    pub keys_per_day: f64,
+    /// Average typing speed in characters per minute; absent without speed samples
+    pub average_cpm: Option<f64>,
+    /// Share of the key presses typed in each local hour of the day, 0 to 23, in percent
+    pub hourly_share: Vec<f64>,
+    /// Hour with the most key presses
+    pub busiest_hour: Option<u32>,
+    pub top_keys: Vec<KeyStats>,
+}
+
+impl DayTypeStats {
+    /// Stats from key presses per hour and key counts, most pressed first
+    pub fn new(
+        active_days: i64,
+        hourly: &[i64; 24],
+        average_cpm: Option<f64>,
+        top_keys: Vec<(String, i64)>,
+    ) -> Self {
+        let keys: i64 = hourly.iter().sum();
+        let share = |count: i64| {
+            if keys > 0 {
+                count as f64 / keys as f64 * 100.0
+            } else {
+                0.0
+            }
+        };
+        let busiest_hour = (0..24)
+            .filter(|&hour| hourly[hour] > 0)
+            .max_by_key(|&hour| (hourly[hour], std::cmp::Reverse(hour)))
+            .map(|hour| hour as u32);
+
+        Self {
+            active_days,
+            keys,
+            keys_per_day: if active_days > 0 {
+                keys as f64 / active_days as f64
+            } else {
+                0.0
+            },
+            average_cpm,
+            hourly_share: hourly.iter().map(|&count| share(count)).collect(),
+            busiest_hour,
+            top_keys: top_keys
+                .into_iter()
+                .map(|(key_name, count)| KeyStats {
+                    key_name,
+                    count,
+                    percentage: share(count),
+                    composed: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Weekday and weekend typing side by side, which averages over the whole week hide
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct WeekdayWeekend {
+    pub weekday: DayTypeStats,
+    pub weekend: DayTypeStats,
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde_json::json;
+use std::io::Write;
+use std::time::Duration;
+
+/// Columns of the terminal a cast is recorded in, which every box fits
+pub const CAST_COLUMNS: usize = 80;
+
+/// How long the last frame stays on screen before a cast ends
+const HOLD_LAST_FRAME: Duration = Duration::from_secs(3);
+
+/// Screens captured from the terminal renderer instead of drawn, written out as an
+/// asciinema v2 recording (<https://docs.asciinema.org/manual/asciicast/v2/>)
+#[derive(Debug, Default)]
+pub struct Cast {
+    title: Option<String>,
+    frames: Vec<(Duration, String)>,
+}
+
+impl Cast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Title shown by players, e.g. on asciinema.org
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Capture `screen` to be shown `at` into the recording, replacing the one before it
+    pub fn capture(&mut self, at: Duration, screen: &str) {
+        self.frames.push((at, screen.to_string()));
+    }
+
+    /// Write the header line and one output event per frame, each clearing the screen
+    /// before drawing the frame from the top left
+    pub fn write(&self, mut out: impl Write) -> Result<()> {
+        let height = self
+            .frames
+            .iter()
+            .map(|(_, screen)| screen.lines().count())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut header = json!({
+            "version": 2,
+            "width": CAST_COLUMNS,
+            "height": height,
+            "timestamp": Utc::now().timestamp(),
+            "env": { "TERM": "xterm-256color" },
+        });
+        if let Some(title) = &self.title {
+            header["title"] = json!(title);
+        }
+        writeln!(out, "{}", header)?;
+
+        for (at, screen) in &self.frames {
+            // Players don't translate newlines the way a terminal's driver does
+            let data = format!("\x1b[H\x1b[2J{}", screen.replace('\n', "\r\n"));
+            writeln!(out, "{}", json!([seconds(*at), "o", data]))?;
+        }
+        if let Some((last, _)) = self.frames.last() {
+            writeln!(
+                out,
+                "{}",
+                json!([seconds(*last + HOLD_LAST_FRAME), "o", ""])
+            )?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Event time in seconds, to the millisecond
+fn seconds(at: Duration) -> f64 {
+    at.as_millis() as f64 / 1000.0
+}
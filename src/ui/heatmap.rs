@@ -1,61 +1,63 @@
-use crate::stats::calculator::AllStats;
+use crate::publish::compact;
+use crate::stats::board::Board;
+use crate::stats::calculator::{
+    AllStats, ComboStats, ContextStats, HourlyStats, KeyStats, RangeSummary, StatsSection,
+};
+use crate::stats::calendar::CalendarDay;
+use crate::stats::effort::KeyboardLayout;
+use crate::stats::normalize::Normalization;
+use crate::stats::rolls::RollAnalysis;
+use crate::stats::sequences::KeySequences;
+use crate::stats::shortcuts::AppShortcuts;
+use crate::stats::speed::DailySpeed;
+use crate::stats::weekend::{DayTypeStats, WeekdayWeekend};
+use crate::ui::markdown::relative_change;
+use crate::ui::plain;
+use chrono::{DateTime, Datelike, NaiveDate};
 use crossterm::style::{Color, Stylize};
+use serde_json::Value;
 use std::collections::HashMap;
 
-/// QWERTY keyboard layout for heatmap display
-const KEYBOARD_LAYOUT: &[&[&str]] = &[
-    &[
-        "Escape", "F1", "F2", "F3", "F4", "F5", "F6", "F7", "F8", "F9", "F10", "F11", "F12",
-    ],
-    &[
-        "`",
-        "1",
-        "2",
-        "3",
-        "4",
-        "5",
-        "6",
-        "7",
-        "8",
-        "9",
-        "0",
-        "-",
-        "=",
-        "Backspace",
-    ],
-    &[
-        "Tab", "q", "w", "e", "r", "t", "y", "u", "i", "o", "p", "[", "]", "\\",
-    ],
-    &[
-        "CapsLock", "a", "s", "d", "f", "g", "h", "j", "k", "l", ";", "'", "Return",
-    ],
-    &[
-        "ShiftLeft",
-        "z",
-        "x",
-        "c",
-        "v",
-        "b",
-        "n",
-        "m",
-        ",",
-        ".",
-        "/",
-        "ShiftRight",
-    ],
-    &[
-        "ControlLeft",
-        "MetaLeft",
-        "Alt",
-        "Space",
-        "AltGr",
-        "MetaRight",
-        "ControlRight",
-    ],
+/// Characters of the punctuation keys, by recorded name
+const PUNCTUATION_KEYS: [(char, &str); 11] = [
+    ('`', "BackQuote"),
+    ('-', "Minus"),
+    ('=', "Equal"),
+    ('[', "LeftBracket"),
+    (']', "RightBracket"),
+    ('\\', "BackSlash"),
+    (';', "SemiColon"),
+    ('\'', "Quote"),
+    (',', "Comma"),
+    ('.', "Dot"),
+    ('/', "Slash"),
 ];
 
+/// Horizontal bar for `fraction` of `width` columns, in eighths of a column, padded with
+/// spaces to the full width
+fn bar(fraction: f64, width: usize) -> String {
+    const PARTIAL: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+    let eighths = (fraction.clamp(0.0, 1.0) * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if let Some(&partial) = (eighths % 8).checked_sub(1).and_then(|i| PARTIAL.get(i)) {
+        bar.push(partial);
+    }
+    let columns = bar.chars().count();
+    bar.push_str(&" ".repeat(width - columns));
+    bar
+}
+
 /// Key display names mapping
 fn get_display_name(key: &str) -> &str {
+    if plain::ascii() {
+        match key {
+            "Backspace" => return "BKSP",
+            "Return" | "Enter" => return "ENTER",
+            "ShiftLeft" | "ShiftRight" => return "SHIFT",
+            "MetaLeft" | "MetaRight" => return "META",
+            _ => {}
+        }
+    }
     match key {
         "Escape" => "ESC",
         "Backspace" => "⌫",
@@ -94,6 +96,38 @@ fn get_key_width(key: &str) -> usize {
     }
 }
 
+/// Character a recorded letter, digit or punctuation key types on QWERTY, e.g. `q`
+/// for `KeyQ`
+fn recorded_char(name: &str) -> Option<char> {
+    let single = |rest: &str| {
+        let mut chars = rest.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    };
+    if let Some(c) = name.strip_prefix("Key").and_then(single) {
+        return c.is_ascii_uppercase().then(|| c.to_ascii_lowercase());
+    }
+    if let Some(c) = name.strip_prefix("Num").and_then(single) {
+        return c.is_ascii_digit().then_some(c);
+    }
+    PUNCTUATION_KEYS
+        .iter()
+        .find(|(_, punctuation)| *punctuation == name)
+        .map(|(c, _)| *c)
+}
+
+/// Find a heatmap key's value by its exact name, or case-insensitively
+fn lookup<'a, T>(values: &'a HashMap<String, T>, key: &str) -> Option<&'a T> {
+    if let Some(value) = values.get(key) {
+        return Some(value);
+    }
+
+    let key_lower = key.to_lowercase();
+    values
+        .iter()
+        .find(|(k, _)| k.to_lowercase() == key_lower)
+        .map(|(_, value)| value)
+}
+
 /// Get heat color based on intensity (0.0 to 1.0)
 fn get_heat_color(intensity: f64) -> Color {
     if intensity == 0.0 {
@@ -137,406 +171,1850 @@ fn get_heat_char(intensity: f64) -> char {
 }
 
 pub struct AsciiHeatmap {
+    board: Board,
     key_frequencies: HashMap<String, i64>,
     max_frequency: i64,
+    /// Press counts relative to a reference distribution, when normalizing
+    normalized: Option<(Normalization, HashMap<String, f64>)>,
+    /// Terminal columns to fit the heatmap in
+    columns: Option<usize>,
 }
 
+/// Intensity of keys pressed no more often than the reference expects
+const EXPECTED_INTENSITY: f64 = 0.05;
+
+/// Number of keys listed under a normalized heatmap
+const OVERUSED_KEYS: usize = 5;
+
+/// Width of the boxes drawn in the terminal, in columns
+const BOX_WIDTH: usize = 80;
+
+/// Fewest columns the heatmap's key list is drawn in, however narrow the terminal
+const MIN_LIST_COLUMNS: usize = 32;
+
 impl AsciiHeatmap {
     pub fn new(stats: &AllStats) -> Self {
-        let max_frequency = stats.key_frequency_map.values().cloned().max().unwrap_or(1);
+        Self::from_counts(stats.key_frequency_map.clone())
+    }
+
+    /// Heatmap of press counts by key name
+    pub fn from_counts(key_frequencies: HashMap<String, i64>) -> Self {
+        let max_frequency = key_frequencies.values().cloned().max().unwrap_or(1);
         Self {
-            key_frequencies: stats.key_frequency_map.clone(),
+            board: Board::standard(),
+            key_frequencies,
             max_frequency,
+            normalized: None,
+            columns: None,
         }
     }
 
-    /// Get the intensity (0.0 to 1.0) for a key
-    fn get_intensity(&self, key: &str) -> f64 {
-        // Try exact match first
-        if let Some(&count) = self.key_frequencies.get(key) {
-            return count as f64 / self.max_frequency as f64;
-        }
-
-        // Try case-insensitive match
-        let key_lower = key.to_lowercase();
-        let key_upper = key.to_uppercase();
-
-        for (k, &count) in &self.key_frequencies {
-            if k.to_lowercase() == key_lower || k.to_uppercase() == key_upper || k == &key_upper {
-                return count as f64 / self.max_frequency as f64;
-            }
-        }
+    /// Draw a keyboard other than a standard one
+    pub fn with_board(mut self, board: Board) -> Self {
+        self.board = board;
+        self
+    }
 
-        0.0
+    /// Color keys by how much more often they're pressed than in the reference, so keys
+    /// that are common anyway (vowels, Space) don't dominate
+    pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+        self.normalized = Some((normalization, normalization.ratios(&self.key_frequencies)));
+        self
     }
 
-    /// Get the count for a key
-    fn get_count(&self, key: &str) -> i64 {
-        if let Some(&count) = self.key_frequencies.get(key) {
-            return count;
-        }
+    /// Fit the heatmap in `columns` terminal columns: the full keyboard in a box when
+    /// there's room, smaller keys on narrower terminals, and a list of keys below that
+    pub fn with_columns(mut self, columns: usize) -> Self {
+        self.columns = Some(columns);
+        self
+    }
 
-        let key_lower = key.to_lowercase();
-        let key_upper = key.to_uppercase();
+    /// Get the intensity (0.0 to 1.0) for a key
+    fn get_intensity(&self, key: &str) -> f64 {
+        let Some((_, ratios)) = &self.normalized else {
+            return lookup(&self.key_frequencies, key)
+                .map_or(0.0, |&count| count as f64 / self.max_frequency as f64);
+        };
 
-        for (k, &count) in &self.key_frequencies {
-            if k.to_lowercase() == key_lower || k.to_uppercase() == key_upper || k == &key_upper {
-                return count;
-            }
+        // Log scale, as the most over-used keys can be pressed hundreds of times more often
+        let max_ratio = ratios.values().cloned().fold(1.0, f64::max);
+        match lookup(ratios, key) {
+            None => 0.0,
+            Some(&ratio) if ratio <= 1.0 => EXPECTED_INTENSITY,
+            Some(&ratio) => (ratio.log2() / max_ratio.log2()).clamp(EXPECTED_INTENSITY, 1.0),
         }
+    }
 
-        0
+    /// Text shown on a key: its board label, the character it types, or its short name
+    fn key_label(&self, key: &str, label: Option<&str>) -> String {
+        let label = label.or_else(|| {
+            self.board
+                .names
+                .get(key)
+                .and_then(|name| name.name.as_deref())
+        });
+        match (label, recorded_char(key)) {
+            (Some(label), _) => label.to_string(),
+            (None, Some(c)) => c.to_string(),
+            (None, None) => get_display_name(key).to_string(),
+        }
     }
 
     /// Render a single key with heat color
-    fn render_key(&self, key: &str, width: usize) -> String {
+    fn render_key(&self, key: &str, display: &str, width: usize) -> String {
         let intensity = self.get_intensity(key);
         let color = get_heat_color(intensity);
-        let _heat_char = get_heat_char(intensity);
-        let display = get_display_name(key);
-        let count = self.get_count(key);
+        let heat_char = get_heat_char(intensity);
 
         // Create key display with padding
-        let content = if count > 0 {
-            format!("{}", display)
-        } else {
-            display.to_string()
-        };
-
-        let padded = format!("{:^width$}", content, width = width);
+        let padded = format!("{:^width$}", display, width = width);
+        if !plain::colors() {
+            // Without colors, the padding around the label shows the heat instead
+            let label = display.trim();
+            let before = width.saturating_sub(label.chars().count()) / 2;
+            let after = width.saturating_sub(label.chars().count() + before);
+            let heat = |count| heat_char.to_string().repeat(count);
+            return format!("{}{}{}", heat(before), label, heat(after));
+        }
 
         // Apply color
         format!("{}", padded.with(color))
     }
 
-    /// Render the full keyboard heatmap
+    /// The board's rows drawn as keys, each with the columns it takes up. Compact rows
+    /// shrink every key to three quarters of its width, but never below its label.
+    fn key_rows(&self, compact: bool) -> Vec<(String, usize)> {
+        let shrink = |width: usize| if compact { width * 3 / 4 } else { width };
+        self.board
+            .rows
+            .iter()
+            .map(|row| {
+                let mut line = String::new();
+                let mut columns = 0;
+                for key in row {
+                    let width = match &key.key {
+                        Some(name) => {
+                            let display = self.key_label(name, key.label.as_deref());
+                            let width = shrink(key.width.unwrap_or_else(|| get_key_width(name)))
+                                .max(display.chars().count());
+                            line.push_str(&self.render_key(name, &display, width));
+                            width
+                        }
+                        None => {
+                            let width = shrink(key.width.unwrap_or(0));
+                            line.push_str(&" ".repeat(width));
+                            width
+                        }
+                    };
+                    line.push(' ');
+                    columns += width + 1;
+                }
+                (line, columns)
+            })
+            .collect()
+    }
+
+    /// Render the keyboard heatmap, laid out for the terminal's width
     pub fn render(&self) -> String {
+        let columns = self.columns.unwrap_or(BOX_WIDTH);
+        if columns >= BOX_WIDTH {
+            return self.render_keyboard(self.key_rows(false), BOX_WIDTH);
+        }
+
+        // Rows start after the box's border and two spaces
+        let rows = self.key_rows(true);
+        let widest = rows.iter().map(|(_, width)| width + 3).max().unwrap_or(0);
+        if widest <= columns {
+            self.render_keyboard(rows, columns)
+        } else {
+            self.render_key_list(columns.max(MIN_LIST_COLUMNS))
+        }
+    }
+
+    /// Render keyboard rows in a box `width` columns wide
+    fn render_keyboard(&self, rows: Vec<(String, usize)>, width: usize) -> String {
+        let inner = width - 2;
+        let blank = format!("│{}│\n", " ".repeat(inner));
         let mut output = String::new();
 
-        output.push_str("\n");
-        output.push_str(
-            "┌──────────────────────────────────────────────────────────────────────────────┐\n",
-        );
-        output.push_str(
-            "│                          ⌨️  KEYBOARD HEATMAP                                │\n",
-        );
-        output.push_str(
-            "├──────────────────────────────────────────────────────────────────────────────┤\n",
-        );
-        output.push_str(
-            "│                                                                              │\n",
-        );
+        output.push('\n');
+        output.push_str(&format!("┌{}┐\n", "─".repeat(inner)));
+        output.push_str(&format!("│{:^inner$}│\n", "⌨️  KEYBOARD HEATMAP"));
+        output.push_str(&format!("├{}┤\n", "─".repeat(inner)));
+        output.push_str(&blank);
 
-        for row in KEYBOARD_LAYOUT {
+        for (row, _) in rows {
             output.push_str("│  ");
-            for key in *row {
-                let width = get_key_width(key);
-                output.push_str(&self.render_key(key, width));
-                output.push(' ');
+            output.push_str(&row);
+            output.push('\n');
+            output.push_str(&blank);
+        }
+
+        output.push_str(&format!("├{}┤\n", "─".repeat(inner)));
+        output.push_str(&self.render_legend(inner));
+        output.push_str(&format!("└{}┘\n", "─".repeat(inner)));
+        output.push_str(&self.render_overused());
+
+        output
+    }
+
+    /// Render the pressed keys as a list with a heat bar each, busiest first, for
+    /// terminals too narrow for any keyboard
+    fn render_key_list(&self, width: usize) -> String {
+        const LABEL: usize = 10;
+        const COUNT: usize = 7;
+        let inner = width - 2;
+        let bar_width = inner - LABEL - COUNT - 4;
+        let mut output = String::new();
+
+        let mut keys: Vec<(String, f64, i64)> = self
+            .board
+            .rows
+            .iter()
+            .flatten()
+            .filter_map(|key| {
+                let name = key.key.as_deref()?;
+                let intensity = self.get_intensity(name);
+                let count = lookup(&self.key_frequencies, name).copied().unwrap_or(0);
+                // Board labels, then typed characters, then the recorded name, which
+                // tells left and right modifiers apart
+                let label = key
+                    .label
+                    .clone()
+                    .or_else(|| recorded_char(name).map(String::from))
+                    .unwrap_or_else(|| name.to_string());
+                (count > 0).then_some((label, intensity, count))
+            })
+            .collect();
+        keys.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.2.cmp(&a.2)));
+
+        output.push('\n');
+        output.push_str(&format!("┌{}┐\n", "─".repeat(inner)));
+        output.push_str(&format!("│{:^inner$}│\n", "⌨️  KEYBOARD HEATMAP"));
+        output.push_str(&format!("├{}┤\n", "─".repeat(inner)));
+        for (label, intensity, count) in keys {
+            let filled = ((intensity * bar_width as f64).round() as usize).clamp(1, bar_width);
+            let label: String = label.chars().take(LABEL).collect();
+            output.push_str(&format!(
+                "│ {:<LABEL$} {}{} {:>COUNT$} │\n",
+                label,
+                "█".repeat(filled).with(get_heat_color(intensity)),
+                " ".repeat(bar_width - filled),
+                count
+            ));
+        }
+        output.push_str(&format!("├{}┤\n", "─".repeat(inner)));
+        output.push_str(&self.render_legend(inner));
+        output.push_str(&format!("└{}┘\n", "─".repeat(inner)));
+        output.push_str(&self.render_overused());
+
+        output
+    }
+
+    /// Render the heat legend in a box `inner` columns wide, wrapped onto more lines
+    /// when it doesn't fit on one
+    fn render_legend(&self, inner: usize) -> String {
+        let (title, items) = if self.normalized.is_some() {
+            (
+                "Compared to English:",
+                [
+                    ("░ Unused", Color::DarkGrey),
+                    ("▒ Expected", Color::Blue),
+                    ("▓ More", Color::Green),
+                    ("█ Much more", Color::Yellow),
+                    ("█ Most", Color::Red),
+                ],
+            )
+        } else {
+            (
+                "Heat Legend:",
+                [
+                    ("░ Cold", Color::DarkGrey),
+                    ("▒ Low", Color::Blue),
+                    ("▓ Med", Color::Green),
+                    ("█ High", Color::Yellow),
+                    ("█ Hot", Color::Red),
+                ],
+            )
+        };
+
+        let mut lines = vec![(format!("  {}", title), title.chars().count() + 2)];
+        for (item, color) in items {
+            let width = item.chars().count() + 1;
+            let (line, columns) = lines.last_mut().expect("legend has a title line");
+            if *columns + width > inner && *columns > 2 {
+                lines.push((format!("  {}", item.with(color)), width + 1));
+            } else {
+                line.push_str(&format!(" {}", item.with(color)));
+                *columns += width;
             }
-            output.push_str("\n");
-            output.push_str("│                                                                              │\n");
         }
+        lines
+            .into_iter()
+            .map(|(line, columns)| {
+                format!("│{}{}│\n", line, " ".repeat(inner.saturating_sub(columns)))
+            })
+            .collect()
+    }
 
-        output.push_str(
-            "├──────────────────────────────────────────────────────────────────────────────┤\n",
-        );
-        output.push_str("│  Heat Legend: ");
-        output.push_str(&format!("{} ", "░ Cold".with(Color::DarkGrey)));
-        output.push_str(&format!("{} ", "▒ Low".with(Color::Blue)));
-        output.push_str(&format!("{} ", "▓ Med".with(Color::Green)));
-        output.push_str(&format!("{} ", "█ High".with(Color::Yellow)));
-        output.push_str(&format!("{}", "█ Hot".with(Color::Red)));
-        output.push_str("                                  │\n");
-        output.push_str(
-            "└──────────────────────────────────────────────────────────────────────────────┘\n",
-        );
+    /// List the keys pressed most often compared to the reference, when normalizing
+    fn render_overused(&self) -> String {
+        let mut output = String::new();
+        if let Some((_, ratios)) = &self.normalized {
+            let mut overused: Vec<(&String, &f64)> =
+                ratios.iter().filter(|(_, &ratio)| ratio > 1.0).collect();
+            overused.sort_by(|a, b| b.1.total_cmp(a.1).then(a.0.cmp(b.0)));
+            if !overused.is_empty() {
+                let keys: Vec<String> = overused
+                    .iter()
+                    .take(OVERUSED_KEYS)
+                    .map(|(key, ratio)| format!("{} {:.0}×", key, ratio))
+                    .collect();
+                output.push_str(&format!(
+                    "   Most over-used compared to English: {}\n",
+                    keys.join(", ")
+                ));
+            }
+        }
 
         output
     }
 
-    /// Render key statistics summary
-    pub fn render_stats(&self, stats: &AllStats) -> String {
+    /// Render the statistics summary and tables of `sections`
+    pub fn render_stats(&self, stats: &AllStats, sections: &[StatsSection]) -> String {
+        const SEPARATOR: &str =
+            "├──────────────────────────────────────────────────────────────────────────────┤\n";
+        let wants = |section| sections.contains(&section);
         let mut output = String::new();
 
-        output.push_str(
-            "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
-        );
-        output.push_str(
-            "│                           📊 KEYBOARD STATISTICS                             │\n",
-        );
-        output.push_str(
-            "├──────────────────────────────────────────────────────────────────────────────┤\n",
-        );
+        // Summary box, with one block per group of figures
+        let mut blocks = Vec::new();
 
-        // General stats
-        output.push_str(&format!(
-            "│  Total Keys Pressed: {:>55} │\n",
-            format!("{}", stats.total_keys).with(Color::Cyan)
-        ));
-        output.push_str(&format!(
-            "│  Total Key Combos: {:>57} │\n",
-            format!("{}", stats.total_combos).with(Color::Cyan)
-        ));
-        output.push_str(&format!(
-            "│  Total Sessions: {:>59} │\n",
-            format!("{}", stats.total_sessions).with(Color::Cyan)
-        ));
-        output.push_str(&format!(
-            "│  Total Time (minutes): {:>53} │\n",
-            format!("{:.1}", stats.total_time_minutes).with(Color::Cyan)
-        ));
-        output.push_str(&format!(
-            "│  Unique Keys Used: {:>57} │\n",
-            format!("{}", stats.unique_keys_used).with(Color::Cyan)
-        ));
+        if wants(StatsSection::Overview) {
+            // General stats
+            let mut block = String::new();
+            block.push_str(&format!(
+                "│  Total Keys Pressed: {:>55} │\n",
+                format!("{}", stats.total_keys).with(Color::Cyan)
+            ));
+            block.push_str(&format!(
+                "│  Total Key Combos: {:>57} │\n",
+                format!("{}", stats.total_combos).with(Color::Cyan)
+            ));
+            block.push_str(&format!(
+                "│  Total Sessions: {:>59} │\n",
+                format!("{}", stats.total_sessions).with(Color::Cyan)
+            ));
+            block.push_str(&format!(
+                "│  Total Time (minutes): {:>53} │\n",
+                format!("{:.1}", stats.total_time_minutes).with(Color::Cyan)
+            ));
+            block.push_str(&format!(
+                "│  Unique Keys Used: {:>57} │\n",
+                format!("{}", stats.unique_keys_used).with(Color::Cyan)
+            ));
+            blocks.push(block);
 
-        output.push_str(
-            "├──────────────────────────────────────────────────────────────────────────────┤\n",
-        );
+            // Most pressed key
+            let mut block = String::new();
+            if let Some(ref key) = stats.most_pressed_key {
+                block.push_str(&format!(
+                    "│  Most Pressed Key: {:>57} │\n",
+                    format!("{} ({}x, {:.1}%)", key.label(), key.count, key.percentage)
+                        .with(Color::Green)
+                ));
+            }
 
-        // Most pressed key
-        if let Some(ref key) = stats.most_pressed_key {
-            output.push_str(&format!(
-                "│  Most Pressed Key: {:>57} │\n",
-                format!("{} ({}x, {:.1}%)", key.key_name, key.count, key.percentage)
-                    .with(Color::Green)
+            // Most pressed combo
+            if let Some(ref combo) = stats.most_pressed_combo {
+                block.push_str(&format!(
+                    "│  Most Pressed Combo: {:>55} │\n",
+                    format!("{} ({}x)", combo.combo, combo.count).with(Color::Green)
+                ));
+            }
+            blocks.push(block);
+        }
+
+        if wants(StatsSection::Keys) {
+            // Special keys
+            let mut block = String::new();
+            block.push_str(&format!(
+                "│  Spacebar: {:>65} │\n",
+                format!("{}", stats.spacebar_count).with(Color::Yellow)
+            ));
+            block.push_str(&format!(
+                "│  Enter: {:>68} │\n",
+                format!("{}", stats.enter_count).with(Color::Yellow)
+            ));
+            block.push_str(&format!(
+                "│  Backspace: {:>64} │\n",
+                format!("{}", stats.backspace_count).with(Color::Yellow)
+            ));
+            block.push_str(&format!(
+                "│  Delete: {:>67} │\n",
+                format!("{}", stats.delete_count).with(Color::Yellow)
+            ));
+            block.push_str(&format!(
+                "│  Tab: {:>70} │\n",
+                format!("{}", stats.tab_count).with(Color::Yellow)
+            ));
+            block.push_str(&format!(
+                "│  Escape: {:>67} │\n",
+                format!("{}", stats.escape_count).with(Color::Yellow)
+            ));
+            block.push_str(&format!(
+                "│  Arrow Keys: {:>63} │\n",
+                format!("{}", stats.arrow_keys_count).with(Color::Yellow)
+            ));
+            blocks.push(block);
+
+            // Key categories
+            let mut block = String::new();
+            block.push_str(&format!(
+                "│  Letter Keys: {:>62} │\n",
+                format!("{}", stats.letter_keys_count).with(Color::Magenta)
+            ));
+            block.push_str(&format!(
+                "│  Number Keys: {:>62} │\n",
+                format!("{}", stats.number_keys_count).with(Color::Magenta)
+            ));
+            block.push_str(&format!(
+                "│  Modifier Keys: {:>60} │\n",
+                format!("{}", stats.modifier_keys_count).with(Color::Magenta)
             ));
+            block.push_str(&format!(
+                "│  Special Keys: {:>61} │\n",
+                format!("{}", stats.special_keys_count).with(Color::Magenta)
+            ));
+            blocks.push(block);
+
+            // Where non-modifier presses land
+            let mut block = String::new();
+            let rows = &stats.row_usage;
+            for (label, percent) in [
+                ("Home Row", rows.home),
+                ("Top Row", rows.top),
+                ("Bottom Row", rows.bottom),
+                ("Number Row", rows.number),
+                ("Off the Rows", rows.other),
+            ] {
+                let value = format!("{:>width$.1}%", percent, width = 72 - label.len());
+                block.push_str(&format!("│  {}: {} │\n", label, value.with(Color::Green)));
+            }
+            blocks.push(block);
+
+            // Hand alternation
+            let alternation = &stats.hand_alternation;
+            if alternation.bigrams > 0 {
+                let mut block = String::new();
+                let value = format!("{:>56.1}%", alternation.rate);
+                block.push_str(&format!(
+                    "│  Hand Alternation: {} │\n",
+                    value.with(Color::Green)
+                ));
+                for line in [
+                    "Share of key pairs typed with one key on each hand. While one hand",
+                    "types, the other can already reach for the next key, so higher is",
+                    "usually smoother and faster.",
+                ] {
+                    let line = format!("{:<75}", line);
+                    block.push_str(&format!("│  {} │\n", line.dark_grey()));
+                }
+                blocks.push(block);
+            }
         }
 
-        // Most pressed combo
-        if let Some(ref combo) = stats.most_pressed_combo {
-            output.push_str(&format!(
-                "│  Most Pressed Combo: {:>55} │\n",
-                format!("{} ({}x)", combo.combo, combo.count).with(Color::Green)
+        if wants(StatsSection::Activity) {
+            // Typing speed
+            let mut block = String::new();
+            block.push_str(&format!(
+                "│  Avg Typing Speed (CPM): {:>51} │\n",
+                format!("{:.1}", stats.average_typing_speed).with(Color::Cyan)
             ));
+            block.push_str(&format!(
+                "│  Max Typing Speed (CPM): {:>51} │\n",
+                format!("{:.1}", stats.max_typing_speed).with(Color::Cyan)
+            ));
+            block.push_str(&format!(
+                "│  Avg Keys Per Minute: {:>54} │\n",
+                format!("{:.1}", stats.keys_per_minute_avg).with(Color::Cyan)
+            ));
+            block.push_str(&format!(
+                "│  Avg Keys Per Session: {:>53} │\n",
+                format!("{:.1}", stats.average_keys_per_session).with(Color::Cyan)
+            ));
+            blocks.push(block);
+
+            // Most active times
+            let mut block = String::new();
+            if let Some(ref hour) = stats.most_active_hour {
+                block.push_str(&format!(
+                    "│  Most Active Hour: {:>57} │\n",
+                    format!("{}:00 ({} keys)", hour.hour, hour.count).with(Color::Green)
+                ));
+            }
+            if let Some(ref day) = stats.most_active_day {
+                block.push_str(&format!(
+                    "│  Most Active Day: {:>58} │\n",
+                    format!("{} ({} keys)", day.day, day.count).with(Color::Green)
+                ));
+            }
+            blocks.push(block);
         }
 
-        output.push_str(
-            "├──────────────────────────────────────────────────────────────────────────────┤\n",
-        );
+        if wants(StatsSection::Overview) {
+            // Time range
+            let mut block = String::new();
+            if let Some(ref first) = stats.first_recorded {
+                block.push_str(&format!(
+                    "│  First Recorded: {:>59} │\n",
+                    first[..19].to_string().with(Color::DarkGrey)
+                ));
+            }
+            if let Some(ref last) = stats.last_recorded {
+                block.push_str(&format!(
+                    "│  Last Recorded: {:>60} │\n",
+                    last[..19].to_string().with(Color::DarkGrey)
+                ));
+            }
+            blocks.push(block);
+        }
+
+        // Metrics added with `[[custom_metrics]]`, whichever sections are shown
+        if !stats.custom_metrics.is_empty() {
+            let mut block = String::new();
+            for metric in &stats.custom_metrics {
+                let value = match &metric.value {
+                    Value::Null => "-".to_string(),
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                let width = 73usize.saturating_sub(metric.name.chars().count());
+                let value = format!("{:>width$}", value, width = width);
+                block.push_str(&format!(
+                    "│  {}: {} │\n",
+                    metric.name,
+                    value.with(Color::Blue)
+                ));
+            }
+            blocks.push(block);
+        }
+
+        if !blocks.is_empty() {
+            output.push_str(
+                "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+            );
+            output.push_str(
+                "│                           📊 KEYBOARD STATISTICS                             │\n",
+            );
+            output.push_str(SEPARATOR);
+            output.push_str(&blocks.join(SEPARATOR));
+            output.push_str(
+                "└──────────────────────────────────────────────────────────────────────────────┘\n",
+            );
+        }
+
+        if wants(StatsSection::Top) {
+            let top_keys = &stats.top_keys[..stats.top_keys.len().min(10)];
+            output.push_str(&render_key_table("🔝 TOP 10 KEYS", top_keys));
+
+            if !stats.top_combos.is_empty() {
+                let top_combos = &stats.top_combos[..stats.top_combos.len().min(10)];
+                output.push_str(&render_combo_table("⌨️  TOP KEY COMBOS", top_combos));
+            }
+
+            // Top chords
+            if !stats.top_chords.is_empty() {
+                output.push_str("\n┌──────────────────────────────────────────────────────────────────────────────┐\n");
+                output.push_str("│                            🎼 TOP KEY CHORDS                                 │\n");
+                output.push_str("├───────┬──────────────────────────┬───────────────┬────────────────────────────┤\n");
+                output.push_str("│ Rank  │         Chord            │     Count     │       Avg Spread (ms)      │\n");
+                output.push_str("├───────┼──────────────────────────┼───────────────┼────────────────────────────┤\n");
+
+                for (i, chord) in stats.top_chords.iter().take(10).enumerate() {
+                    output.push_str(&format!(
+                        "│  {:>2}.  │ {:^24} │ {:>13} │ {:>26.1} │\n",
+                        i + 1,
+                        &chord.chord[..chord.chord.len().min(24)],
+                        chord.count,
+                        chord.avg_spread_ms
+                    ));
+                }
+
+                output.push_str("└───────┴──────────────────────────┴───────────────┴────────────────────────────┘\n");
+            }
+
+            // Combos fired by automation, which the combo counts leave out
+            if !stats.combo_storms.is_empty() {
+                let rule = |left, middle, right| {
+                    format!(
+                        "{}{}{}{}{}{}{}{}{}{}{}\n",
+                        left,
+                        "─".repeat(7),
+                        middle,
+                        "─".repeat(24),
+                        middle,
+                        "─".repeat(10),
+                        middle,
+                        "─".repeat(13),
+                        middle,
+                        "─".repeat(20),
+                        right
+                    )
+                };
+                output.push_str(&format!("\n┌{}┐\n", "─".repeat(78)));
+                // The emoji is drawn two columns wide
+                output.push_str(&format!(
+                    "│{:^77}│\n",
+                    "🌀 COMBO STORMS (left out of the combo counts)"
+                ));
+                output.push_str(&rule("├", "┬", "┤"));
+                output.push_str(&format!(
+                    "│ Rank  │{:^24}│{:^10}│{:^13}│{:^20}│\n",
+                    "Combo", "Storms", "Presses", "Last storm"
+                ));
+                output.push_str(&rule("├", "┼", "┤"));
+
+                for (i, storm) in stats.combo_storms.iter().take(10).enumerate() {
+                    let name: String = storm.combo.chars().take(22).collect();
+                    let last_seen = DateTime::parse_from_rfc3339(&storm.last_seen)
+                        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_else(|_| storm.last_seen.chars().take(18).collect());
+                    output.push_str(&format!(
+                        "│ {:>3}.  │ {:^22} │ {:>8} │ {:>11} │ {:^18} │\n",
+                        i + 1,
+                        name,
+                        storm.storms,
+                        storm.count,
+                        last_seen
+                    ));
+                }
+
+                output.push_str(&rule("└", "┴", "┘"));
+            }
+        }
+
+        if wants(StatsSection::Contexts) {
+            // Top workspaces
+            if !stats.top_workspaces.is_empty() {
+                output.push_str(&render_context_table(
+                    "🗂️  TOP WORKSPACES",
+                    &stats.top_workspaces,
+                ));
+            }
+
+            // Top monitors
+            if !stats.top_monitors.is_empty() {
+                output.push_str(&render_context_table(
+                    "🖥️  TOP MONITORS",
+                    &stats.top_monitors,
+                ));
+            }
+
+            // Top projects
+            if !stats.top_projects.is_empty() {
+                output.push_str(&render_context_table(
+                    "📁 TOP PROJECTS",
+                    &stats.top_projects,
+                ));
+            }
+
+            // Shortcut use per app
+            if !stats.app_shortcuts.is_empty() {
+                output.push_str(&render_app_shortcuts(&stats.app_shortcuts));
+            }
+
+            // Remote vs local work
+            let tagged_keys = stats.remote_keys_count + stats.local_keys_count;
+            if tagged_keys > 0 {
+                let mut split = [
+                    ("Remote (SSH)", stats.remote_keys_count),
+                    ("Local", stats.local_keys_count),
+                ]
+                .map(|(name, count)| ContextStats {
+                    name: name.to_string(),
+                    count,
+                    percentage: (count as f64 / tagged_keys as f64) * 100.0,
+                });
+                split.sort_by_key(|c| std::cmp::Reverse(c.count));
+                output.push_str(&render_context_table("🌐️  REMOTE VS LOCAL", &split));
+            }
+        }
+
+        if wants(StatsSection::Activity) {
+            // Hourly distribution
+            output.push_str(&render_hourly_chart(&stats.hourly_distribution));
+
+            // Weekday × hour grid
+            let max_cell = stats
+                .weekday_hours
+                .iter()
+                .flat_map(|d| d.hours.iter().copied())
+                .max()
+                .unwrap_or(0);
+            if max_cell > 0 {
+                output.push_str(
+                    "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+                );
+                output.push_str(
+                    "│                               📆 WEEKLY RHYTHM                               │\n",
+                );
+                output.push_str(
+                    "├──────────────────────────────────────────────────────────────────────────────┤\n",
+                );
+                let hours: String = (0..24)
+                    .map(|h| {
+                        if h % 3 == 0 {
+                            format!(" {:<2}", h)
+                        } else {
+                            "   ".to_string()
+                        }
+                    })
+                    .collect();
+                output.push_str(&format!("│     {} │\n", hours));
+
+                for day in &stats.weekday_hours {
+                    let cells: String = day
+                        .hours
+                        .iter()
+                        .map(|&count| {
+                            let intensity = count as f64 / max_cell as f64;
+                            if plain::colors() {
+                                format!(" {}", "██".with(get_heat_color(intensity)))
+                            } else {
+                                format!(" {}", get_heat_char(intensity).to_string().repeat(2))
+                            }
+                        })
+                        .collect();
+                    output.push_str(&format!(
+                        "│ {} {} │\n",
+                        day.day.get(..3).unwrap_or(&day.day),
+                        cells
+                    ));
+                }
+
+                output.push_str(
+                    "└──────────────────────────────────────────────────────────────────────────────┘\n",
+                );
+            }
+
+            // Typing speed histogram
+            let speeds = &stats.speed_distribution;
+            if speeds.samples > 0 {
+                output.push_str(
+                    "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+                );
+                output.push_str(
+                    "│                          ⚡ TYPING SPEED (CPM)                               │\n",
+                );
+                output.push_str(
+                    "├──────────────────────────────────────────────────────────────────────────────┤\n",
+                );
+                let percentiles = format!(
+                    "{:<75}",
+                    format!(
+                        "Median {:.0}    90th percentile {:.0}    99th percentile {:.0}",
+                        speeds.p50, speeds.p90, speeds.p99
+                    )
+                );
+                output.push_str(&format!("│  {} │\n", percentiles.with(Color::Cyan)));
+                output.push_str(
+                    "├─────────────┬──────────┬─────────────────────────────────────────────────────┤\n",
+                );
+
+                let max_count = speeds.buckets.iter().map(|b| b.count).max().unwrap_or(0);
+                for bucket in &speeds.buckets {
+                    let label = match bucket.max {
+                        Some(max) => format!("{:.0}-{:.0}", bucket.min, max),
+                        None => format!("{:.0}+", bucket.min),
+                    };
+                    let intensity = if max_count > 0 {
+                        bucket.count as f64 / max_count as f64
+                    } else {
+                        0.0
+                    };
+                    let bar_len = (intensity * 51.0) as usize;
+                    let bar = format!("{}{}", "█".repeat(bar_len), " ".repeat(51 - bar_len));
+
+                    output.push_str(&format!(
+                        "│ {:>11} │ {:>8} │ {} │\n",
+                        label,
+                        bucket.count,
+                        bar.with(get_heat_color(intensity))
+                    ));
+                }
+
+                output.push_str(
+                    "└─────────────┴──────────┴─────────────────────────────────────────────────────┘\n",
+                );
+            }
+
+            // Time between key presses, overall and by hour of day
+            let intervals = &stats.key_intervals;
+            if intervals.intervals > 0 {
+                output.push_str(
+                    "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+                );
+                output.push_str(
+                    "│                      🎵 TYPING RHYTHM (MS BETWEEN KEYS)                      │\n",
+                );
+                output.push_str(
+                    "├──────────────────────────────────────────────────────────────────────────────┤\n",
+                );
+                let percentiles = format!(
+                    "{:<75}",
+                    format!(
+                        "Median {} ms    Middle half {}-{} ms    {} intervals",
+                        intervals.p50_ms, intervals.p25_ms, intervals.p75_ms, intervals.intervals
+                    )
+                );
+                output.push_str(&format!("│  {} │\n", percentiles.with(Color::Cyan)));
+                output.push_str(
+                    "├─────────────┬──────────┬─────────────────────────────────────────────────────┤\n",
+                );
+
+                let max_count = intervals.buckets.iter().map(|b| b.count).max().unwrap_or(0);
+                for bucket in &intervals.buckets {
+                    let intensity = if max_count > 0 {
+                        bucket.count as f64 / max_count as f64
+                    } else {
+                        0.0
+                    };
+                    let bar_len = (intensity * 51.0) as usize;
+                    let bar = format!("{}{}", "█".repeat(bar_len), " ".repeat(51 - bar_len));
+
+                    output.push_str(&format!(
+                        "│ {:>11} │ {:>8} │ {} │\n",
+                        format!("{}-{}", bucket.min_ms, bucket.max_ms),
+                        bucket.count,
+                        bar.with(get_heat_color(intensity))
+                    ));
+                }
+
+                // Median per hour, hotter when slower
+                output.push_str(
+                    "├─────────────┴──────────┴─────────────────────────────────────────────────────┤\n",
+                );
+                output.push_str(&format!("│ {:<76} │\n", "Median by hour of day"));
+                let slowest = intervals
+                    .hourly
+                    .iter()
+                    .filter_map(|h| h.median_ms)
+                    .max()
+                    .unwrap_or(0);
+                for row in intervals.hourly.chunks(8) {
+                    let cells: String = row
+                        .iter()
+                        .map(|hour| match hour.median_ms {
+                            Some(ms) => format!(
+                                " {:02}h {}",
+                                hour.hour,
+                                format!("{:>4}", ms)
+                                    .with(get_heat_color(ms as f64 / slowest.max(1) as f64))
+                            ),
+                            None => format!(" {:02}h {:>4}", hour.hour, "–"),
+                        })
+                        .collect();
+                    output.push_str(&format!("│   {} │\n", cells));
+                }
+
+                output.push_str(
+                    "└──────────────────────────────────────────────────────────────────────────────┘\n",
+                );
+            }
+
+            // Fastest bursts of typing
+            if !stats.top_bursts.is_empty() {
+                output.push_str("\n┌──────────────────────────────────────────────────────────────────────────────┐\n");
+                output.push_str("│                               🔥 TYPING BURSTS                               │\n");
+                output.push_str("├───────┬────────────────────┬───────────┬─────────┬─────────────┬─────────────┤\n");
+                output.push_str("│ Rank  │      Started       │ Duration  │  Keys   │  Peak CPM   │   Avg CPM   │\n");
+                output.push_str("├───────┼────────────────────┼───────────┼─────────┼─────────────┼─────────────┤\n");
+
+                for (i, burst) in stats.top_bursts.iter().enumerate() {
+                    let started = burst.started.get(..16).unwrap_or(&burst.started);
+                    output.push_str(&format!(
+                        "│  {:>2}.  │ {:^18} │ {:>8}s │ {:>7} │ {:>11.0} │ {:>11.0} │\n",
+                        i + 1,
+                        started.replace('T', " "),
+                        format!("{:.0}", burst.duration_secs),
+                        burst.keys,
+                        burst.peak_cpm,
+                        burst.average_cpm
+                    ));
+                }
+
+                output.push_str("└───────┴────────────────────┴───────────┴─────────┴─────────────┴─────────────┘\n");
+            }
+        }
+
+        if wants(StatsSection::Weekend) {
+            let split = &stats.weekday_weekend;
+            if split.weekday.keys > 0 || split.weekend.keys > 0 {
+                output.push_str(&render_weekday_weekend(split));
+            }
+        }
+
+        if wants(StatsSection::Progress) {
+            // Productivity score history
+            if !stats.daily_scores.is_empty() {
+                output.push_str(
+                    "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+                );
+                output.push_str(
+                    "│                          📈 PRODUCTIVITY SCORE                              │\n",
+                );
+                output.push_str(
+                    "├────────────┬───────┬─────────────────────────────────────────────────────────┤\n",
+                );
+
+                for day in &stats.daily_scores {
+                    let intensity = day.score / 100.0;
+                    output.push_str(&format!(
+                        "│ {} │ {:>5.1} │ {} │\n",
+                        day.date,
+                        day.score,
+                        bar(intensity, 55).with(get_heat_color(intensity))
+                    ));
+                }
+
+                output.push_str(
+                    "└────────────┴───────┴─────────────────────────────────────────────────────────┘\n",
+                );
+            }
+
+            // Typing effort, with the same keys estimated on each layout
+            if !stats.daily_effort.is_empty() {
+                output.push_str(
+                    "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+                );
+                output.push_str(
+                    "│                     💪 TYPING EFFORT (per key, 1.0 = home row)              │\n",
+                );
+                output.push_str(
+                    "├────────────┬──────────┬────────────┬─────────────┬─────────────┬─────────────┤\n",
+                );
+                output.push_str("│    Date    │   Keys   │   Total    │");
+                for layout in KeyboardLayout::ALL {
+                    let marker = if stats.daily_effort[0].layout == layout {
+                        "*"
+                    } else {
+                        ""
+                    };
+                    output.push_str(&format!(
+                        " {:^11} │",
+                        format!("{}{}", layout.name(), marker)
+                    ));
+                }
+                output.push('\n');
+                output.push_str(
+                    "├────────────┼──────────┼────────────┼─────────────┼─────────────┼─────────────┤\n",
+                );
+
+                for day in &stats.daily_effort {
+                    output.push_str(&format!(
+                        "│ {} │ {:>8} │ {:>10.0} │",
+                        day.date, day.keys, day.total
+                    ));
+                    let best = day
+                        .layouts
+                        .iter()
+                        .map(|effort| effort.average)
+                        .fold(f64::INFINITY, f64::min);
+                    for effort in &day.layouts {
+                        let value = format!("{:^11.2}", effort.average);
+                        if effort.average <= best {
+                            output.push_str(&format!(" {} │", value.green()));
+                        } else {
+                            output.push_str(&format!(" {} │", value));
+                        }
+                    }
+                    output.push('\n');
+                }
+
+                output.push_str(
+                    "└────────────┴──────────┴────────────┴─────────────┴─────────────┴─────────────┘\n",
+                );
+            }
+
+            // Goal progress
+            if !stats.goals.is_empty() {
+                output.push_str(
+                    "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+                );
+                output.push_str(
+                    "│                                 🎯 GOALS                                    │\n",
+                );
+                output.push_str(
+                    "├──────────────────────────┬─────────────────┬─────────────────────────────────┤\n",
+                );
+
+                for goal in &stats.goals {
+                    let bar_len = (goal.progress * 31.0) as usize;
+                    let bar = format!("{}{}", "█".repeat(bar_len), "░".repeat(31 - bar_len));
+                    let label = format!(
+                        "{} {}{}",
+                        goal.period.label(),
+                        goal.metric.label(),
+                        if goal.met { " ✓" } else { "" }
+                    );
+                    let value = format!(
+                        "{} / {}",
+                        goal.metric.format(goal.current),
+                        goal.metric.format(goal.target)
+                    );
+
+                    output.push_str(&format!(
+                        "│ {:<24} │ {:>15} │ {} │\n",
+                        label,
+                        value,
+                        bar.with(get_heat_color(goal.progress))
+                    ));
+                }
+
+                output.push_str(
+                    "└──────────────────────────┴─────────────────┴─────────────────────────────────┘\n",
+                );
+            }
+        }
+
+        output
+    }
+}
+
+/// Render a ranked table of keys with bars relative to the first
+pub fn render_key_table(title: &str, keys: &[KeyStats]) -> String {
+    let mut output = String::new();
+
+    output.push_str(
+        "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+    );
+    output.push_str(&format!("│{:^78}│\n", title));
+    output.push_str(
+        "├───────┬──────────────┬───────────────┬────────────────────────────────────────┤\n",
+    );
+    output.push_str(
+        "│ Rank  │     Key      │     Count     │              Bar                       │\n",
+    );
+    output.push_str(
+        "├───────┼──────────────┼───────────────┼────────────────────────────────────────┤\n",
+    );
+
+    let max_count = keys.first().map(|k| k.count).unwrap_or(1).max(1);
+    for (i, key) in keys.iter().enumerate() {
+        let intensity = key.count as f64 / max_count as f64;
+        let bar = "█".repeat((intensity * 35.0) as usize);
 
-        // Special keys
-        output.push_str(&format!(
-            "│  Spacebar: {:>65} │\n",
-            format!("{}", stats.spacebar_count).with(Color::Yellow)
-        ));
-        output.push_str(&format!(
-            "│  Enter: {:>68} │\n",
-            format!("{}", stats.enter_count).with(Color::Yellow)
-        ));
-        output.push_str(&format!(
-            "│  Backspace: {:>64} │\n",
-            format!("{}", stats.backspace_count).with(Color::Yellow)
-        ));
         output.push_str(&format!(
-            "│  Delete: {:>67} │\n",
-            format!("{}", stats.delete_count).with(Color::Yellow)
+            "│ {:>3}.  │ {:^12} │ {:>13} │ {:<38} │\n",
+            i + 1,
+            get_display_name(key.label()),
+            key.count,
+            bar.with(get_heat_color(intensity))
         ));
+    }
+
+    output.push_str(
+        "└───────┴──────────────┴───────────────┴────────────────────────────────────────┘\n",
+    );
+
+    output
+}
+
+/// Render a ranked table of key combos with bars relative to the first
+pub fn render_combo_table(title: &str, combos: &[ComboStats]) -> String {
+    let mut output = String::new();
+
+    output.push_str(
+        "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+    );
+    output.push_str(&format!("│{:^78}│\n", title));
+    output.push_str(
+        "├───────┬──────────────────────────┬───────────────┬────────────────────────────┤\n",
+    );
+    output.push_str(
+        "│ Rank  │         Combo            │     Count     │            Bar             │\n",
+    );
+    output.push_str(
+        "├───────┼──────────────────────────┼───────────────┼────────────────────────────┤\n",
+    );
+
+    let max_count = combos.first().map(|c| c.count).unwrap_or(1).max(1);
+    for (i, combo) in combos.iter().enumerate() {
+        let intensity = combo.count as f64 / max_count as f64;
+        let bar = "█".repeat((intensity * 25.0) as usize);
+        let name: String = combo.combo.chars().take(24).collect();
+
         output.push_str(&format!(
-            "│  Tab: {:>70} │\n",
-            format!("{}", stats.tab_count).with(Color::Yellow)
+            "│ {:>3}.  │ {:^24} │ {:>13} │ {:<26} │\n",
+            i + 1,
+            name,
+            combo.count,
+            bar.with(get_heat_color(intensity))
         ));
+    }
+
+    output.push_str(
+        "└───────┴──────────────────────────┴───────────────┴────────────────────────────┘\n",
+    );
+
+    output
+}
+
+/// Render a ranked table of key presses per context value (workspace, monitor, ...)
+fn render_context_table(title: &str, contexts: &[ContextStats]) -> String {
+    let mut output = String::new();
+
+    output.push_str(
+        "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+    );
+    output.push_str(&format!("│{:^78}│\n", title));
+    output.push_str(
+        "├───────┬──────────────────────────┬───────────────┬────────────────────────────┤\n",
+    );
+    output.push_str(
+        "│ Rank  │           Name           │     Share     │            Bar             │\n",
+    );
+    output.push_str(
+        "├───────┼──────────────────────────┼───────────────┼────────────────────────────┤\n",
+    );
+
+    let max_count = contexts.first().map(|c| c.count).unwrap_or(1).max(1);
+    for (i, context) in contexts.iter().take(10).enumerate() {
+        let intensity = context.count as f64 / max_count as f64;
+        let bar = "█".repeat((intensity * 25.0) as usize);
+        let name: String = context.name.chars().take(24).collect();
+
         output.push_str(&format!(
-            "│  Escape: {:>67} │\n",
-            format!("{}", stats.escape_count).with(Color::Yellow)
+            "│  {:>2}.  │ {:^24} │ {:>12.1}% │ {:<26} │\n",
+            i + 1,
+            name,
+            context.percentage,
+            bar.with(get_heat_color(intensity))
         ));
+    }
+
+    output.push_str(
+        "└───────┴──────────────────────────┴───────────────┴────────────────────────────┘\n",
+    );
+
+    output
+}
+
+fn render_app_shortcuts(apps: &[AppShortcuts]) -> String {
+    let mut output = String::new();
+
+    output.push_str(
+        "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+    );
+    output.push_str(
+        "│                             🧭 SHORTCUTS BY APP                              │\n",
+    );
+    output.push_str(
+        "├────────────────────┬──────────┬─────────┬─────────┬──────────────────────────┤\n",
+    );
+    output.push_str(
+        "│        App         │   Keys   │ Combos  │ Per 1k  │        Top combo         │\n",
+    );
+    output.push_str(
+        "├────────────────────┼──────────┼─────────┼─────────┼──────────────────────────┤\n",
+    );
+
+    for app in apps {
+        let name: String = app.app.chars().take(18).collect();
+        let top: String = app
+            .top_combos
+            .first()
+            .map(|c| c.combo.chars().take(24).collect())
+            .unwrap_or_else(|| "–".to_string());
+        let rate = format!("{:>7.1}", app.combos_per_1000_keys);
+        let rate = if app.few_shortcuts {
+            rate.yellow()
+        } else {
+            rate.stylize()
+        };
         output.push_str(&format!(
-            "│  Arrow Keys: {:>63} │\n",
-            format!("{}", stats.arrow_keys_count).with(Color::Yellow)
+            "│ {:<18} │ {:>8} │ {:>7} │ {} │ {:<24} │\n",
+            name, app.keys, app.combos, rate, top
         ));
+    }
 
+    let flagged: Vec<&str> = apps
+        .iter()
+        .filter(|app| app.few_shortcuts)
+        .map(|app| app.app.as_str())
+        .collect();
+    if !flagged.is_empty() {
         output.push_str(
-            "├──────────────────────────────────────────────────────────────────────────────┤\n",
+            "├────────────────────┴──────────┴─────────┴─────────┴──────────────────────────┤\n",
         );
+        let note: String = format!("Few shortcuts in: {}", flagged.join(", "))
+            .chars()
+            .take(76)
+            .collect();
+        output.push_str(&format!("│ {} │\n", format!("{:<76}", note).yellow()));
+        output.push_str(
+            "└──────────────────────────────────────────────────────────────────────────────┘\n",
+        );
+    } else {
+        output.push_str(
+            "└────────────────────┴──────────┴─────────┴─────────┴──────────────────────────┘\n",
+        );
+    }
 
-        // Key categories
-        output.push_str(&format!(
-            "│  Letter Keys: {:>62} │\n",
-            format!("{}", stats.letter_keys_count).with(Color::Magenta)
-        ));
-        output.push_str(&format!(
-            "│  Number Keys: {:>62} │\n",
-            format!("{}", stats.number_keys_count).with(Color::Magenta)
-        ));
+    output
+}
+
+/// Number of keys whose rank moved most listed in a comparison
+const COMPARISON_RANK_CHANGES: usize = 5;
+
+/// Render two time ranges side by side: totals, the top `top_keys` keys of `b` with
+/// their change in share since `a`, and the keys whose rank moved most
+pub fn render_comparison(
+    a_label: &str,
+    b_label: &str,
+    a: &RangeSummary,
+    b: &RangeSummary,
+    top_keys: usize,
+) -> String {
+    let mut output = String::new();
+    let a_label: String = a_label.chars().take(16).collect();
+    let b_label: String = b_label.chars().take(16).collect();
+
+    output.push_str(
+        "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+    );
+    output.push_str(&format!("│{:^78}│\n", "⚖️  COMPARISON"));
+    output.push_str(
+        "├──────────────────────┬──────────────────┬──────────────────┬─────────────────┤\n",
+    );
+    output.push_str(&format!(
+        "│ {:<20} │ {:>16} │ {:>16} │ {:>15} │\n",
+        "Metric", a_label, b_label, "Change"
+    ));
+    output.push_str(
+        "├──────────────────────┼──────────────────┼──────────────────┼─────────────────┤\n",
+    );
+
+    let busiest = |summary: &RangeSummary| {
+        summary
+            .most_active_day
+            .as_ref()
+            .map_or("–".to_string(), |d| d.day.clone())
+    };
+    let rows = [
+        (
+            "Keys pressed",
+            a.total_keys.to_string(),
+            b.total_keys.to_string(),
+            relative_change(b.total_keys as f64, a.total_keys as f64),
+            (b.total_keys - a.total_keys) as f64,
+        ),
+        (
+            "Combos used",
+            a.total_combos.to_string(),
+            b.total_combos.to_string(),
+            relative_change(b.total_combos as f64, a.total_combos as f64),
+            (b.total_combos - a.total_combos) as f64,
+        ),
+        (
+            "Active minutes",
+            a.active_minutes.to_string(),
+            b.active_minutes.to_string(),
+            relative_change(b.active_minutes as f64, a.active_minutes as f64),
+            (b.active_minutes - a.active_minutes) as f64,
+        ),
+        (
+            "Typing speed (CPM)",
+            format!("{:.1}", a.average_typing_speed),
+            format!("{:.1}", b.average_typing_speed),
+            relative_change(b.average_typing_speed, a.average_typing_speed),
+            b.average_typing_speed - a.average_typing_speed,
+        ),
+        (
+            "Most active day",
+            busiest(a),
+            busiest(b),
+            String::new(),
+            0.0,
+        ),
+    ];
+    for (name, a_value, b_value, change, delta) in rows {
         output.push_str(&format!(
-            "│  Modifier Keys: {:>60} │\n",
-            format!("{}", stats.modifier_keys_count).with(Color::Magenta)
+            "│ {:<20} │ {:>16} │ {:>16} │ {} │\n",
+            name,
+            a_value,
+            b_value,
+            colored_change(&change, delta)
         ));
+    }
+
+    output.push_str(
+        "├──────────────────────┼──────────────────┼──────────────────┼─────────────────┤\n",
+    );
+    output.push_str(&format!(
+        "│ {:<20} │ {:>16} │ {:>16} │ {:>15} │\n",
+        "Top keys", a_label, b_label, "Share change"
+    ));
+    output.push_str(
+        "├──────────────────────┼──────────────────┼──────────────────┼─────────────────┤\n",
+    );
+
+    for (i, key) in b.keys.iter().take(top_keys).enumerate() {
+        let before = a.keys.iter().find(|k| k.key_name == key.key_name);
+        let delta = key.percentage - before.map_or(0.0, |k| k.percentage);
         output.push_str(&format!(
-            "│  Special Keys: {:>61} │\n",
-            format!("{}", stats.special_keys_count).with(Color::Magenta)
+            "│ {:>3}. {:<15} │ {:>16} │ {:>16} │ {} │\n",
+            i + 1,
+            get_display_name(key.label()),
+            before.map_or(0, |k| k.count),
+            key.count,
+            colored_change(&format!("{:+.1} pts", delta), delta)
         ));
+    }
 
+    let mut moves: Vec<(&KeyStats, usize, usize)> = b
+        .keys
+        .iter()
+        .take(top_keys)
+        .chain(a.keys.iter().take(top_keys))
+        .filter_map(|key| {
+            let rank_a = a.keys.iter().position(|k| k.key_name == key.key_name)?;
+            let rank_b = b.keys.iter().position(|k| k.key_name == key.key_name)?;
+            (rank_a != rank_b).then_some((key, rank_a + 1, rank_b + 1))
+        })
+        .collect();
+    moves.sort_by_key(|(key, rank_a, rank_b)| {
+        (
+            std::cmp::Reverse(rank_a.abs_diff(*rank_b)),
+            *rank_b,
+            key.key_name.clone(),
+        )
+    });
+    moves.dedup_by(|x, y| x.0.key_name == y.0.key_name);
+
+    if !moves.is_empty() {
         output.push_str(
-            "├──────────────────────────────────────────────────────────────────────────────┤\n",
+            "├──────────────────────┼──────────────────┼──────────────────┼─────────────────┤\n",
         );
-
-        // Typing speed
-        output.push_str(&format!(
-            "│  Avg Typing Speed (CPM): {:>51} │\n",
-            format!("{:.1}", stats.average_typing_speed).with(Color::Cyan)
-        ));
         output.push_str(&format!(
-            "│  Max Typing Speed (CPM): {:>51} │\n",
-            format!("{:.1}", stats.max_typing_speed).with(Color::Cyan)
+            "│ {:<20} │ {:>16} │ {:>16} │ {:>15} │\n",
+            "Biggest rank changes", a_label, b_label, "Moved"
         ));
-        output.push_str(&format!(
-            "│  Avg Keys Per Minute: {:>54} │\n",
-            format!("{:.1}", stats.keys_per_minute_avg).with(Color::Cyan)
-        ));
-        output.push_str(&format!(
-            "│  Avg Keys Per Session: {:>53} │\n",
-            format!("{:.1}", stats.average_keys_per_session).with(Color::Cyan)
-        ));
-
         output.push_str(
-            "├──────────────────────────────────────────────────────────────────────────────┤\n",
+            "├──────────────────────┼──────────────────┼──────────────────┼─────────────────┤\n",
         );
-
-        // Most active times
-        if let Some(ref hour) = stats.most_active_hour {
-            output.push_str(&format!(
-                "│  Most Active Hour: {:>57} │\n",
-                format!("{}:00 ({} keys)", hour.hour, hour.count).with(Color::Green)
-            ));
-        }
-        if let Some(ref day) = stats.most_active_day {
+        for (key, rank_a, rank_b) in moves.into_iter().take(COMPARISON_RANK_CHANGES) {
+            let moved = if rank_b < rank_a {
+                format!("▲ {}", rank_a - rank_b)
+            } else {
+                format!("▼ {}", rank_b - rank_a)
+            };
             output.push_str(&format!(
-                "│  Most Active Day: {:>58} │\n",
-                format!("{} ({} keys)", day.day, day.count).with(Color::Green)
+                "│      {:<15} │ {:>16} │ {:>16} │ {} │\n",
+                get_display_name(key.label()),
+                format!("#{}", rank_a),
+                format!("#{}", rank_b),
+                colored_change(&moved, rank_a as f64 - rank_b as f64)
             ));
         }
+    }
+
+    output.push_str(
+        "└──────────────────────┴──────────────────┴──────────────────┴─────────────────┘\n",
+    );
+
+    output
+}
+
+/// Pad a change to its column, green when it went up and red when it went down
+fn colored_change(change: &str, delta: f64) -> String {
+    let padded = format!("{:>15}", change);
+    if delta >= 0.05 {
+        padded.with(Color::Green).to_string()
+    } else if delta <= -0.05 {
+        padded.with(Color::Red).to_string()
+    } else {
+        padded
+    }
+}
+
+/// Render the breakdown of bigrams and trigrams into rolls, redirects and the like
+pub fn render_roll_table(analysis: &RollAnalysis) -> String {
+    let mut output = String::new();
+
+    output.push_str(
+        "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+    );
+    output.push_str(
+        "│                         🔁 ROLLS AND REDIRECTS                               │\n",
+    );
 
+    let mut first_section = true;
+    for (title, total, categories) in [
+        ("Bigrams", analysis.bigrams, &analysis.bigram_categories),
+        ("Trigrams", analysis.trigrams, &analysis.trigram_categories),
+    ] {
+        if categories.is_empty() {
+            continue;
+        }
+        output.push_str(if first_section {
+            "├────────────────────┬────────────┬──────────┬─────────────────────────────────┤\n"
+        } else {
+            "├────────────────────┼────────────┼──────────┼─────────────────────────────────┤\n"
+        });
+        first_section = false;
+        output.push_str(&format!(
+            "│ {:<18} │ {:>10} │ {:>8} │ {:<31} │\n",
+            title, total, "Share", ""
+        ));
         output.push_str(
-            "├──────────────────────────────────────────────────────────────────────────────┤\n",
+            "├────────────────────┼────────────┼──────────┼─────────────────────────────────┤\n",
         );
 
-        // Time range
-        if let Some(ref first) = stats.first_recorded {
-            output.push_str(&format!(
-                "│  First Recorded: {:>59} │\n",
-                first[..19].to_string().with(Color::DarkGrey)
-            ));
-        }
-        if let Some(ref last) = stats.last_recorded {
+        for share in categories {
+            let bar = format!(
+                "{:<31}",
+                "█".repeat((share.percentage / 100.0 * 31.0) as usize)
+            );
             output.push_str(&format!(
-                "│  Last Recorded: {:>60} │\n",
-                last[..19].to_string().with(Color::DarkGrey)
+                "│ {:<18} │ {:>10} │ {:>7.1}% │ {} │\n",
+                share.category.label(),
+                share.count,
+                share.percentage,
+                bar.with(get_heat_color(share.percentage / 100.0))
             ));
         }
+    }
 
-        output.push_str(
-            "└──────────────────────────────────────────────────────────────────────────────┘\n",
-        );
+    output.push_str(
+        "└────────────────────┴────────────┴──────────┴─────────────────────────────────┘\n",
+    );
+
+    output
+}
+
+/// Short label of a recorded key: the character it types, or its display name
+fn short_key_label(key: &str) -> String {
+    recorded_char(key)
+        .map(String::from)
+        .unwrap_or_else(|| get_display_name(key).to_string())
+}
+
+/// Figures of a weekday or weekend column, in the order of their row labels
+fn day_type_values(day: &DayTypeStats) -> [String; 6] {
+    [
+        day.active_days.to_string(),
+        day.keys.to_string(),
+        format!("{:.0}", day.keys_per_day),
+        day.average_cpm
+            .map(|cpm| format!("{:.1}", cpm))
+            .unwrap_or_else(|| "–".to_string()),
+        day.busiest_hour
+            .map(|hour| format!("{:02}:00", hour))
+            .unwrap_or_else(|| "–".to_string()),
+        day.top_keys
+            .iter()
+            .map(|key| short_key_label(&key.key_name))
+            .collect::<Vec<_>>()
+            .join(" "),
+    ]
+}
+
+/// Render weekday and weekend stats in two columns, then the shape of each one's day
+fn render_weekday_weekend(split: &WeekdayWeekend) -> String {
+    const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let mut output = String::new();
+
+    output.push_str(
+        "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+    );
+    output.push_str(
+        "│                           🌴 WEEKDAYS VS WEEKENDS                            │\n",
+    );
+    output.push_str(
+        "├──────────────────────────┬──────────────────────────┬────────────────────────┤\n",
+    );
+    output.push_str(&format!(
+        "│ {:<24} │ {:>24} │ {:>22} │\n",
+        "", "Weekdays", "Weekends"
+    ));
+    output.push_str(
+        "├──────────────────────────┼──────────────────────────┼────────────────────────┤\n",
+    );
+
+    let labels = [
+        "Active days",
+        "Keys",
+        "Keys per day",
+        "Avg speed (CPM)",
+        "Busiest hour",
+        "Top keys",
+    ];
+    let weekday = day_type_values(&split.weekday);
+    let weekend = day_type_values(&split.weekend);
+    for ((label, weekday), weekend) in labels.iter().zip(weekday).zip(weekend) {
+        let weekday: String = weekday.chars().take(24).collect();
+        let weekend: String = weekend.chars().take(22).collect();
+        output.push_str(&format!(
+            "│ {:<24} │ {} │ {} │\n",
+            label,
+            format!("{:>24}", weekday).with(Color::Cyan),
+            format!("{:>22}", weekend).with(Color::Yellow)
+        ));
+    }
+
+    // Share of each day's keys per hour, on one scale so the two shapes compare
+    output.push_str(
+        "├──────────────────────────┴──────────────────────────┴────────────────────────┤\n",
+    );
+    let hours: String = (0..24).step_by(3).map(|h| format!("{:<6}", h)).collect();
+    output.push_str(&format!("│ {:<10}{}{:<18} │\n", "Hour", hours, ""));
+    let highest = split
+        .weekday
+        .hourly_share
+        .iter()
+        .chain(&split.weekend.hourly_share)
+        .fold(0.0, |max: f64, &share| max.max(share));
+    for (label, day, color) in [
+        ("Weekdays", &split.weekday, Color::Cyan),
+        ("Weekends", &split.weekend, Color::Yellow),
+    ] {
+        let sparks: String = day
+            .hourly_share
+            .iter()
+            .map(|&share| {
+                if share > 0.0 && highest > 0.0 {
+                    let level = ((share / highest) * 7.0).round() as usize;
+                    SPARKS[level.min(7)].to_string().repeat(2)
+                } else {
+                    "  ".to_string()
+                }
+            })
+            .collect();
+        output.push_str(&format!(
+            "│ {:<10}{}{:<18} │\n",
+            label,
+            sparks.with(color),
+            ""
+        ));
+    }
+
+    output.push_str(
+        "└──────────────────────────────────────────────────────────────────────────────┘\n",
+    );
+
+    output
+}
+
+/// Render the most likely next keys after the most followed keys, then the most common
+/// 3-key sequences with bars relative to the most common one
+pub fn render_sequences(sequences: &KeySequences) -> String {
+    let mut output = String::new();
 
-        // Top keys
+    if !sequences.transitions.is_empty() {
         output.push_str(
             "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
         );
         output.push_str(
-            "│                              🔝 TOP 10 KEYS                                  │\n",
+            "│                                 🔮 NEXT KEY                                  │\n",
         );
         output.push_str(
-            "├───────┬──────────────┬───────────────┬────────────────────────────────────────┤\n",
-        );
-        output.push_str(
-            "│ Rank  │     Key      │     Count     │              Bar                       │\n",
+            "├────────────┬──────────┬──────────────────────────────────────────────────────┤\n",
         );
+        output.push_str(&format!(
+            "│ {:<10} │ {:>8} │ {:<52} │\n",
+            "Key", "Followed", "Most likely next"
+        ));
         output.push_str(
-            "├───────┼──────────────┼───────────────┼────────────────────────────────────────┤\n",
+            "├────────────┼──────────┼──────────────────────────────────────────────────────┤\n",
         );
 
-        let max_count = stats.top_keys.first().map(|k| k.count).unwrap_or(1);
-        for (i, key) in stats.top_keys.iter().take(10).enumerate() {
-            let bar_len = ((key.count as f64 / max_count as f64) * 35.0) as usize;
-            let bar = "█".repeat(bar_len);
-            let intensity = key.count as f64 / max_count as f64;
-            let color = get_heat_color(intensity);
-
+        for transition in &sequences.transitions {
+            let key: String = short_key_label(&transition.key_name)
+                .chars()
+                .take(10)
+                .collect();
+            let next = transition
+                .next
+                .iter()
+                .map(|next| {
+                    let label: String = short_key_label(&next.key_name).chars().take(6).collect();
+                    format!("{:<6} {:>3.0}%", label, next.probability * 100.0)
+                })
+                .collect::<Vec<_>>()
+                .join("  ");
             output.push_str(&format!(
-                "│  {:>2}.  │ {:^12} │ {:>13} │ {:<38} │\n",
-                i + 1,
-                get_display_name(&key.key_name),
-                key.count,
-                bar.with(color)
+                "│ {:<10} │ {:>8} │ {:<52} │\n",
+                key, transition.count, next
             ));
         }
 
         output.push_str(
-            "└───────┴──────────────┴───────────────┴────────────────────────────────────────┘\n",
+            "└────────────┴──────────┴──────────────────────────────────────────────────────┘\n",
         );
+    }
 
-        // Top combos
-        if !stats.top_combos.is_empty() {
-            output.push_str("\n┌──────────────────────────────────────────────────────────────────────────────┐\n");
-            output.push_str("│                            ⌨️  TOP KEY COMBOS                                │\n");
-            output.push_str("├───────┬──────────────────────────┬───────────────┬────────────────────────────┤\n");
-            output.push_str("│ Rank  │         Combo            │     Count     │            Bar             │\n");
-            output.push_str("├───────┼──────────────────────────┼───────────────┼────────────────────────────┤\n");
-
-            let max_combo = stats.top_combos.first().map(|c| c.count).unwrap_or(1);
-            for (i, combo) in stats.top_combos.iter().take(10).enumerate() {
-                let bar_len = ((combo.count as f64 / max_combo as f64) * 25.0) as usize;
-                let bar = "█".repeat(bar_len);
-                let intensity = combo.count as f64 / max_combo as f64;
-                let color = get_heat_color(intensity);
-
-                output.push_str(&format!(
-                    "│  {:>2}.  │ {:^24} │ {:>13} │ {:<26} │\n",
-                    i + 1,
-                    &combo.combo[..combo.combo.len().min(24)],
-                    combo.count,
-                    bar.with(color)
-                ));
-            }
-
-            output.push_str("└───────┴──────────────────────────┴───────────────┴────────────────────────────┘\n");
-        }
-
-        // Hourly distribution
+    if !sequences.top_trigrams.is_empty() {
         output.push_str(
             "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
         );
         output.push_str(
-            "│                          ⏰ HOURLY ACTIVITY                                  │\n",
+            "│                            🔗 TOP 3-KEY SEQUENCES                            │\n",
         );
         output.push_str(
-            "├──────────────────────────────────────────────────────────────────────────────┤\n",
+            "├──────────────────────────┬────────────┬──────────┬───────────────────────────┤\n",
+        );
+        output.push_str(&format!(
+            "│ {:<24} │ {:>10} │ {:>8} │ {:<25} │\n",
+            "Sequence", "Count", "Share", ""
+        ));
+        output.push_str(
+            "├──────────────────────────┼────────────┼──────────┼───────────────────────────┤\n",
         );
 
-        let max_hourly = stats
-            .hourly_distribution
-            .iter()
-            .map(|h| h.count)
-            .max()
-            .unwrap_or(1);
-        for h in &stats.hourly_distribution {
-            let bar_len = if max_hourly > 0 {
-                ((h.count as f64 / max_hourly as f64) * 50.0) as usize
-            } else {
-                0
-            };
-            let bar = "█".repeat(bar_len);
-            let intensity = if max_hourly > 0 {
-                h.count as f64 / max_hourly as f64
-            } else {
-                0.0
-            };
-            let color = get_heat_color(intensity);
-
+        let most = sequences.top_trigrams[0].count.max(1) as f64;
+        for trigram in &sequences.top_trigrams {
+            let keys: String = trigram
+                .keys
+                .iter()
+                .map(|key| short_key_label(key))
+                .collect::<Vec<_>>()
+                .join(" ")
+                .chars()
+                .take(24)
+                .collect();
+            let ratio = trigram.count as f64 / most;
+            let bar = format!("{:<25}", "█".repeat((ratio * 25.0) as usize));
             output.push_str(&format!(
-                "│  {:02}:00 │ {:>8} │ {:<50} │\n",
-                h.hour,
-                h.count,
-                bar.with(color)
+                "│ {:<24} │ {:>10} │ {:>7.1}% │ {} │\n",
+                keys,
+                trigram.count,
+                trigram.percentage,
+                bar.with(get_heat_color(ratio))
             ));
         }
 
         output.push_str(
-            "└──────────────────────────────────────────────────────────────────────────────┘\n",
+            "└──────────────────────────┴────────────┴──────────┴───────────────────────────┘\n",
         );
+    }
 
-        output
+    output
+}
+
+/// Render key presses per hour of the day as columns, eight rows high in eighths of a
+/// row, with the counts on the left and the hours below
+fn render_hourly_chart(hours: &[HourlyStats]) -> String {
+    const HEIGHT: usize = 8;
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let mut counts = [0; 24];
+    for hour in hours {
+        if let Some(count) = counts.get_mut(hour.hour as usize) {
+            *count = hour.count;
+        }
+    }
+    let busiest = counts.iter().copied().max().unwrap_or(0).max(1);
+    let mut output = String::new();
+
+    output.push_str(
+        "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+    );
+    output.push_str(
+        "│                              ⏰ HOURLY ACTIVITY                              │\n",
+    );
+    output.push_str(
+        "├──────────────────────────────────────────────────────────────────────────────┤\n",
+    );
+
+    for row in (0..HEIGHT).rev() {
+        // The busiest hour's count at the top, half of it in the middle
+        let label = match row {
+            r if r == HEIGHT - 1 => compact(busiest),
+            r if r == HEIGHT / 2 - 1 => compact(busiest / 2),
+            _ => String::new(),
+        };
+        let columns: String = counts
+            .iter()
+            .map(|&count| {
+                let fraction = count as f64 / busiest as f64;
+                let eighths = (fraction * (HEIGHT * 8) as f64).round() as usize;
+                match eighths.saturating_sub(row * 8) {
+                    0 => "   ".to_string(),
+                    level => {
+                        let cell = LEVELS[level.min(8) - 1].to_string().repeat(2);
+                        format!("{} ", cell.with(get_heat_color(fraction)))
+                    }
+                }
+            })
+            .collect();
+        output.push_str(&format!("│{:>4} ┤{}│\n", label, columns));
     }
+
+    let axis: String = (0..24)
+        .step_by(3)
+        .map(|hour| format!("{:<9}", hour))
+        .collect();
+    output.push_str(&format!("│{:>4} └{}│\n", 0, "─".repeat(72)));
+    output.push_str(&format!("│{:>4}  {}│\n", "", axis));
+
+    output.push_str(
+        "└──────────────────────────────────────────────────────────────────────────────┘\n",
+    );
+
+    output
+}
+
+/// Render average typing speed per day, with bars relative to the fastest day
+pub fn render_speed_chart(days: &[DailySpeed]) -> String {
+    let mut output = String::new();
+
+    output.push_str(
+        "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+    );
+    output.push_str(
+        "│                       🚀 TYPING SPEED PER DAY                                │\n",
+    );
+    output.push_str(
+        "├────────────┬───────┬────────┬────────────────────────────────────────────────┤\n",
+    );
+    output.push_str(&format!(
+        "│ {:^10} │ {:>5} │ {:>6} │ {:<46} │\n",
+        "Date", "WPM", "CPM", ""
+    ));
+    output.push_str(
+        "├────────────┼───────┼────────┼────────────────────────────────────────────────┤\n",
+    );
+
+    let fastest = days
+        .iter()
+        .filter_map(|day| day.average_wpm)
+        .fold(0.0, f64::max);
+    for day in days {
+        match (day.average_wpm, day.average_cpm) {
+            (Some(wpm), Some(cpm)) => {
+                let intensity = if fastest > 0.0 { wpm / fastest } else { 0.0 };
+                output.push_str(&format!(
+                    "│ {} │ {:>5.1} │ {:>6.1} │ {} │\n",
+                    day.date,
+                    wpm,
+                    cpm,
+                    bar(intensity, 46).with(get_heat_color(intensity))
+                ));
+            }
+            _ => {
+                let row = format!("{} │ {:>5} │ {:>6} │ {:<46}", day.date, "-", "-", "");
+                output.push_str(&format!("│ {} │\n", row.dark_grey()));
+            }
+        }
+    }
+
+    output.push_str(
+        "└────────────┴───────┴────────┴────────────────────────────────────────────────┘\n",
+    );
+
+    output
+}
+
+/// Color of a calendar day's shade, from no key presses to the busiest days
+fn calendar_color(level: u8) -> Color {
+    match level {
+        0 => Color::DarkGrey,
+        1 => Color::Rgb {
+            r: 14,
+            g: 68,
+            b: 41,
+        },
+        2 => Color::Rgb {
+            r: 0,
+            g: 109,
+            b: 50,
+        },
+        3 => Color::Rgb {
+            r: 38,
+            g: 166,
+            b: 65,
+        },
+        _ => Color::Rgb {
+            r: 57,
+            g: 211,
+            b: 83,
+        },
+    }
+}
+
+/// A calendar day shaded by its level, with block glyphs when colors are off
+fn calendar_cell(level: u8) -> String {
+    const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+    if plain::colors() {
+        format!("{}", "■".with(calendar_color(level)))
+    } else {
+        SHADES[(level as usize).min(4)].to_string()
+    }
+}
+
+/// Render key presses per day as a calendar with a column per week, starting on Monday
+pub fn render_calendar(days: &[CalendarDay]) -> String {
+    const MARGIN: &str = "          ";
+    let weeks: Vec<&[CalendarDay]> = days.chunks(7).collect();
+    let mut output = String::new();
+
+    output.push_str(
+        "\n┌──────────────────────────────────────────────────────────────────────────────┐\n",
+    );
+    output.push_str(
+        "│                             📅 ACTIVITY CALENDAR                             │\n",
+    );
+    output.push_str(
+        "├──────────────────────────────────────────────────────────────────────────────┤\n",
+    );
+
+    // Month names above the week each month starts in, where they fit
+    let mut months = vec![' '; weeks.len()];
+    let mut free_from = 0;
+    let mut last_month = None;
+    for (i, week) in weeks.iter().enumerate() {
+        let Ok(monday) = NaiveDate::parse_from_str(&week[0].date, "%Y-%m-%d") else {
+            continue;
+        };
+        if last_month != Some(monday.month()) && i >= free_from && i + 3 <= weeks.len() {
+            for (j, c) in monday.format("%b").to_string().chars().enumerate() {
+                months[i + j] = c;
+            }
+            free_from = i + 4;
+        }
+        last_month = Some(monday.month());
+    }
+    let months: String = months.into_iter().collect();
+    output.push_str(&format!("│{}    {:<64}│\n", MARGIN, months));
+
+    for (weekday, label) in ["Mon", "", "Wed", "", "Fri", "", "Sun"].iter().enumerate() {
+        let cells: String = weeks
+            .iter()
+            .map(|week| match week.get(weekday) {
+                Some(day) => calendar_cell(day.level),
+                None => " ".to_string(),
+            })
+            .collect();
+        let padding = " ".repeat(64 - weeks.len());
+        output.push_str(&format!("│{}{:<3} {}{}│\n", MARGIN, label, cells, padding));
+    }
+
+    let total: i64 = days.iter().map(|day| day.count).sum();
+    let legend: String = (0..=4).map(calendar_cell).collect();
+    let summary = format!("{} keys in the last year", total);
+    output.push_str(&format!(
+        "│{}│\n│{}{:<42}Less {} More{}│\n",
+        " ".repeat(78),
+        MARGIN,
+        summary,
+        legend,
+        " ".repeat(11)
+    ));
+
+    output.push_str(
+        "└──────────────────────────────────────────────────────────────────────────────┘\n",
+    );
+
+    output
 }
@@ -0,0 +1,305 @@
+use crate::stats::calculator::{DaySummary, KeyStats, RangeSummary, Seasonality};
+#[cfg(feature = "wasm")]
+use crate::stats::plugins::ReportSection;
+use crate::stats::range::Period;
+use crate::stats::shortcuts::AppShortcuts;
+
+/// Number of most pressed keys listed in periodic reports
+const REPORT_TOP_KEYS: usize = 10;
+
+/// Number of top keys whose share changed most listed in periodic reports
+const REPORT_KEY_CHANGES: usize = 3;
+
+/// Default template for daily summaries
+pub const DEFAULT_TEMPLATE: &str = "- **Keys pressed:** {{total_keys}}
+- **Combos used:** {{total_combos}}
+- **Active time:** {{active_minutes}} min
+- **Typing speed:** {{wpm}} WPM
+- **Corrections:** {{error_rate}}%
+- **Productivity score:** {{score}}/100
+- **Top keys:** {{top_keys}}
+- **Top combos:** {{top_combos}}";
+
+/// Render a day summary by filling in the `{{placeholders}}` of `template`
+pub fn render_day_summary(summary: &DaySummary, template: &str) -> String {
+    let metrics = summary.score.as_ref().map(|s| &s.metrics);
+
+    let top_keys = summary
+        .top_keys
+        .iter()
+        .map(|k| format!("`{}` ({})", k.key_name, k.count))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let top_combos = summary
+        .top_combos
+        .iter()
+        .map(|c| format!("`{}` ({})", c.combo, c.count))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let values = [
+        ("date", summary.date.clone()),
+        ("total_keys", summary.total_keys.to_string()),
+        ("total_combos", summary.total_combos.to_string()),
+        (
+            "active_minutes",
+            metrics.map_or(0, |m| m.active_minutes).to_string(),
+        ),
+        ("wpm", format!("{:.1}", metrics.map_or(0.0, |m| m.wpm))),
+        (
+            "error_rate",
+            format!("{:.1}", metrics.map_or(0.0, |m| m.error_rate * 100.0)),
+        ),
+        (
+            "score",
+            format!("{:.0}", summary.score.as_ref().map_or(0.0, |s| s.score)),
+        ),
+        ("top_keys", none_if_empty(top_keys)),
+        ("top_combos", none_if_empty(top_combos)),
+    ];
+
+    values
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{{{}}}}}", name), value)
+        })
+}
+
+/// Render seasonal patterns as a Markdown table of calendar months
+pub fn render_seasonality(seasonality: &Seasonality) -> String {
+    let years = match (seasonality.years.first(), seasonality.years.last()) {
+        (Some(first), Some(last)) if first != last => format!(" ({}–{})", first, last),
+        (Some(year), _) => format!(" ({})", year),
+        _ => String::new(),
+    };
+
+    let mut out = format!("## 📅 Seasonality{}\n\n", years);
+    if seasonality.years.len() < 2 {
+        out.push_str(
+            "_Less than two years recorded so far; seasonal patterns become meaningful once months repeat across years._\n\n",
+        );
+    }
+
+    out.push_str("| Month | Keys per year | Index | Top keys |\n");
+    out.push_str("| --- | ---: | ---: | --- |\n");
+    for month in &seasonality.months {
+        let top_keys = month
+            .top_keys
+            .iter()
+            .map(|k| format!("`{}` ({:.0}%)", k.key_name, k.percentage))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if month.years == 0 {
+            out.push_str(&format!("| {} | – | – | – |\n", month.name));
+        } else {
+            out.push_str(&format!(
+                "| {} | {:.0} | {:.2} | {} |\n",
+                month.name,
+                month.average_keys,
+                month.index,
+                none_if_empty(top_keys)
+            ));
+        }
+    }
+
+    out
+}
+
+/// Render a periodic report of `current` against the `previous` period as Markdown
+pub fn render_period_report(
+    period: Period,
+    label: &str,
+    previous_label: &str,
+    current: &RangeSummary,
+    previous: &RangeSummary,
+) -> String {
+    let mut out = format!(
+        "## 📊 {} Typing Report: {}\n\n_Compared with {}._\n\n",
+        period.label(),
+        label,
+        previous_label
+    );
+
+    out.push_str(&format!("| | {} | {} | Change |\n", label, previous_label));
+    out.push_str("| --- | ---: | ---: | ---: |\n");
+    let rows = [
+        (
+            "Keys pressed",
+            current.total_keys.to_string(),
+            previous.total_keys.to_string(),
+            relative_change(current.total_keys as f64, previous.total_keys as f64),
+        ),
+        (
+            "Combos used",
+            current.total_combos.to_string(),
+            previous.total_combos.to_string(),
+            relative_change(current.total_combos as f64, previous.total_combos as f64),
+        ),
+        (
+            "Active time",
+            format!("{} min", current.active_minutes),
+            format!("{} min", previous.active_minutes),
+            relative_change(
+                current.active_minutes as f64,
+                previous.active_minutes as f64,
+            ),
+        ),
+        (
+            "Typing speed",
+            format!("{:.1} CPM", current.average_typing_speed),
+            format!("{:.1} CPM", previous.average_typing_speed),
+            relative_change(current.average_typing_speed, previous.average_typing_speed),
+        ),
+        (
+            "Most active day",
+            busiest_day(current),
+            busiest_day(previous),
+            String::new(),
+        ),
+    ];
+    for (name, now, before, delta) in rows {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            name, now, before, delta
+        ));
+    }
+
+    let top_keys = current
+        .keys
+        .iter()
+        .take(REPORT_TOP_KEYS)
+        .map(|k| format!("`{}` ({:.1}%)", k.label(), k.percentage))
+        .collect::<Vec<_>>()
+        .join(", ");
+    out.push_str(&format!("\n**Top keys:** {}\n", none_if_empty(top_keys)));
+
+    let changes = key_share_changes(current, previous);
+    if !changes.is_empty() {
+        out.push_str("\n**Biggest changes in top keys:**\n\n");
+        for (key, now, before) in changes {
+            out.push_str(&format!(
+                "- `{}`: {:.1}% → {:.1}% ({:+.1} points)\n",
+                key,
+                before,
+                now,
+                now - before
+            ));
+        }
+    }
+
+    out
+}
+
+/// Render shortcut use per app as a Markdown section under a heading of `level`,
+/// calling out apps where shortcuts are rare
+pub fn render_app_shortcuts(apps: &[AppShortcuts], level: usize) -> String {
+    let mut out = format!(
+        "\n{} ⌨️ Shortcuts by App\n\n",
+        "#".repeat(level.clamp(1, 6))
+    );
+    out.push_str("| App | Keys | Combos | Per 1000 keys | Top combos |\n");
+    out.push_str("| --- | ---: | ---: | ---: | --- |\n");
+    for app in apps {
+        let top_combos = app
+            .top_combos
+            .iter()
+            .take(3)
+            .map(|c| format!("`{}` ({})", c.combo, c.count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        out.push_str(&format!(
+            "| {} | {} | {} | {:.1} | {} |\n",
+            app.app,
+            app.keys,
+            app.combos,
+            app.combos_per_1000_keys,
+            none_if_empty(top_combos)
+        ));
+    }
+
+    let flagged: Vec<String> = apps
+        .iter()
+        .filter(|app| app.few_shortcuts)
+        .map(|app| format!("**{}**", app.app))
+        .collect();
+    if !flagged.is_empty() {
+        out.push_str(&format!(
+            "\nFew shortcuts in {}: there may be some worth learning.\n",
+            flagged.join(", ")
+        ));
+    }
+
+    out
+}
+
+/// Keys among either period's top keys whose share of all keys changed most, as
+/// `(label, current share, previous share)`
+fn key_share_changes(current: &RangeSummary, previous: &RangeSummary) -> Vec<(String, f64, f64)> {
+    let share = |keys: &[KeyStats], name: &str| {
+        keys.iter()
+            .find(|k| k.key_name == name)
+            .map_or(0.0, |k| k.percentage)
+    };
+
+    let mut changes: Vec<(String, f64, f64)> = Vec::new();
+    for key in current
+        .keys
+        .iter()
+        .take(REPORT_TOP_KEYS)
+        .chain(previous.keys.iter().take(REPORT_TOP_KEYS))
+    {
+        if changes.iter().any(|(label, _, _)| label == key.label()) {
+            continue;
+        }
+        let now = share(&current.keys, &key.key_name);
+        let before = share(&previous.keys, &key.key_name);
+        if (now - before).abs() >= 0.05 {
+            changes.push((key.label().to_string(), now, before));
+        }
+    }
+
+    changes.sort_by(|a, b| (b.1 - b.2).abs().total_cmp(&(a.1 - a.2).abs()));
+    changes.truncate(REPORT_KEY_CHANGES);
+    changes
+}
+
+/// The busiest day of a period with its key count, or a dash when nothing was typed
+fn busiest_day(summary: &RangeSummary) -> String {
+    summary
+        .most_active_day
+        .as_ref()
+        .map_or("–".to_string(), |d| format!("{} ({})", d.day, d.count))
+}
+
+/// Relative change from `previous` to `current`, e.g. `+12.5%`
+pub(crate) fn relative_change(current: f64, previous: f64) -> String {
+    if previous == 0.0 {
+        return if current == 0.0 { "–" } else { "new" }.to_string();
+    }
+    format!("{:+.1}%", (current - previous) / previous * 100.0)
+}
+
+fn none_if_empty(list: String) -> String {
+    if list.is_empty() {
+        "none".to_string()
+    } else {
+        list
+    }
+}
+
+/// Render sections added by plugins under headings of `level`
+#[cfg(feature = "wasm")]
+pub fn render_plugin_sections(sections: &[ReportSection], level: usize) -> String {
+    let hashes = "#".repeat(level.clamp(1, 6));
+    sections
+        .iter()
+        .map(|section| {
+            format!(
+                "\n{} {}\n\n{}\n",
+                hashes,
+                section.title.trim(),
+                section.body.trim_end()
+            )
+        })
+        .collect()
+}
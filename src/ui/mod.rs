@@ -1,3 +1,7 @@
+pub mod cast;
 pub mod heatmap;
+pub mod markdown;
+pub mod pager;
+pub mod plain;
 
 pub use heatmap::AsciiHeatmap;
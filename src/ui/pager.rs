@@ -0,0 +1,55 @@
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Pager used when `$PAGER` isn't set
+const DEFAULT_PAGER: &str = "less";
+
+/// Options passed to `less` when `$LESS` isn't set: quit if the output fits on one
+/// screen, keep colors, and leave the output on screen after quitting
+const DEFAULT_LESS: &str = "FRX";
+
+/// Print `output`, through the user's pager when stdout is a terminal too short to show
+/// it at once. Colors are kept. Falls back to printing when the pager can't be started.
+pub fn page(output: &str) -> io::Result<()> {
+    if let Some(mut command) = pager_for(output) {
+        if let Ok(mut child) = command.stdin(Stdio::piped()).spawn() {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The pager closes its input when quit before the end
+                match stdin.write_all(output.as_bytes()) {
+                    Err(error) if error.kind() != io::ErrorKind::BrokenPipe => return Err(error),
+                    _ => {}
+                }
+            }
+            child.wait()?;
+            return Ok(());
+        }
+    }
+
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(output.as_bytes())?;
+    stdout.flush()
+}
+
+/// The pager command for `output`, or `None` when it should be printed directly
+fn pager_for(output: &str) -> Option<Command> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    let (_, rows) = crossterm::terminal::size().ok()?;
+    if output.lines().count() < rows as usize {
+        return None;
+    }
+
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    let mut words = pager.split_whitespace();
+    let program = words.next()?;
+    if program == "cat" {
+        return None;
+    }
+    let mut command = Command::new(program);
+    command.args(words);
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", DEFAULT_LESS);
+    }
+    Some(command)
+}
@@ -0,0 +1,175 @@
+use crossterm::{style, terminal};
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether terminal output is drawn without colors
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Whether terminal output is drawn with ASCII characters only
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Set how terminal output is drawn for the rest of the run. Colors are also turned off
+/// when the `NO_COLOR` environment variable is set to anything but an empty string.
+pub fn configure(no_color: bool, ascii: bool) {
+    let no_color = no_color || std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if no_color {
+        style::force_color_output(false);
+    }
+    NO_COLOR.store(no_color, Ordering::Relaxed);
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+/// Whether output may be colored
+pub fn colors() -> bool {
+    !NO_COLOR.load(Ordering::Relaxed)
+}
+
+/// Whether output must be plain ASCII
+pub fn ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// Columns of the terminal output is drawn in, or `None` when it goes to a file or
+/// another program
+pub fn terminal_columns() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    terminal::size().ok().map(|(columns, _)| columns as usize)
+}
+
+/// Rendered terminal output as configured: without escape sequences when colors are
+/// off, and with box drawing, block glyphs and emoji replaced in ASCII mode
+pub fn finish(output: &str) -> String {
+    let output = if colors() {
+        output.to_string()
+    } else {
+        strip_escapes(output)
+    };
+    if ascii() {
+        to_ascii(&output)
+    } else {
+        output
+    }
+}
+
+/// `text` without ANSI escape sequences. Crossterm leaves attribute and reset
+/// sequences in place even when colors are turned off.
+fn strip_escapes(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            plain.push(c);
+            continue;
+        }
+        // CSI sequences end with a letter
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    plain
+}
+
+/// ASCII stand-in for `c`, or `None` to drop it
+fn ascii_char(c: char) -> Option<char> {
+    Some(match c {
+        '─' | '━' | '–' => '-',
+        '│' => '|',
+        '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' => '+',
+        '░' | '·' => '.',
+        '▒' => ':',
+        '▓' => '=',
+        '█' | '■' => '#',
+        // Sparkline levels, lowest to highest
+        '▁' => '_',
+        '▂' => '.',
+        '▃' => ',',
+        '▄' => '-',
+        '▅' => '=',
+        '▆' => '+',
+        '▇' => '*',
+        // Bar ends, rounded to a whole column
+        '▏' | '▎' | '▍' => ' ',
+        '▌' | '▋' | '▊' | '▉' => '#',
+        '←' => '<',
+        '→' | '▶' => '>',
+        '↑' | '▲' => '^',
+        '↓' | '▼' => 'v',
+        '•' | '●' => '*',
+        '×' => 'x',
+        'µ' => 'u',
+        '✓' => '+',
+        '✗' => 'x',
+        '⚠' => '!',
+        // Variation selectors only change how the emoji before them look
+        '\u{fe0e}' | '\u{fe0f}' => return None,
+        c if c.is_ascii() => c,
+        // Emoji and other pictographs
+        '\u{2300}'..='\u{23ff}' | '\u{2600}'..='\u{27bf}' | '\u{1f000}'..='\u{1faff}' => {
+            return None
+        }
+        _ => '?',
+    })
+}
+
+/// `text` with every character replaced by an ASCII one. Box lines that got shorter
+/// from dropped emoji are padded back to the width of the box's last border line, and
+/// lines that run past it lose their spare padding.
+fn to_ascii(text: &str) -> String {
+    let mut ascii = String::with_capacity(text.len());
+    let mut box_width = None;
+    for line in text.split_inclusive('\n') {
+        let (line, newline) = match line.strip_suffix('\n') {
+            Some(line) => (line, "\n"),
+            None => (line, ""),
+        };
+        let mut converted = String::with_capacity(line.len());
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if let Some(c) = ascii_char(c) {
+                converted.push(c);
+                continue;
+            }
+            // A dropped emoji takes its variation selector and the space after it along
+            while chars
+                .next_if(|&next| matches!(next, '\u{fe0e}' | '\u{fe0f}'))
+                .is_some()
+            {}
+            chars.next_if_eq(&' ');
+        }
+
+        let trimmed = converted.trim_end();
+        if trimmed.starts_with('+') && trimmed.ends_with('+') {
+            box_width = Some(visible_width(trimmed));
+        } else if let (Some(box_width), Some(inner)) = (
+            box_width,
+            trimmed
+                .strip_prefix('|')
+                .and_then(|_| trimmed.strip_suffix('|'))
+                .filter(|inner| !inner.is_empty()),
+        ) {
+            let width = visible_width(trimmed);
+            if width < box_width {
+                converted = format!("{}{}|", inner, " ".repeat(box_width - width));
+            } else if width > box_width {
+                // Drop spare padding from lines that run past the border
+                let spare = inner.len() - inner.trim_end().len();
+                let keep = inner.len() - spare.min(width - box_width);
+                converted = format!("{}|", &inner[..keep]);
+            }
+        }
+        ascii.push_str(&converted);
+        ascii.push_str(newline);
+    }
+    ascii
+}
+
+/// Characters in `line` that take up a column, skipping escape sequences
+fn visible_width(line: &str) -> usize {
+    strip_escapes(line).chars().count()
+}
@@ -0,0 +1,68 @@
+import { Card, CardContent, CardHeader, CardTitle } from '@/components/ui/card';
+import type { AllStats, KeyboardLayout } from '@/types';
+
+interface EffortCardProps {
+  stats: AllStats;
+}
+
+const LAYOUT_NAMES: Record<KeyboardLayout, string> = {
+  qwerty: 'QWERTY',
+  dvorak: 'Dvorak',
+  colemak: 'Colemak',
+};
+
+export function EffortCard({ stats }: EffortCardProps) {
+  const days = stats.daily_effort.slice(-7).reverse();
+  const layouts = days[0].layouts.map((effort) => effort.layout);
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="text-lg">💪 Typing Effort</CardTitle>
+        <p className="text-sm text-muted-foreground">
+          Average effort per key, where 1.0 is a home-row index finger press. Other layouts
+          show the same typing re-mapped.
+        </p>
+      </CardHeader>
+      <CardContent className="overflow-x-auto">
+        <table className="w-full text-sm">
+          <thead>
+            <tr className="text-muted-foreground">
+              <th className="text-left font-medium p-2">Date</th>
+              <th className="text-right font-medium p-2">Keys</th>
+              <th className="text-right font-medium p-2">Total</th>
+              {layouts.map((layout) => (
+                <th key={layout} className="text-right font-medium p-2">
+                  {LAYOUT_NAMES[layout]}
+                  {layout === days[0].layout && ' (yours)'}
+                </th>
+              ))}
+            </tr>
+          </thead>
+          <tbody>
+            {days.map((day) => {
+              const best = Math.min(...day.layouts.map((effort) => effort.average));
+              return (
+                <tr key={day.date} className="border-t border-border">
+                  <td className="p-2 text-foreground">{day.date}</td>
+                  <td className="p-2 text-right">{day.keys.toLocaleString()}</td>
+                  <td className="p-2 text-right">{Math.round(day.total).toLocaleString()}</td>
+                  {day.layouts.map((effort) => (
+                    <td
+                      key={effort.layout}
+                      className={`p-2 text-right ${
+                        effort.average <= best ? 'font-semibold text-green-600 dark:text-green-400' : ''
+                      }`}
+                    >
+                      {effort.average.toFixed(2)}
+                    </td>
+                  ))}
+                </tr>
+              );
+            })}
+          </tbody>
+        </table>
+      </CardContent>
+    </Card>
+  );
+}
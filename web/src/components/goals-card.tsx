@@ -0,0 +1,50 @@
+import { Card, CardContent, CardHeader, CardTitle } from '@/components/ui/card';
+import type { AllStats, GoalProgress } from '@/types';
+
+interface GoalsCardProps {
+  stats: AllStats;
+}
+
+const METRIC_LABELS: Record<GoalProgress['metric'], string> = {
+  keys: 'keys',
+  active_minutes: 'active minutes',
+  wpm: 'WPM',
+};
+
+function formatValue(goal: GoalProgress, value: number) {
+  return goal.metric === 'wpm' ? value.toFixed(1) : Math.floor(value).toLocaleString();
+}
+
+export function GoalsCard({ stats }: GoalsCardProps) {
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="text-lg">🎯 Goals</CardTitle>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        {stats.goals.map((goal) => (
+          <div
+            key={`${goal.period}-${goal.metric}`}
+            className="p-3 bg-secondary/50 rounded-lg border border-border space-y-2"
+          >
+            <div className="flex justify-between items-center">
+              <span className="text-muted-foreground">
+                {goal.period === 'daily' ? 'Daily' : 'Weekly'} {METRIC_LABELS[goal.metric]}
+                {goal.met && ' ✓'}
+              </span>
+              <span className="font-semibold text-foreground">
+                {formatValue(goal, goal.current)} / {formatValue(goal, goal.target)}
+              </span>
+            </div>
+            <div className="h-2 rounded-full bg-muted overflow-hidden">
+              <div
+                className={`h-full rounded-full ${goal.met ? 'bg-green-500' : 'bg-primary'}`}
+                style={{ width: `${(goal.progress * 100).toFixed(1)}%` }}
+              />
+            </div>
+          </div>
+        ))}
+      </CardContent>
+    </Card>
+  );
+}
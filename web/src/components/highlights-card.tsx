@@ -7,17 +7,29 @@ interface HighlightsCardProps {
 }
 
 export function HighlightsCard({ stats }: HighlightsCardProps) {
+  const latestScore = stats.daily_scores[stats.daily_scores.length - 1];
+
   return (
     <Card>
       <CardHeader>
         <CardTitle className="text-lg">🏆 Highlights</CardTitle>
       </CardHeader>
       <CardContent className="space-y-3">
+        {latestScore && (
+          <div className="flex justify-between items-center p-3 bg-secondary/50 rounded-lg border border-border">
+            <span className="text-muted-foreground">
+              Productivity Score ({latestScore.date})
+            </span>
+            <span className="font-semibold text-foreground">
+              {Math.round(latestScore.score)} / 100
+            </span>
+          </div>
+        )}
         {stats.most_pressed_key && (
           <div className="flex justify-between items-center p-3 bg-secondary/50 rounded-lg border border-border">
             <span className="text-muted-foreground">Most Pressed Key</span>
             <span className="font-semibold text-foreground">
-              {formatKeyName(stats.most_pressed_key.key_name)} (
+              {stats.most_pressed_key.composed ?? formatKeyName(stats.most_pressed_key.key_name)} (
               {stats.most_pressed_key.count.toLocaleString()}x)
             </span>
           </div>
@@ -31,6 +43,17 @@ export function HighlightsCard({ stats }: HighlightsCardProps) {
             </span>
           </div>
         )}
+        {stats.hand_alternation.bigrams > 0 && (
+          <div
+            className="flex justify-between items-center p-3 bg-secondary/50 rounded-lg border border-border"
+            title="Share of key pairs typed with one key on each hand. While one hand types, the other can already reach for the next key."
+          >
+            <span className="text-muted-foreground">Hand Alternation</span>
+            <span className="font-semibold text-foreground">
+              {stats.hand_alternation.rate.toFixed(1)}%
+            </span>
+          </div>
+        )}
         {stats.most_active_hour && (
           <div className="flex justify-between items-center p-3 bg-secondary/50 rounded-lg border border-border">
             <span className="text-muted-foreground">Most Active Hour</span>
@@ -1,10 +1,28 @@
+import { useEffect, useState } from 'react';
+import { Button } from '@/components/ui/button';
 import { cn } from '@/lib/utils';
-import type { AllStats } from '@/types';
+import type { AllStats, KeyHeat, Replay } from '@/types';
 
 interface KeyboardHeatmapProps {
   stats: AllStats;
+  /** Average hour of use per key, from `/api/heatmap?metric=avg_hour` */
+  hourHeat?: KeyHeat[];
+  /** Key presses per day, from `/api/replay`, fetched when a replay starts */
+  loadReplay?: () => Promise<Replay>;
 }
 
+type HeatMode = 'count' | 'hour';
+
+/** Milliseconds each recorded day is shown for during a replay */
+const REPLAY_DAY_MS = 100;
+
+const TIME_OF_DAY = [
+  { from: 0, to: 6, label: 'Night', color: 'bg-indigo-800/80' },
+  { from: 6, to: 12, label: 'Morning', color: 'bg-amber-300/80' },
+  { from: 12, to: 18, label: 'Afternoon', color: 'bg-orange-500/80' },
+  { from: 18, to: 24, label: 'Evening', color: 'bg-purple-600/80' },
+];
+
 const KEYBOARD_LAYOUT = [
   [
     '`',
@@ -90,42 +108,167 @@ function getHeatColor(intensity: number): string {
   return 'bg-red-700/95'; // Extreme hot
 }
 
-function getKeyCount(stats: AllStats, keyName: string): number {
-  const freq = stats.key_frequency_map;
+function getHourColor(hour: number | undefined): string {
+  if (hour === undefined) return 'bg-secondary/50';
+  const period = TIME_OF_DAY.find((p) => hour >= p.from && hour < p.to);
+  return period?.color ?? 'bg-secondary/50';
+}
+
+function formatHour(hour: number): string {
+  const minutes = Math.floor(hour * 60) % (24 * 60);
+  const hh = String(Math.floor(minutes / 60)).padStart(2, '0');
+  const mm = String(minutes % 60).padStart(2, '0');
+  return `${hh}:${mm}`;
+}
 
-  if (freq[keyName] !== undefined) return freq[keyName];
+function lookupKey<T>(map: Record<string, T>, keyName: string): T | undefined {
+  if (map[keyName] !== undefined) return map[keyName];
 
   const upper = keyName.toUpperCase();
   const lower = keyName.toLowerCase();
   const capitalized =
     keyName.charAt(0).toUpperCase() + keyName.slice(1).toLowerCase();
 
-  if (freq[upper] !== undefined) return freq[upper];
-  if (freq[lower] !== undefined) return freq[lower];
-  if (freq[capitalized] !== undefined) return freq[capitalized];
+  if (map[upper] !== undefined) return map[upper];
+  if (map[lower] !== undefined) return map[lower];
+  if (map[capitalized] !== undefined) return map[capitalized];
 
   if (keyName.length === 1) {
     const keyVariant = `Key${upper}`;
-    if (freq[keyVariant] !== undefined) return freq[keyVariant];
+    if (map[keyVariant] !== undefined) return map[keyVariant];
   }
 
-  return 0;
+  return undefined;
 }
 
-function getIntensity(stats: AllStats, keyName: string): number {
-  const count = getKeyCount(stats, keyName);
-  const maxCount = Math.max(...Object.values(stats.key_frequency_map), 1);
+function getKeyCount(counts: Record<string, number>, keyName: string): number {
+  return lookupKey(counts, keyName) ?? 0;
+}
+
+function getIntensity(counts: Record<string, number>, keyName: string): number {
+  const count = getKeyCount(counts, keyName);
+  const maxCount = Math.max(...Object.values(counts), 1);
   return count / maxCount;
 }
 
-export function KeyboardHeatmap({ stats }: KeyboardHeatmapProps) {
+/** Running totals of key presses after each frame of a replay */
+function accumulate(replay: Replay): Record<string, number>[] {
+  const totals: Record<string, number> = {};
+  return replay.frames.map((frame) => {
+    for (const [key, count] of Object.entries(frame.keys)) {
+      totals[key] = (totals[key] ?? 0) + count;
+    }
+    return { ...totals };
+  });
+}
+
+export function KeyboardHeatmap({
+  stats,
+  hourHeat = [],
+  loadReplay,
+}: KeyboardHeatmapProps) {
+  const [mode, setMode] = useState<HeatMode>('count');
+  const [replay, setReplay] = useState<Replay | null>(null);
+  const [replayTotals, setReplayTotals] = useState<Record<string, number>[]>(
+    []
+  );
+  const [replayFrame, setReplayFrame] = useState<number | null>(null);
+  const [replayError, setReplayError] = useState<string | null>(null);
+  const avgHours: Record<string, number> = Object.fromEntries(
+    hourHeat.map((heat) => [heat.key_name, heat.value])
+  );
+
+  const replaying = replayFrame !== null;
+  const counts = replaying
+    ? (replayTotals[replayFrame] ?? {})
+    : stats.key_frequency_map;
+
+  useEffect(() => {
+    if (replayFrame === null) return;
+    if (replayFrame >= replayTotals.length - 1) return;
+    const timer = setTimeout(
+      () => setReplayFrame(replayFrame + 1),
+      REPLAY_DAY_MS
+    );
+    return () => clearTimeout(timer);
+  }, [replayFrame, replayTotals]);
+
+  const startReplay = async () => {
+    if (!loadReplay) return;
+    try {
+      setReplayError(null);
+      const loaded = await loadReplay();
+      if (loaded.frames.length === 0) {
+        setReplayError('No key presses recorded to replay');
+        return;
+      }
+      setReplay(loaded);
+      setReplayTotals(accumulate(loaded));
+      setMode('count');
+      setReplayFrame(0);
+    } catch (err) {
+      setReplayError(err instanceof Error ? err.message : 'Replay failed');
+    }
+  };
+
   return (
     <div className="flex flex-col items-center gap-4 p-10 bg-secondary/20 rounded-lg">
+      <div className="flex gap-2">
+        <Button
+          variant={mode === 'count' && !replaying ? 'default' : 'outline'}
+          size="sm"
+          onClick={() => setMode('count')}
+          disabled={replaying}
+        >
+          Usage
+        </Button>
+        <Button
+          variant={mode === 'hour' ? 'default' : 'outline'}
+          size="sm"
+          onClick={() => setMode('hour')}
+          disabled={hourHeat.length === 0 || replaying}
+        >
+          Time of day
+        </Button>
+        {loadReplay &&
+          (replaying ? (
+            <Button
+              variant="default"
+              size="sm"
+              onClick={() => setReplayFrame(null)}
+            >
+              Stop replay
+            </Button>
+          ) : (
+            <Button variant="outline" size="sm" onClick={startReplay}>
+              Replay
+            </Button>
+          ))}
+      </div>
+
+      {replaying && replay && (
+        <div className="text-sm text-muted-foreground tabular-nums">
+          {replay.frames[replayFrame].start.slice(0, 10)} ·{' '}
+          {Object.values(counts)
+            .reduce((sum, count) => sum + count, 0)
+            .toLocaleString()}{' '}
+          keys
+        </div>
+      )}
+      {replayError && (
+        <div className="text-sm text-destructive">{replayError}</div>
+      )}
+
       {KEYBOARD_LAYOUT.map((row, rowIndex) => (
         <div key={rowIndex} className="flex gap-1">
           {row.map((key) => {
-            const intensity = getIntensity(stats, key);
-            const count = getKeyCount(stats, key);
+            const intensity = getIntensity(counts, key);
+            const count = getKeyCount(counts, key);
+            const avgHour = lookupKey(avgHours, key);
+            const detail =
+              mode === 'hour' && avgHour !== undefined
+                ? `avg. ${formatHour(avgHour)}`
+                : `${count.toLocaleString()} presses`;
             const displayName = KEY_DISPLAY_NAMES[key] || key.toUpperCase();
             const width = KEY_WIDTHS[key] || 'w-12';
 
@@ -135,14 +278,16 @@ export function KeyboardHeatmap({ stats }: KeyboardHeatmapProps) {
                 className={cn(
                   'h-12 flex items-center justify-center rounded-md border border-border text-foreground text-xs font-medium transition-all hover:scale-105 relative group cursor-default',
                   width,
-                  getHeatColor(intensity)
+                  mode === 'hour'
+                    ? getHourColor(avgHour)
+                    : getHeatColor(intensity)
                 )}
-                title={`${key}: ${count} presses`}
+                title={`${key}: ${detail}`}
               >
                 <span className="truncate px-1">{displayName}</span>
                 {count > 0 && (
                   <div className="absolute -top-8 left-1/2 -translate-x-1/2 bg-card text-foreground text-xs px-2 py-1 rounded opacity-0 group-hover:opacity-100 transition-opacity whitespace-nowrap z-10 border border-border">
-                    {count.toLocaleString()} presses
+                    {detail}
                   </div>
                 )}
               </div>
@@ -152,72 +297,91 @@ export function KeyboardHeatmap({ stats }: KeyboardHeatmapProps) {
       ))}
 
       {/* Legend */}
-      <div className="grid grid-cols-4 gap-3 mt-4 text-xs text-muted-foreground">
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-secondary/50 rounded border border-border" />
-          <span>None</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-blue-950/80 rounded border border-border" />
-          <span>Ultra Cold</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-blue-900/80 rounded border border-border" />
-          <span>Ext. Cold</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-blue-800/80 rounded border border-border" />
-          <span>Very Cold</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-blue-700/80 rounded border border-border" />
-          <span>Cold</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-blue-600/80 rounded border border-border" />
-          <span>Semi-Cold</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-cyan-700/80 rounded border border-border" />
-          <span>Cool</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-cyan-500/80 rounded border border-border" />
-          <span>Slightly Cool</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-green-400/80 rounded border border-border" />
-          <span>Neutral</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-yellow-400/80 rounded border border-border" />
-          <span>Warm</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-yellow-500/90 rounded border border-border" />
-          <span>Slightly Warm</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-orange-400/90 rounded border border-border" />
-          <span>Warmer</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-orange-500/90 rounded border border-border" />
-          <span>Hot</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-red-400/90 rounded border border-border" />
-          <span>Very Hot</span>
-        </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-red-500/90 rounded border border-border" />
-          <span>Ext. Hot</span>
+      {mode === 'hour' ? (
+        <div className="flex gap-4 mt-4 text-xs text-muted-foreground">
+          {TIME_OF_DAY.map((period) => (
+            <div key={period.label} className="flex items-center gap-1">
+              <div
+                className={cn(
+                  'w-6 h-4 rounded border border-border',
+                  period.color
+                )}
+              />
+              <span>
+                {period.label} ({formatHour(period.from)}–
+                {formatHour(period.to)})
+              </span>
+            </div>
+          ))}
         </div>
-        <div className="flex items-center gap-1">
-          <div className="w-6 h-4 bg-red-700/95 rounded border border-border" />
-          <span>Extreme</span>
+      ) : (
+        <div className="grid grid-cols-4 gap-3 mt-4 text-xs text-muted-foreground">
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-secondary/50 rounded border border-border" />
+            <span>None</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-blue-950/80 rounded border border-border" />
+            <span>Ultra Cold</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-blue-900/80 rounded border border-border" />
+            <span>Ext. Cold</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-blue-800/80 rounded border border-border" />
+            <span>Very Cold</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-blue-700/80 rounded border border-border" />
+            <span>Cold</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-blue-600/80 rounded border border-border" />
+            <span>Semi-Cold</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-cyan-700/80 rounded border border-border" />
+            <span>Cool</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-cyan-500/80 rounded border border-border" />
+            <span>Slightly Cool</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-green-400/80 rounded border border-border" />
+            <span>Neutral</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-yellow-400/80 rounded border border-border" />
+            <span>Warm</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-yellow-500/90 rounded border border-border" />
+            <span>Slightly Warm</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-orange-400/90 rounded border border-border" />
+            <span>Warmer</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-orange-500/90 rounded border border-border" />
+            <span>Hot</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-red-400/90 rounded border border-border" />
+            <span>Very Hot</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-red-500/90 rounded border border-border" />
+            <span>Ext. Hot</span>
+          </div>
+          <div className="flex items-center gap-1">
+            <div className="w-6 h-4 bg-red-700/95 rounded border border-border" />
+            <span>Extreme</span>
+          </div>
         </div>
-      </div>
+      )}
     </div>
   );
 }
@@ -30,6 +30,19 @@ export function RecordingInfoCard({ stats }: RecordingInfoCardProps) {
             {stats.average_keys_per_session.toFixed(0)}
           </span>
         </div>
+        <div className="flex justify-between items-center p-3 bg-secondary/50 rounded-lg border border-border">
+          <span className="text-muted-foreground">Home / Top / Bottom / Number Rows</span>
+          <span className="font-semibold text-foreground">
+            {[
+              stats.row_usage.home,
+              stats.row_usage.top,
+              stats.row_usage.bottom,
+              stats.row_usage.number,
+            ]
+              .map((percent) => `${Math.round(percent)}%`)
+              .join(' / ')}
+          </span>
+        </div>
         {stats.first_recorded && (
           <div className="flex justify-between items-center p-3 bg-secondary/50 rounded-lg border border-border">
             <span className="text-muted-foreground">First Recorded</span>
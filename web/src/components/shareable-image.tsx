@@ -77,7 +77,7 @@ export function ShareableImage({ stats }: ShareableImageProps) {
     }
   }, []);
 
-  const topKey = stats.most_pressed_key?.key_name || 'N/A';
+  const topKey = stats.most_pressed_key?.composed ?? stats.most_pressed_key?.key_name ?? 'N/A';
   const topCombo = stats.most_pressed_combo?.combo || 'N/A';
 
   return (
@@ -21,7 +21,7 @@ import {
   ChartTooltip,
   ChartTooltipContent,
 } from '@/components/ui/chart';
-import type { AllStats } from '@/types';
+import type { AllStats, CalendarDay, DailySpeed } from '@/types';
 import { formatKeyName } from '@/lib/format-key-name';
 
 interface ChartsProps {
@@ -48,7 +48,7 @@ const formatValue = (value: unknown): string => {
 
 export function TopKeysChart({ stats }: ChartsProps) {
   const data = stats.top_keys.slice(0, 10).map((key) => ({
-    name: formatKeyName(key.key_name),
+    name: key.composed ?? formatKeyName(key.key_name),
     count: key.count,
     percentage: key.percentage.toFixed(1),
   }));
@@ -229,6 +229,313 @@ export function HourlyActivityChart({ stats }: ChartsProps) {
   );
 }
 
+export function SpeedDistributionChart({ stats }: ChartsProps) {
+  const speeds = stats.speed_distribution;
+  const data = speeds.buckets.map((bucket) => ({
+    range:
+      bucket.max === null ? `${bucket.min}+` : `${bucket.min}-${bucket.max}`,
+    count: bucket.count,
+  }));
+
+  if (speeds.samples === 0) {
+    return (
+      <Card>
+        <CardHeader>
+          <CardTitle className="text-lg">⚡ Typing Speed</CardTitle>
+        </CardHeader>
+        <CardContent className="flex items-center justify-center h-[300px] text-muted-foreground">
+          No typing speed samples recorded yet
+        </CardContent>
+      </Card>
+    );
+  }
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="text-lg">⚡ Typing Speed</CardTitle>
+        <p className="text-sm text-muted-foreground">
+          Median {speeds.p50.toFixed(0)} · 90th percentile{' '}
+          {speeds.p90.toFixed(0)} · 99th percentile {speeds.p99.toFixed(0)} CPM
+        </p>
+      </CardHeader>
+      <CardContent>
+        <ChartContainer
+          config={{
+            count: {
+              label: 'Samples',
+              color: CHART_COLORS[4],
+            },
+          }}
+          className="h-[300px]"
+        >
+          <ResponsiveContainer width="100%" height="100%">
+            <BarChart data={data}>
+              <CartesianGrid strokeDasharray="3 3" />
+              <XAxis dataKey="range" angle={-45} textAnchor="end" height={60} />
+              <YAxis />
+              <ChartTooltip content={<ChartTooltipContent />} />
+              <Bar
+                dataKey="count"
+                fill={CHART_COLORS[4]}
+                radius={[4, 4, 0, 0]}
+              />
+            </BarChart>
+          </ResponsiveContainer>
+        </ChartContainer>
+      </CardContent>
+    </Card>
+  );
+}
+
+export function TypingRhythmChart({ stats }: ChartsProps) {
+  const intervals = stats.key_intervals;
+  const histogram = intervals.buckets.map((bucket) => ({
+    range: `${bucket.min_ms}-${bucket.max_ms}`,
+    count: bucket.count,
+  }));
+  const hourly = intervals.hourly.map((hour) => ({
+    hour: `${hour.hour}:00`,
+    median: hour.median_ms,
+  }));
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="text-lg">🎵 Typing Rhythm</CardTitle>
+        <p className="text-sm text-muted-foreground">
+          Median {intervals.p50_ms} ms between keys · middle half{' '}
+          {intervals.p25_ms}-{intervals.p75_ms} ms
+        </p>
+      </CardHeader>
+      <CardContent className="space-y-6">
+        <ChartContainer
+          config={{
+            count: {
+              label: 'Intervals',
+              color: CHART_COLORS[1],
+            },
+          }}
+          className="h-[200px]"
+        >
+          <ResponsiveContainer width="100%" height="100%">
+            <BarChart data={histogram}>
+              <CartesianGrid strokeDasharray="3 3" />
+              <XAxis dataKey="range" angle={-45} textAnchor="end" height={60} />
+              <YAxis />
+              <ChartTooltip content={<ChartTooltipContent />} />
+              <Bar
+                dataKey="count"
+                fill={CHART_COLORS[1]}
+                radius={[4, 4, 0, 0]}
+              />
+            </BarChart>
+          </ResponsiveContainer>
+        </ChartContainer>
+        <ChartContainer
+          config={{
+            median: {
+              label: 'Median ms',
+              color: CHART_COLORS[3],
+            },
+          }}
+          className="h-[160px]"
+        >
+          <ResponsiveContainer width="100%" height="100%">
+            <LineChart data={hourly}>
+              <CartesianGrid strokeDasharray="3 3" />
+              <XAxis dataKey="hour" />
+              <YAxis />
+              <ChartTooltip content={<ChartTooltipContent />} />
+              <Line
+                type="monotone"
+                dataKey="median"
+                stroke={CHART_COLORS[3]}
+                strokeWidth={2}
+                connectNulls
+              />
+            </LineChart>
+          </ResponsiveContainer>
+        </ChartContainer>
+      </CardContent>
+    </Card>
+  );
+}
+
+export function WeekdayWeekendCard({ stats }: ChartsProps) {
+  const { weekday, weekend } = stats.weekday_weekend;
+  const hour = (value: number | null) =>
+    value === null ? '–' : `${String(value).padStart(2, '0')}:00`;
+  const rows = [
+    {
+      label: 'Active days',
+      weekday: weekday.active_days.toLocaleString(),
+      weekend: weekend.active_days.toLocaleString(),
+    },
+    {
+      label: 'Keys per day',
+      weekday: Math.round(weekday.keys_per_day).toLocaleString(),
+      weekend: Math.round(weekend.keys_per_day).toLocaleString(),
+    },
+    {
+      label: 'Avg speed (CPM)',
+      weekday: weekday.average_cpm?.toFixed(1) ?? '–',
+      weekend: weekend.average_cpm?.toFixed(1) ?? '–',
+    },
+    {
+      label: 'Busiest hour',
+      weekday: hour(weekday.busiest_hour),
+      weekend: hour(weekend.busiest_hour),
+    },
+    {
+      label: 'Top keys',
+      weekday: weekday.top_keys.map((k) => formatKeyName(k.key_name)).join(' '),
+      weekend: weekend.top_keys.map((k) => formatKeyName(k.key_name)).join(' '),
+    },
+  ];
+  const shape = weekday.hourly_share.map((share, h) => ({
+    hour: `${h}:00`,
+    weekday: share,
+    weekend: weekend.hourly_share[h] ?? 0,
+  }));
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="text-lg">🌴 Weekdays vs Weekends</CardTitle>
+      </CardHeader>
+      <CardContent className="space-y-6">
+        <table className="w-full text-sm">
+          <thead>
+            <tr className="text-muted-foreground">
+              <th className="text-left font-normal" />
+              <th className="text-right font-normal">Weekdays</th>
+              <th className="text-right font-normal">Weekends</th>
+            </tr>
+          </thead>
+          <tbody>
+            {rows.map((row) => (
+              <tr key={row.label} className="border-t">
+                <td className="py-1">{row.label}</td>
+                <td className="py-1 text-right font-mono">{row.weekday}</td>
+                <td className="py-1 text-right font-mono">{row.weekend}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+        <ChartContainer
+          config={{
+            weekday: { label: 'Weekdays %', color: CHART_COLORS[0] },
+            weekend: { label: 'Weekends %', color: CHART_COLORS[2] },
+          }}
+          className="h-[200px]"
+        >
+          <ResponsiveContainer width="100%" height="100%">
+            <LineChart data={shape}>
+              <CartesianGrid strokeDasharray="3 3" />
+              <XAxis dataKey="hour" />
+              <YAxis />
+              <ChartTooltip content={<ChartTooltipContent />} />
+              <Line
+                type="monotone"
+                dataKey="weekday"
+                stroke={CHART_COLORS[0]}
+                strokeWidth={2}
+                dot={false}
+              />
+              <Line
+                type="monotone"
+                dataKey="weekend"
+                stroke={CHART_COLORS[2]}
+                strokeWidth={2}
+                dot={false}
+              />
+            </LineChart>
+          </ResponsiveContainer>
+        </ChartContainer>
+      </CardContent>
+    </Card>
+  );
+}
+
+export function WeeklyRhythmChart({ stats }: ChartsProps) {
+  const max = Math.max(0, ...stats.weekday_hours.flatMap((day) => day.hours));
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="text-lg">📆 Weekly Rhythm</CardTitle>
+      </CardHeader>
+      <CardContent className="overflow-x-auto">
+        <div
+          className="grid gap-[2px] text-xs text-muted-foreground"
+          style={{ gridTemplateColumns: 'auto repeat(24, minmax(0, 1fr))' }}
+        >
+          <span />
+          {Array.from({ length: 24 }, (_, hour) => (
+            <span key={hour} className="text-center">
+              {hour % 3 === 0 ? hour : ''}
+            </span>
+          ))}
+          {stats.weekday_hours.map((day) => (
+            <div key={day.day} className="contents">
+              <span className="pr-2">{day.day.slice(0, 3)}</span>
+              {day.hours.map((count, hour) => (
+                <div
+                  key={hour}
+                  className="aspect-square rounded-sm bg-secondary/50"
+                  title={`${day.day} ${hour.toString().padStart(2, '0')}:00 · ${count.toLocaleString()} keys`}
+                >
+                  {count > 0 && (
+                    <div
+                      className="h-full w-full rounded-sm"
+                      style={{
+                        backgroundColor: CHART_COLORS[0],
+                        opacity: 0.15 + 0.85 * (count / max),
+                      }}
+                    />
+                  )}
+                </div>
+              ))}
+            </div>
+          ))}
+        </div>
+      </CardContent>
+    </Card>
+  );
+}
+
+export function TypingBurstsCard({ stats }: ChartsProps) {
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="text-lg">🔥 Typing Bursts</CardTitle>
+        <p className="text-sm text-muted-foreground">
+          Fastest sustained runs of typing, by average speed
+        </p>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        {stats.top_bursts.map((burst) => (
+          <div
+            key={burst.started}
+            className="flex justify-between items-center p-3 bg-secondary/50 rounded-lg border border-border"
+          >
+            <span className="text-muted-foreground">
+              {new Date(burst.started).toLocaleString()} ·{' '}
+              {Math.round(burst.duration_secs)}s ·{' '}
+              {burst.keys.toLocaleString()} keys
+            </span>
+            <span className="font-semibold text-foreground">
+              {burst.average_cpm.toFixed(0)} CPM (peak{' '}
+              {burst.peak_cpm.toFixed(0)})
+            </span>
+          </div>
+        ))}
+      </CardContent>
+    </Card>
+  );
+}
+
 export function DailyActivityChart({ stats }: ChartsProps) {
   const data = stats.daily_distribution;
 
@@ -268,6 +575,49 @@ export function DailyActivityChart({ stats }: ChartsProps) {
   );
 }
 
+export function SpeedOverTimeChart({ days }: { days: DailySpeed[] }) {
+  const data = days.map((day) => ({
+    date: day.date.slice(5),
+    wpm: day.average_wpm === null ? null : Number(day.average_wpm.toFixed(1)),
+  }));
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="text-lg">🚀 Typing Speed Over Time</CardTitle>
+      </CardHeader>
+      <CardContent>
+        <ChartContainer
+          config={{
+            wpm: {
+              label: 'WPM',
+              color: CHART_COLORS[0],
+            },
+          }}
+          className="h-[300px]"
+        >
+          <ResponsiveContainer width="100%" height="100%">
+            <LineChart data={data}>
+              <CartesianGrid strokeDasharray="3 3" />
+              <XAxis dataKey="date" interval={6} />
+              <YAxis />
+              <ChartTooltip content={<ChartTooltipContent />} />
+              <Line
+                type="monotone"
+                dataKey="wpm"
+                stroke={CHART_COLORS[0]}
+                strokeWidth={3}
+                dot={{ fill: CHART_COLORS[0], strokeWidth: 2 }}
+                connectNulls
+              />
+            </LineChart>
+          </ResponsiveContainer>
+        </ChartContainer>
+      </CardContent>
+    </Card>
+  );
+}
+
 export function KeyTypesPieChart({ stats }: ChartsProps) {
   const data = [
     { name: 'Letters', value: stats.letter_keys_count, color: CHART_COLORS[0] },
@@ -358,3 +708,52 @@ export function SpecialKeysChart({ stats }: ChartsProps) {
     </Card>
   );
 }
+
+const CALENDAR_SHADES = [
+  'bg-secondary/50',
+  'bg-green-900',
+  'bg-green-700',
+  'bg-green-500',
+  'bg-green-400',
+];
+
+export function ActivityCalendar({ days }: { days: CalendarDay[] }) {
+  const weeks: CalendarDay[][] = [];
+  for (let i = 0; i < days.length; i += 7) {
+    weeks.push(days.slice(i, i + 7));
+  }
+  const total = days.reduce((sum, day) => sum + day.count, 0);
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="text-lg">📅 Activity Calendar</CardTitle>
+        <p className="text-sm text-muted-foreground">
+          {total.toLocaleString()} keys in the last year
+        </p>
+      </CardHeader>
+      <CardContent className="overflow-x-auto">
+        <div className="flex gap-[3px]">
+          {weeks.map((week) => (
+            <div key={week[0].date} className="flex flex-col gap-[3px]">
+              {week.map((day) => (
+                <div
+                  key={day.date}
+                  className={`h-3 w-3 rounded-sm ${CALENDAR_SHADES[day.level]}`}
+                  title={`${day.date} · ${day.count.toLocaleString()} keys`}
+                />
+              ))}
+            </div>
+          ))}
+        </div>
+        <div className="mt-2 flex items-center justify-end gap-[3px] text-xs text-muted-foreground">
+          <span className="mr-1">Less</span>
+          {CALENDAR_SHADES.map((shade) => (
+            <div key={shade} className={`h-3 w-3 rounded-sm ${shade}`} />
+          ))}
+          <span className="ml-1">More</span>
+        </div>
+      </CardContent>
+    </Card>
+  );
+}
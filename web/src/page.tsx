@@ -6,32 +6,76 @@ import {
   TopCombosChart,
   HourlyActivityChart,
   DailyActivityChart,
+  WeeklyRhythmChart,
+  WeekdayWeekendCard,
+  SpeedDistributionChart,
+  TypingRhythmChart,
+  TypingBurstsCard,
+  SpeedOverTimeChart,
   KeyTypesPieChart,
   SpecialKeysChart,
+  ActivityCalendar,
 } from '@/components/stat-charts';
 import { ShareableImage } from '@/components/shareable-image';
 import { HighlightsCard } from '@/components/highlights-card';
 import { RecordingInfoCard } from '@/components/recording-info-card';
+import { GoalsCard } from '@/components/goals-card';
+import { EffortCard } from '@/components/effort-card';
 import { Card, CardContent, CardHeader, CardTitle } from '@/components/ui/card';
-import { Keyboard, RefreshCw } from 'lucide-react';
-import { Button } from '@/components/ui/button';
-import type { AllStats } from '@/types';
+import { Download, Keyboard, RefreshCw } from 'lucide-react';
+import { Button, buttonVariants } from '@/components/ui/button';
+import type {
+  AllStats,
+  CalendarDay,
+  Capabilities,
+  DailySpeed,
+  KeyHeat,
+  Replay,
+} from '@/types';
+
+// Served by the dashboard server itself, so requests carry its token cookie
+const API_BASE = '/api/v1';
 
 function App() {
   const [stats, setStats] = useState<AllStats | null>(null);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
+  const [live, setLive] = useState(false);
+  const [hourHeat, setHourHeat] = useState<KeyHeat[]>([]);
+  const [dailySpeed, setDailySpeed] = useState<DailySpeed[]>([]);
+  const [calendar, setCalendar] = useState<CalendarDay[]>([]);
+  const [capabilities, setCapabilities] = useState<Capabilities | null>(null);
 
   const fetchStats = async () => {
     try {
       setLoading(true);
       setError(null);
-      const response = await fetch('http://localhost:3456/api/stats');
+      const response = await fetch(`${API_BASE}/stats`);
       if (!response.ok) {
         throw new Error('Failed to fetch stats');
       }
       const data = await response.json();
       setStats(data);
+
+      const heatResponse = await fetch(`${API_BASE}/heatmap?metric=avg_hour`);
+      if (heatResponse.ok) {
+        setHourHeat(await heatResponse.json());
+      }
+
+      const speedResponse = await fetch(`${API_BASE}/speed?weeks=12`);
+      if (speedResponse.ok) {
+        setDailySpeed(await speedResponse.json());
+      }
+
+      const calendarResponse = await fetch(`${API_BASE}/calendar`);
+      if (calendarResponse.ok) {
+        setCalendar(await calendarResponse.json());
+      }
+
+      const capabilitiesResponse = await fetch(`${API_BASE}/capabilities`);
+      if (capabilitiesResponse.ok) {
+        setCapabilities(await capabilitiesResponse.json());
+      }
     } catch (err) {
       setError(err instanceof Error ? err.message : 'An error occurred');
     } finally {
@@ -43,6 +87,28 @@ function App() {
     fetchStats();
   }, []);
 
+  const fetchReplay = async (): Promise<Replay> => {
+    const response = await fetch(`${API_BASE}/replay?step=day`);
+    if (!response.ok) {
+      throw new Error('Failed to fetch replay');
+    }
+    return response.json();
+  };
+
+  // Subscribe to live updates pushed while a listener is recording
+  useEffect(() => {
+    const source = new EventSource(`${API_BASE}/stats/stream`);
+
+    source.onopen = () => setLive(true);
+    source.onerror = () => setLive(false);
+    source.addEventListener('stats', (event) => {
+      setStats(JSON.parse((event as MessageEvent).data));
+      setLive(true);
+    });
+
+    return () => source.close();
+  }, []);
+
   if (loading) {
     return (
       <div className="min-h-screen bg-background flex items-center justify-center">
@@ -78,6 +144,14 @@ function App() {
     return null;
   }
 
+  // Key presses could be read back as typed text, so the server only exports them when
+  // it requires a token; otherwise the buttons download combos instead
+  const keyExport = capabilities?.key_export ?? false;
+  const exportData = keyExport ? 'keys' : 'combos';
+  const exportTitle = keyExport
+    ? 'Download every recorded key press'
+    : 'Key presses are only exported when the dashboard is started with --token, so this downloads key combos';
+
   return (
     <div className="min-h-screen bg-background">
       {/* Header */}
@@ -95,10 +169,36 @@ function App() {
                 </p>
               </div>
             </div>
-            <Button onClick={fetchStats} variant="outline" size="sm">
-              <RefreshCw className="w-4 h-4 mr-2" />
-              Refresh
-            </Button>
+            <div className="flex items-center gap-3">
+              {live && (
+                <span className="flex items-center gap-2 text-xs text-muted-foreground">
+                  <span className="h-2 w-2 rounded-full bg-green-500 animate-pulse"></span>
+                  Live
+                </span>
+              )}
+              <a
+                href={`${API_BASE}/export?format=csv&data=${exportData}`}
+                download
+                title={exportTitle}
+                className={buttonVariants('outline', 'sm')}
+              >
+                <Download className="w-4 h-4 mr-2" />
+                {keyExport ? 'CSV' : 'Combos CSV'}
+              </a>
+              <a
+                href={`${API_BASE}/export?format=json&data=${exportData}`}
+                download
+                title={exportTitle}
+                className={buttonVariants('outline', 'sm')}
+              >
+                <Download className="w-4 h-4 mr-2" />
+                {keyExport ? 'JSON' : 'Combos JSON'}
+              </a>
+              <Button onClick={fetchStats} variant="outline" size="sm">
+                <RefreshCw className="w-4 h-4 mr-2" />
+                Refresh
+              </Button>
+            </div>
           </div>
         </div>
       </header>
@@ -110,6 +210,13 @@ function App() {
           <StatCards stats={stats} />
         </section>
 
+        {/* Goals */}
+        {stats.goals.length > 0 && (
+          <section className="mb-8">
+            <GoalsCard stats={stats} />
+          </section>
+        )}
+
         {/* Keyboard Heatmap */}
         <section className="mb-8">
           <Card>
@@ -117,21 +224,51 @@ function App() {
               <CardTitle className="text-lg">⌨️ Keyboard Heatmap</CardTitle>
             </CardHeader>
             <CardContent className="overflow-x-auto">
-              <KeyboardHeatmap stats={stats} />
+              <KeyboardHeatmap
+                stats={stats}
+                hourHeat={hourHeat}
+                loadReplay={fetchReplay}
+              />
             </CardContent>
           </Card>
         </section>
 
+        {/* Activity Calendar */}
+        {calendar.some((day) => day.count > 0) && (
+          <section className="mb-8">
+            <ActivityCalendar days={calendar} />
+          </section>
+        )}
+
         {/* Charts Grid */}
         <section className="mb-8 grid grid-cols-1 lg:grid-cols-2 gap-6">
           <TopKeysChart stats={stats} />
           <TopCombosChart stats={stats} />
           <HourlyActivityChart stats={stats} />
           <DailyActivityChart stats={stats} />
+          <WeeklyRhythmChart stats={stats} />
+          {stats.weekday_weekend.weekend.keys > 0 && (
+            <WeekdayWeekendCard stats={stats} />
+          )}
+          <SpeedDistributionChart stats={stats} />
+          {dailySpeed.some((day) => day.samples > 0) && (
+            <SpeedOverTimeChart days={dailySpeed} />
+          )}
+          {stats.key_intervals.intervals > 0 && (
+            <TypingRhythmChart stats={stats} />
+          )}
+          {stats.top_bursts.length > 0 && <TypingBurstsCard stats={stats} />}
           <KeyTypesPieChart stats={stats} />
           <SpecialKeysChart stats={stats} />
         </section>
 
+        {/* Typing Effort */}
+        {stats.daily_effort.length > 0 && (
+          <section className="mb-8">
+            <EffortCard stats={stats} />
+          </section>
+        )}
+
         {/* Shareable Image */}
         <section className="mb-8">
           <ShareableImage stats={stats} />
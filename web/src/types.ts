@@ -2,6 +2,8 @@ export interface KeyStats {
   key_name: string;
   count: number;
   percentage: number;
+  /** Character an Unknown key most often composed, when composed characters are recorded */
+  composed: string | null;
 }
 
 export interface ComboStats {
@@ -9,6 +11,68 @@ export interface ComboStats {
   count: number;
 }
 
+export interface ChordStats {
+  chord: string;
+  key_count: number;
+  count: number;
+  avg_spread_ms: number;
+}
+
+export interface ComboStormStats {
+  combo: string;
+  storms: number;
+  count: number;
+  last_seen: string;
+}
+
+export interface BigramStats {
+  first_key: string;
+  second_key: string;
+  count: number;
+}
+
+export interface ContextStats {
+  name: string;
+  count: number;
+  percentage: number;
+}
+
+export interface AppShortcuts {
+  app: string;
+  keys: number;
+  combos: number;
+  combos_per_1000_keys: number;
+  /** Whether shortcuts are rare in the app for how much is typed in it */
+  few_shortcuts: boolean;
+  top_combos: ComboStats[];
+}
+
+export interface KeyHeat {
+  key_name: string;
+  count: number;
+  /** Press count, or the average hour of use (0-24) for `metric=avg_hour` */
+  value: number;
+}
+
+/** Key presses recorded in one step of a replay */
+export interface ReplayFrame {
+  /** Local time the step starts at, e.g. `2024-01-31T00:00:00` */
+  start: string;
+  keys: Record<string, number>;
+}
+
+/** Endpoints the server refuses unless it requires a token */
+export interface Capabilities {
+  key_export: boolean;
+  key_stream: boolean;
+}
+
+/** Key presses step by step, oldest first; steps without any are left out */
+export interface Replay {
+  step: 'hour' | 'day';
+  frames: ReplayFrame[];
+}
+
 export interface HourlyStats {
   hour: number;
   count: number;
@@ -19,15 +83,178 @@ export interface DailyStats {
   count: number;
 }
 
+export interface WeekdayHourStats {
+  day: string;
+  /** Presses in each local hour, from midnight */
+  hours: number[];
+}
+
+export interface DailyMetrics {
+  active_minutes: number;
+  wpm: number;
+  error_rate: number;
+  combo_usage: number;
+}
+
+export interface DailyScore {
+  date: string;
+  score: number;
+  metrics: DailyMetrics;
+}
+
+export interface HandAlternation {
+  bigrams: number;
+  alternating: number;
+  same_hand: number;
+  rate: number;
+}
+
+export interface RowUsage {
+  number: number;
+  top: number;
+  home: number;
+  bottom: number;
+  other: number;
+}
+
+export interface SpeedBucket {
+  min: number;
+  /** Absent for the last range when it holds everything faster */
+  max: number | null;
+  count: number;
+}
+
+export interface SpeedDistribution {
+  samples: number;
+  p50: number;
+  p90: number;
+  p99: number;
+  buckets: SpeedBucket[];
+}
+
+export interface IntervalBucket {
+  min_ms: number;
+  max_ms: number;
+  count: number;
+}
+
+export interface HourlyInterval {
+  hour: number;
+  intervals: number;
+  /** Absent for hours without intervals */
+  median_ms: number | null;
+}
+
+export interface KeyIntervals {
+  intervals: number;
+  p50_ms: number;
+  p25_ms: number;
+  p75_ms: number;
+  buckets: IntervalBucket[];
+  hourly: HourlyInterval[];
+}
+
+export interface DayTypeStats {
+  active_days: number;
+  keys: number;
+  keys_per_day: number;
+  /** Absent without typing speed samples */
+  average_cpm: number | null;
+  /** Percent of the keys typed in each local hour, 0 to 23 */
+  hourly_share: number[];
+  busiest_hour: number | null;
+  top_keys: KeyStats[];
+}
+
+export interface WeekdayWeekend {
+  weekday: DayTypeStats;
+  weekend: DayTypeStats;
+}
+
+export interface BurstStats {
+  /** RFC 3339 in local time */
+  started: string;
+  duration_secs: number;
+  keys: number;
+  peak_cpm: number;
+  average_cpm: number;
+}
+
+export interface CalendarDay {
+  date: string;
+  count: number;
+  /** Shade from 0, for no key presses, to 4 for the busiest days */
+  level: number;
+}
+
+export interface DailySpeed {
+  date: string;
+  samples: number;
+  /** Characters per minute; null on days without samples */
+  average_cpm: number | null;
+  /** Words (five characters) per minute; null on days without samples */
+  average_wpm: number | null;
+}
+
+export type KeyboardLayout = 'qwerty' | 'dvorak' | 'colemak';
+
+export interface LayoutEffort {
+  layout: KeyboardLayout;
+  total: number;
+  average: number;
+}
+
+export interface DailyEffort {
+  date: string;
+  keys: number;
+  layout: KeyboardLayout;
+  total: number;
+  average: number;
+  layouts: LayoutEffort[];
+}
+
+export type GoalMetric = 'keys' | 'active_minutes' | 'wpm';
+
+export type GoalPeriod = 'daily' | 'weekly';
+
+export interface GoalProgress {
+  metric: GoalMetric;
+  period: GoalPeriod;
+  target: number;
+  current: number;
+  progress: number;
+  met: boolean;
+  period_start: string;
+}
+
+export interface CustomMetricValue {
+  name: string;
+  value: unknown;
+}
+
 export interface AllStats {
   total_keys: number;
   total_combos: number;
+  total_chords: number;
   total_sessions: number;
   total_time_minutes: number;
   most_pressed_key: KeyStats | null;
   most_pressed_combo: ComboStats | null;
   top_keys: KeyStats[];
   top_combos: ComboStats[];
+  top_chords: ChordStats[];
+  combo_storms: ComboStormStats[];
+  top_bigrams: BigramStats[];
+  hand_alternation: HandAlternation;
+  top_workspaces: ContextStats[];
+  top_monitors: ContextStats[];
+  top_projects: ContextStats[];
+  app_shortcuts: AppShortcuts[];
+  daily_scores: DailyScore[];
+  daily_effort: DailyEffort[];
+  goals: GoalProgress[];
+  remote_keys_count: number;
+  local_keys_count: number;
   spacebar_count: number;
   enter_count: number;
   backspace_count: number;
@@ -39,16 +266,25 @@ export interface AllStats {
   letter_keys_count: number;
   number_keys_count: number;
   special_keys_count: number;
+  row_usage: RowUsage;
   hourly_distribution: HourlyStats[];
   daily_distribution: DailyStats[];
+  weekday_hours: WeekdayHourStats[];
   most_active_hour: HourlyStats | null;
   most_active_day: DailyStats | null;
   average_keys_per_session: number;
   average_typing_speed: number;
   max_typing_speed: number;
+  speed_distribution: SpeedDistribution;
+  top_bursts: BurstStats[];
+  key_intervals: KeyIntervals;
+  weekday_weekend: WeekdayWeekend;
   key_frequency_map: Record<string, number>;
   first_recorded: string | null;
   last_recorded: string | null;
   unique_keys_used: number;
   keys_per_minute_avg: number;
+  custom_metrics: CustomMetricValue[];
+  /** Fields left empty because their data can't follow the filter */
+  unfiltered: string[];
 }
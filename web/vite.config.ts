@@ -11,4 +11,10 @@ export default defineConfig({
       "@": path.resolve(__dirname, "./src"),
     },
   },
+  // `bun dev` sends API requests to a dashboard server started with `kitmap preview --web`
+  server: {
+    proxy: {
+      "/api": "http://localhost:3456",
+    },
+  },
 })